@@ -1,20 +1,63 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use claude_token_monitor::{
     models::*,
     services::{
-        SessionService,
-        session_tracker::SessionTracker, 
-        file_monitor::{FileBasedTokenMonitor, explain_how_this_works},
+        SessionService, ConfigService,
+        config_manager::ConfigManager,
+        profile_manager::{self, ProfileManager},
+        data_paths::{self, BaseDirs},
+        scripting::ScriptEngine,
+        claude_settings::{self, ClaudeSettings},
+        claude_hooks::{self, HookSpec},
+        schema::{self, FileSchemaReport},
+        usage_stats::{self, Bucketing},
+        cache_analysis,
+        analyzer::{self, Analyzer},
+        simulator::{self, Scenario},
+        metrics_history::{MetricsHistory, METRICS_HISTORY_FILE},
+        rrd::RRD_FILE,
+        events::{self, EventLog, EVENTS_FILE},
+        model_names::ModelNames,
+        backup,
+        output_schema::{self, BlocksOutput, EventsOutput, HistoryOutput, HistorySession, StatusOutput},
+        importer::{self, ImportFormat},
+        live_activity::{self, ActivityGrouping, ActivityWindow},
+        session_compare,
+        update_checker::{self, Release},
+        accounts,
+        overage,
+        mqtt::MqttPublisher,
+        telegram::{self, StatusBot},
+        notify_url,
+        rpc,
+        container::{self, ContainerKind},
+        os_users::UserDirectory,
+        permissions,
+        pricing,
+        forecast,
+        snapshot::{self, Snapshot, SnapshotEnvironment},
+        debug_bundle,
+        file_lock,
+        expense::{self, BillingCodes, ExpenseGrouping},
+        session_tracker::{SessionTracker, SESSIONS_FILE},
+        encryption::{StateCipher, StateDir, StateFile},
+        file_monitor::{Billing, FileBasedTokenMonitor, explain_how_this_works},
     },
-    ui::{TerminalUI, RatatuiTerminalUI},
+    ui::{TerminalUI, RatatuiTerminalUI, chart_export, histogram, summary_line, statusline::{self, LauncherItem, StatuslineInput}, top::{self, TopView}},
 };
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use anyhow::Result;
-use chrono::Utc;
+use anyhow::{Context, Result};
+use chrono::{Datelike, Utc};
 use log::debug;
 
+/// Longest span the day and week arguments accept, about ten years; longer ones would overflow the
+/// date arithmetic
+const MAX_REPORT_DAYS: i64 = 3653;
+const MAX_REPORT_WEEKS: i64 = MAX_REPORT_DAYS / 7;
+
 #[derive(Parser)]
 #[command(name = "claude-token-monitor")]
 #[command(about = "A lightweight Rust client for Claude token usage monitoring")]
@@ -23,9 +66,9 @@ struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
     
-    /// Update interval in seconds
-    #[arg(short, long, default_value = "3")]
-    interval: u64,
+    /// Update interval in seconds (defaults to the configured interval)
+    #[arg(short, long, global = true)]
+    interval: Option<u64>,
     
     /// Configuration file path
     #[arg(short, long)]
@@ -43,6 +86,11 @@ struct Cli {
     #[arg(long)]
     basic_ui: bool,
     
+    /// Scan and redraw less often to save energy, as the UI does on battery unless configured
+    /// otherwise
+    #[arg(long, global = true)]
+    low_power: bool,
+    
     /// Explain in detail how this tool works and what it monitors
     #[arg(long)]
     explain_how_this_works: bool,
@@ -50,6 +98,58 @@ struct Cli {
     /// Show about information including version, author, and contributors
     #[arg(long)]
     about: bool,
+    
+    /// Named profile with its own config and data directory (e.g. work, personal)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+    
+    /// Directory for the monitor's own config and state, instead of the platform data directory
+    #[arg(long, global = true, env = "CLAUDE_TOKEN_MONITOR_DATA_DIR", value_name = "DIR")]
+    data_dir: Option<PathBuf>,
+    
+    /// Keep config and state together in the pre-XDG data directory, without migrating
+    #[arg(long, global = true, conflicts_with = "data_dir")]
+    legacy_paths: bool,
+    
+    /// Never write anything (config, session and metrics files, locks, debug.log), e.g. to inspect a
+    /// mounted copy of someone else's data
+    #[arg(long, global = true)]
+    read_only: bool,
+    
+    /// Print only what the command is for: no banner, scan progress or info logging
+    /// (`check`: print nothing while usage is below the limit)
+    #[arg(short, long, global = true)]
+    quiet: bool,
+    
+    /// Check GitHub for a newer release first and say so on stderr
+    #[arg(long, global = true)]
+    check_update: bool,
+    
+    /// Count only usage logged by this OS user (repeatable), instead of the config's [users] only
+    #[arg(long = "user", global = true, value_name = "NAME")]
+    users: Vec<String>,
+}
+
+/// How commands that report data print it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+/// How `report expense` prints its line items
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ExpenseFormat {
+    Text,
+    Csv,
+}
+
+/// Where `claude-statusline` output is shown
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum StatuslineFormat {
+    Claude,  // Claude Code's statusline, fed its session JSON on stdin
+    Raycast, // Raycast script command
+    Alfred,  // Alfred Script Filter JSON
 }
 
 
@@ -62,24 +162,384 @@ enum Commands {
         plan: String,
     },
     /// Show current observed session status
-    Status,
+    Status {
+        #[arg(long, value_enum, default_value = "text")]
+        output: OutputFormat,
+    },
     /// Show observed session history
     History {
         /// Number of sessions to show
         #[arg(short, long, default_value = "10")]
         limit: usize,
+        #[arg(long, value_enum, default_value = "text")]
+        output: OutputFormat,
+    },
+    /// List the latest session windows with the share of the limit each used and whether it was hit
+    Blocks {
+        /// Number of windows to show
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
+        #[arg(long, value_enum, default_value = "text")]
+        output: OutputFormat,
+    },
+    /// List window resets, limit hits, plan switches and alerts, oldest first
+    Events {
+        /// Number of days to list, counting today
+        #[arg(long, default_value = "7", value_parser = clap::value_parser!(u32).range(1..=MAX_REPORT_DAYS))]
+        days: u32,
+        #[arg(long, value_enum, default_value = "text")]
+        output: OutputFormat,
     },
     /// Configure the monitor
     Config {
         /// Set default plan hint
         #[arg(long)]
         plan: Option<String>,
-        /// Set update interval
-        #[arg(long)]
-        interval: Option<u64>,
+        #[command(flatten)]
+        intervals: IntervalArgs,
         /// Set warning threshold (0.0-1.0)
         #[arg(long)]
         threshold: Option<f64>,
+        /// How duplicate entries are recognised: ids, ids-and-content or content
+        #[arg(long)]
+        dedup: Option<String>,
+        #[command(flatten)]
+        session: SessionArgs,
+        #[command(flatten)]
+        goals: GoalArgs,
+    },
+    /// Manage named profiles
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+    /// Print a statusline for Claude Code (reads Claude Code's session JSON on stdin) or a launcher
+    ClaudeStatusline {
+        /// Register this command as the statusline in ~/.claude/settings.json
+        #[arg(long)]
+        install: bool,
+        /// Replace an existing statusline command when installing
+        #[arg(long, requires = "install")]
+        force: bool,
+        /// Print for Claude Code, or a usage peek for the Raycast or Alfred launchers
+        #[arg(long, value_enum, default_value = "claude", conflicts_with = "install")]
+        format: StatuslineFormat,
+    },
+    /// Check current usage against a limit (exits 2 when over, for Claude Code hooks)
+    Check {
+        /// Maximum usage as a fraction of the token limit (defaults to the warning threshold)
+        #[arg(long)]
+        max_usage: Option<f64>,
+        /// Print a warning on stdout and exit 0 instead of failing when over the limit
+        #[arg(long)]
+        warn: bool,
+    },
+    /// Install Claude Code hooks that warn the agent and block tool use near the limit
+    InstallHooks {
+        /// Usage fraction at which tool calls are blocked
+        #[arg(long, default_value = "0.95")]
+        block_at: f64,
+        /// Usage fraction at which prompts carry a warning (defaults to the warning threshold)
+        #[arg(long)]
+        warn_at: Option<f64>,
+    },
+    /// Diagnose configuration and log parsing (detected schemas, unknown or invalid lines)
+    Doctor {
+        /// Take group and other access away from the config and state files and directories
+        #[arg(long)]
+        fix_permissions: bool,
+    },
+    /// Show how duplicate entries (e.g. logs synced between machines) affected the totals
+    Dedupe {
+        /// List every group of files sharing duplicated entries
+        #[arg(long)]
+        report: bool,
+    },
+    /// Import usage recorded outside Claude Code, or another machine's session history
+    Import {
+        /// Format of the file (anthropic-csv: usage export from the Anthropic Console)
+        #[arg(long, requires = "file")]
+        format: Option<String>,
+        /// File to import
+        #[arg(required_unless_present = "sessions", requires = "format")]
+        file: Option<PathBuf>,
+        /// Merge an observed_sessions.json from another machine or an earlier install
+        #[arg(long, conflicts_with_all = ["format", "file"])]
+        sessions: Option<PathBuf>,
+    },
+    /// List or compare session windows
+    Sessions {
+        #[command(subcommand)]
+        action: SessionsAction,
+    },
+    /// Live table of the conversations or projects using the most tokens right now
+    Top {
+        /// Rank conversations or projects
+        #[arg(long, value_enum, ignore_case = true, default_value = "conversation")]
+        by: ActivityGrouping,
+        /// Window to rank by, in minutes
+        #[arg(long, value_enum, default_value = "5")]
+        window: ActivityWindow,
+    },
+    /// Print token usage histograms
+    Usage {
+        #[command(subcommand)]
+        view: UsageView,
+    },
+    /// Save the cumulative usage and burn-rate charts to an image file
+    Chart {
+        /// Image to write; the extension picks the format: .svg or .png
+        #[arg(long, value_name = "FILE")]
+        out: PathBuf,
+        /// How far back to chart, e.g. 24h, 7d or 4w
+        #[arg(long, default_value = "7d")]
+        range: humantime::Duration,
+    },
+    /// Serve usage over JSON-RPC 2.0 on stdin/stdout, one message per line, for editor plugins
+    Rpc,
+    /// Print usage summaries
+    Report {
+        #[command(subcommand)]
+        period: ReportPeriod,
+    },
+    /// Replay recent history under a different plan or model to compare cost and limit hits
+    Simulate {
+        /// Plan to compare with the configured one: pro, max5, max20 or a custom limit
+        #[arg(long, required_unless_present = "model")]
+        plan: Option<String>,
+        /// Reprice every request at this model's rates: opus, sonnet, haiku or a model ID
+        #[arg(long)]
+        model: Option<String>,
+        /// Number of weeks of history to replay
+        #[arg(long, default_value = "4", value_parser = clap::value_parser!(u32).range(1..=MAX_REPORT_WEEKS))]
+        weeks: u32,
+    },
+    /// Save the config, observed sessions, imports and metrics of every profile to a .tar.gz archive
+    Backup {
+        /// Archive to write
+        #[arg(long, value_name = "FILE")]
+        out: PathBuf,
+    },
+    /// Save the current metrics, derived session, config digest and environment to one file for bug
+    /// reports, or compare two such snapshots
+    #[command(args_conflicts_with_subcommands = true)]
+    Snapshot {
+        #[command(subcommand)]
+        action: Option<SnapshotAction>,
+        /// File to write the snapshot to, instead of printing it
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+    },
+    /// Collect redacted diagnostics (doctor output, parse error samples, build info, config
+    /// without secrets) into a .tar.gz to attach to a GitHub issue
+    DebugBundle {
+        /// Archive to write (default: claude-token-monitor-debug-<time>.tar.gz here)
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+    },
+    /// Print the JSON Schema of `--output json` payloads and exported files
+    Schema {
+        /// One schema (status, history, blocks, events, rpc-metrics, observed-sessions, backup-manifest or snapshot) instead of all of them
+        name: Option<String>,
+    },
+    /// Restore an archive written by `backup`
+    Restore {
+        /// Archive to restore
+        file: PathBuf,
+        /// Replace files that already exist
+        #[arg(long)]
+        force: bool,
+    },
+    /// Replace this executable with the latest GitHub release
+    SelfUpdate {
+        /// Only report whether a newer release is out
+        #[arg(long)]
+        check: bool,
+    },
+}
+
+#[derive(Args)]
+struct GoalArgs {
+    /// Daily goal: a token count, a USD amount like '$5', or 'off'
+    #[arg(long)]
+    daily_goal: Option<String>,
+    /// Weekly goal: a token count, a USD amount like '$25', or 'off'
+    #[arg(long)]
+    weekly_goal: Option<String>,
+    /// Goal per billing cycle: a token count, a USD amount like '$100', or 'off'
+    #[arg(long)]
+    monthly_goal: Option<String>,
+    /// Day of the month your subscription renews on (1-31), where billing cycles start
+    #[arg(long, value_name = "DAY")]
+    billing_day: Option<u32>,
+}
+
+#[derive(Args)]
+struct IntervalArgs {
+    /// Set how often the usage files are rescanned, in seconds
+    #[arg(long, value_name = "SECONDS")]
+    interval: Option<u64>,
+    /// Set how often the UI is redrawn, in milliseconds
+    #[arg(long, value_name = "MS")]
+    redraw_ms: Option<u64>,
+    /// Set how often alerts are evaluated, in seconds; 0 evaluates them after every scan
+    #[arg(long, value_name = "SECONDS")]
+    alert_interval: Option<u64>,
+}
+
+#[derive(Args)]
+struct SessionArgs {
+    /// Minutes without requests before a session shows as idle
+    #[arg(long, value_name = "MINUTES")]
+    idle_after: Option<u64>,
+    /// Length of the session windows usage limits apply to, in hours
+    #[arg(long, value_name = "HOURS")]
+    window_hours: Option<u32>,
+    /// How requests group into windows: rolling, fixed (clock-aligned) or gap
+    #[arg(long, value_name = "STRATEGY")]
+    window_strategy: Option<String>,
+    /// Pause that ends a window with the gap strategy
+    #[arg(long, value_name = "MINUTES")]
+    window_gap: Option<u32>,
+}
+
+#[derive(Subcommand)]
+enum ReportPeriod {
+    /// Year in review: tokens, estimated cost, busiest day, top model, cache savings, longest session
+    Year {
+        /// Calendar year (UTC) to summarize, defaults to the current year
+        year: Option<i32>,
+    },
+    /// Week summary (Monday to Sunday, UTC) with adherence to configured goals
+    Week {
+        /// Summarize an earlier week instead (1 = last week)
+        #[arg(long, default_value = "0")]
+        weeks_ago: u32,
+    },
+    /// Billing-cycle summary (from the configured billing day, UTC) with the monthly goal
+    Month {
+        /// Summarize an earlier cycle instead (1 = the previous one)
+        #[arg(long, default_value = "0")]
+        cycles_ago: u32,
+    },
+    /// Recommend a plan from how often each one's limit would have been hit
+    Plan {
+        /// Number of weeks of history to replay
+        #[arg(long, default_value = "4", value_parser = clap::value_parser!(u32).range(1..=MAX_REPORT_WEEKS))]
+        weeks: u32,
+    },
+    /// Repeated large inputs that missed the prompt cache, with estimated savings
+    Cache {
+        /// Number of days to analyze, counting today
+        #[arg(long, default_value = "7", value_parser = clap::value_parser!(u32).range(1..=MAX_REPORT_DAYS))]
+        days: u32,
+    },
+    /// Daily peaks and burn rates recorded by the live monitor
+    History {
+        /// Number of days to include, counting today
+        #[arg(long, default_value = "14", value_parser = clap::value_parser!(u32).range(1..=MAX_REPORT_DAYS))]
+        days: u32,
+    },
+    /// Line items per day, project and model for a calendar month, for billing usage to clients
+    Expense {
+        /// Month to export as YYYY-MM (UTC), defaults to the current month
+        #[arg(long)]
+        month: Option<String>,
+        /// Print a table, or CSV for a spreadsheet or accounting tool
+        #[arg(long, value_enum, default_value = "text")]
+        format: ExpenseFormat,
+        /// Split lines by project or by the billing code in `billing_codes`
+        #[arg(long, value_enum, ignore_case = true, default_value = "project")]
+        group_by: ExpenseGrouping,
+        /// Only include projects tagged with this billing code, e.g. for one client's invoice
+        #[arg(long)]
+        billing_code: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SnapshotAction {
+    /// List what changed between two snapshots: metrics, session, config sections and environment
+    Diff {
+        /// The earlier snapshot
+        a: PathBuf,
+        /// The later snapshot
+        b: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum SessionsAction {
+    /// List recent session windows with their IDs
+    List {
+        /// Number of windows to show
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
+    },
+    /// Compare two windows side by side: duration, tokens by type, models, cache hit rate and cost
+    Compare {
+        /// Window ID from `sessions list`, an observed session ID, or 'latest'
+        a: String,
+        /// Window to compare it with
+        b: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum UsageView {
+    /// Tokens per hour of day (Claude Code usage only)
+    #[command(name = "by-hour")]
+    Hour {
+        /// Number of days to include, counting today
+        #[arg(long, default_value = "7", value_parser = clap::value_parser!(u32).range(1..=MAX_REPORT_DAYS))]
+        days: u32,
+    },
+    /// Tokens per calendar day, including imported usage
+    #[command(name = "by-day")]
+    Day {
+        /// Number of days to include, counting today
+        #[arg(long, default_value = "14", value_parser = clap::value_parser!(u32).range(1..=MAX_REPORT_DAYS))]
+        days: u32,
+    },
+    /// Tokens per weekday, including imported usage
+    #[command(name = "by-weekday")]
+    Weekday {
+        /// Number of days to include, counting today
+        #[arg(long, default_value = "28", value_parser = clap::value_parser!(u32).range(1..=MAX_REPORT_DAYS))]
+        days: u32,
+    },
+    /// Tokens, requests and cost per Claude account, with each account's current window
+    #[command(name = "by-account")]
+    Account {
+        /// Number of days to include, counting today
+        #[arg(long, default_value = "7", value_parser = clap::value_parser!(u32).range(1..=MAX_REPORT_DAYS))]
+        days: u32,
+    },
+    /// The most expensive conversations, flagging requests billed at long-context rates
+    #[command(name = "by-conversation")]
+    Conversation {
+        /// Number of days to include, counting today
+        #[arg(long, default_value = "7", value_parser = clap::value_parser!(u32).range(1..=MAX_REPORT_DAYS))]
+        days: u32,
+        /// Number of conversations to show
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileAction {
+    /// List available profiles
+    List,
+    /// Create a new profile
+    Create {
+        /// Profile name (letters, digits, '-' or '_')
+        name: String,
+    },
+    /// Delete a profile and all of its stored data
+    Delete {
+        /// Profile name
+        name: String,
     },
 }
 
@@ -105,11 +565,45 @@ async fn main() -> Result<()> {
         return Ok(());
     }
     
+    if let Some(Commands::Schema { name }) = &cli.command {
+        println!("{}", serde_json::to_string_pretty(&output_schema::schema_json(name.as_deref())?)?);
+        return Ok(());
+    }
+    
+    if let Some(Commands::Snapshot { action: Some(SnapshotAction::Diff { a, b }), .. }) = &cli.command {
+        return diff_snapshots(a, b);
+    }
+    
+    if cli.read_only {
+        if let Some(command) = cli.command.as_ref().and_then(writing_command) {
+            return Err(anyhow::anyhow!("'{command}' writes to disk and can't run with --read-only"));
+        }
+    }
+    
+    if let Some(Commands::SelfUpdate { check }) = &cli.command {
+        return self_update(*check).await;
+    }
+    
+    if cli.check_update {
+        match newer_release().await {
+            Ok(Some(release)) => eprintln!(
+                "💡 claude-token-monitor {} is available (you have {}); run `claude-token-monitor self-update` to install it",
+                release.tag_name.trim_start_matches('v'),
+                env!("CARGO_PKG_VERSION")
+            ),
+            Ok(None) => {}
+            Err(e) => eprintln!("⚠️  Update check failed: {e:#}"),
+        }
+    }
+    
     // Initialize logging
-    if cli.verbose {
+    if cli.verbose && cli.read_only {
+        env_logger::Builder::new()
+            .filter_level(log::LevelFilter::Debug)
+            .init();
+    } else if cli.verbose {
     // Log to file when verbose
-    use std::fs::OpenOptions;
-    let log_file = OpenOptions::new()
+    let log_file = permissions::private_open_options()
         .create(true)
         .append(true)
         .open("debug.log")?;
@@ -118,6 +612,11 @@ async fn main() -> Result<()> {
         .filter_level(log::LevelFilter::Debug)
         .target(env_logger::Target::Pipe(Box::new(log_file)))
         .init();
+} else if cli.quiet || matches!(cli.command, Some(Commands::ClaudeStatusline { .. } | Commands::Check { .. } | Commands::Doctor { .. } | Commands::DebugBundle { .. } | Commands::Dedupe { .. } | Commands::Top { .. } | Commands::Sessions { .. } | Commands::Usage { .. } | Commands::Chart { .. } | Commands::Report { .. } | Commands::Simulate { .. } | Commands::Events { .. } | Commands::Blocks { .. } | Commands::Snapshot { .. } | Commands::Rpc)) {
+    // Claude Code shows hook stderr to the agent, so only report real problems
+    env_logger::Builder::new()
+        .filter_level(log::LevelFilter::Warn)
+        .init();
 } else {
     // Normal logging to stderr for info/warn/error
     env_logger::Builder::new()
@@ -125,18 +624,149 @@ async fn main() -> Result<()> {
         .init();
 }
 
-    // Setup data directory
-    let data_dir = dirs::data_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("claude-token-monitor");
+    // Setup config and state directories
+    let base_dirs = resolve_base_dirs(&cli)?;
+    for dir in [&base_dirs.config, &base_dirs.state].into_iter().filter(|_| !cli.read_only) {
+        permissions::create_private_dir_all(dir)
+            .with_context(|| format!("Failed to create data directory {} (set --data-dir to use another)", dir.display()))?;
+    }
+    
+    let profile_manager = ProfileManager::new(base_dirs);
+    
+    // Profile management doesn't need any usage data
+    if let Some(Commands::Profile { action }) = &cli.command {
+        return manage_profiles(&profile_manager, action, cli.profile.as_deref());
+    }
+    // Backups cover every profile, and a restore must come before a default config is created
+    match &cli.command {
+        Some(Commands::Backup { out }) => return backup_data(&profile_manager.profile_dir(None)?, out),
+        Some(Commands::Restore { file, force }) => return restore_data(&profile_manager.profile_dir(None)?, file, *force),
+        _ => {}
+    }
+    
+    let BaseDirs { config: config_dir, state: data_dir } = profile_manager.profile_dir(cli.profile.as_deref())?;
+    
+    // Doctor reports config problems instead of failing on them, and so does the bundle of its output
+    if let Some(Commands::Doctor { fix_permissions }) = &cli.command {
+        return run_doctor(&config_dir, &data_dir, cli.read_only, *fix_permissions).await;
+    }
+    if let Some(Commands::DebugBundle { out }) = &cli.command {
+        return write_debug_bundle(&cli, &config_dir, &data_dir, out.as_deref()).await;
+    }
+    
+    // Load configuration (migrating older schema versions)
+    let config_manager = ConfigManager::new(&config_dir);
+    let mut config = if cli.read_only { config_manager.load_without_writing()? } else { config_manager.load_or_create()? };
+    config.validate()?;
+    if let Some(interval) = cli.interval {
+        config.update_interval_seconds = interval;
+    }
+    if cli.low_power {
+        config.low_power = LowPowerMode::On;
+    }
+    if !cli.users.is_empty() {
+        config.users.only = cli.users.iter().cloned().collect();
+        config.validate()?;
+    }
+    // Compile user scripts and parse notification URLs up front so mistakes surface before the UI starts
+    ScriptEngine::new(&config.scripts)?;
+    notify_url::validate_channels(&config.alerts.channels)?;
+    
+    // Claude Code reads the statusline from stdout, so skip the usual progress output
+    if let Some(Commands::ClaudeStatusline { install, force, format }) = &cli.command {
+        if *install {
+            return install_statusline(&monitor_command(&cli)?, *force);
+        }
+        return print_statusline(&config, *format).await;
+    }
     
-    std::fs::create_dir_all(&data_dir)?;
+    // Hooks run on every tool call, so they get the same quiet fast path
+    if let Some(Commands::Check { max_usage, warn }) = &cli.command {
+        return check_usage(&config, max_usage.unwrap_or(config.warning_threshold), *warn, cli.quiet).await;
+    }
+    if let Some(Commands::InstallHooks { block_at, warn_at }) = &cli.command {
+        return install_claude_hooks(&monitor_command(&cli)?, *block_at, warn_at.unwrap_or(config.warning_threshold));
+    }
     
-    // Load configuration
-    let config = load_or_create_config(&data_dir)?;
+    if let Some(Commands::Dedupe { report }) = &cli.command {
+        return show_duplicates(&config, *report).await;
+    }
+    // Session and metrics files are encrypted when [encryption] is configured
+    let state = StateDir::new(data_dir.clone(), StateCipher::from_config(&config.encryption)?).with_read_only(cli.read_only);
+    match &cli.command {
+        Some(Commands::Import { sessions: Some(sessions), .. }) => {
+            return import_sessions(&state, sessions).await;
+        }
+        Some(Commands::Import { format: Some(format), file: Some(file), .. }) => {
+            return import_usage(&data_dir, format.parse()?, file);
+        }
+        Some(Commands::Top { by, window }) => {
+            return run_top(&config, *by, *window).await;
+        }
+        Some(Commands::Usage { view: UsageView::Conversation { days, limit } }) => {
+            return show_conversation_costs(&config, *days, *limit).await;
+        }
+        Some(Commands::Usage { view: UsageView::Account { days } }) => {
+            return show_account_usage(&config, *days).await;
+        }
+        Some(Commands::Chart { out, range }) => {
+            return export_usage_chart(&config, &data_dir, out, (*range).into()).await;
+        }
+        Some(Commands::Usage { view }) => {
+            return show_usage_histogram(&config, &data_dir, view).await;
+        }
+        Some(Commands::Sessions { action: SessionsAction::List { limit } }) => {
+            return list_session_windows(&config, *limit).await;
+        }
+        Some(Commands::Sessions { action: SessionsAction::Compare { a, b } }) => {
+            return compare_session_windows(&config, a, b).await;
+        }
+        Some(Commands::Report { period: ReportPeriod::Year { year } }) => {
+            return show_year_report(&config, &data_dir, year.unwrap_or_else(|| Utc::now().year())).await;
+        }
+        Some(Commands::Report { period: ReportPeriod::Week { weeks_ago } }) => {
+            return show_week_report(&config, &data_dir, *weeks_ago).await;
+        }
+        Some(Commands::Report { period: ReportPeriod::Month { cycles_ago } }) => {
+            return show_month_report(&config, &data_dir, *cycles_ago).await;
+        }
+        Some(Commands::Report { period: ReportPeriod::Plan { weeks } }) => {
+            return show_plan_report(&config, *weeks).await;
+        }
+        Some(Commands::Report { period: ReportPeriod::Cache { days } }) => {
+            return show_cache_report(&config, *days).await;
+        }
+        Some(Commands::Report { period: ReportPeriod::History { days } }) => {
+            return show_metrics_history(&state.file(METRICS_HISTORY_FILE), *days);
+        }
+        Some(Commands::Report { period: ReportPeriod::Expense { month, format, group_by, billing_code } }) => {
+            return show_expense_report(&config, &data_dir, month.as_deref(), *format, *group_by, billing_code.as_deref()).await;
+        }
+        Some(Commands::Events { days, output }) => {
+            return show_events(&config, &state, *days, *output).await;
+        }
+        Some(Commands::Blocks { limit, output }) => {
+            return show_blocks(&config, *limit, *output).await;
+        }
+        Some(Commands::Snapshot { action: None, out }) => {
+            return take_snapshot(&config, cli.profile.as_deref(), cli.read_only, &config_dir, &data_dir, out.as_deref()).await;
+        }
+        Some(Commands::Rpc) => {
+            return run_rpc(&config, &data_dir, &state).await;
+        }
+        Some(Commands::Simulate { plan, model, weeks }) => {
+            let scenario = Scenario {
+                plan: plan.as_deref().map(parse_plan_type).transpose()?,
+                model: model.clone(),
+            };
+            return simulate_scenario(&config, &scenario, *weeks).await;
+        }
+        _ => {}
+    }
     
     // Initialize services (passive observation)
-    let session_tracker = SessionTracker::new(data_dir.join("observed_sessions.json"))?;
+    let mut session_tracker = SessionTracker::open(state.file(SESSIONS_FILE))?;
+    session_tracker.set_session_window(config.session_window);
     let session_service = Arc::new(RwLock::new(session_tracker));
     
     // Update observed sessions from JSONL data
@@ -144,19 +774,26 @@ async fn main() -> Result<()> {
     
     // Initialize file-based token monitor
     let file_monitor = if cli.force_mock {
-        println!("🔧 Running in forced mock mode - using simulated data");
+        if !cli.quiet {
+            println!("🔧 Running in forced mock mode - using simulated data");
+        }
         None
     } else {
-        match FileBasedTokenMonitor::new() {
+        match FileBasedTokenMonitor::from_config(&config) {
             Ok(mut monitor) => {
-                println!("🔍 Scanning Claude usage files...");
+                monitor.set_imports_dir(data_dir.join(importer::IMPORTS_DIR));
+                if !cli.quiet {
+                    println!("🔍 Scanning Claude usage files...");
+                }
                 monitor.scan_usage_files().await?;
-                println!("✅ Found {} usage entries", monitor.entry_count());
-                if let Some((start, end)) = monitor.entry_time_range() {
-                    println!("📊 Data range: {} to {}", 
-                        humantime::format_rfc3339(start.into()),
-                        humantime::format_rfc3339(end.into())
-                    );
+                if !cli.quiet {
+                    println!("✅ Found {} usage entries", monitor.entry_count());
+                    if let Some((start, end)) = monitor.entry_time_range() {
+                        println!("📊 Data range: {} to {}", 
+                            humantime::format_rfc3339(start.into()),
+                            humantime::format_rfc3339(end.into())
+                        );
+                    }
                 }
                 Some(monitor)
             }
@@ -169,43 +806,84 @@ async fn main() -> Result<()> {
         }
     };
     
+    // Apply per-project overrides for the project currently in use
+    if let Some(overrides) = file_monitor.as_ref().and_then(|m| m.active_project_overrides()) {
+        debug!("Applying project overrides: {overrides:?}");
+        config.apply_project_overrides(overrides);
+    }
+    
     // Handle commands
     match cli.command {
         Some(Commands::Monitor { plan }) => {
             let plan_type = parse_plan_type(&plan)?;
-            run_monitor(session_service, file_monitor, plan_type, config, cli.basic_ui, cli.force_mock).await?;
+            if !cli.quiet {
+                print_monitor_banner(&plan_type);
+            }
+            run_monitor(session_service, file_monitor, plan_type, config, &state, cli.basic_ui, cli.force_mock).await?;
+        }
+        Some(Commands::Status { output }) => {
+            show_status(session_service, output).await?;
+        }
+        Some(Commands::History { limit, output }) => {
+            show_history(session_service, file_monitor.as_ref(), limit, output).await?;
         }
-        Some(Commands::Status) => {
-            show_status(session_service).await?;
+        Some(Commands::Config { plan, intervals, threshold, dedup, session, goals }) => {
+            configure_monitor(config_dir, plan, intervals, threshold, dedup, session, goals).await?;
         }
-        Some(Commands::History { limit }) => {
-            show_history(session_service, limit).await?;
+        Some(Commands::Profile { .. } | Commands::Backup { .. } | Commands::Restore { .. }) => {
+            unreachable!("profile, backup and restore commands are handled before scanning")
         }
-        Some(Commands::Config { plan, interval, threshold }) => {
-            configure_monitor(data_dir, plan, interval, threshold).await?;
+        Some(Commands::ClaudeStatusline { .. } | Commands::Check { .. } | Commands::InstallHooks { .. }) => {
+            unreachable!("Claude Code integration commands are handled before scanning")
+        }
+        Some(Commands::Doctor { .. } | Commands::DebugBundle { .. }) => {
+            unreachable!("doctor and debug-bundle are handled before loading config")
+        }
+        Some(Commands::Schema { .. }) => unreachable!("schema is handled before anything is read"),
+        Some(Commands::SelfUpdate { .. }) => unreachable!("self-update is handled before anything is read"),
+        Some(
+            Commands::Dedupe { .. }
+            | Commands::Import { .. }
+            | Commands::Usage { .. }
+            | Commands::Chart { .. }
+            | Commands::Report { .. }
+            | Commands::Simulate { .. }
+            | Commands::Top { .. }
+            | Commands::Sessions { .. }
+            | Commands::Events { .. }
+            | Commands::Blocks { .. }
+            | Commands::Snapshot { .. }
+            | Commands::Rpc,
+        ) => {
+            unreachable!("dedupe, import, usage, chart, report, simulate, top, sessions, events, blocks, snapshot and rpc are handled before scanning")
         }
         None => {
-            // Default to monitoring with Pro plan
-            let plan_type = PlanType::Pro;
-            run_monitor(session_service, file_monitor, plan_type, config, cli.basic_ui, cli.force_mock).await?;
+            // Default to monitoring with the configured plan (Pro unless changed)
+            let plan_type = config.default_plan.clone();
+            if !cli.quiet {
+                print_monitor_banner(&plan_type);
+            }
+            run_monitor(session_service, file_monitor, plan_type, config, &state, cli.basic_ui, cli.force_mock).await?;
         }
     }
     
     Ok(())
 }
 
+fn print_monitor_banner(plan_type: &PlanType) {
+    println!("🧠 Claude Token Monitor - File-Based Edition");
+    println!("Starting monitoring with plan: {plan_type:?}");
+}
 
 async fn run_monitor(
     session_service: Arc<RwLock<SessionTracker>>,
-    file_monitor: Option<FileBasedTokenMonitor>,
+    mut file_monitor: Option<FileBasedTokenMonitor>,
     plan_type: PlanType,
     config: UserConfig,
+    state: &StateDir,
     use_basic_ui: bool,
     use_mock: bool,
 ) -> Result<()> {
-    println!("🧠 Claude Token Monitor - File-Based Edition");
-    println!("Starting monitoring with plan: {plan_type:?}");
-    
     // Update observed sessions from JSONL data (passive monitoring)
     session_service.write().await.update_observed_sessions().await?;
     
@@ -245,7 +923,7 @@ async fn run_monitor(
                     tokens_used: 0,
                     tokens_limit: observed_plan.default_limit(),
                     is_active: false,
-                    reset_time: Utc::now() + chrono::Duration::hours(5),
+                    reset_time: Utc::now() + monitor.session_window().duration(),
                 },
                 usage_rate: 0.0,
                 session_progress: 0.0,
@@ -258,6 +936,16 @@ async fn run_monitor(
                 cache_creation_rate: 0.0,
                 token_consumption_rate: 0.0,
                 input_output_ratio: 1.0,
+                project_label: None,
+                limit_calibrated: false,
+                limit_events: Vec::new(),
+                estimated_cost: 0.0,
+                activity_heatmap: Vec::new(),
+                minute_tokens: Vec::new(),
+                idle_since: None,
+                reset_authoritative: false,
+                selected_model: None,
+                latest_model: None,
             }
         })
     } else {
@@ -265,24 +953,51 @@ async fn run_monitor(
         std::process::exit(1);
     };
     
+    let interval = config.scan_interval();
+    // Mock metrics would trigger home automations, so they aren't published
+    let mqtt = if use_mock { None } else { MqttPublisher::start(&config.mqtt)? };
+    let alert_channels = config.alerts.channels.clone();
+    
     // Initialize and run UI based on CLI flag (Ratatui is default)
     // Try interactive UI first, fall back to status display if it fails
     let ui_result: Result<(), anyhow::Error> = if use_basic_ui {
         // Use basic terminal UI
-        let mut ui = TerminalUI::new(config);
-        match ui.init() {
-            Ok(()) => {
-                let result = ui.run(&metrics).await;
-                let _ = ui.cleanup();
-                result.map_err(|e| e.into())
+        match TerminalUI::new(config) {
+            Ok(ui) => {
+                let mut ui = if use_mock {
+                    ui
+                } else {
+                    ui.with_metrics_history(state.file(METRICS_HISTORY_FILE))
+                        .with_trend_store(state.file(RRD_FILE))
+                        .with_event_log(state.file(EVENTS_FILE))
+                        .with_mqtt(mqtt.clone())
+                };
+                match ui.init() {
+                    Ok(()) => {
+                        let result = ui.run(&metrics, file_monitor.as_mut()).await;
+                        let _ = ui.cleanup();
+                        result
+                    }
+                    Err(e) => Err(e.into())
+                }
             }
-            Err(e) => Err(e.into())
+            Err(e) => Err(e)
         }
     } else {
         // Use enhanced Ratatui interface (default)
         match RatatuiTerminalUI::new(config) {
-            Ok(mut ratatui_ui) => {
-                let result = ratatui_ui.run(&metrics).await;
+            Ok(ratatui_ui) => {
+                // Mock metrics would pollute the recorded history
+                let mut ratatui_ui = if use_mock {
+                    ratatui_ui
+                } else {
+                    ratatui_ui
+                        .with_metrics_history(state.file(METRICS_HISTORY_FILE))
+                        .with_trend_store(state.file(RRD_FILE))
+                        .with_event_log(state.file(EVENTS_FILE))
+                        .with_mqtt(mqtt.clone())
+                };
+                let result = ratatui_ui.run(&metrics, file_monitor.as_mut()).await;
                 let _ = ratatui_ui.cleanup();
                 result
             }
@@ -294,29 +1009,63 @@ async fn run_monitor(
         }
     };
     
-    // If UI fails, show status and exit gracefully
-    if let Err(_) = ui_result {
-        println!("📊 Token Usage Summary:");
-        println!("  Session: {} ({})", metrics.current_session.id, 
-                if metrics.current_session.is_active { "ACTIVE" } else { "INACTIVE" });
-        println!("  Plan: {:?}", metrics.current_session.plan_type);
-        println!("  Usage: {} / {} tokens ({:.1}%)", 
-                metrics.current_session.tokens_used,
-                metrics.current_session.tokens_limit,
-                (metrics.current_session.tokens_used as f64 / metrics.current_session.tokens_limit as f64) * 100.0);
-        println!("  Rate: {:.2} tokens/minute", metrics.usage_rate);
-        println!("  Efficiency: {:.2}", metrics.efficiency_score);
-        if let Some(depletion) = &metrics.projected_depletion {
-            println!("  Projected depletion: {}", humantime::format_rfc3339((*depletion).into()));
+    // Without a terminal (nohup, pipes, services) keep monitoring with a summary line per interval
+    if let Err(e) = ui_result {
+        debug!("Interactive UI failed: {e}");
+        let status_bot = if use_mock { None } else { StatusBot::new(&alert_channels, |name| std::env::var(name).ok())? };
+        if let Some(bot) = &status_bot {
+            bot.start(&telegram::api_base());
         }
-        println!();
-        println!("💡 Interactive UI not available in this environment.");
-        println!("   Use 'claude-token-monitor status' for quick checks.");
+        print_summaries(metrics, file_monitor, interval, mqtt, status_bot).await?;
     }
     
     Ok(())
 }
 
+/// Print a timestamped summary line every `interval` until interrupted, rescanning the usage
+/// files in between, publishing them over MQTT when a broker is configured and keeping the
+/// Telegram `/status` reply current
+async fn print_summaries(
+    mut metrics: UsageMetrics,
+    mut file_monitor: Option<FileBasedTokenMonitor>,
+    interval: std::time::Duration,
+    mut mqtt: Option<MqttPublisher>,
+    status_bot: Option<StatusBot>,
+) -> Result<()> {
+    eprintln!(
+        "💡 Interactive UI not available, printing a summary every {}s (Ctrl-C to stop)",
+        interval.as_secs()
+    );
+    loop {
+        let summary = summary_line(&metrics, Utc::now());
+        println!("{summary}");
+        if let Some(bot) = &status_bot {
+            bot.set_status(summary);
+        }
+        if let (Some(mqtt), Some(monitor)) = (mqtt.as_mut(), file_monitor.as_ref()) {
+            mqtt.publish(&metrics, monitor, Utc::now());
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+        if let Some(monitor) = file_monitor.as_mut() {
+            match monitor.scan_usage_files().await {
+                Ok(()) => {
+                    if let Some(updated) = monitor.calculate_metrics() {
+                        metrics = updated;
+                    }
+                }
+                Err(e) => log::warn!("Failed to rescan usage files: {e}"),
+            }
+        }
+    }
+    if let Some(bot) = &status_bot {
+        bot.stop();
+    }
+    Ok(())
+}
+
 fn generate_mock_metrics(session: TokenSession) -> UsageMetrics {
     use rand::Rng;
     let mut rng = rand::thread_rng();
@@ -342,13 +1091,34 @@ fn generate_mock_metrics(session: TokenSession) -> UsageMetrics {
         cache_creation_rate: rng.gen_range(10.0..50.0),
         token_consumption_rate: usage_rate,
         input_output_ratio: rng.gen_range(1.5..3.0),
+        project_label: None,
+        limit_calibrated: false,
+        limit_events: Vec::new(),
+        estimated_cost: 0.0,
+        activity_heatmap: Vec::new(),
+        minute_tokens: Vec::new(),
+        idle_since: None,
+        reset_authoritative: false,
+        selected_model: None,
+        latest_model: None,
     }
 }
 
-async fn show_status(session_service: Arc<RwLock<SessionTracker>>) -> Result<()> {
+/// Organization and user of the account signed in to Claude Code, for tagging JSON output
+fn claude_identity() -> Option<AccountIdentity> {
+    claude_settings::claude_config_dir()
+        .ok()
+        .and_then(|dir| claude_settings::SubscriptionHints::read(&dir).identity())
+}
+
+async fn show_status(session_service: Arc<RwLock<SessionTracker>>, output: OutputFormat) -> Result<()> {
     let session_service = session_service.read().await;
     let active_session = session_service.get_active_session().await?;
     
+    if output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&StatusOutput { active_session, identity: claude_identity() })?);
+        return Ok(());
+    }
     match active_session {
         Some(session) => {
             println!("📊 Current Session Status:");
@@ -373,62 +1143,241 @@ async fn show_status(session_service: Arc<RwLock<SessionTracker>>) -> Result<()>
 
 async fn show_history(
     session_service: Arc<RwLock<SessionTracker>>,
+    monitor: Option<&FileBasedTokenMonitor>,
     limit: usize,
+    output: OutputFormat,
 ) -> Result<()> {
     let session_service = session_service.read().await;
-    let sessions = session_service.get_session_history(limit).await?;
+    let sessions: Vec<HistorySession> = session_service
+        .get_session_history(limit)
+        .await?
+        .into_iter()
+        .map(|session| HistorySession::new(session, monitor))
+        .collect();
     
+    if output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&HistoryOutput { sessions, identity: claude_identity() })?);
+        return Ok(());
+    }
     if sessions.is_empty() {
         println!("📝 No session history found");
         return Ok(());
     }
     
+    let costs: Vec<f64> = sessions.iter().filter_map(|s| s.estimated_cost.map(|cost| cost.total())).collect();
+    let expensive = pricing::expensive_threshold(&costs);
     println!("📝 Session History ({} sessions):", sessions.len());
-    println!("┌─────────────────────────────────────────────────────────────────────┐");
-    println!("│ ID       │ Plan  │ Tokens    │ Started             │ Status   │");
-    println!("├─────────────────────────────────────────────────────────────────────┤");
+    println!("┌──────────┬───────┬──────────────────────────┬──────────────────────┬──────────┬───────────┬──────────────────────────────┐");
+    println!("│ ID       │ Plan  │ Tokens                   │ Started              │ Status   │ Cost      │ Input / Output / Cache       │");
+    println!("├──────────┼───────┼──────────────────────────┼──────────────────────┼──────────┼───────────┼──────────────────────────────┤");
     
-    for session in sessions {
+    for HistorySession { session, estimated_cost } in &sessions {
         let status = if session.is_active { "ACTIVE" } else { "ENDED" };
         let usage_percent = (session.tokens_used as f64 / session.tokens_limit as f64) * 100.0;
+        let (cost, split) = match estimated_cost {
+            Some(cost) => (
+                format!("${:.2}{}", cost.total(), if cost.total() > expensive { " ▲" } else { "" }),
+                format!("${:.2} / ${:.2} / ${:.2}", cost.input, cost.output, cost.cache_write + cost.cache_read),
+            ),
+            None => ("-".to_string(), "-".to_string()),
+        };
         
-        println!("│ {:<8} │ {:<5} │ {:<9} │ {:<19} │ {:<8} │",
+        println!("│ {:<8} │ {:<5} │ {:<24} │ {:<20} │ {:<8} │ {:<9} │ {:<28} │",
             &session.id[..8],
             format!("{:?}", session.plan_type),
             format!("{}/{} ({:.1}%)", session.tokens_used, session.tokens_limit, usage_percent),
-            humantime::format_rfc3339(session.start_time.into()),
-            status
+            session.start_time.format("%Y-%m-%d %H:%M UTC").to_string(),
+            status,
+            cost,
+            split
         );
     }
     
-    println!("└─────────────────────────────────────────────────────────────────────┘");
+    println!("└──────────┴───────┴──────────────────────────┴──────────────────────┴──────────┴───────────┴──────────────────────────────┘");
+    if costs.iter().any(|&cost| cost > expensive) {
+        println!("▲ costs more than twice the median session");
+    }
+    Ok(())
+}
+
+/// Print the latest session windows against the token limit, newest first
+async fn show_blocks(config: &UserConfig, limit: usize, output: OutputFormat) -> Result<()> {
+    let mut monitor = FileBasedTokenMonitor::from_config(config)?;
+    monitor.scan_usage_files().await?;
+
+    let blocks = monitor.session_blocks();
+    let token_limit = monitor
+        .derive_current_session()
+        .map_or_else(|| config.default_plan.default_limit(), |session| session.tokens_limit);
+    let windows = usage_stats::window_history(&blocks, token_limit, limit);
+
+    if output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&BlocksOutput { token_limit, windows, identity: claude_identity() })?);
+        return Ok(());
+    }
+    if windows.is_empty() {
+        println!("📝 No session windows found");
+        return Ok(());
+    }
+    println!(
+        "📝 Session windows (latest {} of {}), against a limit of {} tokens:\n",
+        windows.len(),
+        blocks.len(),
+        token_limit
+    );
+    println!("   {:<21} {:<11} {:>9} {:>12} {:>8}  {:<20} Reached", "Started", "Resets", "Requests", "Tokens", "% Limit", "");
+    for window in &windows {
+        let filled = ((window.percent_of_limit / 5.0).round() as usize).min(20);
+        println!(
+            "   {:<21} {:<11} {:>9} {:>12} {:>7.1}%  {:<20} {}",
+            window.start_time.format("%Y-%m-%d %H:%M UTC").to_string(),
+            window.reset_time.format("%H:%M UTC").to_string(),
+            window.requests,
+            window.tokens_used,
+            window.percent_of_limit,
+            format!("{}{}", "█".repeat(filled), "░".repeat(20 - filled)),
+            if window.limit_reached { "⛔ yes" } else { "" }
+        );
+    }
+    let hits = windows.iter().filter(|window| window.limit_reached).count();
+    println!("\nLimit hit in {hits} of {} windows", windows.len());
+    Ok(())
+}
+
+/// Write a snapshot of the current view to `out`, or print it
+async fn take_snapshot(
+    config: &UserConfig,
+    profile: Option<&str>,
+    read_only: bool,
+    config_dir: &std::path::Path,
+    data_dir: &std::path::Path,
+    out: Option<&std::path::Path>,
+) -> Result<()> {
+    use std::io::Write;
+
+    let mut monitor = FileBasedTokenMonitor::from_config(config)?;
+    monitor.set_imports_dir(data_dir.join(importer::IMPORTS_DIR));
+    monitor.scan_usage_files().await?;
+
+    let environment = SnapshotEnvironment::collect(&monitor, profile, read_only, config_dir, data_dir);
+    let key = snapshot::digest_key(data_dir, read_only)?;
+    let snapshot = Snapshot::take(&monitor, config, &key, environment, claude_identity(), Utc::now())?;
+    let json = serde_json::to_string_pretty(&snapshot)?;
+    match out {
+        Some(out) => {
+            file_lock::write_atomic_with(out, |writer| Ok(writeln!(writer, "{json}")?))
+                .with_context(|| format!("Failed to write {}", out.display()))?;
+            println!("📸 Saved snapshot to {}", out.display());
+            println!("💡 It holds paths and usage figures but not the config itself; check it before sharing");
+        }
+        None => println!("{json}"),
+    }
+    Ok(())
+}
+
+/// Print what changed between two snapshot files
+fn diff_snapshots(a: &std::path::Path, b: &std::path::Path) -> Result<()> {
+    let (before, after) = (snapshot::load(a)?, snapshot::load(b)?);
+    let taken = |snapshot: &serde_json::Value| snapshot["taken_at"].as_str().unwrap_or("?").to_string();
+    println!("📸 {} ({}) → {} ({})\n", a.display(), taken(&before), b.display(), taken(&after));
+    let changes = snapshot::diff(&before, &after);
+    if before["digest_key_id"] != after["digest_key_id"] {
+        println!("💡 The config digests were made with different keys (another machine or version), so every section differs\n");
+    }
+    if changes.is_empty() {
+        println!("✅ No differences besides when they were taken");
+        return Ok(());
+    }
+    let width = changes.iter().map(|change| change.path.chars().count()).max().unwrap_or_default();
+    for change in &changes {
+        println!(
+            "   {:<width$}  {} → {}",
+            change.path,
+            change.before.as_deref().unwrap_or("(absent)"),
+            change.after.as_deref().unwrap_or("(absent)")
+        );
+    }
+    println!("\n{} differences", changes.len());
+    Ok(())
+}
+
+/// Print the events of the last `days` days: window resets and limit notices found in the logs,
+/// and the plan switches and alerts the live monitor recorded
+async fn show_events(config: &UserConfig, state: &StateDir, days: u32, output: OutputFormat) -> Result<()> {
+    let mut monitor = FileBasedTokenMonitor::from_config(config)?;
+    monitor.scan_usage_files().await?;
+
+    let now = Utc::now();
+    let first_day = now.date_naive().checked_sub_days(chrono::Days::new(days as u64 - 1)).unwrap_or(chrono::NaiveDate::MIN);
+    let since = first_day.and_time(chrono::NaiveTime::MIN).and_utc();
+    let log = EventLog::load(&state.file(EVENTS_FILE))?;
+    let events = events::timeline(&log, events::observed_events(&monitor.session_blocks(), monitor.limit_events(), now), since);
+
+    if output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&EventsOutput { events, identity: claude_identity() })?);
+        return Ok(());
+    }
+    if events.is_empty() {
+        println!("📝 No events in the last {days} days");
+        return Ok(());
+    }
+    println!("📝 Events since {} ({}):\n", since.format("%Y-%m-%d"), events.len());
+    println!("   {:<21} {:<14} Detail", "Time", "Event");
+    for event in &events {
+        println!("   {:<21} {:<14} {}", event.timestamp.format("%Y-%m-%d %H:%M UTC").to_string(), event.kind.name(), event.detail);
+    }
+    if log.events.is_empty() {
+        println!("\nPlan switches and alerts are recorded while `monitor` runs.");
+    }
     Ok(())
 }
 
 async fn configure_monitor(
-    data_dir: PathBuf,
+    config_dir: PathBuf,
     plan: Option<String>,
-    interval: Option<u64>,
+    intervals: IntervalArgs,
     threshold: Option<f64>,
+    dedup: Option<String>,
+    session: SessionArgs,
+    goals: GoalArgs,
 ) -> Result<()> {
-    let config_path = data_dir.join("config.json");
-    let mut config = if config_path.exists() {
-        let content = std::fs::read_to_string(&config_path)?;
-        serde_json::from_str(&content)?
-    } else {
-        UserConfig::default()
-    };
+    let config_manager = ConfigManager::new(&config_dir);
+    let mut config = config_manager.load_or_create()?;
     
     if let Some(plan_str) = plan {
         config.default_plan = parse_plan_type(&plan_str)?;
         println!("✅ Set default plan to: {:?}", config.default_plan);
     }
     
-    if let Some(interval_val) = interval {
+    if let Some(interval_val) = intervals.interval {
         config.update_interval_seconds = interval_val;
         println!("✅ Set update interval to: {interval_val} seconds");
     }
     
+    if let Some(ms) = intervals.redraw_ms {
+        if !REDRAW_MS_RANGE.contains(&ms) {
+            return Err(anyhow::anyhow!(
+                "--redraw-ms must be between {} and {}",
+                REDRAW_MS_RANGE.start(),
+                REDRAW_MS_RANGE.end()
+            ));
+        }
+        config.redraw_interval_ms = ms;
+        println!("✅ Set redraw interval to: {ms} ms");
+    }
+    
+    match intervals.alert_interval {
+        Some(0) => {
+            config.alert_interval_seconds = None;
+            println!("✅ Alerts are evaluated after every scan");
+        }
+        Some(seconds) => {
+            config.alert_interval_seconds = Some(seconds);
+            println!("✅ Set alert interval to: {seconds} seconds");
+        }
+        None => {}
+    }
+    
     if let Some(threshold_val) = threshold {
         if (0.0..=1.0).contains(&threshold_val) {
             config.warning_threshold = threshold_val;
@@ -438,40 +1387,1666 @@ async fn configure_monitor(
         }
     }
     
-    // Save configuration
-    let content = serde_json::to_string_pretty(&config)?;
-    std::fs::write(&config_path, content)?;
+    if let Some(minutes) = session.idle_after {
+        if minutes == 0 {
+            return Err(anyhow::anyhow!("--idle-after must be at least 1 minute"));
+        }
+        config.idle_after_minutes = minutes;
+        println!("✅ Set idle period to: {minutes} minutes");
+    }
     
-    Ok(())
-}
-
-fn parse_plan_type(plan: &str) -> Result<PlanType> {
-    match plan.to_lowercase().as_str() {
-        "pro" => Ok(PlanType::Pro),
-        "max5" => Ok(PlanType::Max5),
-        "max20" => Ok(PlanType::Max20),
-        _ => {
-            if let Ok(limit) = plan.parse::<u32>() {
-                Ok(PlanType::Custom(limit))
-            } else {
-                Err(anyhow::anyhow!("Invalid plan type: {}. Use 'pro', 'max5', 'max20', or a custom limit number", plan))
-            }
+    if session.window_hours.is_some() || session.window_strategy.is_some() || session.window_gap.is_some() {
+        let window = &mut config.session_window;
+        if let Some(hours) = session.window_hours {
+            window.hours = hours;
+        }
+        if let Some(strategy) = session.window_strategy {
+            window.strategy = strategy.parse()?;
         }
+        if let Some(minutes) = session.window_gap {
+            window.gap_minutes = minutes;
+        }
+        window.validate().map_err(|e| anyhow::anyhow!("Invalid session window: {e}"))?;
+        println!("✅ Set session windows to: {}", window.describe());
     }
-}
-
-fn load_or_create_config(data_dir: &PathBuf) -> Result<UserConfig> {
-    let config_path = data_dir.join("config.json");
     
-    if config_path.exists() {
-        let content = std::fs::read_to_string(&config_path)?;
-        Ok(serde_json::from_str(&content)?)
-    } else {
-        let config = UserConfig::default();
-        let content = serde_json::to_string_pretty(&config)?;
-        std::fs::write(&config_path, content)?;
+    if let Some(dedup) = dedup {
+        config.dedup_strategy = dedup.parse()?;
+        println!("✅ Set dedup strategy to: {}", config.dedup_strategy.name());
+    }
+    
+    let periods = [GoalPeriod::Day, GoalPeriod::Week, GoalPeriod::Month];
+    for (period, goal) in periods.into_iter().zip([goals.daily_goal, goals.weekly_goal, goals.monthly_goal]) {
+        if let Some(goal) = goal {
+            config.goals.set(period, &goal)?;
+            println!("✅ Set {} goal to: {}", period.name(), goal.trim());
+        }
+    }
+    
+    if let Some(day) = goals.billing_day {
+        config.goals.billing_day = Some(day);
+        config.goals.validate()?;
+        println!("✅ Set billing day to: {day}");
+    }
+    
+    // Save configuration
+    config_manager.save_config(&config)?;
+    
+    Ok(())
+}
+
+fn manage_profiles(
+    profile_manager: &ProfileManager,
+    action: &ProfileAction,
+    active_profile: Option<&str>,
+) -> Result<()> {
+    match action {
+        ProfileAction::List => {
+            let active = active_profile.unwrap_or(profile_manager::DEFAULT_PROFILE);
+            println!("👤 Profiles:");
+            for name in profile_manager.list()? {
+                let marker = if name == active { "*" } else { " " };
+                println!("  {marker} {name}");
+            }
+        }
+        ProfileAction::Create { name } => {
+            let dir = profile_manager.create(name)?;
+            println!("✅ Created profile '{name}' at {}", dir.display());
+            println!("   Use it with: claude-token-monitor --profile {name}");
+        }
+        ProfileAction::Delete { name } => {
+            profile_manager.delete(name, active_profile)?;
+            println!("✅ Deleted profile '{name}'");
+        }
+    }
+    Ok(())
+}
+
+/// Print one statusline combining Claude Code's session payload with observed usage, or the
+/// usage alone for a launcher
+async fn print_statusline(config: &UserConfig, format: StatuslineFormat) -> Result<()> {
+    use std::io::Read;
+    
+    if format != StatuslineFormat::Claude {
+        // Launchers don't send a payload, and may leave stdin open
+        let metrics = load_current_metrics(config).await?;
+        let item = LauncherItem::new(metrics.as_ref(), config, Utc::now());
+        let output = if format == StatuslineFormat::Raycast { item.raycast() } else { item.alfred() };
+        println!("{output}");
+        return Ok(());
+    }
+
+    // Don't block waiting for input when run by hand
+    let mut payload = String::new();
+    if !atty::is(atty::Stream::Stdin) {
+        std::io::stdin().read_to_string(&mut payload)?;
+    }
+    let input = StatuslineInput::parse(&payload);
+    
+    let metrics = load_current_metrics(config).await?;
+    println!("{}", statusline::render_statusline(&input, metrics.as_ref(), config));
+    Ok(())
+}
+
+/// Scan usage files without any progress output and compute the current metrics
+async fn load_current_metrics(config: &UserConfig) -> Result<Option<UsageMetrics>> {
+    match FileBasedTokenMonitor::from_config(config) {
+        Ok(mut monitor) => {
+            monitor.scan_usage_files().await?;
+            Ok(monitor.calculate_metrics())
+        }
+        Err(e) => {
+            debug!("No usage data available: {e}");
+            Ok(None)
+        }
+    }
+}
+
+/// Compare current usage with `max_usage`, exiting with status 2 when over unless `warn` is set
+async fn check_usage(config: &UserConfig, max_usage: f64, warn: bool, quiet: bool) -> Result<()> {
+    if !(0.0..=1.0).contains(&max_usage) {
+        return Err(anyhow::anyhow!("--max-usage must be between 0.0 and 1.0"));
+    }
+    
+    let Some(metrics) = load_current_metrics(config).await? else {
+        if !quiet {
+            println!("No Claude usage data found");
+        }
+        return Ok(());
+    };
+    
+    let session = &metrics.current_session;
+    let usage = session.tokens_used as f64 / session.tokens_limit.max(1) as f64;
+    if usage < max_usage {
+        if !quiet {
+            println!("✅ Token usage {:.1}% is below {:.0}%", usage * 100.0, max_usage * 100.0);
+        }
+        return Ok(());
+    }
+    
+    let message = format!(
+        "Claude token usage is at {:.1}% of this window's {} token limit (threshold {:.0}%). \
+         The window resets at {}. Wrap up or pause before starting more work.",
+        usage * 100.0,
+        session.tokens_limit,
+        max_usage * 100.0,
+        session.reset_time.format("%H:%M UTC")
+    );
+    if warn {
+        println!("⚠️ {message}");
+        Ok(())
+    } else {
+        eprintln!("{message}");
+        std::process::exit(2);
+    }
+}
+
+/// Command line that re-invokes this binary, with the active directory layout and profile
+fn monitor_command(cli: &Cli) -> Result<String> {
+    let exe = std::env::current_exe()?;
+    let mut command = format!("\"{}\"", exe.display());
+    if let Some(data_dir) = &cli.data_dir {
+        let data_dir = std::path::absolute(data_dir)?;
+        command.push_str(&format!(" --data-dir \"{}\"", data_dir.display()));
+    } else if cli.legacy_paths {
+        command.push_str(" --legacy-paths");
+    }
+    if let Some(profile) = &cli.profile {
+        command.push_str(&format!(" --profile {profile}"));
+    }
+    Ok(command)
+}
+
+/// Config and state base directories: `--data-dir` or `--legacy-paths` keep everything in one
+/// directory, otherwise XDG config and state directories where the platform has them, copying
+/// an existing single-directory layout over on first use
+fn resolve_base_dirs(cli: &Cli) -> Result<BaseDirs> {
+    if let Some(dir) = &cli.data_dir {
+        return Ok(BaseDirs::single(dir));
+    }
+    let legacy = data_paths::legacy_data_dir();
+    if cli.legacy_paths {
+        return Ok(BaseDirs::single(&legacy));
+    }
+    let Some(xdg) = data_paths::xdg_dirs() else {
+        return Ok(BaseDirs::single(&legacy));
+    };
+    if cli.read_only {
+        // Read an unmigrated install where it is instead of copying it
+        let migrated = xdg.config.exists() || xdg.state.exists();
+        return Ok(if !migrated && legacy.is_dir() { BaseDirs::single(&legacy) } else { xdg });
+    }
+    if let Some(copied) = data_paths::migrate_legacy_layout(&legacy, &xdg)? {
+        log::info!(
+            "Copied {copied} files from {} to {} (config) and {} (state); the old directory is kept for --legacy-paths",
+            legacy.display(),
+            xdg.config.display(),
+            xdg.state.display()
+        );
+    }
+    Ok(xdg)
+}
+
+/// Name of a command that changes stored data or the installed executable, for rejecting it under
+/// `--read-only`
+fn writing_command(command: &Commands) -> Option<&'static str> {
+    match command {
+        Commands::Config { .. } => Some("config"),
+        Commands::Import { .. } => Some("import"),
+        Commands::Restore { .. } => Some("restore"),
+        Commands::Profile { action: ProfileAction::Create { .. } | ProfileAction::Delete { .. } } => Some("profile"),
+        Commands::InstallHooks { .. } => Some("install-hooks"),
+        Commands::ClaudeStatusline { install: true, .. } => Some("claude-statusline --install"),
+        Commands::SelfUpdate { check: false } => Some("self-update"),
+        _ => None,
+    }
+}
+
+/// The latest release, when it is newer than this build
+async fn newer_release() -> Result<Option<Release>> {
+    let release = tokio::task::spawn_blocking(update_checker::fetch_latest_release).await??;
+    Ok(release.is_newer_than(env!("CARGO_PKG_VERSION"))?.then_some(release))
+}
+
+/// Report the latest release and, unless `check_only`, replace this executable with its build for
+/// this platform
+async fn self_update(check_only: bool) -> Result<()> {
+    let current = env!("CARGO_PKG_VERSION");
+    let Some(release) = newer_release().await? else {
+        println!("✅ claude-token-monitor {current} is the latest release");
+        return Ok(());
+    };
+    let latest = release.version()?;
+    println!("💡 claude-token-monitor {latest} is available (you have {current})");
+    if !release.html_url.is_empty() {
+        println!("   Release notes: {}", release.html_url);
+    }
+    if check_only {
+        println!("   Run `claude-token-monitor self-update` to install it");
+        return Ok(());
+    }
+    
+    let (os, arch) = (std::env::consts::OS, std::env::consts::ARCH);
+    let asset = release.asset_for(os, arch).cloned().ok_or_else(|| {
+        anyhow::anyhow!("Release {latest} has no build for {os}/{arch}; build it from source with `cargo install --path .` instead")
+    })?;
+    let exe = std::env::current_exe()?.canonicalize()?;
+    println!("⬇️  Downloading {}...", asset.name);
+    let target = exe.clone();
+    tokio::task::spawn_blocking(move || update_checker::install(&release, &asset, &target)).await??;
+    println!("✅ Installed claude-token-monitor {latest} at {}", exe.display());
+    Ok(())
+}
+
+/// Write hook scripts and register them in Claude Code's settings
+fn install_claude_hooks(monitor_command: &str, block_at: f64, warn_at: f64) -> Result<()> {
+    for (flag, value) in [("--block-at", block_at), ("--warn-at", warn_at)] {
+        if !(0.0..=1.0).contains(&value) {
+            return Err(anyhow::anyhow!("{flag} must be between 0.0 and 1.0"));
+        }
+    }
+    
+    let mut settings = ClaudeSettings::load_default()?;
+    let hooks_dir = claude_settings::claude_config_dir()?.join("hooks");
+    let hooks = [HookSpec::warn_on_prompt(warn_at), HookSpec::block_tools(block_at)];
+    let scripts = claude_hooks::install_hooks(&mut settings, &hooks_dir, monitor_command, &hooks)?;
+    settings.save()?;
+    
+    println!("✅ Installed Claude Code hooks in {}", settings.path().display());
+    for (hook, script) in hooks.iter().zip(&scripts) {
+        println!("   {} → {}", hook.event, script.display());
+    }
+    println!("   Prompts get a warning above {:.0}% usage; tool calls are blocked above {:.0}%", warn_at * 100.0, block_at * 100.0);
+    println!("   Restart Claude Code to pick them up");
+    Ok(())
+}
+
+/// Point Claude Code's statusLine setting at this binary
+fn install_statusline(monitor_command: &str, force: bool) -> Result<()> {
+    let command = format!("{monitor_command} claude-statusline");
+    
+    let mut settings = ClaudeSettings::load_default()?;
+    if let Some(existing) = settings.get("statusLine") {
+        let existing_command = existing.get("command").and_then(|c| c.as_str()).unwrap_or_default();
+        if existing_command == command {
+            println!("✅ Statusline already installed in {}", settings.path().display());
+            return Ok(());
+        }
+        if !force {
+            return Err(anyhow::anyhow!(
+                "{} already has a statusLine ({}). Re-run with --force to replace it",
+                settings.path().display(),
+                existing
+            ));
+        }
+    }
+    
+    settings.set("statusLine", serde_json::json!({
+        "type": "command",
+        "command": command,
+        "padding": 0
+    }));
+    settings.save()?;
+    println!("✅ Installed statusline in {}", settings.path().display());
+    println!("   Restart Claude Code to pick it up");
+    Ok(())
+}
+
+/// Write a .tar.gz with doctor's output, redacted samples of unparsable log lines, build info,
+/// the config with secrets stripped and a snapshot, for attaching to an issue
+async fn write_debug_bundle(cli: &Cli, config_dir: &std::path::Path, data_dir: &std::path::Path, out: Option<&std::path::Path>) -> Result<()> {
+    let now = Utc::now();
+    let out = out.map_or_else(|| PathBuf::from(format!("claude-token-monitor-debug-{}.tar.gz", now.format("%Y%m%d-%H%M%S"))), PathBuf::from);
+    let home = dirs::home_dir();
+    let user = std::env::var("USER").or_else(|_| std::env::var("USERNAME")).ok();
+    let redact = |text: &str| debug_bundle::redact_text(text, home.as_deref(), user.as_deref());
+    
+    // Doctor runs as a read-only child so its report is exactly what `doctor` prints
+    let mut doctor = tokio::process::Command::new(std::env::current_exe()?);
+    if let Some(dir) = &cli.data_dir {
+        doctor.arg("--data-dir").arg(std::path::absolute(dir)?);
+    } else if cli.legacy_paths {
+        doctor.arg("--legacy-paths");
+    }
+    if let Some(profile) = &cli.profile {
+        doctor.args(["--profile", profile]);
+    }
+    let doctor = doctor.args(["--read-only", "doctor"]).stdin(std::process::Stdio::null()).output().await?;
+    let doctor_output = format!("{}{}", String::from_utf8_lossy(&doctor.stdout), String::from_utf8_lossy(&doctor.stderr));
+    
+    let loaded = ConfigManager::new(config_dir).load_without_writing().and_then(|config| config.validate().map(|()| config));
+    let config_json = match &loaded {
+        Ok(config) => debug_bundle::redact_config(config)?,
+        Err(e) => serde_json::json!({ "error": redact(&format!("{e:#}")) }),
+    };
+    let config = loaded.unwrap_or_default();
+    
+    let mut files = vec![
+        ("version.txt", debug_bundle::build_info().into_bytes()),
+        ("doctor.txt", redact(&doctor_output).into_bytes()),
+        ("config.json", serde_json::to_vec_pretty(&config_json)?),
+    ];
+    match FileBasedTokenMonitor::from_config(&config) {
+        Ok(mut monitor) => {
+            monitor.set_imports_dir(data_dir.join(importer::IMPORTS_DIR));
+            monitor.scan_usage_files().await?;
+            let samples = debug_bundle::parse_error_samples(monitor.schema_reports(), monitor.failed_files());
+            files.push(("parse-errors.json", serde_json::to_vec_pretty(&samples)?));
+            let environment = SnapshotEnvironment::collect(&monitor, cli.profile.as_deref(), true, config_dir, data_dir);
+            // The account identity is left out along with everything else naming the user
+            let key = snapshot::digest_key(data_dir, true)?;
+            let snapshot = Snapshot::take(&monitor, &config, &key, environment, None, now)?;
+            files.push(("snapshot.json", redact(&serde_json::to_string_pretty(&snapshot)?).into_bytes()));
+        }
+        Err(e) => files.push(("parse-errors.json", serde_json::to_vec_pretty(&serde_json::json!({ "error": redact(&e.to_string()) }))?)),
+    }
+    
+    debug_bundle::write_bundle(&out, &files, now)?;
+    println!("🧰 Wrote {}", out.display());
+    for (name, _) in &files {
+        println!("   {}/{name}", debug_bundle::BUNDLE_DIR);
+    }
+    println!("💡 Home and user names, addresses, commands, URLs and log contents are redacted or hashed;");
+    println!("   look it over (tar -xzf) before attaching it to an issue");
+    Ok(())
+}
+
+/// Report on config health and how well the JSONL logs were understood
+async fn run_doctor(config_dir: &std::path::Path, data_dir: &std::path::Path, read_only: bool, fix_permissions: bool) -> Result<()> {
+    println!("🩺 Claude Token Monitor doctor\n");
+    
+    let config_manager = ConfigManager::new(config_dir);
+    println!("⚙️  Config: {}", config_manager.get_config_path()?.display());
+    println!("🗂️  State: {}", data_dir.display());
+    let loaded = if read_only { config_manager.load_without_writing() } else { config_manager.load_or_create() };
+    match loaded.as_ref().map_err(|e| anyhow::anyhow!("{e:#}")).and_then(|config| {
+        config.validate()?;
+        ScriptEngine::new(&config.scripts)?;
+        notify_url::validate_channels(&config.alerts.channels)?;
         Ok(config)
+    }) {
+        Ok(config) => println!(
+            "   ✅ Valid (version {}, {} script alerts, {} script metrics)",
+            config.version,
+            config.scripts.alerts.len(),
+            config.scripts.metrics.len()
+        ),
+        Err(e) => println!("   ❌ {e:#}"),
+    }
+    let encryption = loaded.as_ref().map(|config| config.encryption.clone()).unwrap_or_default();
+    if encryption.is_enabled() {
+        let state = StateCipher::from_config(&encryption).map(|cipher| StateDir::new(data_dir.to_path_buf(), cipher));
+        match state.and_then(|state| state.file(SESSIONS_FILE).read()) {
+            Ok(_) => println!("\n🔒 Encryption: on, the configured key reads the stored sessions"),
+            Err(e) => println!("\n🔒 Encryption: ❌ {e:#}"),
+        }
+    }
+    let loose = permissions::find_loose_permissions([config_dir, data_dir]);
+    if loose.is_empty() {
+        println!("\n🔐 Permissions: ✅ config and state are private to you");
+    } else if fix_permissions && !read_only {
+        permissions::fix_permissions(&loose)?;
+        println!("\n🔐 Permissions: ✅ took group and other access away from {} files and directories", loose.len());
+    } else {
+        println!("\n🔐 Permissions: ⚠️  {} files and directories other users can access", loose.len());
+        for item in loose.iter().take(10) {
+            println!("   {:04o}  {}", item.mode, item.path.display());
+        }
+        if loose.len() > 10 {
+            println!("   … and {} more", loose.len() - 10);
+        }
+        if !read_only {
+            println!("💡 Run 'claude-token-monitor doctor --fix-permissions' to restrict them");
+        }
+    }
+    
+    // Without a valid config the usage data is read with the defaults
+    let config = loaded.unwrap_or_default();
+    let (unknown_model_pricing, max_request_tokens) = (config.unknown_model_pricing, config.max_request_tokens);
+    
+    match claude_settings::claude_config_dir() {
+        Ok(claude_dir) => {
+            let hints = claude_settings::SubscriptionHints::read(&claude_dir);
+            println!("\n🔑 Claude Code config: {}", claude_dir.display());
+            println!(
+                "   Subscription: {} (tier: {}), plan: {}",
+                hints.subscription_type.as_deref().unwrap_or("unknown"),
+                hints.rate_limit_tier.as_deref().unwrap_or("unknown"),
+                hints.plan_type().map_or("not detected".to_string(), |plan| format!("{plan:?}"))
+            );
+            println!("   Default model: {}", hints.model.as_deref().unwrap_or("not set"));
+        }
+        Err(e) => println!("\n🔑 Claude Code config: ❌ {e}"),
+    }
+    
+    let mut monitor = match FileBasedTokenMonitor::from_config(&config) {
+        Ok(monitor) => monitor,
+        Err(e) => {
+            println!("\n📁 Usage data: ❌ {e}");
+            return Ok(());
+        }
+    };
+    monitor.set_imports_dir(data_dir.join(importer::IMPORTS_DIR));
+    monitor.scan_usage_files().await?;
+    
+    println!("\n📁 Usage data paths:");
+    for path in monitor.get_monitored_paths() {
+        let billing = match monitor.billing_for(path) {
+            Billing::ApiKey => ", API key",
+            Billing::Subscription => "",
+        };
+        match monitor.account_for(path) {
+            Some(account) => println!("   {} (account: {account}{billing})", path.display()),
+            None if billing.is_empty() => println!("   {}", path.display()),
+            None => println!("   {} (API key)", path.display()),
+        }
+    }
+    if !monitor.api_key_entries().is_empty() {
+        println!("   {} entries billed to an API key (reports only, not session windows)", monitor.api_key_entries().len());
+    }
+    if !monitor.imported_entries().is_empty() {
+        println!("   + {} imported entries (reports only)", monitor.imported_entries().len());
+    }
+    
+    if !config.users.shared.is_empty() || !config.users.only.is_empty() {
+        let directory = UserDirectory::load();
+        println!("\n👥 Users:");
+        for name in &config.users.shared {
+            match directory.shared_projects_dir(name) {
+                Ok(projects) => println!("   {name}: {}", projects.display()),
+                Err(e) => println!("   {name}: ❌ {e}"),
+            }
+        }
+        if !config.users.only.is_empty() {
+            println!("   Counting only logs owned by {}", config.users.only.iter().cloned().collect::<Vec<_>>().join(", "));
+        }
+    }
+    
+    if let Some(kind) = ContainerKind::detect() {
+        println!("\n📦 Running in a {}", kind.name());
+        for (from, to) in monitor.path_mappings() {
+            let mounted = container::map_path(monitor.path_mappings(), from).is_some_and(|local| local.exists());
+            println!("   {} → {} {}", from.display(), to.display(), if mounted { "✅" } else { "❌ not mounted" });
+        }
+        if monitor.entry_count() == 0 {
+            let home = dirs::home_dir().map_or("~".to_string(), |home| home.display().to_string());
+            println!("   ⚠️  No usage found. Claude Code logs on the machine it runs on; when that is the host,");
+            println!("      {home}/.claude in here is a separate, empty directory.");
+            println!("   Also checked: {}", container::COMMON_CLAUDE_HOMES.join(", "));
+            println!("💡 Bind-mount the host's ~/.claude, e.g. in devcontainer.json:");
+            println!("   \"mounts\": [\"source=${{localEnv:HOME}}/.claude,target={home}/.claude,type=bind\"]");
+            println!("   and map it when it lands elsewhere: path_mappings in the config, or");
+            println!("   {}=\"<host ~/.claude>=<mount point>\"", container::PATH_MAPPINGS_ENV);
+        }
+    }
+    
+    let reports = monitor.schema_reports();
+    let mut per_schema: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+    for report in reports {
+        for (schema, lines) in &report.usage_lines {
+            let entry = per_schema.entry(schema).or_default();
+            entry.0 += 1;
+            entry.1 += lines;
+        }
+    }
+    let total = |f: fn(&FileSchemaReport) -> usize| -> usize {
+        reports.iter().map(f).sum()
+    };
+    
+    println!("\n📄 {} files, {} usage entries", reports.len(), monitor.entry_count());
+    for schema in schema::SCHEMAS {
+        if let Some((files, lines)) = per_schema.get(schema.id) {
+            println!("   {:<16} {lines:>8} lines in {files} files  ({})", schema.id, schema.description);
+        }
+    }
+    println!("   {:<16} {:>8} lines", "non-usage", total(|r| r.non_usage_lines));
+    
+    let versions: BTreeSet<&str> = reports
+        .iter()
+        .flat_map(|report| report.claude_versions.iter().map(String::as_str))
+        .collect();
+    if !versions.is_empty() {
+        println!("   Claude Code versions seen: {}", versions.into_iter().collect::<Vec<_>>().join(", "));
+    }
+    
+    let duplicates = monitor.duplicate_report();
+    println!("\n🔁 Deduplication ({} strategy):", duplicates.strategy.name());
+    println!(
+        "   {} entries read, {} duplicates removed ({} within files, {} across files), {} tokens",
+        duplicates.raw_entries,
+        duplicates.removed_entries(),
+        duplicates.same_file_copies,
+        duplicates.cross_file.iter().map(|group| group.extra_copies).sum::<usize>(),
+        duplicates.inflated_tokens()
+    );
+    if duplicates.entries_without_ids > 0 {
+        println!("   {} entries have no message or request ID and are matched by timestamp and usage", duplicates.entries_without_ids);
+    }
+    
+    let unknown_models = pricing::unknown_models(monitor.report_entries(), monitor.prices());
+    if unknown_models.is_empty() {
+        println!("\n💲 Pricing: ✅ every model seen has known prices");
+    } else {
+        println!(
+            "\n💲 Pricing: ⚠️  {} unrecognized models, priced at unknown_model_pricing (${} in / ${} out per million tokens):",
+            unknown_models.len(),
+            unknown_model_pricing.input,
+            unknown_model_pricing.output
+        );
+        for unknown in &unknown_models {
+            println!("   {:<36} {:>8} requests {:>12} tokens {:>10}", unknown.model, unknown.requests, unknown.tokens, format!("${:.2}", unknown.estimated_cost));
+        }
+        println!("💡 Set unknown_model_pricing in the config to their list prices if they differ");
+    }
+    
+    let future = total(|r| r.future_entries);
+    let oversized = total(|r| r.oversized_entries);
+    let clamped = total(|r| r.clamped_timings);
+    if future + oversized + clamped > 0 {
+        println!("\n🚧 Implausible entries:");
+        println!("   Ahead of the clock: {future} (left out until their time comes)");
+        println!("   Over {max_request_tokens} tokens: {oversized} (left out; max_request_tokens in the config)");
+        println!("   Impossible timings: {clamped} (counted without their timings)");
+        for report in reports.iter().filter(|r| r.future_entries + r.oversized_entries + r.clamped_timings > 0) {
+            println!(
+                "   {}: {} ahead, {} over the ceiling, {} timings",
+                report.path.display(),
+                report.future_entries,
+                report.oversized_entries,
+                report.clamped_timings
+            );
+        }
+    }
+
+    let unknown = total(|r| r.unknown_lines);
+    let malformed = total(|r| r.malformed_usage_lines);
+    let invalid = total(|r| r.invalid_lines);
+    if unknown + malformed + invalid == 0 && monitor.failed_files().is_empty() {
+        println!("\n✅ Every line matched a known schema");
+        return Ok(());
+    }
+    
+    println!("\n⚠️  Lines that were not counted:");
+    println!("   Unknown schema:     {unknown}");
+    println!("   Malformed usage:    {malformed}");
+    println!("   Invalid JSON:       {invalid}");
+    for report in reports.iter().filter(|r| r.unknown_lines + r.malformed_usage_lines + r.invalid_lines > 0) {
+        println!(
+            "\n   {} ({}): {} unknown, {} malformed, {} invalid",
+            report.path.display(),
+            report.detected_schema().unwrap_or("no usage schema"),
+            report.unknown_lines,
+            report.malformed_usage_lines,
+            report.invalid_lines
+        );
+        for (shape, lines) in &report.unknown_shapes {
+            println!("      {lines:>6} × {shape}");
+        }
+    }
+    for (path, error) in monitor.failed_files() {
+        println!("\n   ❌ {}: {error}", path.display());
+    }
+    println!("\n💡 Unknown lines usually mean Claude Code changed its log format; please report the shapes above");
+    Ok(())
+}
+
+/// Summarize deduplication, listing cross-file duplicates when `detailed` is set
+async fn show_duplicates(config: &UserConfig, detailed: bool) -> Result<()> {
+    let mut monitor = FileBasedTokenMonitor::from_config(config)?;
+    monitor.scan_usage_files().await?;
+    let report = monitor.duplicate_report();
+    
+    println!("🔁 Deduplication ({} strategy)\n", report.strategy.name());
+    println!("   Entries read:      {:>12}", report.raw_entries);
+    println!("   Unique entries:    {:>12}", report.unique_entries);
+    println!("   Tokens before:     {:>12}", report.raw_tokens);
+    println!("   Tokens counted:    {:>12}", report.unique_tokens);
+    println!("   Removed:           {:>12} copies, {} tokens", report.removed_entries(), report.inflated_tokens());
+    println!("     within one file: {:>12} copies", report.same_file_copies);
+    println!(
+        "     across files:    {:>12} copies, {} tokens",
+        report.cross_file.iter().map(|group| group.extra_copies).sum::<usize>(),
+        report.cross_file_tokens()
+    );
+    
+    if report.cross_file.is_empty() {
+        println!("\n✅ No entries are duplicated across files");
+        return Ok(());
+    }
+    if !detailed {
+        println!("\n💡 {} groups of files share entries; run with --report to list them", report.cross_file.len());
+        return Ok(());
+    }
+    
+    println!("\n📄 Files sharing entries (largest inflation first):");
+    for group in &report.cross_file {
+        println!(
+            "\n   {} entries, {} extra copies, {} tokens ({:.1}% of the raw total)",
+            group.entries,
+            group.extra_copies,
+            group.inflated_tokens,
+            group.inflated_tokens as f64 / report.raw_tokens.max(1) as f64 * 100.0
+        );
+        for file in &group.files {
+            println!("      {}", file.display());
+        }
+    }
+    println!("\nThe monitor counts each entry once, so these copies are excluded from every total.");
+    Ok(())
+}
+
+/// Import an external usage export into the profile's import store
+fn import_usage(data_dir: &std::path::Path, format: ImportFormat, file: &std::path::Path) -> Result<()> {
+    let summary = importer::import_file(format, file, &data_dir.join(importer::IMPORTS_DIR))?;
+    
+    println!("✅ Imported {} rows from {}", summary.rows, file.display());
+    println!("   {} new, {} replaced from earlier imports, {} skipped", summary.added, summary.updated, summary.skipped_rows);
+    if let (Some(first), Some(last)) = (summary.first_day, summary.last_day) {
+        println!("   {} tokens from {} to {}", summary.tokens, first.format("%Y-%m-%d"), last.format("%Y-%m-%d"));
+    }
+    println!("   Stored in {}", summary.store.display());
+    println!("   Imported usage appears in reports but not in Claude Code session limits");
+    Ok(())
+}
+
+/// Print a token histogram for the requested grouping
+async fn show_usage_histogram(config: &UserConfig, data_dir: &std::path::Path, view: &UsageView) -> Result<()> {
+    let (bucketing, days) = match view {
+        UsageView::Hour { days } => (Bucketing::Hour, *days),
+        UsageView::Day { days } => (Bucketing::Day, *days),
+        UsageView::Weekday { days } => (Bucketing::Weekday, *days),
+        UsageView::Account { .. } => unreachable!("by-account is shown by show_account_usage"),
+        UsageView::Conversation { .. } => unreachable!("by-conversation is shown by show_conversation_costs"),
+    };
+    
+    let mut monitor = FileBasedTokenMonitor::from_config(config)?;
+    monitor.set_imports_dir(data_dir.join(importer::IMPORTS_DIR));
+    monitor.scan_usage_files().await?;
+    
+    // Imported rows are daily totals, so they have no meaningful hour
+    let histogram = if bucketing == Bucketing::Hour {
+        usage_stats::histogram(monitor.usage_entries().iter(), bucketing, days, Utc::now())
+    } else {
+        usage_stats::histogram(monitor.report_entries(), bucketing, days, Utc::now())
+    };
+    println!("{}", histogram::render_histogram(&histogram, 40));
+    Ok(())
+}
+
+/// Render the Claude Code usage of the last `range` to an SVG or PNG chart
+async fn export_usage_chart(config: &UserConfig, data_dir: &std::path::Path, out: &std::path::Path, range: std::time::Duration) -> Result<()> {
+    let range = chrono::Duration::from_std(range).context("--range is too long")?;
+    if range < chrono::Duration::hours(1) {
+        return Err(anyhow::anyhow!("--range must be at least 1h"));
+    }
+    
+    let mut monitor = FileBasedTokenMonitor::from_config(config)?;
+    monitor.set_imports_dir(data_dir.join(importer::IMPORTS_DIR));
+    monitor.scan_usage_files().await?;
+    
+    // About a few hundred points whatever the range: 10 minutes up to two days, then hours, then days
+    let bucket = if range <= chrono::Duration::days(2) {
+        chrono::Duration::minutes(10)
+    } else if range <= chrono::Duration::days(31) {
+        chrono::Duration::hours(1)
+    } else {
+        chrono::Duration::days(1)
+    };
+    let now = Utc::now();
+    let start = now.checked_sub_signed(range).context("--range is too long")?;
+    let buckets = usage_stats::token_buckets(monitor.usage_entries().iter(), start, now, bucket);
+    chart_export::export_usage_charts(out, &buckets, bucket)?;
+    println!(
+        "📈 Charted {} tokens from {} to {} UTC in {}",
+        buckets.iter().map(|(_, tokens)| tokens).sum::<u64>(),
+        start.format("%Y-%m-%d %H:%M"),
+        now.format("%Y-%m-%d %H:%M"),
+        out.display()
+    );
+    Ok(())
+}
+
+/// Print the most expensive conversations, with the requests billed at long-context rates
+async fn show_conversation_costs(config: &UserConfig, days: u32, limit: usize) -> Result<()> {
+    let mut monitor = FileBasedTokenMonitor::from_config(config)?;
+    monitor.scan_usage_files().await?;
+    
+    let since = (Utc::now().date_naive() - chrono::Duration::days(days as i64 - 1)).and_hms_opt(0, 0, 0).unwrap().and_utc();
+    let entries = monitor.usage_entries().iter().chain(monitor.api_key_entries());
+    let conversations = usage_stats::expensive_conversations(entries, since, monitor.prices());
+    
+    println!("💸 Most expensive conversations (UTC, last {days} day{})\n", if days == 1 { "" } else { "s" });
+    if conversations.is_empty() {
+        println!("📝 No conversations in this period");
+        return Ok(());
+    }
+    let shown = &conversations[..conversations.len().min(limit)];
+    let label = |conversation: &usage_stats::ConversationCost| {
+        conversation.project.as_deref().map_or_else(|| "unknown".to_string(), |project| monitor.project_label(project))
+    };
+    let width = shown.iter().map(|conversation| label(conversation).chars().count()).max().unwrap_or(0).max("Project".len());
+    println!(
+        "   {:<12}  {:<width$}  {:>8}  {:>12}  {:>9}  {:>11}  Last active",
+        "Conversation", "Project", "Requests", "Tokens", "Cost", "Long ctx"
+    );
+    for conversation in shown {
+        let long_context = match conversation.long_context_requests {
+            0 => "-".to_string(),
+            requests => format!("⚠️ {requests}"),
+        };
+        println!(
+            "   {:<12}  {:<width$}  {:>8}  {:>12}  {:>9}  {long_context:>11}  {}",
+            conversation.conversation.chars().take(8).collect::<String>(),
+            label(conversation),
+            conversation.requests,
+            conversation.tokens,
+            format!("${:.2}", conversation.cost),
+            conversation.last_activity.format("%m-%d %H:%M")
+        );
+    }
+    if conversations.len() > shown.len() {
+        println!("\n   ... and {} more", conversations.len() - shown.len());
+    }
+    
+    let (requests, cost) = conversations
+        .iter()
+        .fold((0, 0.0), |(requests, cost), conversation| (requests + conversation.long_context_requests, cost + conversation.long_context_cost));
+    if requests > 0 {
+        println!(
+            "\n⚠️  {requests} request(s) sent prompts over {} tokens and were priced at long-context rates \
+             ({}x input, {}x output): ${cost:.2}",
+            pricing::LONG_CONTEXT_THRESHOLD,
+            pricing::LONG_CONTEXT_INPUT_MULTIPLIER,
+            pricing::LONG_CONTEXT_OUTPUT_MULTIPLIER
+        );
     }
+    Ok(())
+}
+
+/// Print each account's usage over the last `days` and its own session window
+async fn show_account_usage(config: &UserConfig, days: u32) -> Result<()> {
+    let mut monitor = FileBasedTokenMonitor::from_config(config)?;
+    monitor.scan_usage_files().await?;
+    
+    let now = Utc::now();
+    let since = (now.date_naive() - chrono::Duration::days(days as i64 - 1)).and_hms_opt(0, 0, 0).unwrap().and_utc();
+    let entries = monitor.usage_entries().iter().chain(monitor.api_key_entries());
+    let usage = accounts::account_breakdown(entries, monitor.session_window(), since, now, monitor.prices());
+    
+    println!("👥 Usage by account (UTC, last {days} day{})\n", if days == 1 { "" } else { "s" });
+    if monitor.account_names().is_empty() {
+        println!("   No accounts configured; add Claude Code homes under \"accounts\" in the config or set {}", accounts::ACCOUNTS_ENV);
+        println!("   (e.g. {}=personal=~/.claude,work=~/work/.claude)\n", accounts::ACCOUNTS_ENV);
+    }
+    if usage.is_empty() {
+        println!("📝 No usage in this period");
+        return Ok(());
+    }
+    let width = usage.iter().map(|account| account.account.chars().count()).max().unwrap_or(0).max("Account".len());
+    println!("   {:<width$}  {:>12}  {:>8}  {:>9}  {:>13}  {:>9}", "Account", "Tokens", "Requests", "Cost", "Window tokens", "Resets");
+    for account in &usage {
+        let (window_tokens, resets) = match account.window_reset {
+            Some(reset) => (account.window_tokens.to_string(), reset.format("%H:%M UTC").to_string()),
+            None => ("-".to_string(), "-".to_string()),
+        };
+        println!(
+            "   {:<width$}  {:>12}  {:>8}  {:>9}  {window_tokens:>13}  {resets:>9}",
+            account.account,
+            account.tokens,
+            account.requests,
+            format!("${:.2}", account.estimated_cost)
+        );
+    }
+    Ok(())
+}
+
+/// Show the busiest conversations or projects, live in a terminal or once when piped
+async fn run_top(config: &UserConfig, grouping: ActivityGrouping, window: ActivityWindow) -> Result<()> {
+    let sort_window = window.index();
+    
+    // Imported rows are daily totals, so only Claude Code's own logs are recent enough to rank
+    let mut monitor = FileBasedTokenMonitor::from_config(config)?;
+    monitor.scan_usage_files().await?;
+    
+    if !atty::is(atty::Stream::Stdout) {
+        let now = Utc::now();
+        let table = live_activity::rank_activity(monitor.usage_entries(), now, grouping, sort_window);
+        println!("{}", top::render_activity(&table, grouping, sort_window, &monitor, now));
+        return Ok(());
+    }
+    let mut view = TopView::new(grouping, sort_window, config.scan_interval())?;
+    let result = view.run(&mut monitor).await;
+    view.cleanup()?;
+    result
+}
+
+/// Answer JSON-RPC requests on stdin until it closes; stdout carries only protocol messages
+async fn run_rpc(config: &UserConfig, data_dir: &std::path::Path, state: &StateDir) -> Result<()> {
+    let mut monitor = FileBasedTokenMonitor::from_config(config)?;
+    monitor.set_imports_dir(data_dir.join(importer::IMPORTS_DIR));
+    let mut sessions = SessionTracker::open(state.file(SESSIONS_FILE))?;
+    sessions.set_session_window(config.session_window);
+
+    let interval = config.scan_interval();
+    let server = rpc::RpcServer::new(monitor, sessions, interval);
+    let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+    rpc::serve(server, stdin, tokio::io::stdout(), interval).await
+}
+
+/// Print the year-in-review summary
+async fn show_year_report(config: &UserConfig, data_dir: &std::path::Path, year: i32) -> Result<()> {
+    let mut monitor = FileBasedTokenMonitor::from_config(config)?;
+    monitor.set_imports_dir(data_dir.join(importer::IMPORTS_DIR));
+    monitor.scan_usage_files().await?;
+    
+    let review = usage_stats::year_review(
+        monitor.report_entries(),
+        &monitor.session_blocks(),
+        year,
+        monitor.prices(),
+        &ModelNames::from_config(config),
+    );
+    
+    println!("🎉 {} in review\n", review.year);
+    if review.entries == 0 {
+        println!("   No usage recorded in {}", review.year);
+        return Ok(());
+    }
+    println!("   Total tokens:      {:>14}", review.tokens);
+    println!("   Requests:          {:>14}", review.entries);
+    println!("   Active days:       {:>14}", review.active_days);
+    println!("   Estimated cost:    {:>14}", format!("${:.2}", review.estimated_cost));
+    if let Some((day, tokens)) = &review.busiest_day {
+        println!("   Busiest day:       {:>14}  {} tokens", day.format("%Y-%m-%d"), tokens);
+    }
+    if let Some((model, tokens)) = &review.top_model {
+        println!(
+            "   Most-used model:   {model}  {tokens} tokens ({:.1}%)",
+            *tokens as f64 / review.tokens.max(1) as f64 * 100.0
+        );
+    }
+    println!(
+        "   Cache savings:     {:>14}  {} tokens read from cache",
+        format!("${:.2}", review.cache_savings),
+        review.cache_read_tokens
+    );
+    if let Some(session) = &review.longest_session {
+        let span = session.last_activity - session.start_time;
+        println!(
+            "   Longest session:   {:>14}  {} tokens, started {}",
+            format!("{}h {:02}m", span.num_hours(), span.num_minutes() % 60),
+            session.tokens_used,
+            session.start_time.format("%Y-%m-%d %H:%M UTC")
+        );
+    }
+    println!("\n💡 Costs are estimates at API list prices, including imported Console usage");
+    Ok(())
+}
+
+/// Print the latest session windows, most recent first, with the IDs `sessions compare` takes
+async fn list_session_windows(config: &UserConfig, limit: usize) -> Result<()> {
+    let mut monitor = FileBasedTokenMonitor::from_config(config)?;
+    monitor.scan_usage_files().await?;
+    
+    let blocks = monitor.session_blocks();
+    if blocks.is_empty() {
+        println!("📝 No session windows found");
+        return Ok(());
+    }
+    println!("📝 Session windows (latest {} of {}):\n", limit.min(blocks.len()), blocks.len());
+    println!("   {:<18} {:<21} {:>9} {:>9} {:>12}", "ID", "Started", "Duration", "Requests", "Tokens");
+    for block in blocks.iter().rev().take(limit) {
+        let span = block.last_activity - block.start_time;
+        println!(
+            "   {:<18} {:<21} {:>9} {:>9} {:>12}",
+            session_compare::window_id(block),
+            block.start_time.format("%Y-%m-%d %H:%M UTC").to_string(),
+            format!("{}h {:02}m", span.num_hours(), span.num_minutes() % 60),
+            block.entry_count,
+            block.tokens_used
+        );
+    }
+    Ok(())
+}
+
+/// Print two session windows side by side with the change from the first to the second
+async fn compare_session_windows(config: &UserConfig, a: &str, b: &str) -> Result<()> {
+    let mut monitor = FileBasedTokenMonitor::from_config(config)?;
+    monitor.scan_usage_files().await?;
+    
+    let blocks = monitor.session_blocks();
+    let [a, b] = [a, b].map(|id| {
+        session_compare::find_window(&blocks, id)
+            .map(|block| session_compare::summarize(monitor.usage_entries(), block, monitor.prices()))
+    });
+    let (a, b) = (a?, b?);
+    
+    let duration = |summary: &session_compare::SessionSummary| {
+        let span = summary.duration();
+        format!("{}h {:02}m", span.num_hours(), span.num_minutes() % 60)
+    };
+    let change = |before: f64, after: f64, unit: &str| {
+        if before == 0.0 {
+            format!("{:+.0}{unit}", after - before)
+        } else {
+            format!("{:+.0}{unit} ({:+.0}%)", after - before, (after - before) / before * 100.0)
+        }
+    };
+    let models: BTreeSet<&str> = a.models.iter().chain(&b.models).map(|(model, _)| model.as_str()).collect();
+    let label_width = models.iter().map(|model| model.len() + 2).max().unwrap_or(0).max(16);
+    let row = |label: &str, a: String, b: String, difference: String| {
+        println!("{}", format!("   {label:<label_width$} {a:>20} {b:>20}   {difference}").trim_end());
+    };
+    
+    println!("⚖️  Session comparison\n");
+    row("", a.id.clone(), b.id.clone(), "Change".to_string());
+    row(
+        "Started",
+        a.start_time.format("%Y-%m-%d %H:%M").to_string(),
+        b.start_time.format("%Y-%m-%d %H:%M").to_string(),
+        String::new(),
+    );
+    let minutes = |summary: &session_compare::SessionSummary| summary.duration().num_minutes() as f64;
+    row("Duration", duration(&a), duration(&b), change(minutes(&a), minutes(&b), " min"));
+    let counts = [
+        ("Requests", a.requests as u64, b.requests as u64),
+        ("Input", a.input_tokens, b.input_tokens),
+        ("Output", a.output_tokens, b.output_tokens),
+        ("Cache creation", a.cache_creation_tokens, b.cache_creation_tokens),
+        ("Cache read", a.cache_read_tokens, b.cache_read_tokens),
+        ("Total tokens", a.total_tokens(), b.total_tokens()),
+    ];
+    for (label, count_a, count_b) in counts {
+        row(label, count_a.to_string(), count_b.to_string(), change(count_a as f64, count_b as f64, ""));
+    }
+    row(
+        "Cache hit rate",
+        format!("{:.1}%", a.cache_hit_rate() * 100.0),
+        format!("{:.1}%", b.cache_hit_rate() * 100.0),
+        format!("{:+.1} pts", (b.cache_hit_rate() - a.cache_hit_rate()) * 100.0),
+    );
+    row(
+        "Estimated cost",
+        format!("${:.2}", a.estimated_cost),
+        format!("${:.2}", b.estimated_cost),
+        format!("{:+.2} USD", b.estimated_cost - a.estimated_cost),
+    );
+    
+    println!("\n   Tokens by model:");
+    let model_tokens = |summary: &session_compare::SessionSummary, model: &str| {
+        let tokens = summary.models.iter().find(|(name, _)| name == model).map_or(0, |(_, tokens)| *tokens);
+        let share = tokens as f64 / summary.total_tokens().max(1) as f64 * 100.0;
+        (tokens, format!("{tokens} ({share:.0}%)"))
+    };
+    for model in models {
+        let ((tokens_a, shown_a), (tokens_b, shown_b)) = (model_tokens(&a, model), model_tokens(&b, model));
+        row(&format!("  {model}"), shown_a, shown_b, change(tokens_a as f64, tokens_b as f64, ""));
+    }
+    println!("\n💡 Times are UTC; costs are estimates at API list prices");
+    Ok(())
+}
+
+/// Replay recent session windows against every plan and print the recommendation
+async fn show_plan_report(config: &UserConfig, weeks: u32) -> Result<()> {
+    let mut monitor = FileBasedTokenMonitor::from_config(config)?;
+    monitor.scan_usage_files().await?;
+    
+    let analyzer = Analyzer::new(Utc::now()).with_history_weeks(weeks);
+    let analysis = match monitor.analyze_history(analyzer, &config.default_plan) {
+        Ok(analysis) => analysis,
+        Err(e) => {
+            println!("ℹ️  {e}");
+            return Ok(());
+        }
+    };
+    
+    println!(
+        "🧭 Plan fit over the last {:.0} days ({} session windows)\n",
+        analysis.analyzed_days, analysis.sessions_analyzed
+    );
+    println!("   {:<8} {:>10} {:>12} {:>14} {:>10}", "Plan", "Limit", "Limit hits", "Hits / month", "Price");
+    for fit in &analysis.plan_fits {
+        println!(
+            "   {:<8} {:>10} {:>12} {:>14.1} {:>10}",
+            format!("{:?}", fit.plan),
+            fit.plan.default_limit(),
+            format!("{} ({:.0}%)", fit.limit_hits, fit.hit_rate * 100.0),
+            fit.monthly_limit_hits,
+            format!("${:.0}/mo", fit.monthly_cost)
+        );
+    }
+    if let Some(monthly) = analysis.pay_as_you_go_monthly {
+        println!("   {:<8} {:>10} {:>12} {:>14} {:>10}", "API", "-", "-", "-", format!("${monthly:.0}/mo"));
+    }
+    
+    println!();
+    if analysis.recommend_pay_as_you_go {
+        println!(
+            "✅ Recommended: API pay-as-you-go (~${:.2}/month at list prices, less than {:?})",
+            analysis.pay_as_you_go_monthly.unwrap_or_default(),
+            analysis.recommended_plan
+        );
+    } else {
+        println!("✅ Recommended: {:?}", analysis.recommended_plan);
+    }
+    println!(
+        "   Average session {:.0} min, token use per session {:+.0}% from the older to the newer half",
+        analysis.average_session_duration,
+        analysis.efficiency_trend * 100.0
+    );
+    if analysis.idle_gaps > 0 {
+        println!(
+            "   {:.0}% of session time was idle ({} pauses of {}+ min)",
+            analysis.idle_share * 100.0,
+            analysis.idle_gaps,
+            config.idle_after_minutes
+        );
+    }
+    if !analysis.peak_usage_times.is_empty() {
+        let peaks: Vec<String> = analysis.peak_usage_times.iter().map(|(hour, _)| format!("{hour:02}:00")).collect();
+        println!("   Busiest session start hours (UTC): {}", peaks.join(", "));
+    }
+    println!(
+        "\n💡 A plan fits when at most {:.0}% of windows would reach its limit; limits are the monitor's plan defaults",
+        analyzer::MAX_LIMIT_HIT_RATE * 100.0
+    );
+    Ok(())
+}
+
+/// Compare recent history as observed with the same history under a scenario
+async fn simulate_scenario(config: &UserConfig, scenario: &Scenario, weeks: u32) -> Result<()> {
+    scenario.validate()?;
+    let mut monitor = FileBasedTokenMonitor::from_config(config)?;
+    monitor.scan_usage_files().await?;
+    
+    let now = Utc::now();
+    let simulation = match simulator::simulate(
+        monitor.usage_entries(),
+        &monitor.session_blocks(),
+        &config.default_plan,
+        scenario,
+        monitor.prices(),
+        now - chrono::Duration::weeks(weeks as i64),
+        now,
+    ) {
+        Ok(simulation) => simulation,
+        Err(e) => {
+            println!("ℹ️  {e}");
+            return Ok(());
+        }
+    };
+    let (baseline, what_if) = (&simulation.baseline, &simulation.scenario);
+    let price = |outcome: &simulator::Outcome| {
+        outcome.monthly_price().map_or_else(|| "-".to_string(), |price| format!("${price:.0}/mo"))
+    };
+    let hits = |outcome: &simulator::Outcome| {
+        format!("{} ({:.0}%)", outcome.limit_hits, outcome.limit_hits as f64 / simulation.windows.max(1) as f64 * 100.0)
+    };
+    
+    println!(
+        "🔮 What-if over the last {:.0} days ({} session windows)\n",
+        simulation.analyzed_days, simulation.windows
+    );
+    println!("   {:<18} {:>16} {:>16}", "", "Current", "Scenario");
+    println!("   {:<18} {:>16} {:>16}", "Plan", format!("{:?}", baseline.plan), format!("{:?}", what_if.plan));
+    println!("   {:<18} {:>16} {:>16}", "Model", "as logged", scenario.model.as_deref().unwrap_or("as logged"));
+    println!("   {:<18} {:>16} {:>16}", "Limit hits", hits(baseline), hits(what_if));
+    println!("   {:<18} {:>16.1} {:>16.1}", "Hits / month", baseline.monthly_limit_hits, what_if.monthly_limit_hits);
+    println!("   {:<18} {:>16} {:>16}", "Plan price", price(baseline), price(what_if));
+    println!(
+        "   {:<18} {:>16} {:>16}",
+        "API cost",
+        format!("${:.2}", baseline.api_cost),
+        format!("${:.2}", what_if.api_cost)
+    );
+    println!(
+        "   {:<18} {:>16} {:>16}",
+        "API cost / month",
+        format!("${:.2}", baseline.monthly_api_cost),
+        format!("${:.2}", what_if.monthly_api_cost)
+    );
+    
+    println!();
+    if scenario.plan.is_some() {
+        let avoided = baseline.monthly_limit_hits - what_if.monthly_limit_hits;
+        let hits_change = if avoided >= 0.0 {
+            format!("avoid ~{avoided:.1} limit hits a month")
+        } else {
+            format!("add ~{:.1} limit hits a month", -avoided)
+        };
+        match (baseline.monthly_price(), what_if.monthly_price()) {
+            (Some(current), Some(other)) if other >= current => println!(
+                "   {:?} would {hits_change} for ${:.0}/month more",
+                what_if.plan,
+                other - current
+            ),
+            (Some(current), Some(other)) => println!(
+                "   {:?} would {hits_change} and save ${:.0}/month",
+                what_if.plan,
+                current - other
+            ),
+            _ => println!("   {:?} would {hits_change}", what_if.plan),
+        }
+    }
+    if let Some(model) = &scenario.model {
+        let saved = baseline.api_cost - what_if.api_cost;
+        if saved >= 0.0 {
+            println!("   Using {model} for every request would have cost ${saved:.2} less at API rates");
+        } else {
+            println!("   Using {model} for every request would have cost ${:.2} more at API rates", -saved);
+        }
+        println!("   Token counts are replayed as logged, so limit hits don't change with the model");
+    }
+    Ok(())
+}
+
+/// Print one row per day of the metrics recorded by the live monitor
+fn show_metrics_history(file: &StateFile, days: u32) -> Result<()> {
+    let history = MetricsHistory::load(file)?;
+    let since = (Utc::now() - chrono::Duration::days(days as i64 - 1))
+        .date_naive()
+        .and_time(chrono::NaiveTime::MIN)
+        .and_utc();
+    let daily = history.daily_since(since);
+    if daily.is_empty() {
+        println!("ℹ️  No metrics recorded in the last {days} days. The live monitor records them while it runs.");
+        return Ok(());
+    }
+    
+    println!("📈 Recorded metrics, last {days} days (UTC)\n");
+    println!("   {:<12} {:>14} {:>8} {:>14} {:>10}", "Day", "Peak session", "% limit", "Avg tokens/min", "Peak cost");
+    for day in &daily {
+        println!(
+            "   {:<12} {:>14} {:>7.0}% {:>14.1} {:>10}",
+            day.timestamp.format("%a %m-%d"),
+            day.tokens_used,
+            day.tokens_used as f64 / day.tokens_limit.max(1) as f64 * 100.0,
+            day.usage_rate,
+            format!("${:.2}", day.estimated_cost)
+        );
+    }
+    println!("\n   Days without a row had no monitor running.");
+    Ok(())
+}
+
+/// Print repeated uncached inputs and what prompt caching could have saved
+async fn show_cache_report(config: &UserConfig, days: u32) -> Result<()> {
+    let mut monitor = FileBasedTokenMonitor::from_config(config)?;
+    monitor.scan_usage_files().await?;
+    
+    let since = Utc::now() - chrono::Duration::days(days as i64);
+    let opportunities = cache_analysis::find_cache_opportunities(monitor.usage_entries().iter(), since, monitor.prices());
+    let writes = cache_analysis::cache_writes(monitor.usage_entries().iter(), since, monitor.prices());
+    
+    if writes.five_minute_tokens + writes.one_hour_tokens > 0 {
+        println!("✍️  Cache writes (last {days} days)\n");
+        println!("   {:<4} {:>12} {:>10}", "TTL", "Tokens", "Cost");
+        println!("   {:<4} {:>12} {:>10}", "5m", writes.five_minute_tokens, format!("${:.2}", writes.five_minute_cost));
+        println!("   {:<4} {:>12} {:>10}", "1h", writes.one_hour_tokens, format!("${:.2}", writes.one_hour_cost));
+        println!("   Writes logged without a TTL count as {} (cache_ttl in the config)\n", config.cache_ttl.name());
+    }
+    
+    println!("💾 Prompt caching opportunities (last {days} days)\n");
+    if opportunities.is_empty() {
+        println!("✅ No repeated large inputs missed the cache");
+        return Ok(());
+    }
+    
+    let total: f64 = opportunities.iter().map(|opportunity| opportunity.potential_savings).sum();
+    println!("   Estimated savings: ${total:.2} at API list prices\n");
+    for opportunity in opportunities.iter().take(10) {
+        let project = opportunity
+            .project
+            .as_deref()
+            .map(|project| monitor.project_label(project))
+            .unwrap_or_else(|| "unknown".to_string());
+        println!("   {project} ({})", opportunity.model.as_deref().unwrap_or("unknown model"));
+        println!(
+            "      {} requests resent ~{} uncached input tokens, {:.0}% read from cache",
+            opportunity.requests,
+            opportunity.repeated_tokens,
+            opportunity.cache_read_share * 100.0
+        );
+        println!(
+            "      {} to {}, {} cache TTL would cover the gaps: save ~${:.2}",
+            opportunity.first_seen.format("%m-%d %H:%M"),
+            opportunity.last_seen.format("%m-%d %H:%M UTC"),
+            opportunity.ttl.name(),
+            opportunity.potential_savings
+        );
+    }
+    if opportunities.len() > 10 {
+        println!("\n   ... and {} smaller groups", opportunities.len() - 10);
+    }
+    println!("\n💡 Sizes are matched by token count only; mark stable prompt prefixes with cache_control to cache them");
+    Ok(())
+}
+
+/// Print a week's usage by day and how it compared with the configured goals
+async fn show_week_report(config: &UserConfig, data_dir: &std::path::Path, weeks_ago: u32) -> Result<()> {
+    let mut monitor = FileBasedTokenMonitor::from_config(config)?;
+    monitor.set_imports_dir(data_dir.join(importer::IMPORTS_DIR));
+    monitor.scan_usage_files().await?;
+    
+    let today = Utc::now().date_naive();
+    let first = usage_stats::week_start(today) - chrono::Duration::weeks(weeks_ago as i64);
+    // The current week only counts days that have started
+    let last = (first + chrono::Duration::days(6)).min(today);
+    let days = usage_stats::daily_usage(monitor.report_entries(), first, last, monitor.prices());
+    
+    let mut week = usage_stats::PeriodUsage::default();
+    for (_, usage) in &days {
+        week += *usage;
+    }
+    let goals = &config.goals;
+    let within_daily_goals = |usage: usage_stats::PeriodUsage| {
+        usage_stats::goal_progress(goals, usage, usage_stats::PeriodUsage::default(), usage_stats::PeriodUsage::default())
+            .iter()
+            .filter(|goal| goal.period == GoalPeriod::Day)
+            .all(|goal| goal.met())
+    };
+    
+    println!("📆 Week of {} (UTC)\n", first.format("%Y-%m-%d"));
+    println!("   {:<12} {:>12} {:>10}", "Day", "Tokens", "Cost");
+    for (day, usage) in &days {
+        let marker = if within_daily_goals(*usage) { "" } else { "  ⚠️ over daily goal" };
+        println!(
+            "   {:<12} {:>12} {:>10}{marker}",
+            day.format("%a %m-%d").to_string(),
+            usage.tokens,
+            format!("${:.2}", usage.cost)
+        );
+    }
+    println!("   {:<12} {:>12} {:>10}", "Total", week.tokens, format!("${:.2}", week.cost));
+    
+    let week_start = first.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let week_end = week_start + chrono::Duration::days(7);
+    print_billing_split(&monitor, week_start, week_end);
+    print_user_split(&monitor, week_start, week_end);
+    print_overage_split(config, &monitor, week_start, week_end);
+    print_latency_stats(config, &monitor, week_start, week_end);
+    print_error_rates(config, &monitor, week_start, week_end);
+    print_unknown_models(&monitor, week_start, week_end);
+    let opportunities = cache_analysis::find_cache_opportunities(
+        monitor.usage_entries().iter().filter(|entry| entry.timestamp < week_end),
+        week_start,
+        monitor.prices(),
+    );
+    if !opportunities.is_empty() {
+        println!(
+            "\n💾 Missed caching: ~${:.2} across {} groups of repeated inputs (see 'report cache')",
+            opportunities.iter().map(|opportunity| opportunity.potential_savings).sum::<f64>(),
+            opportunities.len()
+        );
+    }
+    
+    // Monthly goals are covered by `report month`
+    let weekly_goals = GoalsConfig { monthly_tokens: None, monthly_cost: None, ..goals.clone() };
+    if weekly_goals.is_empty() {
+        println!("\n💡 Set goals with 'claude-token-monitor config --daily-goal <tokens|$usd> --weekly-goal <tokens|$usd>'");
+        return Ok(());
+    }
+    
+    println!("\n🎯 Goal adherence");
+    if goals.daily_tokens.is_some() || goals.daily_cost.is_some() {
+        let met = days.iter().filter(|(_, usage)| within_daily_goals(*usage)).count();
+        println!("   Daily goals met on {met} of {} days", days.len());
+    }
+    for goal in usage_stats::goal_progress(goals, usage_stats::PeriodUsage::default(), week, usage_stats::PeriodUsage::default())
+        .iter()
+        .filter(|goal| goal.period == GoalPeriod::Week)
+    {
+        let amounts = match goal.metric {
+            usage_stats::GoalMetric::Tokens => format!("{} of {} tokens", goal.used, goal.goal),
+            usage_stats::GoalMetric::Cost => format!("${:.2} of ${:.2}", goal.used, goal.goal),
+        };
+        let status = if goal.met() { "✅ within" } else { "❌ over" };
+        println!("   Weekly goal: {status} ({amounts}, {:.0}%)", goal.ratio() * 100.0);
+    }
+    Ok(())
+}
+
+/// Print the usage from `since` until `until` on the subscription and billed to API keys, when
+/// there is any of the latter
+fn print_billing_split(monitor: &FileBasedTokenMonitor, since: chrono::DateTime<Utc>, until: chrono::DateTime<Utc>) {
+    let (subscription, api_key) = usage_stats::billing_split(monitor.report_entries(), since, until, monitor.prices());
+    if api_key.tokens == 0 {
+        return;
+    }
+    println!("\n🔑 By billing");
+    println!("   {:<12} {:>12} {:>10}  counted against plan windows", "Subscription", subscription.tokens, format!("${:.2}", subscription.cost));
+    println!("   {:<12} {:>12} {:>10}  billed per token", "API key", api_key.tokens, format!("${:.2}", api_key.cost));
+}
+
+/// Print each OS user's share of the usage from `since` until `until`, when more than one user
+/// logged any
+fn print_user_split(monitor: &FileBasedTokenMonitor, since: chrono::DateTime<Utc>, until: chrono::DateTime<Utc>) {
+    let users = usage_stats::user_split(monitor.report_entries(), since, until, monitor.prices());
+    if users.len() < 2 {
+        return;
+    }
+    let total: u64 = users.iter().map(|(_, usage)| usage.tokens).sum();
+    println!("\n👥 By user");
+    for (user, usage) in &users {
+        println!(
+            "   {:<12} {:>12} {:>10}  {:>5.1}%",
+            user,
+            usage.tokens,
+            format!("${:.2}", usage.cost),
+            usage.tokens as f64 / total.max(1) as f64 * 100.0
+        );
+    }
+}
+
+/// Print how much of the Claude Code usage from `since` until `until` the plan's allowance covers
+/// and what the rest would cost as extra usage
+fn print_overage_split(
+    config: &UserConfig,
+    monitor: &FileBasedTokenMonitor,
+    since: chrono::DateTime<Utc>,
+    until: chrono::DateTime<Utc>,
+) {
+    let limit = config.default_plan.default_limit();
+    let prices = monitor.prices();
+    let split = overage::split_overage(monitor.usage_entries(), monitor.session_window(), limit as u64, config.overage_rate, since, until, |entry| {
+        prices.cost(entry)
+    });
+    if split.total_tokens() == 0 {
+        return;
+    }
+    println!(
+        "\n💳 Included vs overage ({:?} plan, {limit} tokens per {} window)",
+        config.default_plan,
+        monitor.session_window().describe()
+    );
+    println!("   {:<12} {:>12} {:>10}", "Included", split.included_tokens, format!("${:.2}", split.included_cost));
+    println!("   {:<12} {:>12} {:>10}", "Overage", split.overage_tokens, format!("${:.2}", split.overage_cost));
+    if split.windows_over > 0 {
+        println!(
+            "   {} window(s) went past the allowance; overage is priced at {}x API rates",
+            split.windows_over, config.overage_rate
+        );
+    }
+}
+
+/// Print response-time percentiles and output speed per model for the requests from `since`
+/// until `until` that logged timings
+fn print_latency_stats(config: &UserConfig, monitor: &FileBasedTokenMonitor, since: chrono::DateTime<Utc>, until: chrono::DateTime<Utc>) {
+    let default_model = monitor.subscription_hints().model.clone();
+    let latency = usage_stats::model_latency(
+        monitor.usage_entries().iter().filter(|entry| entry.timestamp < until),
+        since,
+        default_model.as_deref(),
+        &ModelNames::from_config(config),
+    );
+    if latency.is_empty() {
+        return;
+    }
+    println!("\n⏱️  Response times (ms, p50 / p90 / p99)");
+    println!("   {:<28} {:>8} {:>20} {:>20} {:>10}", "Model", "Requests", "Total", "First token", "Output/s");
+    for model in &latency {
+        let percentiles = |[p50, p90, p99]: [u64; 3]| format!("{p50} / {p90} / {p99}");
+        println!(
+            "   {:<28} {:>8} {:>20} {:>20} {:>10.0}",
+            model.model,
+            model.requests,
+            percentiles(model.duration_ms),
+            model.ttft_ms.map_or_else(|| "-".to_string(), percentiles),
+            model.output_tokens_per_second
+        );
+    }
+}
+
+/// Print how much of the usage from `since` until `until` came from models without known prices,
+/// when any did, since its cost is only as right as the fallback rates
+fn print_unknown_models(monitor: &FileBasedTokenMonitor, since: chrono::DateTime<Utc>, until: chrono::DateTime<Utc>) {
+    let unknown = pricing::unknown_models(
+        monitor.report_entries().filter(|entry| entry.timestamp >= since && entry.timestamp < until),
+        monitor.prices(),
+    );
+    if unknown.is_empty() {
+        return;
+    }
+    println!("\n⚠️  Unrecognized models, priced at unknown_model_pricing (see doctor)");
+    println!("   {:<28} {:>8} {:>12} {:>10}", "Model", "Requests", "Tokens", "Cost");
+    for model in &unknown {
+        println!("   {:<28} {:>8} {:>12} {:>10}", model.model, model.requests, model.tokens, format!("${:.2}", model.estimated_cost));
+    }
+}
+
+/// Print the share of failed Claude Code requests from `since` until `until` per day and model,
+/// against the same length of time before, when any failed
+fn print_error_rates(config: &UserConfig, monitor: &FileBasedTokenMonitor, since: chrono::DateTime<Utc>, until: chrono::DateTime<Utc>) {
+    let default_model = monitor.subscription_hints().model.clone();
+    let names = ModelNames::from_config(config);
+    let rates = |since, until| {
+        usage_stats::error_rates(
+            monitor.usage_entries().iter().chain(monitor.api_key_entries()),
+            monitor.request_errors(),
+            since,
+            until,
+            default_model.as_deref(),
+            &names,
+        )
+    };
+    let current = rates(since, until);
+    if current.total.errors == 0 {
+        return;
+    }
+    let previous = rates(since - (until - since), since).total;
+    let percent = |rate: usage_stats::ErrorRate| format!("{:.1}%", rate.rate() * 100.0);
+
+    println!(
+        "\n🚨 Request errors: {} of {} requests ({}, {} the period before)",
+        current.total.errors,
+        current.total.requests,
+        percent(current.total),
+        percent(previous)
+    );
+    println!("   {:<28} {:>8} {:>8} {:>8}", "", "Requests", "Errors", "Rate");
+    for (day, rate) in current.by_day.iter().filter(|(_, rate)| rate.errors > 0) {
+        println!("   {:<28} {:>8} {:>8} {:>8}", day.format("%a %m-%d").to_string(), rate.requests, rate.errors, percent(*rate));
+    }
+    for (model, rate) in current.by_model.iter().filter(|(_, rate)| rate.errors > 0) {
+        println!("   {:<28} {:>8} {:>8} {:>8}", model, rate.requests, rate.errors, percent(*rate));
+    }
+}
+
+/// Print a billing cycle's usage by day, the pace for the cycle in progress and how it compares
+/// with the monthly goal
+async fn show_month_report(config: &UserConfig, data_dir: &std::path::Path, cycles_ago: u32) -> Result<()> {
+    let mut monitor = FileBasedTokenMonitor::from_config(config)?;
+    monitor.set_imports_dir(data_dir.join(importer::IMPORTS_DIR));
+    monitor.scan_usage_files().await?;
+    
+    let billing_day = config.goals.billing_day();
+    let today = Utc::now().date_naive();
+    let mut first = usage_stats::billing_cycle_start(today, billing_day);
+    for _ in 0..cycles_ago {
+        first = usage_stats::billing_cycle_start(first - chrono::Duration::days(1), billing_day);
+    }
+    let next = usage_stats::next_billing_cycle(first, billing_day);
+    let cycle_days = (next - first).num_days();
+    // The current cycle only counts days that have started
+    let last = (next - chrono::Duration::days(1)).min(today);
+    let days = usage_stats::daily_usage(monitor.report_entries(), first, last, monitor.prices());
+    
+    let mut cycle = usage_stats::PeriodUsage::default();
+    for (_, usage) in &days {
+        cycle += *usage;
+    }
+    
+    println!(
+        "🧾 Billing cycle {} to {} (UTC, renews on day {billing_day})\n",
+        first.format("%Y-%m-%d"),
+        (next - chrono::Duration::days(1)).format("%Y-%m-%d")
+    );
+    println!("   {:<12} {:>12} {:>10}", "Day", "Tokens", "Cost");
+    for (day, usage) in days.iter().filter(|(_, usage)| usage.tokens > 0) {
+        println!("   {:<12} {:>12} {:>10}", day.format("%a %m-%d").to_string(), usage.tokens, format!("${:.2}", usage.cost));
+    }
+    println!("   {:<12} {:>12} {:>10}", "Total", cycle.tokens, format!("${:.2}", cycle.cost));
+    let midnight = |day: chrono::NaiveDate| day.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    print_billing_split(&monitor, midnight(first), midnight(next));
+    print_user_split(&monitor, midnight(first), midnight(next));
+    print_overage_split(config, &monitor, midnight(first), midnight(next));
+    print_latency_stats(config, &monitor, midnight(first), midnight(next));
+    print_unknown_models(&monitor, midnight(first), midnight(next));
+    
+    // Projections of the cycle in progress: tokens on a straight line, cost also by weekday
+    let elapsed = days.len() as i64;
+    let forecast = (next > today && elapsed > 0).then(|| {
+        let forecast = forecast::forecast_cycle(monitor.report_entries(), billing_day, Utc::now(), monitor.prices());
+        let scale = cycle_days as f64 / forecast.elapsed_days.max(1.0);
+        println!("\n📈 Day {elapsed} of {cycle_days}: on pace for ~{:.0} tokens this cycle", cycle.tokens as f64 * scale);
+        println!("   Forecast cost: ~${:.2} on a straight line", forecast.linear);
+        match forecast.weekday {
+            Some(weekday) => println!(
+                "   Forecast cost: ~${weekday:.2} by weekday (last {} days' average for each remaining day)",
+                forecast::HISTORY_DAYS
+            ),
+            None => println!("   Forecast cost by weekday: needs a week of history"),
+        }
+        (forecast, scale)
+    });
+    
+    let goals = usage_stats::goal_progress(
+        &config.goals,
+        usage_stats::PeriodUsage::default(),
+        usage_stats::PeriodUsage::default(),
+        cycle,
+    );
+    if goals.is_empty() {
+        println!("\n💡 Set a budget with 'claude-token-monitor config --monthly-goal <tokens|$usd> --billing-day <day>'");
+        return Ok(());
+    }
+    
+    println!("\n🎯 Goal adherence");
+    for goal in &goals {
+        let amounts = match goal.metric {
+            usage_stats::GoalMetric::Tokens => format!("{} of {} tokens", goal.used, goal.goal),
+            usage_stats::GoalMetric::Cost => format!("${:.2} of ${:.2}", goal.used, goal.goal),
+        };
+        let status = if goal.met() { "✅ within" } else { "❌ over" };
+        let on_pace_to_exceed = match (forecast, goal.metric) {
+            (Some((forecast, _)), usage_stats::GoalMetric::Cost) => forecast.exceeds(goal.goal),
+            (Some((_, scale)), usage_stats::GoalMetric::Tokens) => goal.used * scale > goal.goal,
+            (None, _) => false,
+        };
+        let projection = if goal.met() && on_pace_to_exceed { ", ⚠️ on pace to exceed it" } else { "" };
+        println!("   Monthly goal: {status} ({amounts}, {:.0}%{projection})", goal.ratio() * 100.0);
+    }
+    Ok(())
+}
+
+/// Print a calendar month's usage as line items per UTC day, project (or billing code), model and
+/// billing, as a table or as CSV, optionally only for projects tagged with `billing_code`
+async fn show_expense_report(
+    config: &UserConfig,
+    data_dir: &std::path::Path,
+    month: Option<&str>,
+    format: ExpenseFormat,
+    grouping: ExpenseGrouping,
+    billing_code: Option<&str>,
+) -> Result<()> {
+    let month = match month {
+        Some(month) => expense::parse_month(month)?,
+        None => Utc::now().date_naive().with_day(1).unwrap_or_default(),
+    };
+    let codes = BillingCodes::from_config(config);
+    if let Some(code) = billing_code.filter(|code| !config.billing_codes.values().any(|configured| configured == code)) {
+        anyhow::bail!("No project is tagged with billing code '{code}'; set them under billing_codes in the config");
+    }
+    let mut monitor = FileBasedTokenMonitor::from_config(config)?;
+    monitor.set_imports_dir(data_dir.join(importer::IMPORTS_DIR));
+    monitor.scan_usage_files().await?;
+    
+    let mut lines = expense::expense_lines(
+        monitor.report_entries(),
+        month,
+        |project| monitor.project_label(project),
+        &codes,
+        grouping,
+        monitor.prices(),
+        &ModelNames::from_config(config),
+    );
+    if let Some(code) = billing_code {
+        lines.retain(|line| line.billing_code.as_deref() == Some(code));
+    }
+    if format == ExpenseFormat::Csv {
+        return expense::write_csv(&lines, std::io::stdout().lock());
+    }
+    
+    let scope = billing_code.map(|code| format!(", billing code {code}")).unwrap_or_default();
+    println!("🧾 Expenses for {}{scope} (UTC days, API-equivalent cost)\n", month.format("%B %Y"));
+    if lines.is_empty() {
+        println!("   No usage recorded this month");
+        return Ok(());
+    }
+    println!("   {:<10} {:<12} {:<20} {:<28} {:<12} {:>12} {:>10}", "Date", "Code", "Project", "Model", "Billing", "Tokens", "Cost");
+    for line in &lines {
+        println!(
+            "   {:<10} {:<12} {:<20} {:<28} {:<12} {:>12} {:>10}",
+            line.date.format("%Y-%m-%d").to_string(),
+            line.billing_code.as_deref().unwrap_or("-"),
+            line.project,
+            line.model,
+            expense::billing_name(line.billing),
+            line.total_tokens(),
+            format!("${:.2}", line.cost)
+        );
+    }
+    let tokens: u64 = lines.iter().map(|line| line.total_tokens()).sum();
+    let cost: f64 = lines.iter().map(|line| line.cost).sum();
+    println!("   {:<10} {:<12} {:<20} {:<28} {:<12} {:>12} {:>10}", "Total", "", "", "", "", tokens, format!("${cost:.2}"));
+    if !config.billing_codes.is_empty() && billing_code.is_none() {
+        println!("\n   {:<16} {:>10} {:>14} {:>10}", "By billing code", "Requests", "Tokens", "Cost");
+        for (code, requests, tokens, cost) in expense::totals_by_code(&lines) {
+            println!("   {:<16} {:>10} {:>14} {:>10}", code.unwrap_or("(untagged)"), requests, tokens, format!("${cost:.2}"));
+        }
+    }
+    println!("\n💡 --format csv prints these line items for a spreadsheet or accounting tool");
+    Ok(())
+}
+
+/// Merge another observed sessions file into this profile's history
+async fn import_sessions(state: &StateDir, path: &std::path::Path) -> Result<()> {
+    let mut tracker = SessionTracker::open(state.file(SESSIONS_FILE))?;
+    let summary = tracker.import_sessions(path).await?;
+    
+    println!("✅ Read {} sessions from {}", summary.read, path.display());
+    println!("   {} new windows", summary.added);
+    println!("   {} overlapping windows added to this machine's totals", summary.overlapping);
+    println!("   {} replaced from an earlier import", summary.replaced);
+    println!("   {} already observed here (the larger count is kept)", summary.already_observed);
+    println!("   Run 'claude-token-monitor history' to see the merged history");
+    Ok(())
+}
+
+fn backup_data(dirs: &BaseDirs, out: &std::path::Path) -> Result<()> {
+    let manifest = backup::create_backup(dirs, out, Utc::now())?;
+    println!("✅ Backed up {} files to {}", manifest.files, out.display());
+    println!("   Restore with: claude-token-monitor restore {}", out.display());
+    Ok(())
+}
+
+fn restore_data(dirs: &BaseDirs, file: &std::path::Path, force: bool) -> Result<()> {
+    let manifest = backup::restore_backup(file, dirs, force)?;
+    println!(
+        "✅ Restored {} files from a backup made {} (v{})",
+        manifest.files,
+        manifest.created.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+        manifest.app_version
+    );
+    if !dirs.is_single() {
+        println!("   Config: {}", dirs.config.display());
+    }
+    println!("   State:  {}", dirs.state.display());
+    Ok(())
+}
+
+fn parse_plan_type(plan: &str) -> Result<PlanType> {
+    plan.parse()
 }
 
 /// Display about information including version, author, and contributors
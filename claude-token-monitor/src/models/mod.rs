@@ -1,10 +1,11 @@
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt;
 
 /// Represents a Claude AI usage session with token tracking
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TokenSession {
     pub id: String,
     pub start_time: DateTime<Utc>,
@@ -32,7 +33,7 @@ impl fmt::Debug for TokenSession {
 }
 
 /// Claude AI plan types with their respective limits
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub enum PlanType {
     Pro,
     Max5,
@@ -50,8 +51,43 @@ impl PlanType {
         }
     }
 
-    pub fn session_duration_hours(&self) -> u32 {
-        5 // All plans use 5-hour sessions
+    /// Short name, e.g. "Max5" or "Custom(50000)"
+    pub fn name(&self) -> String {
+        match self {
+            PlanType::Pro => "Pro".to_string(),
+            PlanType::Max5 => "Max5".to_string(),
+            PlanType::Max20 => "Max20".to_string(),
+            PlanType::Custom(limit) => format!("Custom({limit})"),
+        }
+    }
+
+    /// Monthly subscription price in USD (custom limits have no known price)
+    pub fn monthly_price(&self) -> Option<f64> {
+        match self {
+            PlanType::Pro => Some(20.0),
+            PlanType::Max5 => Some(100.0),
+            PlanType::Max20 => Some(200.0),
+            PlanType::Custom(_) => None,
+        }
+    }
+}
+
+impl std::str::FromStr for PlanType {
+    type Err = anyhow::Error;
+
+    fn from_str(plan: &str) -> Result<Self, Self::Err> {
+        match plan.to_lowercase().as_str() {
+            "pro" => Ok(PlanType::Pro),
+            "max5" => Ok(PlanType::Max5),
+            "max20" => Ok(PlanType::Max20),
+            _ => {
+                if let Ok(limit) = plan.parse::<u32>() {
+                    Ok(PlanType::Custom(limit))
+                } else {
+                    Err(anyhow::anyhow!("Invalid plan type: {}. Use 'pro', 'max5', 'max20', or a custom limit number", plan))
+                }
+            }
+        }
     }
 }
 
@@ -70,6 +106,128 @@ pub struct UsageMetrics {
     pub cache_creation_rate: f64, // cache creation tokens per minute
     pub token_consumption_rate: f64, // tokens per minute
     pub input_output_ratio: f64, // input tokens / output tokens
+    
+    #[serde(default)]
+    pub project_label: Option<String>, // label of the project the current session is attributed to
+    #[serde(default)]
+    pub limit_calibrated: bool, // tokens_limit was calibrated from observed resets
+    #[serde(default)]
+    pub limit_events: Vec<LimitEvent>, // limit notices observed during the current session
+    #[serde(default)]
+    pub estimated_cost: f64, // estimated API-equivalent cost of the current session in USD
+    #[serde(default)]
+    pub activity_heatmap: Vec<[u32; 24]>, // tokens per hour of day for recent days, oldest first
+    #[serde(default)]
+    pub minute_tokens: Vec<u64>, // tokens in each of the last 60 minutes up to now, oldest first
+    #[serde(default)]
+    pub idle_since: Option<DateTime<Utc>>, // last request, once nothing has arrived for the idle period
+    #[serde(default)]
+    pub reset_authoritative: bool, // the session's reset time was announced by Claude Code, not estimated
+    #[serde(default)]
+    pub selected_model: Option<String>, // model Claude Code's settings select, if one is set
+    #[serde(default)]
+    pub latest_model: Option<String>, // model the latest request was logged with
+}
+
+impl UsageMetrics {
+    /// How the reset time is known, for showing next to it
+    pub fn reset_source(&self) -> &'static str {
+        if self.reset_authoritative {
+            "authoritative"
+        } else {
+            "estimated"
+        }
+    }
+
+    /// Active, idle (window open but nothing for the idle period) or inactive
+    pub fn status(&self) -> SessionStatus {
+        if !self.current_session.is_active {
+            SessionStatus::Inactive
+        } else if self.idle_since.is_some() {
+            SessionStatus::Idle
+        } else {
+            SessionStatus::Active
+        }
+    }
+}
+
+/// Whether the current session is in use
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionStatus {
+    Active,
+    Idle,     // window open but nothing for the idle period
+    Inactive, // the window has reset
+}
+
+impl fmt::Display for SessionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SessionStatus::Active => "Active",
+            SessionStatus::Idle => "Idle",
+            SessionStatus::Inactive => "Inactive",
+        })
+    }
+}
+
+/// Kind of usage limit notice written by Claude Code
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LimitEventKind {
+    LimitReached,
+    ApproachingLimit,
+}
+
+/// A usage limit notice observed in the JSONL logs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LimitEvent {
+    pub timestamp: DateTime<Utc>,
+    pub kind: LimitEventKind,
+    pub reset_time: Option<DateTime<Utc>>, // reset time announced in the notice, if any
+}
+
+/// A request that failed, from an API error Claude Code logged or a response that stopped with an error
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RequestError {
+    pub timestamp: DateTime<Utc>,
+    pub model: Option<String>, // model of the failed response, or of the conversation's last response
+    pub has_usage: bool,       // the response also logged usage, so it is among the usage entries
+}
+
+/// A usage window reconstructed from observed entries
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionBlock {
+    pub start_time: DateTime<Utc>,
+    pub last_activity: DateTime<Utc>,
+    pub reset_time: DateTime<Utc>,
+    pub tokens_used: u32,
+    pub entry_count: usize,
+    pub limit_reached: bool, // a limit-reached notice was observed in this window
+    #[serde(default)]
+    pub idle_gaps: Vec<(DateTime<Utc>, DateTime<Utc>)>, // pauses between requests of at least the idle period
+    #[serde(default)]
+    pub reset_authoritative: bool, // reset_time was announced by Claude Code rather than estimated
+}
+
+impl SessionBlock {
+    /// The window as a finished session under `plan_type`
+    pub fn to_session(&self, plan_type: PlanType) -> TokenSession {
+        TokenSession {
+            id: format!("block-{}", self.start_time.timestamp()),
+            start_time: self.start_time,
+            end_time: Some(self.last_activity),
+            tokens_limit: plan_type.default_limit(),
+            plan_type,
+            tokens_used: self.tokens_used,
+            is_active: false,
+            reset_time: self.reset_time,
+        }
+    }
+}
+
+/// Token limit inferred from windows that stopped at a consistent ceiling
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LimitCalibration {
+    pub limit: u32,
+    pub samples: usize,
 }
 
 /// Point-in-time token usage data
@@ -90,34 +248,726 @@ impl fmt::Debug for TokenUsagePoint {
     }
 }
 
+/// Organization and user of the signed-in Claude account, for grouping data aggregated centrally
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AccountIdentity {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organization_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+}
+
+/// Current schema version of the stored config file
+pub const CONFIG_SCHEMA_VERSION: u32 = 1;
+
+/// Current schema version of the stored observed sessions file
+pub const SESSIONS_SCHEMA_VERSION: u32 = 1;
+
+/// Minutes without requests before a session counts as idle, unless configured
+pub const DEFAULT_IDLE_MINUTES: u64 = 10;
+
+/// Milliseconds between redraws of the UI, unless configured
+pub const DEFAULT_REDRAW_MS: u64 = 100;
+
+/// Allowed range of `redraw_interval_ms`: from about 60 frames a second to one every 10 seconds
+pub const REDRAW_MS_RANGE: std::ops::RangeInclusive<u64> = 16..=10_000;
+
+/// Tokens a single logged request may count before it is taken for a corrupt line, unless
+/// configured; several times the largest context window
+pub const DEFAULT_MAX_REQUEST_TOKENS: u32 = 5_000_000;
+
+/// Lowest allowed `max_request_tokens`, so a typo can't drop real requests
+pub const MIN_MAX_REQUEST_TOKENS: u32 = 100_000;
+
+/// Account names label entries in reports, so keep them short and plain like profile names
+pub fn validate_account_name(name: &str) -> anyhow::Result<()> {
+    if name.is_empty() || name.len() > 64 || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(anyhow::anyhow!("invalid account name '{name}': use 1-64 letters, digits, '-' or '_'"));
+    }
+    Ok(())
+}
+
+/// Path mappings need a path on both sides, and the local side must be absolute (or start at `~`)
+pub fn validate_path_mappings(mappings: &BTreeMap<std::path::PathBuf, std::path::PathBuf>) -> anyhow::Result<()> {
+    for (from, to) in mappings {
+        if from.as_os_str().is_empty() || to.as_os_str().is_empty() {
+            return Err(anyhow::anyhow!("'{}' = '{}' needs a path on both sides", from.display(), to.display()));
+        }
+        if !to.is_absolute() && !to.starts_with("~") {
+            return Err(anyhow::anyhow!("'{}' must be an absolute path", to.display()));
+        }
+    }
+    Ok(())
+}
+
 /// User configuration settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct UserConfig {
+    pub version: u32, // schema version, 0 for files written before versioning
     pub default_plan: PlanType,
     pub timezone: String,
-    pub update_interval_seconds: u64,
+    pub update_interval_seconds: u64, // how often the usage files are rescanned
+    pub redraw_interval_ms: u64, // how often the UI is redrawn between key presses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alert_interval_seconds: Option<u64>, // how often alerts are evaluated; after every scan when unset
+    pub low_power: LowPowerMode, // when to scan and redraw less often to save energy
     pub warning_threshold: f64, // percentage at which to warn
+    pub idle_after_minutes: u64, // minutes without requests before the session counts as idle
     pub auto_switch_plans: bool,
     pub color_scheme: ColorScheme,
     pub custom_limits: HashMap<String, u32>,
+    pub layout: LayoutConfig,
+    pub scripts: ScriptsConfig,
+    pub alerts: AlertsConfig,
+    pub dedup_strategy: DedupStrategy,
+    pub cache_ttl: CacheTtl, // assumed for cache writes whose logs don't say which TTL they used
+    pub max_request_tokens: u32, // requests logged with more tokens than this are dropped as corrupt
+    pub chart_graphics: ChartGraphics,
+    pub goals: GoalsConfig,
+    pub overage_rate: f64, // extra usage past the plan's allowance, as a multiple of API list prices
+    pub encryption: EncryptionConfig,
+    pub mqtt: MqttConfig,
+    pub smtp: SmtpConfig,
+    pub users: UsersConfig,
+    pub session_window: SessionWindowConfig,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub rate_limits: BTreeMap<String, RateLimit>, // per-minute API limits by model name or part of it, e.g. sonnet
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub model_aliases: BTreeMap<String, String>, // display names by model ID or ID prefix, e.g. claude-sonnet-4 = Sonnet 4
+    pub model_grouping: ModelGrouping,
+    pub unknown_model_pricing: ModelPricing, // rates for model IDs the monitor doesn't recognize, Sonnet's by default
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub billing_codes: BTreeMap<String, String>, // billing code by project directory (or a parent of it) or project label
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub accounts: BTreeMap<String, std::path::PathBuf>, // extra Claude Code homes by account name, e.g. work = ~/work/.claude
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub api_key_accounts: BTreeSet<String>, // accounts whose Claude Code uses an API key, "default" for the discovered home
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub path_mappings: BTreeMap<std::path::PathBuf, std::path::PathBuf>, // host path prefixes to where they are mounted here, e.g. in a dev container
 }
 
 impl Default for UserConfig {
     fn default() -> Self {
         Self {
+            version: CONFIG_SCHEMA_VERSION,
             default_plan: PlanType::Pro,
             timezone: "UTC".to_string(),
             update_interval_seconds: 3,
+            redraw_interval_ms: DEFAULT_REDRAW_MS,
+            alert_interval_seconds: None,
+            low_power: LowPowerMode::default(),
             warning_threshold: 0.85,
+            idle_after_minutes: DEFAULT_IDLE_MINUTES,
             auto_switch_plans: true,
             color_scheme: ColorScheme::default(),
             custom_limits: HashMap::new(),
+            layout: LayoutConfig::default(),
+            scripts: ScriptsConfig::default(),
+            alerts: AlertsConfig::default(),
+            dedup_strategy: DedupStrategy::default(),
+            cache_ttl: CacheTtl::default(),
+            max_request_tokens: DEFAULT_MAX_REQUEST_TOKENS,
+            chart_graphics: ChartGraphics::default(),
+            goals: GoalsConfig::default(),
+            overage_rate: 1.0,
+            encryption: EncryptionConfig::default(),
+            mqtt: MqttConfig::default(),
+            smtp: SmtpConfig::default(),
+            users: UsersConfig::default(),
+            session_window: SessionWindowConfig::default(),
+            rate_limits: BTreeMap::new(),
+            model_aliases: BTreeMap::new(),
+            model_grouping: ModelGrouping::default(),
+            unknown_model_pricing: ModelPricing::SONNET,
+            billing_codes: BTreeMap::new(),
+            accounts: BTreeMap::new(),
+            api_key_accounts: BTreeSet::new(),
+            path_mappings: BTreeMap::new(),
+        }
+    }
+}
+
+impl UserConfig {
+    /// Check settings that can't be expressed through the type system
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.idle_after_minutes == 0 {
+            return Err(anyhow::anyhow!("idle_after_minutes must be at least 1"));
+        }
+        if !REDRAW_MS_RANGE.contains(&self.redraw_interval_ms) {
+            return Err(anyhow::anyhow!(
+                "redraw_interval_ms must be between {} and {}",
+                REDRAW_MS_RANGE.start(),
+                REDRAW_MS_RANGE.end()
+            ));
+        }
+        if self.alert_interval_seconds == Some(0) {
+            return Err(anyhow::anyhow!("alert_interval_seconds must be at least 1"));
+        }
+        if self.max_request_tokens < MIN_MAX_REQUEST_TOKENS {
+            return Err(anyhow::anyhow!("max_request_tokens must be at least {MIN_MAX_REQUEST_TOKENS}"));
+        }
+        self.layout
+            .validate()
+            .map_err(|e| anyhow::anyhow!("Invalid [layout] section in config: {e}"))?;
+        self.alerts
+            .validate()
+            .map_err(|e| anyhow::anyhow!("Invalid [alerts] section in config: {e}"))?;
+        self.goals
+            .validate()
+            .map_err(|e| anyhow::anyhow!("Invalid [goals] section in config: {e}"))?;
+        if !(self.overage_rate.is_finite() && self.overage_rate > 0.0) {
+            return Err(anyhow::anyhow!("overage_rate must be a positive multiple of API prices"));
+        }
+        for name in self.accounts.keys() {
+            validate_account_name(name).map_err(|e| anyhow::anyhow!("Invalid [accounts] section in config: {e}"))?;
+        }
+        for name in &self.api_key_accounts {
+            validate_account_name(name).map_err(|e| anyhow::anyhow!("Invalid api_key_accounts in config: {e}"))?;
+        }
+        self.users
+            .validate()
+            .map_err(|e| anyhow::anyhow!("Invalid [users] section in config: {e}"))?;
+        self.session_window
+            .validate()
+            .map_err(|e| anyhow::anyhow!("Invalid [session_window] section in config: {e}"))?;
+        for (model, limit) in &self.rate_limits {
+            if model.trim().is_empty() {
+                return Err(anyhow::anyhow!("Invalid [rate_limits] section in config: model names can't be empty"));
+            }
+            limit
+                .validate()
+                .map_err(|e| anyhow::anyhow!("Invalid [rate_limits.{model}] section in config: {e}"))?;
+        }
+        self.unknown_model_pricing
+            .validate()
+            .map_err(|e| anyhow::anyhow!("Invalid [unknown_model_pricing] section in config: {e}"))?;
+        for (model, alias) in &self.model_aliases {
+            if model.trim().is_empty() || alias.trim().is_empty() {
+                return Err(anyhow::anyhow!("Invalid [model_aliases] section in config: '{model}' = '{alias}' needs a name on both sides"));
+            }
+        }
+        for (project, code) in &self.billing_codes {
+            if project.trim().is_empty() || code.trim().is_empty() {
+                return Err(anyhow::anyhow!("Invalid [billing_codes] section in config: '{project}' = '{code}' needs a project and a code"));
+            }
+        }
+        validate_path_mappings(&self.path_mappings)
+            .map_err(|e| anyhow::anyhow!("Invalid path_mappings in config: {e}"))?;
+        self.mqtt
+            .validate()
+            .map_err(|e| anyhow::anyhow!("Invalid [mqtt] section in config: {e}"))?;
+        self.smtp
+            .validate()
+            .map_err(|e| anyhow::anyhow!("Invalid [smtp] section in config: {e}"))?;
+        if self.smtp.server.is_none() && self.alerts.channels.iter().any(|channel| matches!(channel, AlertChannelConfig::Email { .. })) {
+            return Err(anyhow::anyhow!("Invalid [alerts] section in config: email channels need an [smtp] server"));
+        }
+        self.encryption
+            .validate()
+            .map_err(|e| anyhow::anyhow!("Invalid [encryption] section in config: {e}"))
+    }
+
+    /// Time between rescans of the usage files
+    pub fn scan_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.update_interval_seconds.max(1))
+    }
+
+    /// Time between redraws of the UI
+    pub fn redraw_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.redraw_interval_ms.clamp(*REDRAW_MS_RANGE.start(), *REDRAW_MS_RANGE.end()))
+    }
+
+    /// Time between alert evaluations, None when alerts are evaluated after every scan
+    pub fn alert_interval(&self) -> Option<std::time::Duration> {
+        self.alert_interval_seconds.map(|seconds| std::time::Duration::from_secs(seconds.max(1)))
+    }
+
+    /// Apply per-project overrides on top of this config
+    pub fn apply_project_overrides(&mut self, overrides: &ProjectOverrides) {
+        if let Some(plan) = overrides.plan_type() {
+            self.default_plan = plan;
+        }
+        if let Some(threshold) = overrides.warning_threshold {
+            if (0.0..=1.0).contains(&threshold) {
+                self.warning_threshold = threshold;
+            }
+        }
+    }
+}
+
+/// Period a usage goal applies to (UTC days, weeks starting on Monday, billing cycles starting on
+/// the configured billing day)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GoalPeriod {
+    Day,
+    Week,
+    Month,
+}
+
+impl GoalPeriod {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GoalPeriod::Day => "daily",
+            GoalPeriod::Week => "weekly",
+            GoalPeriod::Month => "monthly",
+        }
+    }
+}
+
+/// Token and cost budgets to stay within; unset goals are not tracked
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GoalsConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily_tokens: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weekly_tokens: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily_cost: Option<f64>, // USD at API list prices
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weekly_cost: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monthly_tokens: Option<u64>, // per billing cycle
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monthly_cost: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub billing_day: Option<u32>, // day of month the subscription renews, 1 (calendar months) when unset
+}
+
+impl GoalsConfig {
+    /// Whether no goal is set (the billing day alone tracks nothing)
+    pub fn is_empty(&self) -> bool {
+        self == &Self { billing_day: self.billing_day, ..Self::default() }
+    }
+
+    /// Day of month billing cycles start on; months without that day start on their last day
+    pub fn billing_day(&self) -> u32 {
+        self.billing_day.unwrap_or(1)
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for (name, tokens) in [
+            ("daily_tokens", self.daily_tokens),
+            ("weekly_tokens", self.weekly_tokens),
+            ("monthly_tokens", self.monthly_tokens),
+        ] {
+            if tokens == Some(0) {
+                return Err(anyhow::anyhow!("{name} must be greater than 0"));
+            }
+        }
+        for (name, cost) in [
+            ("daily_cost", self.daily_cost),
+            ("weekly_cost", self.weekly_cost),
+            ("monthly_cost", self.monthly_cost),
+        ] {
+            if cost.is_some_and(|cost| !(cost.is_finite() && cost > 0.0)) {
+                return Err(anyhow::anyhow!("{name} must be a positive amount"));
+            }
+        }
+        if self.billing_day.is_some_and(|day| !(1..=31).contains(&day)) {
+            return Err(anyhow::anyhow!("billing_day must be a day of the month (1-31)"));
+        }
+        Ok(())
+    }
+
+    /// Set a period's goal from a token count ("2000000"), a USD amount ("$5") or "off" to clear both
+    pub fn set(&mut self, period: GoalPeriod, value: &str) -> anyhow::Result<()> {
+        // Work on a copy so a rejected value leaves the goals unchanged
+        let mut goals = self.clone();
+        let (tokens, cost) = match period {
+            GoalPeriod::Day => (&mut goals.daily_tokens, &mut goals.daily_cost),
+            GoalPeriod::Week => (&mut goals.weekly_tokens, &mut goals.weekly_cost),
+            GoalPeriod::Month => (&mut goals.monthly_tokens, &mut goals.monthly_cost),
+        };
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("off") {
+            *tokens = None;
+            *cost = None;
+        } else if let Some(amount) = value.strip_prefix('$') {
+            *cost = Some(amount.parse().map_err(|_| anyhow::anyhow!("Invalid cost goal: {value}"))?);
+        } else {
+            *tokens = Some(value.replace(['_', ','], "").parse().map_err(|_| {
+                anyhow::anyhow!("Invalid {} goal: {value}. Use a token count, a USD amount like '$5', or 'off'", period.name())
+            })?);
+        }
+        goals.validate()?;
+        *self = goals;
+        Ok(())
+    }
+}
+
+/// At-rest encryption of the session and metrics files; off unless a key source is set
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EncryptionConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_file: Option<std::path::PathBuf>, // file whose contents are the secret
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub passphrase_env: Option<String>, // environment variable holding the passphrase
+}
+
+impl EncryptionConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.key_file.is_some() || self.passphrase_env.is_some()
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.key_file.is_some() && self.passphrase_env.is_some() {
+            return Err(anyhow::anyhow!("set either key_file or passphrase_env, not both"));
+        }
+        if self.passphrase_env.as_deref().is_some_and(|name| name.trim().is_empty()) {
+            return Err(anyhow::anyhow!("passphrase_env must name an environment variable"));
         }
+        Ok(())
+    }
+}
+
+/// MQTT broker the monitor publishes usage to, for home automation; off unless a broker is set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub broker: Option<String>, // host, host:port or mqtt://host:port, port 1883 when left out
+    pub topic_prefix: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password_env: Option<String>, // environment variable holding the password
+    pub home_assistant_discovery: bool, // announce the topics as Home Assistant sensors
+    pub discovery_prefix: String,       // where Home Assistant listens for announcements
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            broker: None,
+            topic_prefix: "claude-token-monitor".to_string(),
+            username: None,
+            password_env: None,
+            home_assistant_discovery: false,
+            discovery_prefix: "homeassistant".to_string(),
+        }
+    }
+}
+
+impl MqttConfig {
+    /// Broker host and port, when publishing is configured. `mqtts://` brokers are refused, as
+    /// the client is built without TLS.
+    pub fn broker_address(&self) -> anyhow::Result<Option<(String, u16)>> {
+        let Some(broker) = self.broker.as_deref().map(str::trim) else {
+            return Ok(None);
+        };
+        let url = if broker.contains("://") { broker.to_string() } else { format!("mqtt://{broker}") };
+        let url = url::Url::parse(&url).map_err(|e| anyhow::anyhow!("broker '{broker}' is not a host or host:port: {e}"))?;
+        match url.scheme() {
+            "mqtt" => {}
+            "mqtts" => return Err(anyhow::anyhow!("mqtts:// brokers aren't supported; use a plain mqtt:// broker")),
+            scheme => return Err(anyhow::anyhow!("broker scheme '{scheme}://' isn't supported; use mqtt://")),
+        }
+        if !url.username().is_empty() || url.password().is_some() {
+            return Err(anyhow::anyhow!("set the broker's credentials with username and password_env, not in the address"));
+        }
+        let host = match url.host() {
+            Some(url::Host::Ipv6(address)) => address.to_string(),
+            Some(host) => host.to_string(),
+            None => return Err(anyhow::anyhow!("broker needs a host name")),
+        };
+        Ok(Some((host, url.port().unwrap_or(1883))))
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        self.broker_address()?;
+        for (name, prefix) in [("topic_prefix", &self.topic_prefix), ("discovery_prefix", &self.discovery_prefix)] {
+            let prefix = prefix.trim_end_matches('/');
+            if prefix.is_empty() || prefix.contains(['+', '#']) {
+                return Err(anyhow::anyhow!("{name} must be a topic without the wildcards + and #"));
+            }
+        }
+        if self.password_env.is_some() && self.username.is_none() {
+            return Err(anyhow::anyhow!("password_env needs a username"));
+        }
+        Ok(())
+    }
+}
+
+/// How the connection to the mail server is secured
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    #[default]
+    StartTls, // plain connection upgraded with STARTTLS, port 587 by default
+    Tls,      // TLS from the start, port 465 by default
+    None,     // unencrypted, for a relay on the local machine; port 25 by default
+}
+
+/// Mail server that email alert channels send through; off unless a server is set
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmtpConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>, // host or host:port
+    pub security: SmtpSecurity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password_env: Option<String>, // environment variable holding the password
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>, // sender address, the username when left out
+}
+
+impl SmtpConfig {
+    /// Server host and port, when sending is configured
+    pub fn server_address(&self) -> anyhow::Result<Option<(String, u16)>> {
+        let Some(server) = self.server.as_deref().map(str::trim) else {
+            return Ok(None);
+        };
+        let (host, port) = match server.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse().map_err(|_| anyhow::anyhow!("server port '{port}' is not a number"))?,
+            ),
+            None => (
+                server,
+                match self.security {
+                    SmtpSecurity::StartTls => 587,
+                    SmtpSecurity::Tls => 465,
+                    SmtpSecurity::None => 25,
+                },
+            ),
+        };
+        if host.is_empty() {
+            return Err(anyhow::anyhow!("server needs a host name"));
+        }
+        Ok(Some((host.to_string(), port)))
+    }
+
+    /// Address mail is sent from
+    pub fn sender(&self) -> Option<&str> {
+        self.from.as_deref().or(self.username.as_deref()).filter(|address| is_email_address(address))
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.server_address()?.is_none() {
+            return Ok(());
+        }
+        if self.sender().is_none() {
+            return Err(anyhow::anyhow!("set 'from' to the sender's email address"));
+        }
+        if self.password_env.is_some() && self.username.is_none() {
+            return Err(anyhow::anyhow!("password_env needs a username"));
+        }
+        if self.username.is_some() && self.security == SmtpSecurity::None {
+            return Err(anyhow::anyhow!("signing in needs security = \"starttls\" or \"tls\", so the password isn't sent in the clear"));
+        }
+        Ok(())
+    }
+}
+
+/// Roughly `name@domain`: enough to catch a missing or mistyped address in the config
+fn is_email_address(address: &str) -> bool {
+    address
+        .split_once('@')
+        .is_some_and(|(name, domain)| !name.is_empty() && domain.contains('.') && !address.contains([' ', '<', '>', ',', '\r', '\n']))
+}
+
+/// Other OS users on a shared machine whose Claude Code logs are read, and which users' usage counts
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsersConfig {
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub shared: BTreeSet<String>, // users who granted read access to their ~/.claude/projects
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub only: BTreeSet<String>, // count only logs these users own, everyone's when empty
+}
+
+impl UsersConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for name in self.shared.iter().chain(&self.only) {
+            if name.is_empty() || name.len() > 64 || name.contains('/') || name.chars().any(char::is_whitespace) {
+                return Err(anyhow::anyhow!("'{name}' is not a user name"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How requests are grouped into the windows a usage limit applies to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WindowStrategy {
+    #[default]
+    Rolling, // starts at the first request after the previous window reset
+    Fixed,   // aligned to the clock, at whole multiples of the window length since the Unix epoch
+    Gap,     // runs until a pause of `gap_minutes`, at most the window length
+}
+
+impl WindowStrategy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WindowStrategy::Rolling => "rolling",
+            WindowStrategy::Fixed => "fixed",
+            WindowStrategy::Gap => "gap",
+        }
+    }
+}
+
+impl std::str::FromStr for WindowStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(strategy: &str) -> Result<Self, Self::Err> {
+        match strategy.to_lowercase().as_str() {
+            "rolling" => Ok(WindowStrategy::Rolling),
+            "fixed" => Ok(WindowStrategy::Fixed),
+            "gap" => Ok(WindowStrategy::Gap),
+            _ => Err(anyhow::anyhow!(
+                "Invalid window strategy: {strategy}. Use 'rolling', 'fixed' or 'gap'"
+            )),
+        }
+    }
+}
+
+/// Length and strategy of the session windows, 5 hours rolling from the first request by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionWindowConfig {
+    pub hours: u32,
+    pub strategy: WindowStrategy,
+    pub gap_minutes: u32, // pause that ends a window with the gap strategy
+}
+
+impl Default for SessionWindowConfig {
+    fn default() -> Self {
+        Self {
+            hours: 5,
+            strategy: WindowStrategy::default(),
+            gap_minutes: 60,
+        }
+    }
+}
+
+impl SessionWindowConfig {
+    pub fn duration(&self) -> chrono::Duration {
+        chrono::Duration::hours(self.hours as i64)
+    }
+
+    /// E.g. "5-hour rolling" or "5-hour gap (60 min)"
+    pub fn describe(&self) -> String {
+        match self.strategy {
+            WindowStrategy::Gap => format!("{}-hour gap ({} min)", self.hours, self.gap_minutes),
+            strategy => format!("{}-hour {}", self.hours, strategy.name()),
+        }
+    }
+
+    /// Start of a window opened by a request at `timestamp`
+    pub fn window_start(&self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
+        match self.strategy {
+            WindowStrategy::Fixed => {
+                let length = self.duration().num_seconds().max(1);
+                let start = timestamp.timestamp().div_euclid(length) * length;
+                DateTime::from_timestamp(start, 0).unwrap_or(timestamp)
+            }
+            WindowStrategy::Rolling | WindowStrategy::Gap => timestamp,
+        }
+    }
+
+    /// When the window that started at `start` and last saw a request at `last_activity` resets
+    pub fn reset_time(&self, start: DateTime<Utc>, last_activity: DateTime<Utc>) -> DateTime<Utc> {
+        match self.strategy {
+            WindowStrategy::Gap => {
+                (last_activity + chrono::Duration::minutes(self.gap_minutes as i64)).min(start + self.duration())
+            }
+            WindowStrategy::Rolling | WindowStrategy::Fixed => start + self.duration(),
+        }
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !(1..=168).contains(&self.hours) {
+            return Err(anyhow::anyhow!("hours must be between 1 and 168"));
+        }
+        if self.strategy == WindowStrategy::Gap && self.gap_minutes == 0 {
+            return Err(anyhow::anyhow!("gap_minutes must be at least 1"));
+        }
+        Ok(())
+    }
+}
+
+/// Per-minute API rate limits of a model, as listed for the organization's usage tier; unset
+/// limits are not tracked
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimit {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requests_per_minute: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_tokens_per_minute: Option<u32>, // uncached input and cache writes; cache reads don't count
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_tokens_per_minute: Option<u32>,
+}
+
+impl RateLimit {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let limits = [self.requests_per_minute, self.input_tokens_per_minute, self.output_tokens_per_minute];
+        if limits.iter().all(Option::is_none) {
+            return Err(anyhow::anyhow!("set at least one of requests_per_minute, input_tokens_per_minute or output_tokens_per_minute"));
+        }
+        if limits.contains(&Some(0)) {
+            return Err(anyhow::anyhow!("limits must be at least 1"));
+        }
+        Ok(())
+    }
+}
+
+/// API list prices for a model family in USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub output: f64,
+    pub cache_write: f64,
+    pub cache_read: f64,
+    #[serde(default)]
+    pub long_context: bool, // prompts past 200k tokens are billed at the long-context tier
+}
+
+impl ModelPricing {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let prices = [self.input, self.output, self.cache_write, self.cache_read];
+        if !prices.iter().all(|price| price.is_finite() && *price >= 0.0) {
+            return Err(anyhow::anyhow!("prices must be zero or more USD per million tokens"));
+        }
+        Ok(())
+    }
+}
+
+/// Per-project settings read from `.claude-token-monitor.toml` in a project directory
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectOverrides {
+    pub label: Option<String>,
+    pub plan: Option<String>, // "pro", "max5", "max20" or a custom limit
+    pub token_limit: Option<u32>,
+    pub warning_threshold: Option<f64>,
+}
+
+impl ProjectOverrides {
+    /// Effective plan for the project, with `token_limit` taking precedence
+    pub fn plan_type(&self) -> Option<PlanType> {
+        if let Some(limit) = self.token_limit {
+            return Some(PlanType::Custom(limit));
+        }
+        self.plan.as_deref().and_then(|plan| match plan.parse() {
+            Ok(plan_type) => Some(plan_type),
+            Err(e) => {
+                log::warn!("Ignoring project plan override: {e}");
+                None
+            }
+        })
     }
 }
 
 /// Color scheme for terminal UI
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ColorScheme {
     pub progress_bar_full: String,
     pub progress_bar_empty: String,
@@ -140,6 +990,408 @@ impl Default for ColorScheme {
     }
 }
 
+/// Maximum relative size a single layout widget may request
+pub const MAX_WIDGET_SIZE: u16 = 12;
+
+/// Widgets that can be placed on the Overview tab
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WidgetKind {
+    Rate,
+    Cache,
+    Progress,
+    Efficiency,
+    Cost,
+    Chart,
+    Heatmap,
+    Custom,  // output of a user-specified command
+    Scripts, // derived metrics and alerts from user scripts
+    #[serde(rename = "rate_limits")]
+    RateLimits, // recent requests and tokens per minute against the configured API rate limits
+    Models,     // tokens and requests per model in the current window, in each model's color
+}
+
+impl WidgetKind {
+    /// Small stat panels share a row; charts and heatmaps take full-width rows
+    pub fn is_panel(&self) -> bool {
+        !matches!(self, WidgetKind::Chart | WidgetKind::Heatmap)
+    }
+}
+
+/// A widget and its size relative to its neighbours
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WidgetConfig {
+    pub widget: WidgetKind,
+    #[serde(default = "default_widget_size")]
+    pub size: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>, // custom panels only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>, // custom panels only, run through the shell on every refresh
+}
+
+fn default_widget_size() -> u16 {
+    1
+}
+
+impl WidgetConfig {
+    pub fn new(widget: WidgetKind, size: u16) -> Self {
+        Self {
+            widget,
+            size,
+            title: None,
+            command: None,
+        }
+    }
+
+    /// A custom panel showing the stdout of `command`
+    pub fn custom(title: &str, command: &str, size: u16) -> Self {
+        Self {
+            widget: WidgetKind::Custom,
+            size,
+            title: Some(title.to_string()),
+            command: Some(command.to_string()),
+        }
+    }
+}
+
+/// Widgets shown in the Overview tab's detailed view, in display order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayoutConfig {
+    pub widgets: Vec<WidgetConfig>,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            widgets: vec![
+                WidgetConfig::new(WidgetKind::Rate, 1),
+                WidgetConfig::new(WidgetKind::Cache, 1),
+                WidgetConfig::new(WidgetKind::Progress, 1),
+                WidgetConfig::new(WidgetKind::Efficiency, 1),
+                WidgetConfig::new(WidgetKind::Chart, 1),
+            ],
+        }
+    }
+}
+
+impl LayoutConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.widgets.is_empty() {
+            return Err(anyhow::anyhow!("at least one widget is required"));
+        }
+        let mut seen = Vec::new();
+        for widget in &self.widgets {
+            if widget.size == 0 || widget.size > MAX_WIDGET_SIZE {
+                return Err(anyhow::anyhow!(
+                    "size of '{:?}' widget must be between 1 and {}, got {}",
+                    widget.widget, MAX_WIDGET_SIZE, widget.size
+                ));
+            }
+            if widget.widget == WidgetKind::Custom {
+                if widget.command.as_deref().is_none_or(|command| command.trim().is_empty()) {
+                    return Err(anyhow::anyhow!("custom widget needs a non-empty 'command'"));
+                }
+                // Any number of custom panels is fine
+                continue;
+            }
+            if widget.command.is_some() {
+                return Err(anyhow::anyhow!("'command' is only allowed on custom widgets, not '{:?}'", widget.widget));
+            }
+            if seen.contains(&widget.widget) {
+                return Err(anyhow::anyhow!("'{:?}' widget is listed more than once", widget.widget));
+            }
+            seen.push(widget.widget);
+        }
+        Ok(())
+    }
+
+    /// Commands of all custom panels in the layout
+    pub fn panel_commands(&self) -> Vec<String> {
+        let mut commands: Vec<String> = Vec::new();
+        for command in self.widgets.iter().filter_map(|widget| widget.command.as_ref()) {
+            if !commands.contains(command) {
+                commands.push(command.clone());
+            }
+        }
+        commands
+    }
+
+    /// Group widgets into display rows: consecutive panels share a row,
+    /// every chart or heatmap gets a row of its own
+    pub fn rows(&self) -> Vec<Vec<WidgetConfig>> {
+        let mut rows: Vec<Vec<WidgetConfig>> = Vec::new();
+        for widget in &self.widgets {
+            match rows.last_mut() {
+                Some(row) if widget.widget.is_panel() && row.iter().all(|w| w.widget.is_panel()) => {
+                    row.push(widget.clone());
+                }
+                _ => rows.push(vec![widget.clone()]),
+            }
+        }
+        rows
+    }
+}
+
+/// A named Rhai expression evaluated against the current metrics
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScriptRule {
+    pub name: String,
+    pub expression: String,
+}
+
+/// User scripts evaluated on every refresh
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScriptsConfig {
+    pub alerts: Vec<ScriptRule>,  // must evaluate to a bool; fires while true
+    pub metrics: Vec<ScriptRule>, // any value, shown as a derived metric
+}
+
+/// Every `CTM_EVENT` name, which email and Telegram channels can be limited to
+pub const ALERT_EVENTS: [&str; 8] = [
+    "threshold",
+    "limit_reached",
+    "script",
+    "rate",
+    "reset_soon",
+    "window_summary",
+    "budget_forecast",
+    "weekly_summary",
+];
+
+/// Where alerts are delivered
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum AlertChannelConfig {
+    /// Run a shell command with the alert details in `CTM_*` environment variables
+    Command { command: String },
+    /// Email the alert through the `[smtp]` server, only for the listed events when any are
+    Email {
+        to: Vec<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        events: Vec<String>,
+    },
+    /// Message a Telegram chat as the bot whose token is in `bot_token_env`, only for the listed
+    /// events when any are; the headless monitor also answers `/status` there
+    Telegram {
+        bot_token_env: String,
+        chat_id: i64,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        events: Vec<String>,
+    },
+    /// Send to every Apprise-style notification URL (`ntfy://`, `discord://`, `apprise://`, ...),
+    /// only for the listed events when any are; `${NAME}` in a URL reads an environment variable
+    Notify {
+        urls: Vec<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        events: Vec<String>,
+    },
+}
+
+impl AlertChannelConfig {
+    /// Whether the channel delivers alerts of this `CTM_EVENT` kind
+    pub fn wants(&self, event: &str) -> bool {
+        match self {
+            AlertChannelConfig::Command { .. } => true,
+            AlertChannelConfig::Email { events, .. }
+            | AlertChannelConfig::Telegram { events, .. }
+            | AlertChannelConfig::Notify { events, .. } => {
+                events.is_empty() || events.iter().any(|wanted| wanted == event)
+            }
+        }
+    }
+}
+
+/// Sustained burn rate that triggers an alert, e.g. over 500 tokens/min for 10 minutes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateRule {
+    pub tokens_per_minute: f64,
+    pub minutes: u32, // 1 to 60; at least 80% of these minutes must have usage
+    #[serde(default = "default_rate_cooldown")]
+    pub cooldown_minutes: u32, // quiet time after firing before the rule can fire again
+}
+
+fn default_rate_cooldown() -> u32 {
+    30
+}
+
+/// Usage thresholds and the channels alerts are sent to
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertsConfig {
+    pub thresholds: Vec<f64>, // fractions of the token limit; empty means just the warning threshold
+    pub rates: Vec<RateRule>,
+    pub reset_lead_minutes: Vec<u32>, // countdown alerts this many minutes before the window resets
+    pub window_summary: bool,         // alert with the finished window's totals once it resets
+    pub weekly_summary: bool,         // alert with last week's tokens, cost, top projects and limit hits once it ends
+    pub channels: Vec<AlertChannelConfig>,
+}
+
+impl AlertsConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for threshold in &self.thresholds {
+            if !(*threshold > 0.0 && *threshold <= 1.0) {
+                return Err(anyhow::anyhow!("threshold {threshold} must be between 0 and 1"));
+            }
+        }
+        for rule in &self.rates {
+            if rule.tokens_per_minute.is_nan() || rule.tokens_per_minute <= 0.0 {
+                return Err(anyhow::anyhow!("rate rule needs a positive 'tokens_per_minute'"));
+            }
+            if !(1..=60).contains(&rule.minutes) {
+                return Err(anyhow::anyhow!("rate rule 'minutes' must be between 1 and 60, got {}", rule.minutes));
+            }
+        }
+        for lead in &self.reset_lead_minutes {
+            if !(1..=300).contains(lead) {
+                return Err(anyhow::anyhow!("reset lead time must be between 1 and 300 minutes, got {lead}"));
+            }
+        }
+        for channel in &self.channels {
+            match channel {
+                AlertChannelConfig::Command { command } if command.trim().is_empty() => {
+                    return Err(anyhow::anyhow!("command channel needs a non-empty 'command'"));
+                }
+                AlertChannelConfig::Command { .. } => {}
+                AlertChannelConfig::Email { to, events } => {
+                    if to.is_empty() || to.iter().any(|address| !is_email_address(address)) {
+                        return Err(anyhow::anyhow!("email channel needs 'to' addresses like name@example.com"));
+                    }
+                    validate_channel_events("email", events)?;
+                }
+                AlertChannelConfig::Telegram { bot_token_env, chat_id, events } => {
+                    if bot_token_env.trim().is_empty() || *chat_id == 0 {
+                        return Err(anyhow::anyhow!("telegram channel needs a 'bot_token_env' and a numeric 'chat_id'"));
+                    }
+                    validate_channel_events("telegram", events)?;
+                }
+                AlertChannelConfig::Notify { urls, events } => {
+                    if urls.is_empty() {
+                        return Err(anyhow::anyhow!("notify channel needs 'urls' like ntfy://ntfy.sh/my-topic"));
+                    }
+                    validate_channel_events("notify", events)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn validate_channel_events(channel: &str, events: &[String]) -> anyhow::Result<()> {
+    match events.iter().find(|event| !ALERT_EVENTS.contains(&event.as_str())) {
+        Some(event) => Err(anyhow::anyhow!("unknown event '{event}' in {channel} channel; use {}", ALERT_EVENTS.join(", "))),
+        None => Ok(()),
+    }
+}
+
+/// Which fields identify two usage entries as copies of the same API response
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DedupStrategy {
+    #[default]
+    Ids,           // message and request IDs; entries missing both fall back to content
+    IdsAndContent, // IDs plus timestamp and usage, so only exact copies collapse
+    Content,       // timestamp and usage only, for logs without usable IDs
+}
+
+impl DedupStrategy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DedupStrategy::Ids => "ids",
+            DedupStrategy::IdsAndContent => "ids-and-content",
+            DedupStrategy::Content => "content",
+        }
+    }
+}
+
+impl std::str::FromStr for DedupStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(strategy: &str) -> Result<Self, Self::Err> {
+        match strategy.to_lowercase().as_str() {
+            "ids" => Ok(DedupStrategy::Ids),
+            "ids-and-content" => Ok(DedupStrategy::IdsAndContent),
+            "content" => Ok(DedupStrategy::Content),
+            _ => Err(anyhow::anyhow!(
+                "Invalid dedup strategy: {strategy}. Use 'ids', 'ids-and-content' or 'content'"
+            )),
+        }
+    }
+}
+
+/// Prompt cache lifetime; 1-hour cache writes cost twice the input price instead of 1.25 times
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum CacheTtl {
+    #[default]
+    #[serde(rename = "5m")]
+    FiveMinutes, // the default prompt cache
+    #[serde(rename = "1h")]
+    OneHour, // extended cache, writes cost more
+}
+
+impl CacheTtl {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CacheTtl::FiveMinutes => "5m",
+            CacheTtl::OneHour => "1h",
+        }
+    }
+}
+
+impl std::str::FromStr for CacheTtl {
+    type Err = anyhow::Error;
+
+    fn from_str(ttl: &str) -> Result<Self, Self::Err> {
+        match ttl.to_lowercase().as_str() {
+            "5m" => Ok(CacheTtl::FiveMinutes),
+            "1h" => Ok(CacheTtl::OneHour),
+            _ => Err(anyhow::anyhow!("Invalid cache TTL: {ttl}. Use '5m' or '1h'")),
+        }
+    }
+}
+
+/// Whether the Overview chart is drawn as an inline image instead of Braille lines
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChartGraphics {
+    #[default]
+    Off,   // Braille lines, which work everywhere
+    Auto,  // an image when the terminal is known to support kitty or sixel graphics
+    Kitty, // kitty graphics protocol (kitty, WezTerm, Ghostty)
+    Sixel, // sixel images (foot, mlterm, xterm with sixel support)
+}
+
+/// When the UI backs off scanning and redrawing to save energy
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LowPowerMode {
+    #[default]
+    Auto, // while running on battery, where that can be detected (Linux and macOS)
+    On,
+    Off,
+}
+
+/// How models are told apart in reports, after `model_aliases` are applied
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelGrouping {
+    #[default]
+    Model,   // each model ID as logged
+    Version, // snapshots of a version together, e.g. claude-sonnet-4-20250514 as claude-sonnet-4
+    Family,  // every Opus, Sonnet or Haiku version together
+}
+
+/// Versioned envelope for the persisted observed sessions file
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SessionsFile {
+    pub version: u32,
+    pub sessions: Vec<TokenSession>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub imported: Vec<TokenSession>, // sessions merged in from other machines' files
+}
+
 /// Application state and runtime data
 #[derive(Debug, Clone)]
 pub struct AppState {
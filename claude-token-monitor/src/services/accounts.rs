@@ -0,0 +1,129 @@
+use super::file_monitor::{Billing, UsageEntry};
+use super::pricing::Prices;
+use crate::models::{validate_account_name, SessionWindowConfig};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Claude Code homes to monitor by account name, as `name=path,name=path`, on top of the config's
+/// `accounts`
+pub const ACCOUNTS_ENV: &str = "CLAUDE_ACCOUNTS";
+
+/// Label for entries from a discovered data directory that no account claims
+pub const DEFAULT_ACCOUNT: &str = "default";
+
+/// Parse `name=path` pairs separated by commas
+pub fn parse_accounts(spec: &str) -> Result<BTreeMap<String, PathBuf>> {
+    let mut accounts = BTreeMap::new();
+    for pair in spec.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+        let (name, path) = pair
+            .split_once('=')
+            .ok_or_else(|| anyhow!("'{pair}' is not name=path"))?;
+        let name = name.trim();
+        validate_account_name(name)?;
+        accounts.insert(name.to_string(), PathBuf::from(path.trim()));
+    }
+    Ok(accounts)
+}
+
+/// Accounts from `CLAUDE_ACCOUNTS`, none when it is unset or invalid
+pub fn accounts_from_env() -> BTreeMap<String, PathBuf> {
+    match std::env::var(ACCOUNTS_ENV) {
+        Ok(spec) => parse_accounts(&spec).unwrap_or_else(|e| {
+            log::warn!("Ignoring {ACCOUNTS_ENV}: {e}");
+            BTreeMap::new()
+        }),
+        Err(_) => BTreeMap::new(),
+    }
+}
+
+/// The directory holding an account's conversation logs: `projects/` under a Claude Code home,
+/// or the path itself when it already is one (a leading `~` is the home directory)
+pub fn projects_dir(home: &Path) -> PathBuf {
+    let home = match (home.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(user_home)) => user_home.join(rest),
+        _ => home.to_path_buf(),
+    };
+    let projects = home.join("projects");
+    if projects.is_dir() {
+        projects
+    } else {
+        home
+    }
+}
+
+/// One account's usage over the reported days and in its own session window
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountUsage {
+    pub account: String,
+    pub tokens: u64,
+    pub requests: usize,
+    pub estimated_cost: f64, // API-equivalent USD
+    pub last_activity: Option<DateTime<Utc>>,
+    pub window_tokens: u64,                // in the account's window in progress, 0 when none is
+    pub window_reset: Option<DateTime<Utc>>, // when that window resets
+}
+
+/// Usage per account since `since`, busiest first, with each account's current window.
+///
+/// Separate subscriptions have separate limits, so windows are tracked per account rather than
+/// over the merged entries; usage billed to an API key has no window. Untagged entries count under
+/// `DEFAULT_ACCOUNT`.
+pub fn account_breakdown<'a>(
+    entries: impl IntoIterator<Item = &'a UsageEntry>,
+    window: &SessionWindowConfig,
+    since: DateTime<Utc>,
+    now: DateTime<Utc>,
+    prices: Prices,
+) -> Vec<AccountUsage> {
+    // Window start, reset and tokens per account, following the same rule as the session blocks
+    let mut windows: BTreeMap<&str, (DateTime<Utc>, DateTime<Utc>, u64)> = BTreeMap::new();
+    let mut usage: BTreeMap<&str, AccountUsage> = BTreeMap::new();
+    for entry in entries {
+        if entry.timestamp > now {
+            continue;
+        }
+        let account = entry.account.as_deref().unwrap_or(DEFAULT_ACCOUNT);
+        let tokens = entry.usage.total_tokens() as u64;
+        match windows.get_mut(account) {
+            _ if entry.billing == Billing::ApiKey => {}
+            Some((start, reset, window_tokens)) if entry.timestamp < *reset => {
+                *reset = window.reset_time(*start, entry.timestamp);
+                *window_tokens += tokens;
+            }
+            _ => {
+                let start = window.window_start(entry.timestamp);
+                windows.insert(account, (start, window.reset_time(start, entry.timestamp), tokens));
+            }
+        }
+        if entry.timestamp < since {
+            continue;
+        }
+        let totals = usage.entry(account).or_insert_with(|| AccountUsage {
+            account: account.to_string(),
+            tokens: 0,
+            requests: 0,
+            estimated_cost: 0.0,
+            last_activity: None,
+            window_tokens: 0,
+            window_reset: None,
+        });
+        totals.tokens += tokens;
+        totals.requests += 1;
+        totals.estimated_cost += prices.cost(entry);
+        totals.last_activity = totals.last_activity.max(Some(entry.timestamp));
+    }
+
+    let mut usage: Vec<AccountUsage> = usage.into_values().collect();
+    for totals in &mut usage {
+        if let Some((_, reset, tokens)) = windows.get(totals.account.as_str()) {
+            if *reset > now {
+                totals.window_tokens = *tokens;
+                totals.window_reset = Some(*reset);
+            }
+        }
+    }
+    usage.sort_by(|a, b| b.tokens.cmp(&a.tokens).then_with(|| a.account.cmp(&b.account)));
+    usage
+}
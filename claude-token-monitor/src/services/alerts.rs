@@ -0,0 +1,497 @@
+use super::custom_panel::shell_command;
+use super::email;
+use super::forecast::SpendForecast;
+use super::notify_url::{self, Notification};
+use super::telegram;
+use super::usage_stats::WeeklySummary;
+use crate::models::*;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::HashSet;
+use std::process::Stdio;
+use std::time::Duration;
+
+/// Alert commands taking longer than this are killed
+pub const ALERT_COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What triggered an alert
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertKind {
+    Threshold(f64), // usage crossed this fraction of the token limit
+    LimitReached,   // Claude Code reported the usage limit was hit
+    Script(String), // a user script alert condition became true
+    Rate { tokens_per_minute: f64, limit: f64, minutes: u32 }, // averaged above `limit` for `minutes`
+    ResetSoon { lead: u32, minutes_left: u32 }, // the window resets within `lead` minutes, in `minutes_left`
+    WindowSummary(DateTime<Utc>), // the window that started at this time has reset
+    BudgetForecast { forecast: f64, budget: f64 }, // the billing cycle is on pace to cost more than the monthly cost goal
+    WeeklySummary(WeeklySummary), // the week has ended
+}
+
+impl AlertKind {
+    /// Stable identifier exposed to channels as `CTM_EVENT`
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            AlertKind::Threshold(_) => "threshold",
+            AlertKind::LimitReached => "limit_reached",
+            AlertKind::Script(_) => "script",
+            AlertKind::Rate { .. } => "rate",
+            AlertKind::ResetSoon { .. } => "reset_soon",
+            AlertKind::WindowSummary(_) => "window_summary",
+            AlertKind::BudgetForecast { .. } => "budget_forecast",
+            AlertKind::WeeklySummary(_) => "weekly_summary",
+        }
+    }
+}
+
+/// A single alert with a snapshot of the metrics that triggered it
+#[derive(Debug, Clone)]
+pub struct Alert {
+    pub kind: AlertKind,
+    pub message: String,
+    pub timestamp: DateTime<Utc>,
+    pub usage_pct: f64,
+    pub tokens_used: u32,
+    pub tokens_limit: u32,
+    pub reset_time: DateTime<Utc>,
+    pub plan: PlanType,
+    pub project: Option<String>,
+    pub estimated_cost: f64,
+    pub identity: Option<AccountIdentity>, // signed-in account, set by the manager
+}
+
+impl Alert {
+    pub fn from_metrics(kind: AlertKind, metrics: &UsageMetrics) -> Self {
+        let session = &metrics.current_session;
+        let usage_pct = Self::usage_fraction(metrics) * 100.0;
+        let message = match &kind {
+            AlertKind::Threshold(threshold) => format!(
+                "Token usage at {usage_pct:.1}% (crossed {:.0}%), {} tokens remaining",
+                threshold * 100.0,
+                session.tokens_limit.saturating_sub(session.tokens_used)
+            ),
+            AlertKind::LimitReached => format!(
+                "Usage limit reached, resets at {}",
+                session.reset_time.format("%H:%M UTC")
+            ),
+            AlertKind::Script(name) => format!("Script alert: {name}"),
+            AlertKind::Rate { tokens_per_minute, limit, minutes } => format!(
+                "Burning {tokens_per_minute:.0} tokens/min for the last {minutes} minutes (alert above {limit:.0}), a runaway loop?"
+            ),
+            AlertKind::ResetSoon { minutes_left, .. } => format!(
+                "Session window resets in {minutes_left} minutes at {}, {} tokens remaining",
+                session.reset_time.format("%H:%M UTC"),
+                session.tokens_limit.saturating_sub(session.tokens_used)
+            ),
+            AlertKind::WindowSummary(start_time) => format!(
+                "Window {}-{} finished: {} tokens ({usage_pct:.1}% of {}), about ${:.2}",
+                start_time.format("%H:%M"),
+                session.reset_time.format("%H:%M UTC"),
+                session.tokens_used,
+                session.tokens_limit,
+                metrics.estimated_cost
+            ),
+            AlertKind::BudgetForecast { forecast, budget } => format!(
+                "Spending is on pace for ${forecast:.2} this billing cycle, over the ${budget:.2} monthly budget"
+            ),
+            AlertKind::WeeklySummary(summary) => format!(
+                "Week of {}: {} tokens, about ${:.2}, limit hit {} times",
+                summary.week_start.format("%Y-%m-%d"),
+                summary.usage.tokens,
+                summary.usage.cost,
+                summary.limit_hits
+            ),
+        };
+
+        Self {
+            kind,
+            message,
+            timestamp: Utc::now(),
+            usage_pct,
+            tokens_used: session.tokens_used,
+            tokens_limit: session.tokens_limit,
+            reset_time: session.reset_time,
+            plan: session.plan_type.clone(),
+            project: metrics.project_label.clone(),
+            estimated_cost: metrics.estimated_cost,
+            identity: None,
+        }
+    }
+
+    fn usage_fraction(metrics: &UsageMetrics) -> f64 {
+        let session = &metrics.current_session;
+        if session.tokens_limit == 0 {
+            0.0
+        } else {
+            session.tokens_used as f64 / session.tokens_limit as f64
+        }
+    }
+
+    /// Environment passed to command channels
+    pub fn env_vars(&self) -> Vec<(&'static str, String)> {
+        let mut vars = vec![
+            ("CTM_EVENT", self.kind.event_name().to_string()),
+            ("CTM_MESSAGE", self.message.clone()),
+            ("CTM_TIMESTAMP", self.timestamp.to_rfc3339()),
+            ("CTM_USAGE_PCT", format!("{:.1}", self.usage_pct)),
+            ("CTM_TOKENS_USED", self.tokens_used.to_string()),
+            ("CTM_TOKENS_LIMIT", self.tokens_limit.to_string()),
+            ("CTM_REMAINING_TOKENS", self.tokens_limit.saturating_sub(self.tokens_used).to_string()),
+            ("CTM_RESET_TIME", self.reset_time.to_rfc3339()),
+            (
+                "CTM_MINUTES_TO_RESET",
+                self.reset_time.signed_duration_since(self.timestamp).num_minutes().max(0).to_string(),
+            ),
+            ("CTM_PLAN", format!("{:?}", self.plan)),
+            ("CTM_PROJECT", self.project.clone().unwrap_or_default()),
+            ("CTM_ESTIMATED_COST", format!("{:.2}", self.estimated_cost)),
+        ];
+        match &self.kind {
+            AlertKind::Threshold(threshold) => vars.push(("CTM_THRESHOLD_PCT", format!("{:.0}", threshold * 100.0))),
+            AlertKind::Script(name) => vars.push(("CTM_SCRIPT_ALERT", name.clone())),
+            AlertKind::Rate { tokens_per_minute, limit, minutes } => {
+                vars.push(("CTM_RATE", format!("{tokens_per_minute:.0}")));
+                vars.push(("CTM_RATE_LIMIT", format!("{limit:.0}")));
+                vars.push(("CTM_RATE_MINUTES", minutes.to_string()));
+            }
+            AlertKind::ResetSoon { lead, .. } => vars.push(("CTM_RESET_LEAD_MINUTES", lead.to_string())),
+            AlertKind::WindowSummary(start_time) => vars.push(("CTM_WINDOW_START", start_time.to_rfc3339())),
+            AlertKind::BudgetForecast { forecast, budget } => {
+                vars.push(("CTM_FORECAST_COST", format!("{forecast:.2}")));
+                vars.push(("CTM_BUDGET", format!("{budget:.2}")));
+            }
+            AlertKind::WeeklySummary(summary) => {
+                vars.push(("CTM_WEEK_START", summary.week_start.format("%Y-%m-%d").to_string()));
+                vars.push(("CTM_WEEK_TOKENS", summary.usage.tokens.to_string()));
+                vars.push(("CTM_WEEK_COST", format!("{:.2}", summary.usage.cost)));
+                vars.push(("CTM_WEEK_LIMIT_HITS", summary.limit_hits.to_string()));
+                let projects: Vec<&str> = summary.top_projects.iter().map(|(project, _)| project.as_str()).collect();
+                vars.push(("CTM_WEEK_TOP_PROJECTS", projects.join("; ")));
+            }
+            AlertKind::LimitReached => {}
+        }
+        if let Some(identity) = &self.identity {
+            vars.push(("CTM_ORGANIZATION_ID", identity.organization_id.clone().unwrap_or_default()));
+            vars.push(("CTM_USER_ID", identity.user_id.clone().unwrap_or_default()));
+        }
+        vars
+    }
+}
+
+impl Alert {
+    /// Subject line of the alert as an email
+    pub fn email_subject(&self) -> String {
+        match &self.kind {
+            AlertKind::WeeklySummary(summary) => format!("Claude usage, week of {}", summary.week_start.format("%Y-%m-%d")),
+            _ => format!("Claude Token Monitor: {}", self.message),
+        }
+    }
+
+    /// Body of the alert as an email: the weekly report, or the message and the window's figures
+    pub fn email_body(&self) -> String {
+        if let AlertKind::WeeklySummary(summary) = &self.kind {
+            return summary.report();
+        }
+        let mut lines = vec![
+            self.message.clone(),
+            String::new(),
+            format!("Usage: {:.1}% of {} tokens ({} used)", self.usage_pct, self.tokens_limit, self.tokens_used),
+            format!("Window resets: {}", self.reset_time.format("%Y-%m-%d %H:%M UTC")),
+            format!("Plan: {:?}", self.plan),
+            format!("Estimated cost: ${:.2}", self.estimated_cost),
+        ];
+        if let Some(project) = &self.project {
+            lines.push(format!("Project: {project}"));
+        }
+        lines.join("\n")
+    }
+
+    /// The alert for notification services: the email's subject and body, flagged as a warning
+    /// unless it's a summary
+    pub fn notification(&self) -> Notification {
+        Notification {
+            title: self.email_subject(),
+            body: self.email_body(),
+            warning: !matches!(self.kind, AlertKind::WindowSummary(_) | AlertKind::WeeklySummary(_)),
+        }
+    }
+}
+
+/// Share of a rate rule's minutes that must have usage for its rate to count as sustained, so a
+/// pause to read a diff doesn't hide a loop but a single large request still doesn't count
+const SUSTAINED_ACTIVE_SHARE: f64 = 0.8;
+
+/// Average tokens per minute over the last `minutes` of `minute_tokens`, or None unless at least
+/// `SUSTAINED_ACTIVE_SHARE` of those minutes had usage
+fn sustained_rate(minute_tokens: &[u64], minutes: usize) -> Option<f64> {
+    if minutes == 0 || minute_tokens.len() < minutes {
+        return None;
+    }
+    let window = &minute_tokens[minute_tokens.len() - minutes..];
+    let active = window.iter().filter(|tokens| **tokens > 0).count();
+    if (active as f64) < (minutes as f64 * SUSTAINED_ACTIVE_SHARE).ceil() {
+        return None;
+    }
+    Some(window.iter().sum::<u64>() as f64 / minutes as f64)
+}
+
+/// Turns metric snapshots into alerts, firing each one once per session window.
+///
+/// Rate rules are the exception: they ignore windows, firing when the rate has been sustained and
+/// then staying quiet for their cooldown, so a loop that keeps running is reported once per cooldown.
+///
+/// The window summary fires once the reset time of the last active window seen has passed, with
+/// that window's totals as they were last observed.
+///
+/// The budget forecast fires once per billing cycle, after its first day, when the spend forecast
+/// passes the monthly cost goal.
+///
+/// The weekly summary fires once for each finished week it is given, unless that week's was
+/// already sent (by an earlier run, as the event log records).
+pub struct AlertManager {
+    thresholds: Vec<f64>,
+    rates: Vec<RateRule>,
+    reset_leads: Vec<u32>, // longest first
+    window_summary: bool,
+    channels: Vec<AlertChannelConfig>,
+    session_id: Option<String>,
+    fired: HashSet<String>,
+    rate_fired: Vec<Option<DateTime<Utc>>>, // when each rate rule last fired
+    finishing: Option<Alert>,                // summary of the active window, sent once it resets
+    identity: Option<AccountIdentity>,
+    monthly_budget: Option<f64>,
+    spend_forecast: Option<SpendForecast>,
+    budget_fired: Option<NaiveDate>, // start of the cycle the budget forecast last fired in
+    smtp: SmtpConfig,
+    weekly_summary: Option<WeeklySummary>, // the last finished week, when the summary is on
+    weekly_sent: Option<NaiveDate>,        // start of the week the summary was last sent for
+}
+
+impl AlertManager {
+    pub fn new(config: &UserConfig) -> Self {
+        let mut thresholds = if config.alerts.thresholds.is_empty() {
+            vec![config.warning_threshold]
+        } else {
+            config.alerts.thresholds.clone()
+        };
+        thresholds.sort_by(|a, b| a.total_cmp(b));
+        thresholds.dedup();
+        let mut reset_leads = config.alerts.reset_lead_minutes.clone();
+        reset_leads.sort_unstable_by(|a, b| b.cmp(a));
+        reset_leads.dedup();
+
+        Self {
+            thresholds,
+            rates: config.alerts.rates.clone(),
+            reset_leads,
+            window_summary: config.alerts.window_summary,
+            channels: config.alerts.channels.clone(),
+            session_id: None,
+            fired: HashSet::new(),
+            rate_fired: vec![None; config.alerts.rates.len()],
+            finishing: None,
+            identity: None,
+            monthly_budget: config.goals.monthly_cost,
+            spend_forecast: None,
+            budget_fired: None,
+            smtp: config.smtp.clone(),
+            weekly_summary: None,
+            weekly_sent: None,
+        }
+    }
+
+    /// Tag every alert with the signed-in account, so channels can group them by organization and user
+    pub fn set_identity(&mut self, identity: Option<AccountIdentity>) {
+        self.identity = identity;
+    }
+
+    /// Take the latest forecast of the billing cycle's spend, checked against the monthly cost goal
+    pub fn set_spend_forecast(&mut self, forecast: Option<SpendForecast>) {
+        self.spend_forecast = forecast;
+    }
+
+    /// Take the summary of the last finished week, sent unless it already was
+    pub fn set_weekly_summary(&mut self, summary: Option<WeeklySummary>) {
+        self.weekly_summary = summary;
+    }
+
+    /// Note the week whose summary an earlier run sent
+    pub fn set_weekly_summary_sent(&mut self, week_start: Option<NaiveDate>) {
+        self.weekly_sent = week_start;
+    }
+
+    /// Alerts that started firing since the last check
+    pub fn check(&mut self, metrics: &UsageMetrics, script_alerts: &[String]) -> Vec<Alert> {
+        self.check_at(metrics, script_alerts, Utc::now())
+    }
+
+    /// `check` as of `now`
+    pub fn check_at(&mut self, metrics: &UsageMetrics, script_alerts: &[String], now: DateTime<Utc>) -> Vec<Alert> {
+        // A new window re-arms every alert
+        if self.session_id.as_deref() != Some(metrics.current_session.id.as_str()) {
+            self.session_id = Some(metrics.current_session.id.clone());
+            self.fired.clear();
+        }
+
+        let mut alerts = Vec::new();
+        let usage = Alert::usage_fraction(metrics);
+
+        // Only the highest newly crossed threshold is worth announcing
+        let crossed: Vec<f64> = self
+            .thresholds
+            .iter()
+            .copied()
+            .filter(|threshold| usage >= *threshold && self.fired.insert(format!("threshold:{threshold}")))
+            .collect();
+        if let Some(highest) = crossed.last() {
+            alerts.push(Alert::from_metrics(AlertKind::Threshold(*highest), metrics));
+        }
+
+        let limit_reached = metrics.limit_events.iter().any(|event| event.kind == LimitEventKind::LimitReached);
+        if limit_reached && self.fired.insert("limit_reached".to_string()) {
+            alerts.push(Alert::from_metrics(AlertKind::LimitReached, metrics));
+        }
+
+        // Script alerts re-arm as soon as their condition turns false
+        self.fired.retain(|key| match key.strip_prefix("script:") {
+            Some(name) => script_alerts.iter().any(|alert| alert == name),
+            None => true,
+        });
+        for name in script_alerts {
+            if self.fired.insert(format!("script:{name}")) {
+                alerts.push(Alert::from_metrics(AlertKind::Script(name.clone()), metrics));
+            }
+        }
+
+        for (rule, last_fired) in self.rates.iter().zip(&mut self.rate_fired) {
+            let cooled_down = last_fired.is_none_or(|fired| now - fired >= chrono::Duration::minutes(rule.cooldown_minutes.into()));
+            let rate = sustained_rate(&metrics.minute_tokens, rule.minutes as usize).filter(|rate| *rate > rule.tokens_per_minute);
+            if let Some(rate) = rate.filter(|_| cooled_down) {
+                *last_fired = Some(now);
+                let kind = AlertKind::Rate { tokens_per_minute: rate, limit: rule.tokens_per_minute, minutes: rule.minutes };
+                alerts.push(Alert::from_metrics(kind, metrics));
+            }
+        }
+
+        // Like thresholds, only the shortest newly reached lead time is worth announcing
+        let session = &metrics.current_session;
+        if session.is_active && now < session.reset_time {
+            let remaining = session.reset_time - now;
+            let reached: Vec<u32> = self
+                .reset_leads
+                .iter()
+                .copied()
+                .filter(|lead| remaining <= chrono::Duration::minutes((*lead).into()) && self.fired.insert(format!("reset:{lead}")))
+                .collect();
+            if let Some(shortest) = reached.last() {
+                // Whole minutes, rounded up so a reset 4m30s away isn't announced as 4 minutes off
+                let minutes_left = u32::try_from((remaining.num_seconds() + 59) / 60).unwrap_or(u32::MAX);
+                let kind = AlertKind::ResetSoon { lead: *shortest, minutes_left };
+                alerts.push(Alert::from_metrics(kind, metrics));
+            }
+        }
+
+        if self.window_summary {
+            if let Some(mut summary) = self.finishing.take_if(|summary| now >= summary.reset_time) {
+                summary.timestamp = now;
+                alerts.push(summary);
+            }
+            if session.is_active && now < session.reset_time {
+                self.finishing = Some(Alert::from_metrics(AlertKind::WindowSummary(session.start_time), metrics));
+            }
+        }
+
+        if let (Some(forecast), Some(budget)) = (self.spend_forecast, self.monthly_budget) {
+            if forecast.elapsed_days >= 1.0 && forecast.exceeds(budget) && self.budget_fired != Some(forecast.cycle_start) {
+                self.budget_fired = Some(forecast.cycle_start);
+                alerts.push(Alert::from_metrics(AlertKind::BudgetForecast { forecast: forecast.expected(), budget }, metrics));
+            }
+        }
+
+        if let Some(summary) = self.weekly_summary.take_if(|summary| self.weekly_sent.is_none_or(|sent| sent < summary.week_start)) {
+            self.weekly_sent = Some(summary.week_start);
+            alerts.push(Alert::from_metrics(AlertKind::WeeklySummary(summary), metrics));
+        }
+
+        for alert in &mut alerts {
+            alert.identity = self.identity.clone();
+        }
+        alerts
+    }
+
+    /// Deliver alerts to every configured channel without waiting for them to finish
+    pub fn dispatch(&self, alerts: &[Alert]) {
+        for alert in alerts {
+            log::warn!("Alert: {}", alert.message);
+            for channel in &self.channels {
+                let channel = channel.clone();
+                let alert = alert.clone();
+                let smtp = self.smtp.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = send(&channel, &alert, &smtp).await {
+                        log::warn!("Failed to deliver alert: {e}");
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// Deliver one alert through one channel, unless it's limited to other events; email goes
+/// through `smtp`
+pub async fn send(channel: &AlertChannelConfig, alert: &Alert, smtp: &SmtpConfig) -> Result<()> {
+    if !channel.wants(alert.kind.event_name()) {
+        return Ok(());
+    }
+    match channel {
+        AlertChannelConfig::Command { command } => run_alert_command(command, alert).await,
+        AlertChannelConfig::Email { to, .. } => {
+            let (smtp, to) = (smtp.clone(), to.clone());
+            let (subject, body) = (alert.email_subject(), alert.email_body());
+            tokio::task::spawn_blocking(move || email::send_email(&smtp, &to, &subject, &body)).await?
+        }
+        AlertChannelConfig::Telegram { bot_token_env, chat_id, .. } => {
+            let (bot_token_env, chat_id, text) = (bot_token_env.clone(), *chat_id, alert.email_body());
+            tokio::task::spawn_blocking(move || telegram::send_message(&telegram::api_base(), &telegram::bot_token(&bot_token_env)?, chat_id, &text)).await?
+        }
+        AlertChannelConfig::Notify { urls, .. } => {
+            let (urls, notification) = (urls.clone(), alert.notification());
+            tokio::task::spawn_blocking(move || {
+                // One unreachable service doesn't keep the alert from the others
+                let errors: Vec<String> = urls
+                    .iter()
+                    .filter_map(|url| notify_url::notify(url, &notification).err())
+                    .map(|e| e.to_string())
+                    .collect();
+                if errors.is_empty() {
+                    Ok(())
+                } else {
+                    Err(anyhow!(errors.join("; ")))
+                }
+            })
+            .await?
+        }
+    }
+}
+
+async fn run_alert_command(command: &str, alert: &Alert) -> Result<()> {
+    let mut cmd = shell_command(command);
+    cmd.envs(alert.env_vars())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let output = tokio::time::timeout(ALERT_COMMAND_TIMEOUT, cmd.output())
+        .await
+        .map_err(|_| anyhow!("alert command timed out after {}s", ALERT_COMMAND_TIMEOUT.as_secs()))??;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!(
+            "alert command failed ({}): {}",
+            output.status,
+            stderr.lines().next().unwrap_or_default()
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,193 @@
+use super::{AnalyticsService, PlanFit, UsageAnalysis};
+use crate::models::{PlanType, SessionWindowConfig, TokenSession, TokenUsagePoint};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Timelike, Utc};
+use std::collections::BTreeMap;
+
+pub const DEFAULT_HISTORY_WEEKS: u32 = 4;
+
+pub const DAYS_PER_MONTH: f64 = 30.44;
+const PEAK_HOURS: usize = 3;
+
+/// A plan fits when no more than this share of sessions would have reached its limit
+pub const MAX_LIMIT_HIT_RATE: f64 = 0.05;
+
+/// Subscription plans considered for recommendations, cheapest first
+const SUBSCRIPTION_PLANS: [PlanType; 3] = [PlanType::Pro, PlanType::Max5, PlanType::Max20];
+
+/// Analytics over observed usage, evaluated as of a fixed point in time
+#[derive(Debug, Clone)]
+pub struct Analyzer {
+    now: DateTime<Utc>,
+    history_weeks: u32,
+    session_limit: u32,
+    session_minutes: f64,
+    api_cost: Option<f64>,
+    idle_gaps: Vec<(DateTime<Utc>, DateTime<Utc>)>,
+}
+
+impl Analyzer {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now,
+            history_weeks: DEFAULT_HISTORY_WEEKS,
+            session_limit: PlanType::Pro.default_limit(),
+            session_minutes: SessionWindowConfig::default().duration().num_minutes() as f64,
+            api_cost: None,
+            idle_gaps: Vec::new(),
+        }
+    }
+
+    /// Only analyze sessions that started in the last `weeks` weeks
+    pub fn with_history_weeks(mut self, weeks: u32) -> Self {
+        self.history_weeks = weeks.max(1);
+        self
+    }
+
+    /// Token limit of the current session, used for efficiency
+    pub fn with_session_limit(mut self, limit: u32) -> Self {
+        self.session_limit = limit;
+        self
+    }
+
+    /// Length of the current session's window, which the limit is spread over for efficiency
+    pub fn with_session_length(mut self, length: Duration) -> Self {
+        self.session_minutes = length.num_minutes().max(1) as f64;
+        self
+    }
+
+    /// API-equivalent cost of the usage in the analyzed period, used to price pay-as-you-go
+    pub fn with_api_cost(mut self, cost: f64) -> Self {
+        self.api_cost = Some(cost);
+        self
+    }
+
+    /// Pauses between requests inside sessions, counted against their duration
+    pub fn with_idle_gaps(mut self, gaps: Vec<(DateTime<Utc>, DateTime<Utc>)>) -> Self {
+        self.idle_gaps = gaps;
+        self
+    }
+
+    /// Earliest session start included in pattern analysis
+    pub fn history_start(&self) -> DateTime<Utc> {
+        self.now - Duration::weeks(self.history_weeks as i64)
+    }
+}
+
+impl AnalyticsService for Analyzer {
+    /// Tokens per minute between the first and last point of a cumulative history
+    fn calculate_usage_rate(&self, history: &[TokenUsagePoint]) -> f64 {
+        let (Some(first), Some(last)) = (history.first(), history.last()) else {
+            return 0.0;
+        };
+        let minutes = (last.timestamp - first.timestamp).num_seconds() as f64 / 60.0;
+        if minutes <= 0.0 {
+            return 0.0;
+        }
+        last.tokens_used.saturating_sub(first.tokens_used) as f64 / minutes
+    }
+
+    fn predict_depletion(&self, current_usage: u32, limit: u32, usage_rate: f64) -> Option<DateTime<Utc>> {
+        if usage_rate <= 0.0 {
+            return None;
+        }
+        let minutes_remaining = limit.saturating_sub(current_usage) as f64 / usage_rate;
+        Some(self.now + Duration::minutes(minutes_remaining as i64))
+    }
+
+    /// 1.0 while usage stays at or below the rate that spreads the limit over the whole window
+    fn calculate_efficiency(&self, usage_rate: f64, session_progress: f64) -> f64 {
+        if session_progress <= 0.0 {
+            return 1.0;
+        }
+        let expected_rate = self.session_limit as f64 / self.session_minutes;
+        let actual_rate = if usage_rate > 0.0 { usage_rate } else { 0.1 };
+        (expected_rate / actual_rate).clamp(0.0, 1.0)
+    }
+
+    /// Replay the sessions of the last few weeks against each plan's limit and recommend the
+    /// cheapest plan that would rarely have been hit, or API billing when that costs less
+    fn analyze_usage_patterns(&self, sessions: &[TokenSession]) -> Result<UsageAnalysis> {
+        let history_start = self.history_start();
+        let mut sessions: Vec<&TokenSession> = sessions
+            .iter()
+            .filter(|session| session.start_time >= history_start && session.start_time <= self.now)
+            .collect();
+        if sessions.is_empty() {
+            return Err(anyhow!("No sessions in the last {} weeks to analyze", self.history_weeks));
+        }
+        sessions.sort_by_key(|session| session.start_time);
+
+        let first_start = sessions[0].start_time.max(history_start);
+        let analyzed_days = ((self.now - first_start).num_minutes() as f64 / (24.0 * 60.0)).max(1.0);
+        let per_month = DAYS_PER_MONTH / analyzed_days;
+
+        let total_session_minutes = sessions
+            .iter()
+            .map(|session| {
+                let end = session.end_time.unwrap_or(self.now).min(session.reset_time);
+                (end - session.start_time).num_minutes().max(0) as f64
+            })
+            .sum::<f64>();
+        let average_session_duration = total_session_minutes / sessions.len() as f64;
+
+        let idle_gaps: Vec<_> = self.idle_gaps.iter().filter(|(start, _)| *start >= first_start).collect();
+        let idle_minutes: f64 = idle_gaps.iter().map(|(start, end)| (*end - *start).num_minutes() as f64).sum();
+        let idle_share = if total_session_minutes > 0.0 { (idle_minutes / total_session_minutes).min(1.0) } else { 0.0 };
+
+        let mut hours: BTreeMap<u32, u32> = BTreeMap::new();
+        for session in &sessions {
+            *hours.entry(session.start_time.hour()).or_default() += session.tokens_used;
+        }
+        let mut peak_usage_times: Vec<(u32, u32)> = hours.into_iter().collect();
+        peak_usage_times.sort_by_key(|(hour, tokens)| (std::cmp::Reverse(*tokens), *hour));
+        peak_usage_times.truncate(PEAK_HOURS);
+
+        let (older, newer) = sessions.split_at(sessions.len() / 2);
+        let average = |half: &[&TokenSession]| {
+            half.iter().map(|session| session.tokens_used as f64).sum::<f64>() / half.len().max(1) as f64
+        };
+        let efficiency_trend = if older.is_empty() || average(older) == 0.0 {
+            0.0
+        } else {
+            (average(newer) - average(older)) / average(older)
+        };
+
+        let plan_fits: Vec<PlanFit> = SUBSCRIPTION_PLANS
+            .iter()
+            .map(|plan| {
+                let limit = plan.default_limit();
+                let limit_hits = sessions.iter().filter(|session| session.tokens_used >= limit).count();
+                PlanFit {
+                    plan: plan.clone(),
+                    limit_hits,
+                    hit_rate: limit_hits as f64 / sessions.len() as f64,
+                    monthly_limit_hits: limit_hits as f64 * per_month,
+                    monthly_cost: plan.monthly_price().unwrap_or_default(),
+                }
+            })
+            .collect();
+
+        // Fall back to the plan with the fewest limit hits when none fits
+        let recommended = plan_fits
+            .iter()
+            .find(|fit| fit.hit_rate <= MAX_LIMIT_HIT_RATE)
+            .or_else(|| plan_fits.iter().min_by_key(|fit| fit.limit_hits))
+            .expect("at least one subscription plan");
+        let pay_as_you_go_monthly = self.api_cost.map(|cost| cost * per_month);
+
+        Ok(UsageAnalysis {
+            average_session_duration,
+            idle_gaps: idle_gaps.len(),
+            idle_share,
+            peak_usage_times,
+            efficiency_trend,
+            recommended_plan: recommended.plan.clone(),
+            sessions_analyzed: sessions.len(),
+            analyzed_days,
+            recommend_pay_as_you_go: pay_as_you_go_monthly.is_some_and(|monthly| monthly < recommended.monthly_cost),
+            pay_as_you_go_monthly,
+            plan_fits,
+        })
+    }
+}
@@ -0,0 +1,179 @@
+use super::data_paths::{is_config_file, BaseDirs};
+use super::file_lock::{write_atomic, write_atomic_with};
+use super::permissions::create_private_dir_all;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Version of the archive layout, bumped when restoring needs to change
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const CONFIG_PREFIX: &str = "config";
+const STATE_PREFIX: &str = "state";
+
+// Largest single file accepted from an archive, so a corrupt one can't fill the disk
+const MAX_RESTORE_FILE_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Describes a backup archive; stored as `manifest.json` next to the `config/` and `state/` trees
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct BackupManifest {
+    pub version: u32,
+    pub created: DateTime<Utc>,
+    pub app_version: String,
+    pub files: usize,
+}
+
+/// Locks and half-written temporary files belong to running instances, not to the backup
+fn is_transient(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "lock" || extension == "tmp")
+}
+
+/// Every file and directory to archive with its name in the archive, config files under `config/`
+/// and the rest (sessions, imports, metrics) under `state/`, profiles included
+fn backup_entries(dirs: &BaseDirs, out: &Path) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let roots: Vec<(&Path, Option<&str>)> = if dirs.is_single() {
+        vec![(&dirs.state, None)]
+    } else {
+        vec![(&dirs.config, Some(CONFIG_PREFIX)), (&dirs.state, Some(STATE_PREFIX))]
+    };
+    let out = out.canonicalize().ok();
+    let mut entries = Vec::new();
+    for (root, prefix) in roots {
+        if !root.is_dir() {
+            continue;
+        }
+        for entry in WalkDir::new(root).min_depth(1).follow_links(false).sort_by_file_name() {
+            let entry = entry?;
+            let relative = entry.path().strip_prefix(root)?;
+            if entry.file_type().is_dir() {
+                // Kept so profiles without any files yet survive a restore
+                let prefixes = prefix.map_or(vec![CONFIG_PREFIX, STATE_PREFIX], |prefix| vec![prefix]);
+                entries.extend(prefixes.into_iter().map(|prefix| (entry.path().to_path_buf(), Path::new(prefix).join(relative))));
+                continue;
+            }
+            if !entry.file_type().is_file() || is_transient(entry.path()) {
+                continue;
+            }
+            if out.is_some() && entry.path().canonicalize().ok() == out {
+                continue; // an earlier backup written to the same path
+            }
+            // The single-directory layout is split the same way the XDG migration does
+            let prefix = prefix.unwrap_or(if is_config_file(relative) { CONFIG_PREFIX } else { STATE_PREFIX });
+            entries.push((entry.path().to_path_buf(), Path::new(prefix).join(relative)));
+        }
+    }
+    Ok(entries)
+}
+
+/// Write config, observed sessions, imports and the metrics stores of every profile to a
+/// gzipped tar archive at `out`
+pub fn create_backup(dirs: &BaseDirs, out: &Path, now: DateTime<Utc>) -> Result<BackupManifest> {
+    let entries = backup_entries(dirs, out)?;
+    let manifest = BackupManifest {
+        version: BACKUP_FORMAT_VERSION,
+        created: now,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        files: entries.iter().filter(|(path, _)| path.is_file()).count(),
+    };
+    let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+
+    write_atomic_with(out, |writer| {
+        let mut archive = tar::Builder::new(GzEncoder::new(writer, Compression::default()));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(manifest_json.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(now.timestamp().max(0) as u64);
+        header.set_cksum();
+        archive.append_data(&mut header, MANIFEST_FILE, manifest_json.as_slice())?;
+        for (path, name) in &entries {
+            archive
+                .append_path_with_name(path, name)
+                .with_context(|| format!("Failed to add {} to the backup", path.display()))?;
+        }
+        archive.into_inner()?.finish()?;
+        Ok(())
+    })?;
+    Ok(manifest)
+}
+
+/// Where an archived file goes, or None for names outside `config/` and `state/`
+fn restore_target(dirs: &BaseDirs, name: &Path) -> Result<Option<PathBuf>> {
+    let mut components = name.components();
+    let base = match components.next() {
+        Some(Component::Normal(prefix)) if prefix == CONFIG_PREFIX => &dirs.config,
+        Some(Component::Normal(prefix)) if prefix == STATE_PREFIX => &dirs.state,
+        _ => return Ok(None),
+    };
+    let relative = components.as_path();
+    if relative.as_os_str().is_empty() || !relative.components().all(|component| matches!(component, Component::Normal(_))) {
+        return Err(anyhow!("Backup contains an unsafe path: {}", name.display()));
+    }
+    Ok(Some(base.join(relative)))
+}
+
+/// Restore a backup made by `create_backup` into `dirs`.
+///
+/// The whole archive is read and checked before anything is written. Files already present are
+/// only replaced when `overwrite` is set; files the backup doesn't contain are left alone.
+pub fn restore_backup(archive_path: &Path, dirs: &BaseDirs, overwrite: bool) -> Result<BackupManifest> {
+    let file = std::fs::File::open(archive_path).with_context(|| format!("Failed to open {}", archive_path.display()))?;
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+    let mut manifest: Option<BackupManifest> = None;
+    let mut files: Vec<(PathBuf, Vec<u8>)> = Vec::new();
+    let mut directories: Vec<PathBuf> = Vec::new();
+
+    let entries = archive
+        .entries()
+        .with_context(|| format!("{} is not a backup archive", archive_path.display()))?;
+    for entry in entries {
+        let mut entry = entry.with_context(|| format!("{} is not a valid backup archive", archive_path.display()))?;
+        let name = entry.path()?.into_owned();
+        if entry.header().entry_type().is_dir() {
+            directories.extend(restore_target(dirs, &name)?);
+            continue;
+        }
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        if entry.size() > MAX_RESTORE_FILE_SIZE {
+            return Err(anyhow!("{} in the backup is too large to restore ({} bytes)", name.display(), entry.size()));
+        }
+        let mut content = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut content)?;
+        if name == Path::new(MANIFEST_FILE) {
+            manifest = Some(serde_json::from_slice(&content).context("Backup manifest is not valid")?);
+        } else if let Some(target) = restore_target(dirs, &name)? {
+            files.push((target, content));
+        }
+    }
+
+    let manifest = manifest.ok_or_else(|| anyhow!("{} has no {MANIFEST_FILE}; not a claude-token-monitor backup", archive_path.display()))?;
+    if manifest.version > BACKUP_FORMAT_VERSION {
+        return Err(anyhow!(
+            "{} was written by version {} with backup format v{}; this version reads up to v{BACKUP_FORMAT_VERSION}",
+            archive_path.display(),
+            manifest.app_version,
+            manifest.version
+        ));
+    }
+    let existing = files.iter().filter(|(target, _)| target.exists()).count();
+    if existing > 0 && !overwrite {
+        return Err(anyhow!("Restoring would replace {existing} existing files; pass --force to replace them"));
+    }
+
+    for directory in &directories {
+        create_private_dir_all(directory)?;
+    }
+    for (target, content) in &files {
+        write_atomic(target, content)?;
+    }
+    Ok(BackupManifest { files: files.len(), ..manifest })
+}
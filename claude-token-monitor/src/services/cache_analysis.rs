@@ -0,0 +1,176 @@
+use super::file_monitor::UsageEntry;
+use super::pricing::Prices;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::BTreeMap;
+
+pub use crate::models::CacheTtl;
+
+/// Smallest prompt prefix the API will cache
+pub const MIN_CACHEABLE_TOKENS: u32 = 1024;
+
+const MIN_REPEATS: usize = 3;
+const SIZE_TOLERANCE: f64 = 0.1; // uncached inputs within 10% of each other are treated as the same payload
+const LOW_CACHE_READ_SHARE: f64 = 0.2;
+
+impl CacheTtl {
+    /// Longest gap between requests the cache survives
+    fn max_gap(&self) -> Duration {
+        match self {
+            CacheTtl::FiveMinutes => Duration::minutes(5),
+            CacheTtl::OneHour => Duration::hours(1),
+        }
+    }
+}
+
+/// Cache writes over a period by TTL, with what they cost at API list prices
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CacheWrites {
+    pub five_minute_tokens: u64,
+    pub one_hour_tokens: u64,
+    pub five_minute_cost: f64,
+    pub one_hour_cost: f64,
+}
+
+/// Cache writes since `since`, priced at each TTL's write rate
+pub fn cache_writes<'a>(
+    entries: impl Iterator<Item = &'a UsageEntry>,
+    since: DateTime<Utc>,
+    prices: Prices,
+) -> CacheWrites {
+    let mut writes = CacheWrites::default();
+    for entry in entries.filter(|entry| entry.timestamp >= since) {
+        let pricing = prices.for_entry(entry).rates_for(&entry.usage);
+        let (five_minute, one_hour) = (entry.usage.cache_creation_5m_tokens(), entry.usage.cache_creation_1h_tokens());
+        writes.five_minute_tokens += five_minute as u64;
+        writes.one_hour_tokens += one_hour as u64;
+        writes.five_minute_cost += five_minute as f64 * pricing.cache_write / 1_000_000.0;
+        writes.one_hour_cost += one_hour as f64 * pricing.extended_cache_write() / 1_000_000.0;
+    }
+    writes
+}
+
+/// Requests that resent a similar large uncached input while rarely reading from the cache
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheOpportunity {
+    pub project: Option<String>,
+    pub model: Option<String>,
+    pub requests: usize,
+    pub repeated_tokens: u32, // smallest uncached input in the group, assumed to be the shared prefix
+    pub cache_read_share: f64, // share of the group's input already served from the cache
+    pub ttl: CacheTtl,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub potential_savings: f64, // USD at API list prices
+}
+
+/// Find groups of at least three requests from the same project and model whose uncached input
+/// was large and nearly the same size, repeated within an hour of each other, with few cache reads.
+///
+/// Only token counts are available, so a repeated payload is inferred from matching sizes;
+/// savings assume the repeated part could have been written to the cache once and read back after,
+/// at the rates `prices` gives the group's model.
+pub fn find_cache_opportunities<'a>(
+    entries: impl Iterator<Item = &'a UsageEntry>,
+    since: DateTime<Utc>,
+    prices: Prices,
+) -> Vec<CacheOpportunity> {
+    let mut groups: BTreeMap<(Option<&str>, Option<&str>), Vec<&UsageEntry>> = BTreeMap::new();
+    for entry in entries {
+        if entry.timestamp >= since && entry.usage.input_tokens >= MIN_CACHEABLE_TOKENS {
+            groups
+                .entry((entry.project.as_deref(), entry.model.as_deref()))
+                .or_default()
+                .push(entry);
+        }
+    }
+
+    let mut opportunities = Vec::new();
+    for ((project, model), mut group) in groups {
+        group.sort_by_key(|entry| entry.usage.input_tokens);
+        for cluster in size_clusters(&group) {
+            if let Some(opportunity) = analyze_cluster(project, model, cluster, prices) {
+                opportunities.push(opportunity);
+            }
+        }
+    }
+    opportunities.sort_by(|a, b| b.potential_savings.total_cmp(&a.potential_savings));
+    opportunities
+}
+
+/// Split entries sorted by input size into runs of similar sizes
+fn size_clusters<'a, 'b>(sorted: &'b [&'a UsageEntry]) -> Vec<&'b [&'a UsageEntry]> {
+    let mut clusters = Vec::new();
+    let mut start = 0;
+    for index in 1..=sorted.len() {
+        let smallest = sorted[start].usage.input_tokens as f64;
+        if index == sorted.len() || sorted[index].usage.input_tokens as f64 > smallest * (1.0 + SIZE_TOLERANCE) {
+            clusters.push(&sorted[start..index]);
+            start = index;
+        }
+    }
+    clusters
+}
+
+fn analyze_cluster(project: Option<&str>, model: Option<&str>, cluster: &[&UsageEntry], prices: Prices) -> Option<CacheOpportunity> {
+    if cluster.len() < MIN_REPEATS {
+        return None;
+    }
+    let mut by_time = cluster.to_vec();
+    by_time.sort_by_key(|entry| entry.timestamp);
+
+    // Repeats more than an hour apart would miss even the extended cache
+    let mut runs: Vec<Vec<&UsageEntry>> = Vec::new();
+    for entry in by_time {
+        match runs.last_mut() {
+            Some(run) if entry.timestamp - run[run.len() - 1].timestamp <= CacheTtl::OneHour.max_gap() => run.push(entry),
+            _ => runs.push(vec![entry]),
+        }
+    }
+    runs.retain(|run| run.len() >= MIN_REPEATS);
+    if runs.is_empty() {
+        return None;
+    }
+
+    let (mut input, mut read) = (0u64, 0u64);
+    for entry in runs.iter().flatten() {
+        input += entry.usage.input_tokens as u64 + entry.usage.cache_creation_tokens() as u64 + entry.usage.cache_read_tokens() as u64;
+        read += entry.usage.cache_read_tokens() as u64;
+    }
+    let cache_read_share = read as f64 / input.max(1) as f64;
+    if cache_read_share >= LOW_CACHE_READ_SHARE {
+        return None;
+    }
+
+    let pricing = prices.for_entry(cluster[0]); // the whole cluster is one model
+    let repeated_tokens = cluster[0].usage.input_tokens; // clusters are sorted by size
+    let mut ttl = CacheTtl::FiveMinutes;
+    let mut potential_savings = 0.0;
+    for run in &runs {
+        let run_ttl = if run.windows(2).all(|pair| pair[1].timestamp - pair[0].timestamp <= CacheTtl::FiveMinutes.max_gap()) {
+            CacheTtl::FiveMinutes
+        } else {
+            CacheTtl::OneHour
+        };
+        ttl = ttl.max(run_ttl);
+        let write_price = match run_ttl {
+            CacheTtl::FiveMinutes => pricing.cache_write,
+            CacheTtl::OneHour => pricing.extended_cache_write(),
+        };
+        let repeats = run.len() as f64;
+        let uncached = repeats * pricing.input;
+        let cached = write_price + (repeats - 1.0) * pricing.cache_read;
+        potential_savings += repeated_tokens as f64 * (uncached - cached) / 1_000_000.0;
+    }
+
+    Some(CacheOpportunity {
+        project: project.map(str::to_string),
+        model: model.map(str::to_string),
+        requests: runs.iter().map(Vec::len).sum(),
+        repeated_tokens,
+        cache_read_share,
+        ttl,
+        first_seen: runs[0][0].timestamp,
+        last_seen: runs.iter().flatten().map(|entry| entry.timestamp).max()?,
+        potential_savings,
+    })
+}
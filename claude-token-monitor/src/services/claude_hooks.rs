@@ -0,0 +1,125 @@
+use super::claude_settings::ClaudeSettings;
+use super::file_lock::write_atomic;
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+/// Prefix of every hook script this tool writes, used to find and replace earlier installs
+pub const HOOK_SCRIPT_PREFIX: &str = "claude-token-monitor-";
+
+/// A Claude Code hook that runs `check` before the agent acts
+#[derive(Debug, Clone, PartialEq)]
+pub struct HookSpec {
+    pub event: &'static str,           // Claude Code hook event name
+    pub matcher: Option<&'static str>, // tool matcher, for tool events only
+    pub check_args: String,            // arguments passed to `claude-token-monitor check`
+}
+
+impl HookSpec {
+    /// Block tool calls once usage passes `max_usage` (check exits 2, which Claude Code treats as a block)
+    pub fn block_tools(max_usage: f64) -> Self {
+        Self {
+            event: "PreToolUse",
+            matcher: Some("*"),
+            check_args: format!("check --quiet --max-usage {max_usage}"),
+        }
+    }
+
+    /// Tell the agent about high usage with each prompt (stdout is added to its context)
+    pub fn warn_on_prompt(max_usage: f64) -> Self {
+        Self {
+            event: "UserPromptSubmit",
+            matcher: None,
+            check_args: format!("check --quiet --warn --max-usage {max_usage}"),
+        }
+    }
+
+    fn script_name(&self) -> String {
+        let event = self.event.to_lowercase();
+        if cfg!(windows) {
+            format!("{HOOK_SCRIPT_PREFIX}{event}.cmd")
+        } else {
+            format!("{HOOK_SCRIPT_PREFIX}{event}.sh")
+        }
+    }
+}
+
+/// Write hook scripts into `hooks_dir` and register them in the Claude settings.
+///
+/// Hooks installed by an earlier run are replaced rather than duplicated.
+pub fn install_hooks(
+    settings: &mut ClaudeSettings,
+    hooks_dir: &Path,
+    monitor_command: &str,
+    hooks: &[HookSpec],
+) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(hooks_dir)?;
+
+    let mut all_hooks = match settings.get("hooks") {
+        Some(Value::Object(existing)) => existing.clone(),
+        Some(_) => return Err(anyhow!("'hooks' in {} is not an object", settings.path().display())),
+        None => serde_json::Map::new(),
+    };
+
+    let mut scripts = Vec::new();
+    for hook in hooks {
+        let script_path = hooks_dir.join(hook.script_name());
+        write_hook_script(&script_path, &format!("{monitor_command} {}", hook.check_args))?;
+
+        let groups = all_hooks
+            .entry(hook.event.to_string())
+            .or_insert_with(|| Value::Array(Vec::new()));
+        let Value::Array(groups) = groups else {
+            return Err(anyhow!("hooks.{} in {} is not an array", hook.event, settings.path().display()));
+        };
+        remove_installed_hooks(groups);
+
+        let mut group = json!({
+            "hooks": [{ "type": "command", "command": script_path.display().to_string() }]
+        });
+        if let Some(matcher) = hook.matcher {
+            group["matcher"] = json!(matcher);
+        }
+        groups.push(group);
+        scripts.push(script_path);
+    }
+
+    settings.set("hooks", Value::Object(all_hooks));
+    Ok(scripts)
+}
+
+/// Drop hook commands pointing at our scripts, and any groups left empty by that
+fn remove_installed_hooks(groups: &mut Vec<Value>) {
+    for group in groups.iter_mut() {
+        if let Some(Value::Array(commands)) = group.get_mut("hooks") {
+            commands.retain(|command| {
+                !command
+                    .get("command")
+                    .and_then(|c| c.as_str())
+                    .is_some_and(|c| c.contains(HOOK_SCRIPT_PREFIX))
+            });
+        }
+    }
+    groups.retain(|group| {
+        group
+            .get("hooks")
+            .and_then(|hooks| hooks.as_array())
+            .is_none_or(|hooks| !hooks.is_empty())
+    });
+}
+
+fn write_hook_script(path: &Path, command: &str) -> Result<()> {
+    let script = if cfg!(windows) {
+        format!("@echo off\r\nrem Installed by claude-token-monitor install-hooks\r\n{command}\r\n")
+    } else {
+        format!("#!/bin/sh\n# Installed by claude-token-monitor install-hooks\nexec {command}\n")
+    };
+    write_atomic(path, script)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
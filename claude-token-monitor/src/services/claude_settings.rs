@@ -0,0 +1,279 @@
+use super::file_lock::write_atomic;
+use super::model_names::without_snapshot_date;
+use crate::models::{AccountIdentity, PlanType, UsageMetrics};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+/// Claude Code's configuration directory (`$CLAUDE_CONFIG_DIR` or `~/.claude`)
+pub fn claude_config_dir() -> Result<PathBuf> {
+    if let Ok(dir) = std::env::var("CLAUDE_CONFIG_DIR") {
+        if !dir.trim().is_empty() {
+            return Ok(PathBuf::from(dir));
+        }
+    }
+    dirs::home_dir()
+        .map(|home| home.join(".claude"))
+        .ok_or_else(|| anyhow!("Unable to determine home directory"))
+}
+
+/// Keys under which Claude Code and the API report when a usage limit resets
+const RESET_TIME_KEYS: [&str; 5] = [
+    "resetsAt",
+    "resetAt",
+    "rateLimitResetsAt",
+    "resets_at",
+    "anthropic-ratelimit-unified-reset",
+];
+
+/// A usage limit reset stated in `value` or its `rateLimit`, `error` or `headers` objects, as Unix
+/// seconds (a number or a numeric string) or an RFC 3339 timestamp
+pub fn explicit_reset_time(value: &Value) -> Option<DateTime<Utc>> {
+    let nested = ["rateLimit", "error", "headers"]
+        .iter()
+        .filter_map(|key| value.get(key))
+        .flat_map(|inner| [Some(inner), inner.get("headers")]);
+    std::iter::once(Some(value))
+        .chain(nested)
+        .flatten()
+        .flat_map(|object| RESET_TIME_KEYS.iter().filter_map(move |key| object.get(key)))
+        .find_map(|reset| match reset {
+            Value::Number(seconds) => DateTime::from_timestamp(seconds.as_i64()?, 0),
+            Value::String(text) => match text.trim().parse::<i64>() {
+                Ok(seconds) => DateTime::from_timestamp(seconds, 0),
+                Err(_) => DateTime::parse_from_rfc3339(text.trim()).ok().map(|reset| reset.with_timezone(&Utc)),
+            },
+            _ => None,
+        })
+}
+
+/// Claude Code's user `settings.json`, edited in place without touching unrelated keys
+pub struct ClaudeSettings {
+    path: PathBuf,
+    settings: Map<String, Value>,
+}
+
+impl ClaudeSettings {
+    /// Load the user settings file from the Claude config directory
+    pub fn load_default() -> Result<Self> {
+        Self::load(&claude_config_dir()?.join("settings.json"))
+    }
+
+    /// Load a settings file, treating a missing file as empty settings
+    pub fn load(path: &Path) -> Result<Self> {
+        let settings = if path.exists() {
+            let content = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            match serde_json::from_str(&content)
+                .with_context(|| format!("{} is not valid JSON", path.display()))?
+            {
+                Value::Object(settings) => settings,
+                _ => return Err(anyhow!("{} must contain a JSON object", path.display())),
+            }
+        } else {
+            Map::new()
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            settings,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.settings.get(key)
+    }
+
+    pub fn set(&mut self, key: &str, value: Value) {
+        self.settings.insert(key.to_string(), value);
+    }
+
+    /// Write the settings back, keeping the previous file as `settings.json.bak`
+    pub fn save(&self) -> Result<()> {
+        if self.path.exists() {
+            let backup = self.path.with_extension("json.bak");
+            std::fs::copy(&self.path, &backup)
+                .with_context(|| format!("Failed to back up {}", self.path.display()))?;
+        }
+        let content = serde_json::to_string_pretty(&self.settings)?;
+        write_atomic(&self.path, content + "\n")
+    }
+}
+
+/// The model Claude Code is set to use, and where the setting comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectedModel {
+    pub model: String,  // an alias such as "opus" or a full model ID
+    pub source: String, // ANTHROPIC_MODEL or the settings file setting it
+}
+
+/// The `ANTHROPIC_MODEL` environment variable, when set to a model
+pub fn env_model() -> Option<String> {
+    std::env::var("ANTHROPIC_MODEL").ok().filter(|model| !model.trim().is_empty())
+}
+
+/// The model Claude Code picks for a session in `project_dir`, by its precedence: `env_model`
+/// (the `ANTHROPIC_MODEL` environment variable), then the project's `.claude/settings.local.json`
+/// and `.claude/settings.json`, then the user's `settings.json` in `config_dir`
+pub fn selected_model(config_dir: &Path, project_dir: Option<&Path>, env_model: Option<&str>) -> Option<SelectedModel> {
+    if let Some(model) = env_model.filter(|model| !model.trim().is_empty()) {
+        return Some(SelectedModel { model: model.to_string(), source: "ANTHROPIC_MODEL".to_string() });
+    }
+    let project_files = project_dir
+        .into_iter()
+        .flat_map(|dir| ["settings.local.json", "settings.json"].map(|name| dir.join(".claude").join(name)));
+    project_files.chain([config_dir.join("settings.json")]).find_map(|path| {
+        let settings = ClaudeSettings::load(&path).ok()?;
+        let model = settings.get("model")?.as_str()?.trim();
+        (!model.is_empty()).then(|| SelectedModel { model: model.to_string(), source: path.display().to_string() })
+    })
+}
+
+/// Whether requests logged with `logged` are what the `selected` model setting asks for: the same
+/// family for aliases like `opus` (`opusplan` allows Opus and Sonnet), the same model for full IDs
+/// whatever their snapshot date. None when it can't be told, as for `default` or Claude Code's own
+/// `<synthetic>` notices.
+pub fn model_matches(selected: &str, logged: &str) -> Option<bool> {
+    let (selected, logged) = (selected.trim().to_lowercase(), logged.to_lowercase());
+    if selected.is_empty() || selected == "default" || logged == "<synthetic>" {
+        return None;
+    }
+    if selected.starts_with("opusplan") {
+        return Some(logged.contains("opus") || logged.contains("sonnet"));
+    }
+    if selected.starts_with("claude-") {
+        let id = without_snapshot_date(&selected);
+        return Some(without_snapshot_date(&logged) == id || logged.starts_with(id));
+    }
+    ["opus", "sonnet", "haiku"]
+        .into_iter()
+        .find(|family| selected.starts_with(family))
+        .map(|family| logged.contains(family))
+}
+
+/// The model `metrics` were last logged with, when it isn't the one the settings select
+pub fn model_mismatch(metrics: &UsageMetrics) -> Option<&str> {
+    let (selected, latest) = (metrics.selected_model.as_deref()?, metrics.latest_model.as_deref()?);
+    (model_matches(selected, latest) == Some(false)).then_some(latest)
+}
+
+/// Plan and model hints found in Claude Code's local configuration
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SubscriptionHints {
+    pub subscription_type: Option<String>, // e.g. "pro", "max"
+    pub rate_limit_tier: Option<String>,   // e.g. "default_claude_max_20x"
+    pub model: Option<String>,             // configured default model, e.g. "opus"
+    pub organization_id: Option<String>,   // organization of the signed-in account
+    pub user_id: Option<String>,           // the signed-in account itself
+    pub reset_time: Option<DateTime<Utc>>, // usage limit reset Claude Code last recorded, if it did
+}
+
+impl SubscriptionHints {
+    /// Read hints from the Claude config directory, ignoring anything missing or unreadable.
+    ///
+    /// Only the subscription fields of `.credentials.json` are deserialized; tokens are never kept.
+    pub fn read(config_dir: &Path) -> Self {
+        Self::read_with(config_dir, env_model().as_deref())
+    }
+
+    /// Like `read`, with `env_model` standing in for the `ANTHROPIC_MODEL` environment variable
+    pub fn read_with(config_dir: &Path, env_model: Option<&str>) -> Self {
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct OauthInfo {
+            subscription_type: Option<String>,
+            rate_limit_tier: Option<String>,
+        }
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Credentials {
+            claude_ai_oauth: Option<OauthInfo>,
+        }
+
+        let mut hints = Self::default();
+
+        if let Some(oauth) = read_json_file::<Credentials>(&config_dir.join(".credentials.json"))
+            .and_then(|credentials| credentials.claude_ai_oauth)
+        {
+            hints.subscription_type = oauth.subscription_type;
+            hints.rate_limit_tier = oauth.rate_limit_tier;
+        }
+
+        // Claude Code keeps the signed-in account in `.claude.json`, next to the config directory
+        // unless CLAUDE_CONFIG_DIR moved it inside
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct OauthAccount {
+            organization_uuid: Option<String>,
+            account_uuid: Option<String>,
+        }
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct GlobalConfig {
+            oauth_account: Option<OauthAccount>,
+            #[serde(flatten)]
+            rest: Map<String, Value>, // searched for a usage limit reset
+        }
+
+        let global_configs = [Some(config_dir.join(".claude.json")), config_dir.parent().map(|dir| dir.join(".claude.json"))];
+        if let Some(config) = global_configs
+            .into_iter()
+            .flatten()
+            .find_map(|path| read_json_file::<GlobalConfig>(&path).filter(|config| config.oauth_account.is_some()))
+        {
+            hints.reset_time = explicit_reset_time(&Value::Object(config.rest));
+            if let Some(account) = config.oauth_account {
+                hints.organization_id = account.organization_uuid;
+                hints.user_id = account.account_uuid;
+            }
+        }
+
+        hints.model = selected_model(config_dir, None, env_model).map(|selected| selected.model);
+
+        hints
+    }
+
+    /// Organization and user IDs, when either is known
+    pub fn identity(&self) -> Option<AccountIdentity> {
+        (self.organization_id.is_some() || self.user_id.is_some()).then(|| AccountIdentity {
+            organization_id: self.organization_id.clone(),
+            user_id: self.user_id.clone(),
+        })
+    }
+
+    /// Plan implied by the subscription, if it maps onto a known plan
+    pub fn plan_type(&self) -> Option<PlanType> {
+        match self.subscription_type.as_deref()?.to_lowercase().as_str() {
+            "pro" => Some(PlanType::Pro),
+            "max" => {
+                let tier = self.rate_limit_tier.as_deref().unwrap_or_default();
+                if tier.contains("20x") {
+                    Some(PlanType::Max20)
+                } else {
+                    Some(PlanType::Max5)
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Size cap for local Claude config files we read hints from
+const MAX_HINT_FILE_SIZE: u64 = 1024 * 1024;
+
+fn read_json_file<T: serde::de::DeserializeOwned>(path: &Path) -> Option<T> {
+    let metadata = std::fs::metadata(path).ok()?;
+    if metadata.len() > MAX_HINT_FILE_SIZE {
+        log::warn!("Ignoring oversized file {}", path.display());
+        return None;
+    }
+    let content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content)
+        .map_err(|e| log::debug!("Ignoring unreadable {}: {e}", path.display()))
+        .ok()
+}
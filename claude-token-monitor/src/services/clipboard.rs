@@ -0,0 +1,77 @@
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use std::io::Write;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+// Clipboard tools get this long to take the text before the terminal is used instead
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Clipboard tools to try, with their arguments and the environment variable that must be set
+/// for them to reach a clipboard (empty when none is needed)
+const CLIPBOARD_COMMANDS: [(&str, &[&str], &str); 5] = [
+    ("pbcopy", &[], ""),
+    ("wl-copy", &[], "WAYLAND_DISPLAY"),
+    ("xclip", &["-selection", "clipboard"], "DISPLAY"),
+    ("xsel", &["--clipboard", "--input"], "DISPLAY"),
+    ("clip.exe", &[], ""),
+];
+
+/// How copied text reached the clipboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipboardMethod {
+    Command(&'static str), // a system clipboard tool
+    Osc52,                 // the terminal's OSC 52 escape, which also works over SSH
+}
+
+impl ClipboardMethod {
+    pub fn description(&self) -> String {
+        match self {
+            ClipboardMethod::Command(command) => format!("with {command}"),
+            ClipboardMethod::Osc52 => "through the terminal (OSC 52)".to_string(),
+        }
+    }
+}
+
+/// Copy `text` with the first system clipboard tool that works, or else ask the terminal to set
+/// its clipboard by writing an OSC 52 escape to `terminal`
+pub async fn copy_to_clipboard(text: &str, terminal: &mut impl Write) -> Result<ClipboardMethod> {
+    for (command, args, required_var) in CLIPBOARD_COMMANDS {
+        if !required_var.is_empty() && std::env::var_os(required_var).is_none() {
+            continue;
+        }
+        match run_clipboard_command(command, args, text).await {
+            Ok(()) => return Ok(ClipboardMethod::Command(command)),
+            Err(e) => log::debug!("Clipboard command {command} failed: {e}"),
+        }
+    }
+    terminal.write_all(osc52_sequence(text).as_bytes())?;
+    terminal.flush()?;
+    Ok(ClipboardMethod::Osc52)
+}
+
+/// OSC 52 escape setting the clipboard to `text`
+pub fn osc52_sequence(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64::engine::general_purpose::STANDARD.encode(text))
+}
+
+/// Pipe `text` into a clipboard tool, killing it if it doesn't exit in time
+async fn run_clipboard_command(command: &str, args: &[&str], text: &str) -> Result<()> {
+    let mut child = Command::new(command)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+    let copied = async {
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes()).await?;
+        }
+        child.wait().await
+    };
+    let status = tokio::time::timeout(COMMAND_TIMEOUT, copied).await.map_err(|_| anyhow!("timed out"))??;
+    if status.success() { Ok(()) } else { Err(anyhow!("exited with {status}")) }
+}
@@ -0,0 +1,137 @@
+use super::ConfigService;
+use super::file_lock::write_atomic;
+use crate::models::*;
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// File-backed configuration store with schema migration on load
+pub struct ConfigManager {
+    config_path: PathBuf,
+}
+
+impl ConfigManager {
+    pub fn new(data_dir: &Path) -> Self {
+        Self {
+            config_path: data_dir.join("config.json"),
+        }
+    }
+
+    /// Load the config, migrating older schema versions, or create a default one
+    pub fn load_or_create(&self) -> Result<UserConfig> {
+        if self.config_path.exists() {
+            self.load_config()
+        } else {
+            let config = UserConfig::default();
+            self.save_config(&config)?;
+            Ok(config)
+        }
+    }
+
+    /// Load the config without writing anything: older schemas are migrated in memory only and a
+    /// missing config means the defaults (`--read-only`)
+    pub fn load_without_writing(&self) -> Result<UserConfig> {
+        if !self.config_path.exists() {
+            return Ok(UserConfig::default());
+        }
+        Ok(self.read_config()?.0)
+    }
+
+    /// The stored config migrated to the current schema, with the version and text it was stored as
+    fn read_config(&self) -> Result<(UserConfig, u32, String)> {
+        let content = std::fs::read_to_string(&self.config_path)
+            .with_context(|| format!("Failed to read config {}", self.config_path.display()))?;
+        let value: Value = serde_json::from_str(&content)
+            .with_context(|| format!("Config {} is not valid JSON", self.config_path.display()))?;
+        let (config, from_version) = migrate_config(value)?;
+        Ok((config, from_version, content))
+    }
+}
+
+impl ConfigService for ConfigManager {
+    fn load_config(&self) -> Result<UserConfig> {
+        let (config, from_version, content) = self.read_config()?;
+
+        if from_version < CONFIG_SCHEMA_VERSION {
+            // Keep the original around in case the migration lost something the user cared about
+            let backup_path = self.config_path.with_extension(format!("json.v{from_version}.bak"));
+            write_atomic(&backup_path, &content)?;
+            self.save_config(&config)?;
+            log::info!(
+                "Migrated config from schema v{from_version} to v{CONFIG_SCHEMA_VERSION} (backup: {})",
+                backup_path.display()
+            );
+        }
+
+        Ok(config)
+    }
+
+    fn save_config(&self, config: &UserConfig) -> Result<()> {
+        let content = serde_json::to_string_pretty(config)?;
+        write_atomic(&self.config_path, content)
+    }
+
+    fn get_config_path(&self) -> Result<PathBuf> {
+        Ok(self.config_path.clone())
+    }
+}
+
+/// Migrate a stored config document to the current schema.
+///
+/// Returns the migrated config and the schema version it was stored with.
+/// Missing fields are filled from defaults rather than failing deserialization.
+pub fn migrate_config(value: Value) -> Result<(UserConfig, u32)> {
+    let Value::Object(stored) = value else {
+        return Err(anyhow!("Config must be a JSON object"));
+    };
+
+    let from_version = stored
+        .get("version")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0) as u32;
+
+    if from_version > CONFIG_SCHEMA_VERSION {
+        log::warn!(
+            "Config schema v{from_version} is newer than supported v{CONFIG_SCHEMA_VERSION}; unknown fields will be ignored"
+        );
+    }
+
+    // Overlay stored fields on top of the defaults so newly added fields get sane values
+    let mut merged = serde_json::to_value(UserConfig::default())?;
+    if let Value::Object(ref mut base) = merged {
+        for (key, field) in stored {
+            base.insert(key, field);
+        }
+    }
+
+    let mut config: UserConfig = serde_json::from_value(merged)
+        .map_err(|e| anyhow!("Failed to migrate config from schema v{}: {}", from_version, e))?;
+    config.version = config.version.max(CONFIG_SCHEMA_VERSION);
+
+    Ok((config, from_version))
+}
+
+/// Migrate a stored observed sessions document to the current schema.
+///
+/// Schema v0 was a bare JSON array of sessions; v1 wraps it in a versioned envelope.
+pub fn migrate_sessions(value: Value) -> Result<(Vec<TokenSession>, u32)> {
+    match value {
+        Value::Array(_) => {
+            let sessions = serde_json::from_value(value)
+                .map_err(|e| anyhow!("Failed to migrate sessions from schema v0: {}", e))?;
+            Ok((sessions, 0))
+        }
+        Value::Object(_) => {
+            let file: SessionsFile = serde_json::from_value(value)
+                .map_err(|e| anyhow!("Failed to read sessions file: {}", e))?;
+            if file.version > SESSIONS_SCHEMA_VERSION {
+                log::warn!(
+                    "Sessions schema v{} is newer than supported v{SESSIONS_SCHEMA_VERSION}",
+                    file.version
+                );
+            }
+            Ok((file.sessions, file.version))
+        }
+        _ => Err(anyhow!("Sessions file must be a JSON array or object")),
+    }
+}
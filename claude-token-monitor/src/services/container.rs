@@ -0,0 +1,119 @@
+use crate::models::validate_path_mappings;
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Path mappings as `from=to,from=to`, on top of the config's `path_mappings`
+pub const PATH_MAPPINGS_ENV: &str = "CLAUDE_PATH_MAPPINGS";
+
+/// Where dev-container images usually keep, or get a bind mount of, a Claude Code home
+pub const COMMON_CLAUDE_HOMES: [&str; 4] = [
+    "/home/node/.claude",
+    "/home/vscode/.claude",
+    "/home/codespace/.claude",
+    "/root/.claude",
+];
+
+/// Kind of container the monitor runs in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerKind {
+    DevContainer, // VS Code / devcontainer CLI
+    Codespaces,
+    Docker,
+    Podman,
+    Other, // seen only in the cgroups of PID 1
+}
+
+impl ContainerKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ContainerKind::DevContainer => "dev container",
+            ContainerKind::Codespaces => "GitHub Codespace",
+            ContainerKind::Docker => "Docker container",
+            ContainerKind::Podman => "Podman container",
+            ContainerKind::Other => "container",
+        }
+    }
+
+    /// The container this process runs in, if any
+    pub fn detect() -> Option<Self> {
+        Self::detect_at(Path::new("/"), |name| std::env::var(name).ok())
+    }
+
+    /// The container whose root filesystem is `root` and whose environment `var` reads
+    pub fn detect_at(root: &Path, var: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        if var("CODESPACES").is_some_and(|value| value == "true") {
+            Some(ContainerKind::Codespaces)
+        } else if var("REMOTE_CONTAINERS").is_some() || var("DEVCONTAINER").is_some() {
+            Some(ContainerKind::DevContainer)
+        } else if root.join("run/.containerenv").exists() {
+            Some(ContainerKind::Podman)
+        } else if root.join(".dockerenv").exists() {
+            Some(ContainerKind::Docker)
+        } else if var("container").is_some() {
+            Some(ContainerKind::Other)
+        } else {
+            let cgroup = std::fs::read_to_string(root.join("proc/1/cgroup")).unwrap_or_default();
+            ["docker", "kubepods", "containerd", "libpod", "lxc"]
+                .iter()
+                .any(|runtime| cgroup.contains(runtime))
+                .then_some(ContainerKind::Other)
+        }
+    }
+}
+
+/// Parse `from=to` pairs separated by commas
+pub fn parse_path_mappings(spec: &str) -> Result<BTreeMap<PathBuf, PathBuf>> {
+    let mut mappings = BTreeMap::new();
+    for pair in spec.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+        let (from, to) = pair
+            .split_once('=')
+            .ok_or_else(|| anyhow!("'{pair}' is not from=to"))?;
+        mappings.insert(PathBuf::from(from.trim()), PathBuf::from(to.trim()));
+    }
+    validate_path_mappings(&mappings)?;
+    Ok(mappings)
+}
+
+/// Mappings from `CLAUDE_PATH_MAPPINGS`, none when it is unset or invalid
+pub fn path_mappings_from_env() -> BTreeMap<PathBuf, PathBuf> {
+    match std::env::var(PATH_MAPPINGS_ENV) {
+        Ok(spec) => parse_path_mappings(&spec).unwrap_or_else(|e| {
+            log::warn!("Ignoring {PATH_MAPPINGS_ENV}: {e}");
+            BTreeMap::new()
+        }),
+        Err(_) => BTreeMap::new(),
+    }
+}
+
+/// `path` rewritten through the mapping with the longest matching `from` prefix, None when no
+/// mapping covers it (a leading `~` on the local side is the home directory)
+pub fn map_path(mappings: &BTreeMap<PathBuf, PathBuf>, path: &Path) -> Option<PathBuf> {
+    let (from, to) = mappings
+        .iter()
+        .filter(|(from, _)| path.starts_with(from))
+        .max_by_key(|(from, _)| from.components().count())?;
+    let rest = path.strip_prefix(from).ok()?;
+    Some(expand_home(to).join(rest))
+}
+
+/// The log directory a mapping makes available here: the local side of a mapped Claude Code home
+/// (`.claude` or `.config/claude`) or of its `projects/` directory
+pub fn mapped_data_dir(from: &Path, to: &Path) -> Option<PathBuf> {
+    let is_claude_home = |path: &Path| matches!(path.file_name().and_then(|name| name.to_str()), Some(".claude" | "claude"));
+    let to = expand_home(to);
+    if is_claude_home(from) {
+        Some(to.join("projects"))
+    } else if from.ends_with("projects") && from.parent().is_some_and(is_claude_home) {
+        Some(to)
+    } else {
+        None
+    }
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
@@ -0,0 +1,64 @@
+use crate::models::LayoutConfig;
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Commands taking longer than this are killed and reported as timed out
+pub const PANEL_COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Keep a misbehaving command from flooding the UI
+const MAX_PANEL_OUTPUT: usize = 4096;
+
+/// Run a custom panel command through the shell and return what should be shown in the panel
+pub async fn run_panel_command(command: &str) -> String {
+    let mut cmd = shell_command(command);
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let output = match tokio::time::timeout(PANEL_COMMAND_TIMEOUT, cmd.output()).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => return format!("⚠️ Failed to run command: {e}"),
+        Err(_) => return format!("⚠️ Command timed out after {}s", PANEL_COMMAND_TIMEOUT.as_secs()),
+    };
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().next().unwrap_or_default();
+        return format!("⚠️ Command failed ({}) {}", output.status, reason).trim_end().to_string();
+    }
+
+    let mut stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    if stdout.len() > MAX_PANEL_OUTPUT {
+        let mut end = MAX_PANEL_OUTPUT;
+        while !stdout.is_char_boundary(end) {
+            end -= 1;
+        }
+        stdout.truncate(end);
+    }
+    stdout.trim_end().to_string()
+}
+
+/// Run every custom panel command in the layout concurrently, keyed by command
+pub async fn run_panel_commands(layout: &LayoutConfig) -> HashMap<String, String> {
+    let commands = layout.panel_commands();
+    let outputs = futures::future::join_all(commands.iter().map(|command| run_panel_command(command))).await;
+    commands.into_iter().zip(outputs).collect()
+}
+
+/// Build a command that runs `command` through the platform shell
+#[cfg(unix)]
+pub(crate) fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(windows)]
+pub(crate) fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
@@ -0,0 +1,90 @@
+use super::permissions::create_private_dir_all;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Directory name used under every base directory
+pub const APP_DIR: &str = "claude-token-monitor";
+
+/// Base directories for config and for state (sessions, imports, metrics); the same directory
+/// in the single-directory layout
+#[derive(Debug, Clone, PartialEq)]
+pub struct BaseDirs {
+    pub config: PathBuf,
+    pub state: PathBuf,
+}
+
+impl BaseDirs {
+    pub fn single(dir: &Path) -> Self {
+        Self {
+            config: dir.to_path_buf(),
+            state: dir.to_path_buf(),
+        }
+    }
+
+    pub fn is_single(&self) -> bool {
+        self.config == self.state
+    }
+}
+
+/// The single directory used before config and state were split (`~/.local/share/claude-token-monitor` on Linux)
+pub fn legacy_data_dir() -> PathBuf {
+    dirs::data_dir().unwrap_or_else(|| PathBuf::from(".")).join(APP_DIR)
+}
+
+/// `$XDG_CONFIG_HOME` and `$XDG_STATE_HOME` based directories, on platforms that have a state directory
+pub fn xdg_dirs() -> Option<BaseDirs> {
+    Some(BaseDirs {
+        config: dirs::config_dir()?.join(APP_DIR),
+        state: dirs::state_dir()?.join(APP_DIR),
+    })
+}
+
+/// `config.json` and its migration backups belong with the config; everything else is state
+pub fn is_config_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with("config.json"))
+}
+
+/// Copy the single-directory layout at `legacy` into `dirs`, config files to the config directory
+/// and everything else to the state directory, keeping profile subdirectories.
+///
+/// Runs only while neither new directory exists, so it happens once; the legacy directory is left
+/// in place for `--legacy-paths`. Returns the number of files copied, or None when nothing was done.
+pub fn migrate_legacy_layout(legacy: &Path, dirs: &BaseDirs) -> Result<Option<usize>> {
+    if !legacy.is_dir() || dirs.config.exists() || dirs.state.exists() {
+        return Ok(None);
+    }
+    let mut copied = 0;
+    for entry in WalkDir::new(legacy).follow_links(false) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(legacy)?;
+        let base = if is_config_file(relative) { &dirs.config } else { &dirs.state };
+        let target = base.join(relative);
+        if let Some(parent) = target.parent() {
+            create_private_dir_all(parent)?;
+        }
+        std::fs::copy(entry.path(), &target)
+            .with_context(|| format!("Failed to copy {} to {}", entry.path().display(), target.display()))?;
+        copied += 1;
+    }
+    // Profiles without any files yet still need to exist in both places
+    for base in [&dirs.config, &dirs.state] {
+        create_private_dir_all(base)?;
+    }
+    let profiles = legacy.join(super::profile_manager::PROFILES_DIR);
+    if profiles.is_dir() {
+        for entry in std::fs::read_dir(&profiles)?.filter_map(|entry| entry.ok()) {
+            if entry.path().is_dir() {
+                for base in [&dirs.config, &dirs.state] {
+                    create_private_dir_all(&base.join(super::profile_manager::PROFILES_DIR).join(entry.file_name()))?;
+                }
+            }
+        }
+    }
+    Ok(Some(copied))
+}
@@ -0,0 +1,225 @@
+use super::file_lock::write_atomic_with;
+use super::schema::{classify_line, FileSchemaReport, LineClass};
+use super::snapshot::digest;
+use crate::models::UserConfig;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Directory every file of a bundle is stored under
+pub const BUNDLE_DIR: &str = "claude-token-monitor-debug";
+
+/// Config values that name people, places or secrets; replaced outright
+const REDACTED_KEYS: [&str; 9] = ["username", "from", "to", "chat_id", "command", "key_file", "urls", "server", "broker"];
+
+/// Config sections of paths, user names and client codes; every string in them, keys included,
+/// is hashed so equal values still match up
+const HASHED_SECTIONS: [&str; 5] = ["accounts", "api_key_accounts", "billing_codes", "path_mappings", "users"];
+
+/// Line fields kept readable in samples, as they describe the format rather than the content
+const CLEAR_FIELDS: [&str; 7] = ["type", "subtype", "version", "timestamp", "model", "role", "stop_reason"];
+
+// Keep the bundle small even when every line of every file is broken
+const SAMPLES_PER_FILE: usize = 3;
+const MAX_SAMPLES: usize = 40;
+const MAX_SAMPLE_LINE: usize = 1024 * 1024;
+
+/// `#` and the digest of an identifier: unreadable, but the same identifier always hashes the same
+pub fn hash_identifier(text: &str) -> String {
+    format!("#{}", digest(text))
+}
+
+/// `text` with `word` replaced wherever it stands on its own, between path separators,
+/// punctuation or spaces rather than inside a longer name
+fn replace_word(text: &str, word: &str, with: &str) -> String {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let (mut replaced, mut copied) = (String::with_capacity(text.len()), 0);
+    for (start, _) in text.match_indices(word) {
+        let end = start + word.len();
+        if !text[..start].chars().next_back().is_some_and(is_word) && !text[end..].chars().next().is_some_and(is_word) {
+            replaced.push_str(&text[copied..start]);
+            replaced.push_str(with);
+            copied = end;
+        }
+    }
+    replaced.push_str(&text[copied..]);
+    replaced
+}
+
+/// `text` with the home directory shown as `~` and the OS user name hidden
+pub fn redact_text(text: &str, home: Option<&Path>, user: Option<&str>) -> String {
+    let mut text = match home.map(|home| home.display().to_string()).filter(|home| home.len() > 1) {
+        Some(home) => text.replace(&home, "~"),
+        None => text.to_string(),
+    };
+    if let Some(user) = user.filter(|user| user.len() > 2) {
+        text = replace_word(&text, user, "<user>");
+    }
+    text
+}
+
+fn hash_strings(value: &mut Value) {
+    match value {
+        Value::String(text) => *text = hash_identifier(text),
+        Value::Array(items) => items.iter_mut().for_each(hash_strings),
+        Value::Object(fields) => {
+            *fields = std::mem::take(fields)
+                .into_iter()
+                .map(|(key, mut field)| {
+                    hash_strings(&mut field);
+                    (hash_identifier(&key), field)
+                })
+                .collect();
+        }
+        _ => {}
+    }
+}
+
+fn redact_strings(value: &mut Value) {
+    match value {
+        // Which service a URL goes to helps; the rest of it is often a token
+        Value::String(text) => {
+            *text = match text.split_once("://") {
+                Some((scheme, _)) => format!("{scheme}://[redacted]"),
+                None => "[redacted]".to_string(),
+            }
+        }
+        Value::Number(_) => *value = Value::from("[redacted]"),
+        Value::Array(items) => items.iter_mut().for_each(redact_strings),
+        _ => {}
+    }
+}
+
+fn redact_fields(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                if REDACTED_KEYS.contains(&key.as_str()) {
+                    redact_strings(field);
+                } else {
+                    redact_fields(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_fields),
+        _ => {}
+    }
+}
+
+/// The config as JSON with addresses, commands, URLs and key files replaced and paths and names
+/// hashed. Environment variable names stay, as they hold no secret themselves.
+pub fn redact_config(config: &UserConfig) -> Result<Value> {
+    let mut value = serde_json::to_value(config)?;
+    if let Value::Object(sections) = &mut value {
+        for (name, section) in sections.iter_mut() {
+            if HASHED_SECTIONS.contains(&name.as_str()) {
+                hash_strings(section);
+            }
+        }
+    }
+    redact_fields(&mut value);
+    Ok(value)
+}
+
+/// A log line with every string hashed except the fields describing its format, and long text
+/// reduced to its length
+pub fn redact_line(value: &Value) -> Value {
+    match value {
+        Value::Object(fields) => fields
+            .iter()
+            .map(|(key, field)| {
+                let field = match field {
+                    Value::String(_) if CLEAR_FIELDS.contains(&key.as_str()) => field.clone(),
+                    _ => redact_line(field),
+                };
+                (key.clone(), field)
+            })
+            .collect(),
+        Value::Array(items) => items.iter().map(redact_line).collect(),
+        Value::String(text) if text.chars().count() > 64 => Value::from(format!("<text, {} chars>", text.chars().count())),
+        Value::String(text) => Value::from(hash_identifier(text)),
+        other => other.clone(),
+    }
+}
+
+/// A line the parser couldn't use, with nothing identifying left in it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParseErrorSample {
+    pub file: String, // hashed path of the log
+    pub line: usize,  // 1-based
+    pub problem: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sample: Option<Value>, // the redacted line, when it is JSON
+}
+
+/// Up to a few invalid or unrecognised lines of each file the scan reported problems in, re-read
+/// from disk, and the files that couldn't be read at all
+pub fn parse_error_samples(reports: &[FileSchemaReport], failed_files: &[(PathBuf, String)]) -> Vec<ParseErrorSample> {
+    let mut samples: Vec<ParseErrorSample> = failed_files
+        .iter()
+        .map(|(path, error)| ParseErrorSample {
+            file: hash_identifier(&path.display().to_string()),
+            line: 0,
+            problem: format!("unreadable: {}", error.replace(&path.display().to_string(), "<file>")),
+            sample: None,
+        })
+        .collect();
+    for report in reports.iter().filter(|report| report.unknown_lines > 0 || report.invalid_lines > 0) {
+        let Ok(content) = std::fs::read_to_string(&report.path) else {
+            continue;
+        };
+        let file = hash_identifier(&report.path.display().to_string());
+        let problems = content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()).filter_map(|(index, line)| {
+            let (problem, sample) = if line.len() > MAX_SAMPLE_LINE {
+                (format!("line of {} bytes is over the size limit", line.len()), None)
+            } else {
+                match serde_json::from_str::<Value>(line) {
+                    Err(e) => (format!("invalid JSON: {e}"), None),
+                    Ok(json) if classify_line(&json) == LineClass::Unknown => ("unknown schema".to_string(), Some(redact_line(&json))),
+                    Ok(_) => return None,
+                }
+            };
+            Some(ParseErrorSample { file: file.clone(), line: index + 1, problem, sample })
+        });
+        samples.extend(problems.take(SAMPLES_PER_FILE));
+        if samples.len() >= MAX_SAMPLES {
+            break;
+        }
+    }
+    samples.truncate(MAX_SAMPLES);
+    samples
+}
+
+/// Version, build and platform of this executable
+pub fn build_info() -> String {
+    format!(
+        "claude-token-monitor {}\nbuilt: {} (build {})\ncommit: {}\nplatform: {} {}\n",
+        env!("CARGO_PKG_VERSION"),
+        env!("CLAUDE_TOKEN_MONITOR_BUILD_TIME"),
+        env!("CLAUDE_TOKEN_MONITOR_BUILD_ID"),
+        option_env!("CLAUDE_TOKEN_MONITOR_GIT_HASH").unwrap_or("unknown"),
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    )
+}
+
+/// Write `files` (name and contents) to a gzipped tar archive at `out`, under `BUNDLE_DIR/`
+pub fn write_bundle(out: &Path, files: &[(&str, Vec<u8>)], now: DateTime<Utc>) -> Result<()> {
+    write_atomic_with(out, |writer| {
+        let mut archive = tar::Builder::new(GzEncoder::new(writer, Compression::default()));
+        for (name, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(now.timestamp().max(0) as u64);
+            header.set_cksum();
+            archive.append_data(&mut header, format!("{BUNDLE_DIR}/{name}"), contents.as_slice())?;
+        }
+        archive.into_inner()?.finish()?;
+        Ok(())
+    })
+}
@@ -0,0 +1,155 @@
+use super::file_monitor::UsageEntry;
+use crate::models::DedupStrategy;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+
+/// Entries sharing a dedup key that were found in more than one file
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrossFileDuplicates {
+    pub files: Vec<PathBuf>,  // every file holding a copy
+    pub entries: usize,       // distinct entries duplicated across exactly these files
+    pub extra_copies: usize,  // copies dropped by deduplication
+    pub inflated_tokens: u64, // tokens those copies would have added
+}
+
+/// How deduplication changed the scanned totals
+#[derive(Debug, Clone, Default)]
+pub struct DuplicateReport {
+    pub strategy: DedupStrategy,
+    pub raw_entries: usize,
+    pub unique_entries: usize,
+    pub raw_tokens: u64,
+    pub unique_tokens: u64,
+    pub entries_without_ids: usize, // entries with neither a message nor a request ID
+    pub same_file_copies: usize, // repeated lines inside a single file (e.g. one per content block)
+    pub cross_file: Vec<CrossFileDuplicates>, // largest inflation first
+}
+
+impl DuplicateReport {
+    pub fn removed_entries(&self) -> usize {
+        self.raw_entries - self.unique_entries
+    }
+
+    pub fn inflated_tokens(&self) -> u64 {
+        self.raw_tokens - self.unique_tokens
+    }
+
+    pub fn cross_file_tokens(&self) -> u64 {
+        self.cross_file.iter().map(|group| group.inflated_tokens).sum()
+    }
+}
+
+/// Identity of an entry under a dedup strategy
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum DedupKey {
+    Ids(Option<String>, Option<String>),
+    Content(DateTime<Utc>, [u32; 4]),
+    IdsAndContent(Option<String>, Option<String>, DateTime<Utc>, [u32; 4]),
+}
+
+fn usage_fingerprint(entry: &UsageEntry) -> [u32; 4] {
+    let usage = &entry.usage;
+    [
+        usage.input_tokens,
+        usage.output_tokens,
+        usage.cache_creation_tokens(),
+        usage.cache_read_tokens(),
+    ]
+}
+
+fn dedup_key(strategy: DedupStrategy, entry: &UsageEntry) -> DedupKey {
+    let has_ids = entry.message_id.is_some() || entry.request_id.is_some();
+    match strategy {
+        DedupStrategy::Ids if has_ids => DedupKey::Ids(entry.message_id.clone(), entry.request_id.clone()),
+        // Without IDs every entry would share one key, so only identical content collapses
+        DedupStrategy::Ids | DedupStrategy::Content => DedupKey::Content(entry.timestamp, usage_fingerprint(entry)),
+        DedupStrategy::IdsAndContent => DedupKey::IdsAndContent(
+            entry.message_id.clone(),
+            entry.request_id.clone(),
+            entry.timestamp,
+            usage_fingerprint(entry),
+        ),
+    }
+}
+
+/// One deduplicated entry and where its copies came from
+struct Copies {
+    entry: UsageEntry,
+    files: BTreeSet<usize>,
+    count: usize,
+    raw_tokens: u64,
+}
+
+/// Collapse entries sharing a dedup key, keeping the latest copy.
+///
+/// `entries` pairs each entry with the index of its source file in `files`.
+pub fn deduplicate(
+    mut entries: Vec<(usize, UsageEntry)>,
+    files: &[PathBuf],
+    strategy: DedupStrategy,
+) -> (Vec<UsageEntry>, DuplicateReport) {
+    entries.sort_by_key(|(_, entry)| entry.timestamp);
+
+    let mut report = DuplicateReport {
+        strategy,
+        raw_entries: entries.len(),
+        ..DuplicateReport::default()
+    };
+
+    let mut copies: HashMap<DedupKey, Copies> = HashMap::new();
+    for (file, entry) in entries {
+        let tokens = entry.usage.total_tokens() as u64;
+        report.raw_tokens += tokens;
+        if entry.message_id.is_none() && entry.request_id.is_none() {
+            report.entries_without_ids += 1;
+        }
+        let key = dedup_key(strategy, &entry);
+        match copies.get_mut(&key) {
+            Some(existing) => {
+                existing.files.insert(file);
+                existing.count += 1;
+                existing.raw_tokens += tokens;
+                existing.entry = entry;
+            }
+            None => {
+                copies.insert(key, Copies {
+                    entry,
+                    files: BTreeSet::from([file]),
+                    count: 1,
+                    raw_tokens: tokens,
+                });
+            }
+        }
+    }
+
+    let mut cross_file: BTreeMap<Vec<usize>, CrossFileDuplicates> = BTreeMap::new();
+    let mut unique = Vec::with_capacity(copies.len());
+    for copy in copies.into_values() {
+        let kept_tokens = copy.entry.usage.total_tokens() as u64;
+        report.unique_tokens += kept_tokens;
+
+        if copy.files.len() > 1 {
+            let file_set: Vec<usize> = copy.files.into_iter().collect();
+            let group = cross_file.entry(file_set.clone()).or_insert_with(|| CrossFileDuplicates {
+                files: file_set.iter().filter_map(|index| files.get(*index).cloned()).collect(),
+                entries: 0,
+                extra_copies: 0,
+                inflated_tokens: 0,
+            });
+            group.entries += 1;
+            group.extra_copies += copy.count - 1;
+            group.inflated_tokens += copy.raw_tokens - kept_tokens;
+        } else {
+            report.same_file_copies += copy.count - 1;
+        }
+        unique.push(copy.entry);
+    }
+
+    unique.sort_by_key(|entry| entry.timestamp);
+    report.unique_entries = unique.len();
+    report.cross_file = cross_file.into_values().collect();
+    report.cross_file.sort_by_key(|group| std::cmp::Reverse(group.inflated_tokens));
+
+    (unique, report)
+}
@@ -0,0 +1,179 @@
+use crate::models::{SmtpConfig, SmtpSecurity};
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use rustls::pki_types::ServerName;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Connecting to and each exchange with the mail server give up after this long
+pub const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A plain or TLS connection to the mail server
+trait Stream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Stream for T {}
+
+/// One SMTP session, reading replies line by line
+struct Session {
+    stream: BufReader<Box<dyn Stream>>,
+}
+
+impl Session {
+    /// Read a (possibly multi-line) reply, failing unless its code is `expected`
+    fn reply(&mut self, expected: u16) -> Result<String> {
+        let mut text = String::new();
+        loop {
+            let mut line = String::new();
+            if self.stream.read_line(&mut line)? == 0 {
+                return Err(anyhow!("mail server closed the connection"));
+            }
+            let line = line.trim_end();
+            let code: u16 = line.get(..3).and_then(|code| code.parse().ok()).ok_or_else(|| anyhow!("unexpected reply from mail server: {line}"))?;
+            text.push_str(line.get(4..).unwrap_or_default());
+            text.push('\n');
+            if line.as_bytes().get(3) != Some(&b'-') {
+                if code != expected {
+                    return Err(anyhow!("mail server replied {line}"));
+                }
+                return Ok(text);
+            }
+        }
+    }
+
+    /// Send a command line and read the reply
+    fn command(&mut self, command: &str, expected: u16) -> Result<String> {
+        let stream = self.stream.get_mut();
+        stream.write_all(command.as_bytes())?;
+        stream.write_all(b"\r\n")?;
+        stream.flush()?;
+        self.reply(expected)
+    }
+}
+
+/// TLS to `host` over `stream`, verified against the bundled web PKI roots
+fn tls_stream(host: &str, stream: Box<dyn Stream>) -> Result<Box<dyn Stream>> {
+    let roots = rustls::RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() };
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let name = ServerName::try_from(host.to_string()).map_err(|_| anyhow!("'{host}' is not a valid server name"))?;
+    let connection = rustls::ClientConnection::new(Arc::new(config), name)?;
+    Ok(Box::new(rustls::StreamOwned::new(connection, stream)))
+}
+
+/// Fail unless `address` can go into a header or an SMTP command as is: no control characters
+/// (which could end the line and start another), spaces, angle brackets or commas
+fn check_address(address: &str) -> Result<()> {
+    if address.is_empty() || address.contains(|c: char| c.is_control() || matches!(c, ' ' | '<' | '>' | ',')) {
+        return Err(anyhow!("'{}' is not a usable email address", address.escape_debug()));
+    }
+    Ok(())
+}
+
+/// `text` as an RFC 2047 encoded word when it isn't plain ASCII, for headers, with control
+/// characters (line breaks included) replaced by spaces so it stays one header line
+fn encode_header(text: &str) -> String {
+    let text: String = text.chars().map(|c| if c.is_control() { ' ' } else { c }).collect();
+    if text.is_ascii() {
+        text.to_string()
+    } else {
+        format!("=?UTF-8?B?{}?=", base64::engine::general_purpose::STANDARD.encode(text))
+    }
+}
+
+/// The message as sent after DATA: headers, the body with CRLF line ends and leading dots doubled,
+/// and the terminating dot line. Fails on addresses that would break the headers.
+pub fn format_message(from: &str, to: &[String], subject: &str, body: &str, date: DateTime<Utc>) -> Result<String> {
+    check_address(from)?;
+    for address in to {
+        check_address(address)?;
+    }
+    let mut message = format!(
+        "From: {from}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n",
+        to.join(", "),
+        encode_header(subject),
+        date.to_rfc2822()
+    );
+    for line in body.lines() {
+        if line.starts_with('.') {
+            message.push('.');
+        }
+        message.push_str(line);
+        message.push_str("\r\n");
+    }
+    message.push_str(".\r\n");
+    Ok(message)
+}
+
+/// Connect to the first of `host`'s addresses that accepts
+fn connect(host: &str, port: u16) -> Result<TcpStream> {
+    let mut last_error = None;
+    for address in (host, port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, SMTP_TIMEOUT) {
+            Ok(tcp) => return Ok(tcp),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    match last_error {
+        Some(e) => Err(e).with_context(|| format!("Failed to connect to {host}:{port}")),
+        None => Err(anyhow!("mail server {host} has no address")),
+    }
+}
+
+/// Send a plain-text email to `to` through the configured server. Blocks until the server has
+/// accepted it; the password is read from `password_env` on every send.
+pub fn send_email(smtp: &SmtpConfig, to: &[String], subject: &str, body: &str) -> Result<()> {
+    let (host, port) = smtp.server_address()?.ok_or_else(|| anyhow!("no [smtp] server configured"))?;
+    let from = smtp.sender().ok_or_else(|| anyhow!("no sender address in [smtp] 'from'"))?;
+    // Checked before connecting, as the addresses also go into the MAIL and RCPT commands
+    let message = format_message(from, to, subject, body, Utc::now())?;
+    let tcp = connect(&host, port)?;
+    tcp.set_read_timeout(Some(SMTP_TIMEOUT))?;
+    tcp.set_write_timeout(Some(SMTP_TIMEOUT))?;
+
+    let stream: Box<dyn Stream> = match smtp.security {
+        SmtpSecurity::Tls => tls_stream(&host, Box::new(tcp))?,
+        SmtpSecurity::StartTls | SmtpSecurity::None => Box::new(tcp),
+    };
+    let mut session = Session { stream: BufReader::new(stream) };
+    session.reply(220)?;
+    let mut extensions = session.command("EHLO localhost", 250)?;
+    if smtp.security == SmtpSecurity::StartTls {
+        session.command("STARTTLS", 220)?;
+        // Nothing may follow the reply before the handshake, so no buffered input is lost here
+        let plain = session.stream.into_inner();
+        session = Session { stream: BufReader::new(tls_stream(&host, plain)?) };
+        extensions = session.command("EHLO localhost", 250)?;
+    }
+
+    if let Some(username) = &smtp.username {
+        let password = match &smtp.password_env {
+            Some(var) => std::env::var(var).with_context(|| format!("SMTP password variable {var} is not set"))?,
+            None => String::new(),
+        };
+        let auth = extensions.lines().find_map(|line| line.strip_prefix("AUTH ")).unwrap_or_default();
+        let base64 = &base64::engine::general_purpose::STANDARD;
+        if auth.split_whitespace().any(|mechanism| mechanism == "PLAIN") || !auth.contains("LOGIN") {
+            session.command(&format!("AUTH PLAIN {}", base64.encode(format!("\0{username}\0{password}"))), 235)?;
+        } else {
+            session.command("AUTH LOGIN", 334)?;
+            session.command(&base64.encode(username), 334)?;
+            session.command(&base64.encode(password), 235)?;
+        }
+    }
+
+    session.command(&format!("MAIL FROM:<{from}>"), 250)?;
+    for recipient in to {
+        session.command(&format!("RCPT TO:<{recipient}>"), 250)?;
+    }
+    session.command("DATA", 354)?;
+    session.stream.get_mut().write_all(message.as_bytes())?;
+    session.reply(250)?;
+    // The message is accepted; a server hanging up early on QUIT doesn't matter
+    let _ = session.command("QUIT", 221);
+    Ok(())
+}
@@ -0,0 +1,228 @@
+use super::file_lock::write_atomic;
+use crate::models::EncryptionConfig;
+use anyhow::{anyhow, Context, Result};
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Start of every encrypted file; plaintext state files start with `{` or `[`
+const MAGIC: &[u8; 8] = b"CTM-ENC1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const HEADER_LEN: usize = MAGIC.len() + SALT_LEN + NONCE_LEN;
+
+type Salt = [u8; SALT_LEN];
+
+/// Whether `data` was written by `StateCipher::encrypt`
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Encrypts state files with XChaCha20-Poly1305 under a key derived (Argon2id) from a passphrase
+/// or key file.
+///
+/// Each file stores the salt it was written with; keys are derived once per salt and cached, as
+/// derivation is deliberately slow.
+#[derive(Clone)]
+pub struct StateCipher {
+    secret: Arc<Vec<u8>>,
+    keys: Arc<Mutex<Vec<(Salt, Key)>>>, // derived so far, by salt
+}
+
+impl fmt::Debug for StateCipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StateCipher").field("secret", &"[REDACTED]").finish()
+    }
+}
+
+impl StateCipher {
+    pub fn new(secret: &[u8]) -> Result<Self> {
+        if secret.is_empty() {
+            return Err(anyhow!("Encryption passphrase or key file is empty"));
+        }
+        Ok(Self {
+            secret: Arc::new(secret.to_vec()),
+            keys: Arc::new(Mutex::new(Vec::new())),
+        })
+    }
+
+    /// The cipher configured in `[encryption]`, or None when encryption is off
+    pub fn from_config(config: &EncryptionConfig) -> Result<Option<Self>> {
+        if let Some(path) = &config.key_file {
+            let secret = std::fs::read(path).with_context(|| format!("Failed to read encryption key file {}", path.display()))?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                if std::fs::metadata(path)?.permissions().mode() & 0o077 != 0 {
+                    log::warn!("Encryption key file {} is readable by other users; chmod 600 it", path.display());
+                }
+            }
+            return Self::new(trim_line_end(&secret)).map(Some);
+        }
+        if let Some(variable) = &config.passphrase_env {
+            let passphrase = std::env::var(variable)
+                .map_err(|_| anyhow!("Encryption is on but ${variable} is not set to the passphrase"))?;
+            return Self::new(passphrase.as_bytes()).map(Some);
+        }
+        Ok(None)
+    }
+
+    fn key_for(&self, salt: &Salt) -> Result<Key> {
+        let mut keys = self.keys.lock().map_err(|_| anyhow!("Encryption key cache is poisoned"))?;
+        if let Some((_, key)) = keys.iter().find(|(known, _)| known == salt) {
+            return Ok(*key);
+        }
+        let mut key = Key::default();
+        Argon2::default()
+            .hash_password_into(&self.secret, salt, &mut key)
+            .map_err(|e| anyhow!("Failed to derive encryption key: {e}"))?;
+        keys.push((*salt, key));
+        Ok(key)
+    }
+
+    /// Encrypt with a fresh nonce, reusing the salt of a key already derived when there is one
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let known_salt = self.keys.lock().ok().and_then(|keys| keys.first().map(|(salt, _)| *salt));
+        let salt = known_salt.unwrap_or_else(|| {
+            let mut salt: Salt = [0; SALT_LEN];
+            rand::thread_rng().fill_bytes(&mut salt);
+            salt
+        });
+        let key = self.key_for(&salt)?;
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let mut output = Vec::with_capacity(HEADER_LEN + plaintext.len() + 16);
+        output.extend_from_slice(MAGIC);
+        output.extend_from_slice(&salt);
+        let ciphertext = XChaCha20Poly1305::new(&key)
+            .encrypt(XNonce::from_slice(&nonce), Payload { msg: plaintext, aad: &output })
+            .map_err(|_| anyhow!("Failed to encrypt"))?;
+        output.extend_from_slice(&nonce);
+        output.extend_from_slice(&ciphertext);
+        Ok(output)
+    }
+
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        if !is_encrypted(data) || data.len() < HEADER_LEN {
+            return Err(anyhow!("Not an encrypted state file"));
+        }
+        let (header, rest) = data.split_at(MAGIC.len() + SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let salt: Salt = header[MAGIC.len()..].try_into()?;
+        let key = self.key_for(&salt)?;
+        XChaCha20Poly1305::new(&key)
+            .decrypt(XNonce::from_slice(nonce), Payload { msg: ciphertext, aad: header })
+            .map_err(|_| anyhow!("wrong passphrase or key file, or the file is damaged"))
+    }
+}
+
+fn trim_line_end(secret: &[u8]) -> &[u8] {
+    let end = secret.iter().rposition(|byte| !matches!(byte, b'\n' | b'\r')).map_or(0, |last| last + 1);
+    &secret[..end]
+}
+
+/// A persisted state file, encrypted when a cipher is configured.
+///
+/// Plaintext files are still read with a cipher set, so turning encryption on converts each file
+/// the next time it is saved.
+#[derive(Debug, Clone)]
+pub struct StateFile {
+    path: PathBuf,
+    cipher: Option<StateCipher>,
+    read_only: bool,
+}
+
+impl StateFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            cipher: None,
+            read_only: false,
+        }
+    }
+
+    pub fn with_cipher(mut self, cipher: Option<StateCipher>) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
+    /// Never touch the file on disk: writes are dropped (`--read-only`)
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The file's contents, or None when it doesn't exist yet
+    pub fn read(&self) -> Result<Option<String>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let data = std::fs::read(&self.path).with_context(|| format!("Failed to read {}", self.path.display()))?;
+        let data = if is_encrypted(&data) {
+            let cipher = self.cipher.as_ref().ok_or_else(|| {
+                anyhow!("{} is encrypted; set the same [encryption] key in the config to read it", self.path.display())
+            })?;
+            cipher
+                .decrypt(&data)
+                .with_context(|| format!("Failed to decrypt {}", self.path.display()))?
+        } else {
+            data
+        };
+        String::from_utf8(data)
+            .map(Some)
+            .with_context(|| format!("{} is not valid UTF-8", self.path.display()))
+    }
+
+    /// Replace the file with `contents`; does nothing for a read-only file
+    pub fn write(&self, contents: &str) -> Result<()> {
+        if self.read_only {
+            return Ok(());
+        }
+        match &self.cipher {
+            Some(cipher) => write_atomic(&self.path, cipher.encrypt(contents.as_bytes())?),
+            None => write_atomic(&self.path, contents),
+        }
+    }
+}
+
+/// A state directory whose files share one cipher and access mode
+#[derive(Debug, Clone)]
+pub struct StateDir {
+    dir: PathBuf,
+    cipher: Option<StateCipher>,
+    read_only: bool,
+}
+
+impl StateDir {
+    pub fn new(dir: PathBuf, cipher: Option<StateCipher>) -> Self {
+        Self {
+            dir,
+            cipher,
+            read_only: false,
+        }
+    }
+
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    pub fn file(&self, name: &str) -> StateFile {
+        StateFile::new(self.dir.join(name))
+            .with_cipher(self.cipher.clone())
+            .with_read_only(self.read_only)
+    }
+}
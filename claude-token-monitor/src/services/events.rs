@@ -0,0 +1,132 @@
+use super::alerts::{Alert, AlertKind};
+use super::encryption::StateFile;
+use crate::models::{LimitEvent, LimitEventKind, SessionBlock};
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// File in the data dir holding the events the live monitor noticed
+pub const EVENTS_FILE: &str = "events.json";
+
+/// Recorded events kept, oldest dropped first
+pub const MAX_EVENTS: usize = 2000;
+
+/// What happened at a point on the usage timeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum EventKind {
+    WindowReset,
+    LimitReached,
+    PlanSwitch,
+    Alert,
+}
+
+impl EventKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventKind::WindowReset => "window reset",
+            EventKind::LimitReached => "limit reached",
+            EventKind::PlanSwitch => "plan switch",
+            EventKind::Alert => "alert",
+        }
+    }
+}
+
+/// A notable event, with a line describing it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TimelineEvent {
+    pub timestamp: DateTime<Utc>,
+    pub kind: EventKind,
+    pub detail: String,
+}
+
+/// Events only the live monitor can see (plan switches and alerts), kept between runs; window
+/// resets and limit notices come from the logs again on every scan
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EventLog {
+    #[serde(default)]
+    pub events: Vec<TimelineEvent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan: Option<String>, // plan in effect when last seen, to notice the next switch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weekly_summary: Option<NaiveDate>, // start of the week the weekly summary was last sent for
+}
+
+impl EventLog {
+    pub fn load(file: &StateFile) -> Result<Self> {
+        let Some(content) = file.read()? else {
+            return Ok(Self::default());
+        };
+        serde_json::from_str(&content).with_context(|| format!("{} is not a valid event log", file.path().display()))
+    }
+
+    /// Replace the saved log; concurrent readers see either the old or the new file
+    pub fn save(&self, file: &StateFile) -> Result<()> {
+        file.write(&serde_json::to_string(self)?)
+    }
+
+    /// Add events in time order, dropping the oldest past `MAX_EVENTS`
+    pub fn record(&mut self, events: impl IntoIterator<Item = TimelineEvent>) {
+        self.events.extend(events);
+        self.events.sort_by_key(|event| event.timestamp);
+        let excess = self.events.len().saturating_sub(MAX_EVENTS);
+        self.events.drain(..excess);
+    }
+
+    /// Note the plan in effect at `now`, recording a switch when it differs from the one last seen.
+    ///
+    /// Returns true when anything changed and the log is worth saving.
+    pub fn observe_plan(&mut self, plan: &str, now: DateTime<Utc>) -> bool {
+        match self.plan.replace(plan.to_string()) {
+            Some(previous) if previous == plan => false,
+            Some(previous) => {
+                self.record([TimelineEvent { timestamp: now, kind: EventKind::PlanSwitch, detail: format!("{previous} → {plan}") }]);
+                true
+            }
+            None => true,
+        }
+    }
+
+    /// Record alerts that just fired, and the week of a weekly summary among them
+    pub fn record_alerts(&mut self, alerts: &[Alert]) {
+        for alert in alerts {
+            if let AlertKind::WeeklySummary(summary) = &alert.kind {
+                self.weekly_summary = Some(summary.week_start);
+            }
+        }
+        self.record(alerts.iter().map(|alert| TimelineEvent {
+            timestamp: alert.timestamp,
+            kind: EventKind::Alert,
+            detail: alert.message.clone(),
+        }));
+    }
+}
+
+/// Window resets (up to `now`) and limit-reached notices found in the logs
+pub fn observed_events(blocks: &[SessionBlock], limit_events: &[LimitEvent], now: DateTime<Utc>) -> Vec<TimelineEvent> {
+    let resets = blocks.iter().filter(|block| block.reset_time <= now).map(|block| TimelineEvent {
+        timestamp: block.reset_time,
+        kind: EventKind::WindowReset,
+        detail: format!("window from {} reset after {} tokens", block.start_time.format("%H:%M"), block.tokens_used),
+    });
+    let limits = limit_events
+        .iter()
+        .filter(|event| event.kind == LimitEventKind::LimitReached)
+        .map(|event| TimelineEvent {
+            timestamp: event.timestamp,
+            kind: EventKind::LimitReached,
+            detail: match event.reset_time {
+                Some(reset) => format!("usage limit reached, resets at {}", reset.format("%H:%M UTC")),
+                None => "usage limit reached".to_string(),
+            },
+        });
+    resets.chain(limits).collect()
+}
+
+/// The recorded and observed events from `since`, oldest first
+pub fn timeline(log: &EventLog, observed: Vec<TimelineEvent>, since: DateTime<Utc>) -> Vec<TimelineEvent> {
+    let mut events: Vec<TimelineEvent> = log.events.iter().cloned().chain(observed).filter(|event| event.timestamp >= since).collect();
+    events.sort_by_key(|event| (event.timestamp, event.kind));
+    events
+}
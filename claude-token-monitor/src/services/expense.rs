@@ -0,0 +1,203 @@
+use super::file_monitor::{Billing, UsageEntry};
+use super::model_names::ModelNames;
+use super::pricing::Prices;
+use anyhow::{anyhow, Result};
+use crate::models::UserConfig;
+use chrono::{Datelike, Months, NaiveDate};
+use clap::ValueEnum;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::Path;
+
+/// Project column for entries logged outside any project, such as imported Console usage
+pub const NO_PROJECT: &str = "(none)";
+
+/// Columns of the CSV export, in order
+pub const CSV_HEADERS: [&str; 12] = [
+    "date",
+    "billing_code",
+    "project",
+    "model",
+    "billing",
+    "requests",
+    "input_tokens",
+    "output_tokens",
+    "cache_write_tokens",
+    "cache_read_tokens",
+    "total_tokens",
+    "cost_usd",
+];
+
+/// Billing codes tagging projects, from `billing_codes` in the config
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BillingCodes {
+    codes: Vec<(String, String)>, // project directory or label and its code, longest first
+}
+
+impl BillingCodes {
+    pub fn new(codes: &BTreeMap<String, String>) -> Self {
+        let mut codes: Vec<(String, String)> = codes.iter().map(|(project, code)| (project.clone(), code.clone())).collect();
+        codes.sort_by_key(|(project, _)| std::cmp::Reverse(project.len()));
+        Self { codes }
+    }
+
+    pub fn from_config(config: &UserConfig) -> Self {
+        Self::new(&config.billing_codes)
+    }
+
+    /// The code of the project in directory `project` labelled `label`: the one configured for
+    /// the label, or for the deepest configured directory containing the project
+    pub fn code(&self, project: &str, label: &str) -> Option<&str> {
+        let by_label = self.codes.iter().find(|(key, _)| key == label);
+        by_label
+            .or_else(|| self.codes.iter().find(|(key, _)| Path::new(project).starts_with(key)))
+            .map(|(_, code)| code.as_str())
+    }
+}
+
+/// What expense line items are split by besides day, model and billing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ExpenseGrouping {
+    #[default]
+    #[value(alias = "projects")]
+    Project,
+    #[value(alias = "code", alias = "tag")]
+    BillingCode, // one line for all projects sharing a code, naming them
+}
+
+/// Usage of one model in one project (or billing code) on one UTC day
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpenseLine {
+    pub date: NaiveDate,
+    pub billing_code: Option<String>,
+    pub project: String, // the project's label, or every project of the line's code joined by "; "
+    pub model: String,
+    pub billing: Billing,
+    pub requests: usize,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_write_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cost: f64, // API-equivalent USD
+}
+
+impl ExpenseLine {
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens + self.cache_write_tokens + self.cache_read_tokens
+    }
+}
+
+/// First day of a `YYYY-MM` month
+pub fn parse_month(text: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("{}-01", text.trim()), "%Y-%m-%d")
+        .map_err(|_| anyhow!("'{text}' is not a month; use YYYY-MM, e.g. 2025-01"))
+}
+
+/// The entries of the calendar month starting on `month` (UTC days) as line items per day,
+/// project (or billing code), model and billing, in that order. Projects are named by
+/// `project_label`, models by `names`, and entries are priced by `prices`.
+pub fn expense_lines<'a>(
+    entries: impl Iterator<Item = &'a UsageEntry>,
+    month: NaiveDate,
+    project_label: impl Fn(&str) -> String,
+    codes: &BillingCodes,
+    grouping: ExpenseGrouping,
+    prices: Prices,
+    names: &ModelNames,
+) -> Vec<ExpenseLine> {
+    let first = month.with_day(1).unwrap_or(month);
+    let next = first + Months::new(1);
+    // Keyed by day, project directory or code, model and whether billed per token; with the
+    // projects seen. Directories rather than labels, so projects sharing a name stay apart.
+    let mut lines: BTreeMap<(NaiveDate, String, String, bool), ExpenseLine> = BTreeMap::new();
+    let mut projects: BTreeMap<(NaiveDate, String, String, bool), BTreeSet<String>> = BTreeMap::new();
+    for entry in entries {
+        let date = entry.timestamp.date_naive();
+        if date < first || date >= next {
+            continue;
+        }
+        let project = entry.project.as_deref().map_or_else(|| NO_PROJECT.to_string(), &project_label);
+        let billing_code = entry.project.as_deref().and_then(|path| codes.code(path, &project)).map(str::to_string);
+        let model = names.name(entry.model.as_deref().or(prices.default_model).unwrap_or("unknown"));
+        let group = match grouping {
+            ExpenseGrouping::Project => entry.project.as_deref().unwrap_or(NO_PROJECT).to_string(),
+            ExpenseGrouping::BillingCode => billing_code.clone().unwrap_or_default(),
+        };
+        let key = (date, group, model.clone(), entry.billing == Billing::ApiKey);
+        projects.entry(key.clone()).or_default().insert(project);
+        let line = lines.entry(key).or_insert_with(|| ExpenseLine {
+            date,
+            billing_code,
+            project: String::new(),
+            model,
+            billing: entry.billing,
+            requests: 0,
+            input_tokens: 0,
+            output_tokens: 0,
+            cache_write_tokens: 0,
+            cache_read_tokens: 0,
+            cost: 0.0,
+        });
+        let usage = &entry.usage;
+        line.requests += 1;
+        line.input_tokens += usage.input_tokens as u64;
+        line.output_tokens += usage.output_tokens as u64;
+        line.cache_write_tokens += usage.cache_creation_tokens() as u64;
+        line.cache_read_tokens += usage.cache_read_tokens() as u64;
+        line.cost += prices.cost(entry);
+    }
+    lines
+        .into_iter()
+        .map(|(key, mut line)| {
+            line.project = projects[&key].iter().map(String::as_str).collect::<Vec<_>>().join("; ");
+            line
+        })
+        .collect()
+}
+
+/// Requests, tokens and cost per billing code (None for untagged usage), most expensive first
+pub fn totals_by_code(lines: &[ExpenseLine]) -> Vec<(Option<&str>, usize, u64, f64)> {
+    let mut totals: BTreeMap<Option<&str>, (usize, u64, f64)> = BTreeMap::new();
+    for line in lines {
+        let total = totals.entry(line.billing_code.as_deref()).or_default();
+        total.0 += line.requests;
+        total.1 += line.total_tokens();
+        total.2 += line.cost;
+    }
+    let mut totals: Vec<_> = totals.into_iter().map(|(code, (requests, tokens, cost))| (code, requests, tokens, cost)).collect();
+    totals.sort_by(|a, b| b.3.total_cmp(&a.3).then_with(|| a.0.cmp(&b.0)));
+    totals
+}
+
+/// How a line is billed, as the CSV's billing column names it
+pub fn billing_name(billing: Billing) -> &'static str {
+    match billing {
+        Billing::Subscription => "subscription",
+        Billing::ApiKey => "api-key",
+    }
+}
+
+/// Write `lines` as CSV with a header row, costs to four decimal places so small lines don't
+/// round away
+pub fn write_csv(lines: &[ExpenseLine], writer: impl Write) -> Result<()> {
+    let mut csv = csv::Writer::from_writer(writer);
+    csv.write_record(CSV_HEADERS)?;
+    for line in lines {
+        csv.write_record([
+            line.date.format("%Y-%m-%d").to_string(),
+            line.billing_code.clone().unwrap_or_default(),
+            line.project.clone(),
+            line.model.clone(),
+            billing_name(line.billing).to_string(),
+            line.requests.to_string(),
+            line.input_tokens.to_string(),
+            line.output_tokens.to_string(),
+            line.cache_write_tokens.to_string(),
+            line.cache_read_tokens.to_string(),
+            line.total_tokens().to_string(),
+            format!("{:.4}", line.cost),
+        ])?;
+    }
+    csv.flush()?;
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use super::permissions::{create_private_dir_all, private_open_options};
+use anyhow::{Context, Result};
+use std::fs::{File, TryLockError};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// How long to wait for another instance to release a lock before giving up, and how often to retry
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+const LOCK_RETRY: Duration = Duration::from_millis(25);
+
+/// Advisory lock on a state file, held until dropped.
+///
+/// The lock is taken on a `<file>.lock` sidecar rather than the file itself, so the file can still
+/// be replaced by rename while it is held. Only other instances that take the same lock are kept
+/// out; the OS releases it if the process dies.
+///
+/// Taking a lock blocks for up to `LOCK_TIMEOUT`, so async code takes it with `spawn_blocking`.
+#[derive(Debug)]
+pub struct FileLock {
+    _file: File,
+}
+
+impl FileLock {
+    /// Wait for sole access to `path`, for read-modify-write cycles
+    pub fn exclusive(path: &Path) -> Result<Self> {
+        Self::acquire(path, File::try_lock)
+    }
+
+    /// Wait until no writer holds `path`; any number of readers can share it
+    pub fn shared(path: &Path) -> Result<Self> {
+        Self::acquire(path, File::try_lock_shared)
+    }
+
+    fn acquire(path: &Path, try_lock: fn(&File) -> Result<(), TryLockError>) -> Result<Self> {
+        let file = Self::open(path)?;
+        let deadline = Instant::now() + LOCK_TIMEOUT;
+        loop {
+            match try_lock(&file) {
+                Ok(()) => return Ok(Self { _file: file }),
+                Err(TryLockError::WouldBlock) if Instant::now() < deadline => std::thread::sleep(LOCK_RETRY),
+                Err(TryLockError::WouldBlock) => {
+                    anyhow::bail!("Timed out waiting for another instance to release {}", lock_path(path).display())
+                }
+                Err(TryLockError::Error(e)) => return Err(e).with_context(|| format!("Failed to lock {}", path.display())),
+            }
+        }
+    }
+
+    fn open(path: &Path) -> Result<File> {
+        let lock_path = lock_path(path);
+        if let Some(parent) = lock_path.parent() {
+            create_private_dir_all(parent)?;
+        }
+        private_open_options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .with_context(|| format!("Failed to open {}", lock_path.display()))
+    }
+}
+
+pub fn lock_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".lock");
+    path.with_file_name(name)
+}
+
+/// Replace `path` with `contents` through a temporary file, so readers see either the old or the
+/// new contents and a crash mid-write never leaves a truncated file behind. The file ends up
+/// readable only by its owner, as do any directories created for it.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    write_atomic_with(path, |writer| Ok(writer.write_all(contents.as_ref())?))
+}
+
+/// Like `write_atomic`, for contents produced in pieces by `write`.
+///
+/// The temporary file is flushed to disk before the rename, and the directory after it, so the
+/// rename can't reach the disk ahead of the data it points to.
+pub fn write_atomic_with(path: &Path, write: impl FnOnce(&mut BufWriter<File>) -> Result<()>) -> Result<()> {
+    let parent = path.parent().filter(|parent| !parent.as_os_str().is_empty());
+    if let Some(parent) = parent {
+        create_private_dir_all(parent)?;
+    }
+    // Per-process name so two instances never write the same temporary file
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.tmp", std::process::id()));
+    let temp_path = path.with_file_name(name);
+
+    let written = (|| -> Result<()> {
+        let file = private_open_options().write(true).create(true).truncate(true).open(&temp_path)?;
+        let mut writer = BufWriter::new(file);
+        write(&mut writer)?;
+        let file = writer.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+        Ok(())
+    })();
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e.context(format!("Failed to write {}", temp_path.display())));
+    }
+    std::fs::rename(&temp_path, path).with_context(|| format!("Failed to write {}", path.display()))?;
+    #[cfg(unix)]
+    if let Some(parent) = parent {
+        // Best effort: not every filesystem can sync a directory
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+    Ok(())
+}
@@ -1,9 +1,23 @@
+use super::claude_settings::{claude_config_dir, env_model, explicit_reset_time, selected_model, SelectedModel, SubscriptionHints};
+use super::analyzer::Analyzer;
+use super::pricing::Prices;
+use super::dedupe::{self, DuplicateReport};
+use super::accounts;
+use super::container::{self, ContainerKind};
+use super::os_users::UserDirectory;
+use super::importer;
+use super::intern::{self, Interned};
+use super::{AnalyticsService, UsageAnalysis};
+use super::json_line;
+use super::schema::{classify_line, line_shape, FileSchemaReport, LineClass, SchemaDescriptor};
+use super::transcript::{self, LineType, LogLine, ServiceTier};
 use crate::models::*;
 use anyhow::{anyhow, Result};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Timelike, Utc};
 use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt;
 use std::path::{Path, PathBuf};
 use std::sync::{mpsc, Arc, Mutex};
@@ -14,15 +28,91 @@ use walkdir::WalkDir;
 const MAX_JSON_SIZE: usize = 1024 * 1024; // 1MB max per JSON line
 const MAX_JSON_DEPTH: usize = 32; // Maximum nesting depth
 const MAX_FILE_SIZE: usize = 50 * 1024 * 1024; // 50MB max file size
+const MAX_PROJECT_CONFIG_SIZE: u64 = 64 * 1024; // 64KB max per-project config
+
+/// How far ahead of this machine's clock a logged request may be, for clocks slightly out of sync;
+/// entries further ahead are left out until the time comes
+pub const FUTURE_TOLERANCE: chrono::Duration = chrono::Duration::minutes(5);
+
+// Limit calibration heuristics
+const CALIBRATION_IDLE_BEFORE_RESET_MINUTES: i64 = 30; // usage must stop this long before the reset
+const CALIBRATION_RESUME_AFTER_RESET_MINUTES: i64 = 15; // and resume this soon after it
+const CALIBRATION_TOLERANCE: f64 = 0.10; // ceilings within 10% count as consistent
+const CALIBRATION_MIN_SAMPLES: usize = 2;
+
+// Number of days shown in the activity heatmap
+const HEATMAP_DAYS: usize = 7;
+/// Minutes of per-minute token counts in the metrics, the longest window a rate alert can watch
+pub const MINUTE_TOKENS_SPAN: usize = 60;
+
+/// Per-project override file looked up in each project's working directory
+pub const PROJECT_CONFIG_FILE: &str = ".claude-token-monitor.toml";
+
+/// How the usage of an entry is paid for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Billing {
+    #[default]
+    Subscription, // a Claude plan, counted against its session windows
+    ApiKey,       // billed per token: Claude Code on an API key, or imported Console usage
+}
+
+/// Which sources of usage a view includes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SourceFilter {
+    #[default]
+    All,
+    ClaudeCode, // Claude Code's logs, on a subscription or an API key
+    Imported,   // API usage imported from outside Claude Code
+}
+
+impl SourceFilter {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SourceFilter::All => "All",
+            SourceFilter::ClaudeCode => "Claude Code",
+            SourceFilter::Imported => "Imported API",
+        }
+    }
+
+    /// The filter after this one, wrapping around
+    pub fn next(self) -> Self {
+        match self {
+            SourceFilter::All => SourceFilter::ClaudeCode,
+            SourceFilter::ClaudeCode => SourceFilter::Imported,
+            SourceFilter::Imported => SourceFilter::All,
+        }
+    }
+}
 
 /// Claude usage entry from JSONL files
+///
+/// Strings repeated across entries are interned; the IDs unique to each request are not.
 #[derive(Clone, Deserialize, Serialize)]
 pub struct UsageEntry {
     pub timestamp: DateTime<Utc>,
     pub usage: TokenUsage,
-    pub model: Option<String>,
+    pub model: Option<Interned>,
     pub message_id: Option<String>,
     pub request_id: Option<String>,
+    #[serde(default)]
+    pub project: Option<Interned>, // working directory of the Claude Code session
+    #[serde(default)]
+    pub conversation: Option<Interned>, // Claude Code's sessionId, one per conversation
+    #[serde(default)]
+    pub account: Option<Interned>, // configured account whose Claude home the log is in
+    #[serde(default)]
+    pub user: Option<Interned>, // OS user owning the log file
+    #[serde(default)]
+    pub billing: Billing,
+    #[serde(default)]
+    pub duration_ms: Option<u64>, // time the request took end to end, when logged
+    #[serde(default)]
+    pub ttft_ms: Option<u64>, // time to the first output token, when logged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_tier: Option<ServiceTier>,
+    #[serde(default, skip_serializing_if = "<[_]>::is_empty")]
+    pub tool_uses: Box<[Interned]>, // tools the response called, by name; a boxed slice keeps entries smaller than a Vec
 }
 
 impl fmt::Debug for UsageEntry {
@@ -33,10 +123,86 @@ impl fmt::Debug for UsageEntry {
             .field("model", &self.model)
             .field("message_id", &self.message_id.as_ref().map(|_| "[REDACTED]")) // Redact message ID
             .field("request_id", &self.request_id.as_ref().map(|_| "[REDACTED]")) // Redact request ID
+            .field("project", &self.project)
+            .field("conversation", &self.conversation.as_ref().map(|_| "[REDACTED]")) // Redact conversation ID
+            .field("account", &self.account)
+            .field("user", &self.user)
+            .field("billing", &self.billing)
+            .field("duration_ms", &self.duration_ms)
+            .field("ttft_ms", &self.ttft_ms)
+            .field("service_tier", &self.service_tier)
+            .field("tool_uses", &self.tool_uses)
             .finish()
     }
 }
 
+/// Metrics are recomputed at least this often even when no new usage arrived, as sessions go
+/// idle and windows end with time alone
+pub const METRICS_MAX_AGE: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Size and timing of the latest scan of the usage files
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ScanStats {
+    pub scans: u64,             // completed since the monitor was created
+    pub files: usize,           // JSONL files indexed by the latest one
+    pub duration: std::time::Duration,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub changed: bool,          // whether the latest one ingested new entries, events or errors, or saw the selected model change
+    pub skipped: bool,          // whether the latest one found no file changed and parsed nothing
+    pub data_changed_at: Option<DateTime<Utc>>, // end of the latest scan that ingested something new
+    pub late_entries: u64,      // ingested older than entries an earlier scan already had, since the monitor was created
+    pub late_since: Option<DateTime<Utc>>, // earliest of those the latest scan ingested; windows from here on changed
+}
+
+/// How much a scan ingested, to tell whether the next one brought anything new
+#[derive(Debug, Clone, Default, PartialEq)]
+struct IngestSummary {
+    usage_entries: usize,
+    api_key_entries: usize,
+    imported_entries: usize,
+    limit_events: usize,
+    request_errors: usize,
+    latest: Option<DateTime<Utc>>,
+    selected_model: Option<SelectedModel>, // changed with /model, which writes no log
+}
+
+/// Timestamps in `new` without a counterpart in `old`, both sorted: the entries a scan added
+fn added_timestamps(old: &[DateTime<Utc>], new: &[DateTime<Utc>]) -> Vec<DateTime<Utc>> {
+    let mut old = old.iter().peekable();
+    let mut added = Vec::new();
+    for timestamp in new {
+        while old.next_if(|before| *before < timestamp).is_some() {}
+        if old.next_if(|before| *before == timestamp).is_none() {
+            added.push(*timestamp);
+        }
+    }
+    added
+}
+
+/// Deterministic ID of the observed session whose window starts at `start`
+fn observed_session_id(start: DateTime<Utc>) -> String {
+    format!("observed-{}", start.timestamp())
+}
+
+/// Hash of the path, size and modification time of every file a scan reads
+fn file_fingerprint<'a>(files: impl Iterator<Item = (&'a Path, Option<std::fs::Metadata>)>) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    for (path, metadata) in files {
+        path.hash(&mut hasher);
+        metadata.map(|metadata| (metadata.len(), metadata.modified().ok())).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// The conversation log written to most recently, as of the latest scan
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversationWrite {
+    pub path: PathBuf,
+    pub modified: DateTime<Utc>,
+    pub project: Option<String>, // project of the file's latest request
+}
+
 /// Token usage information
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TokenUsage {
@@ -44,19 +210,21 @@ pub struct TokenUsage {
     pub output_tokens: u32,
     pub cache_creation_input_tokens: Option<u32>,
     pub cache_read_input_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_creation_1h_input_tokens: Option<u32>, // part of the cache writes with the 1-hour TTL, None when not logged
 }
 
 impl TokenUsage {
     pub fn total_tokens(&self) -> u32 {
-        self.input_tokens 
-            + self.output_tokens 
-            + self.cache_creation_input_tokens.unwrap_or(0)
-            + self.cache_read_input_tokens.unwrap_or(0)
+        self.input_tokens
+            .saturating_add(self.output_tokens)
+            .saturating_add(self.cache_creation_input_tokens.unwrap_or(0))
+            .saturating_add(self.cache_read_input_tokens.unwrap_or(0))
     }
     
     /// Calculate cache hit rate (cache read tokens / total input tokens)
     pub fn cache_hit_rate(&self) -> f64 {
-        let total_input = self.input_tokens + self.cache_creation_input_tokens.unwrap_or(0);
+        let total_input = self.input_tokens as u64 + self.cache_creation_input_tokens.unwrap_or(0) as u64;
         if total_input == 0 {
             0.0
         } else {
@@ -73,12 +241,66 @@ impl TokenUsage {
     pub fn cache_read_tokens(&self) -> u32 {
         self.cache_read_input_tokens.unwrap_or(0)
     }
+
+    /// Cache write tokens with the 1-hour TTL
+    pub fn cache_creation_1h_tokens(&self) -> u32 {
+        self.cache_creation_1h_input_tokens.unwrap_or(0).min(self.cache_creation_tokens())
+    }
+
+    /// Cache write tokens with the default 5-minute TTL
+    pub fn cache_creation_5m_tokens(&self) -> u32 {
+        self.cache_creation_tokens() - self.cache_creation_1h_tokens()
+    }
+
+    /// Token counts of a logged usage object, including the split of cache writes by TTL
+    /// (`cache_creation.ephemeral_1h_input_tokens`) when it is there
+    fn from_log(usage: &transcript::Usage) -> Self {
+        let tokens = |count: Option<u64>| count.map(|count| u32::try_from(count).unwrap_or(u32::MAX));
+        TokenUsage {
+            input_tokens: tokens(usage.input_tokens).unwrap_or(0),
+            output_tokens: tokens(usage.output_tokens).unwrap_or(0),
+            cache_creation_input_tokens: tokens(usage.cache_creation_input_tokens),
+            cache_read_input_tokens: tokens(usage.cache_read_input_tokens),
+            cache_creation_1h_input_tokens: usage
+                .cache_creation
+                .as_ref()
+                .map(|breakdown| tokens(breakdown.ephemeral_1h_input_tokens).unwrap_or(0)),
+        }
+    }
 }
 
 /// File-based Claude token monitor that reads JSONL files
 pub struct FileBasedTokenMonitor {
     claude_data_paths: Vec<PathBuf>,
+    accounts: Vec<(String, PathBuf)>, // account name and the canonical directory of its logs
+    api_key_accounts: BTreeSet<String>, // accounts whose Claude Code runs on an API key
+    path_mappings: BTreeMap<PathBuf, PathBuf>, // paths as seen elsewhere (the host) to where they are here
+    user_directory: UserDirectory, // OS users, to name log file owners
+    only_users: BTreeSet<String>, // skip logs other OS users own, unless empty
     usage_entries: Vec<UsageEntry>,
+    api_key_entries: Vec<UsageEntry>, // Claude Code usage billed to an API key, kept out of session math
+    imports_dir: Option<PathBuf>,
+    imported_entries: Vec<UsageEntry>, // API usage imported from outside Claude Code, kept out of session math
+    project_overrides: HashMap<String, ProjectOverrides>,
+    limit_events: Vec<LimitEvent>,
+    request_errors: Vec<RequestError>,
+    subscription_hints: SubscriptionHints,
+    unknown_model_pricing: ModelPricing, // rates of models the price list doesn't recognize
+    claude_config_dir: Option<PathBuf>, // where settings.json is re-read for the selected model on each scan
+    env_model: Option<String>, // ANTHROPIC_MODEL, which selects the model over any settings
+    selected_model: Option<SelectedModel>,
+    schema_reports: Vec<FileSchemaReport>,
+    failed_files: Vec<(PathBuf, String)>,
+    dedup_strategy: DedupStrategy,
+    cache_ttl: CacheTtl, // assumed for cache writes logged without their TTL
+    max_request_tokens: u32, // entries logged with more tokens are dropped as corrupt
+    duplicate_report: DuplicateReport,
+    idle_after: chrono::Duration, // pause in requests after which a session counts as idle
+    session_window: SessionWindowConfig,
+    scan_stats: ScanStats,
+    scan_fingerprint: Option<u64>, // of the files the latest full scan parsed; None forces a full scan
+    future_due: Option<DateTime<Utc>>, // when the first entry left out as ahead of the clock comes within tolerance
+    latest_write: Option<ConversationWrite>,
     _last_scan: DateTime<Utc>,
     _watcher: Option<Arc<Mutex<RecommendedWatcher>>>,
 }
@@ -173,10 +395,9 @@ impl FileBasedTokenMonitor {
                 .collect();
                 
             if window_entries.len() >= 3 {  // Need enough data points
-                let window_tokens: u32 = window_entries
+                let window_tokens = window_entries
                     .iter()
-                    .map(|entry| entry.usage.total_tokens())
-                    .sum();
+                    .fold(0u32, |total, entry| total.saturating_add(entry.usage.total_tokens()));
                     
                 let detected_plan = self.detect_plan_type_from_usage(window_tokens, current_time, window_end);
                 
@@ -191,7 +412,7 @@ impl FileBasedTokenMonitor {
                 last_detected_plan = Some(detected_plan);
             }
             
-            current_time = current_time + window_size;
+            current_time += window_size;
         }
         
         debug!("Found {} potential plan changes", plan_changes.len());
@@ -205,15 +426,273 @@ impl FileBasedTokenMonitor {
         } else {
             log::info!("Found Claude data paths: {claude_data_paths:?}");
         }
+        
+        let config_dir = claude_config_dir().ok();
+        let env_model = env_model();
+        let subscription_hints = config_dir
+            .as_deref()
+            .map(|dir| SubscriptionHints::read_with(dir, env_model.as_deref()))
+            .unwrap_or_default();
+        if let Some(plan) = subscription_hints.plan_type() {
+            log::info!("Using plan {plan:?} from Claude subscription info");
+        }
 
         Ok(Self {
             claude_data_paths,
+            accounts: Vec::new(),
+            api_key_accounts: BTreeSet::new(),
+            path_mappings: BTreeMap::new(),
+            user_directory: UserDirectory::default(),
+            only_users: BTreeSet::new(),
             usage_entries: Vec::new(),
+            api_key_entries: Vec::new(),
+            imports_dir: None,
+            imported_entries: Vec::new(),
+            project_overrides: HashMap::new(),
+            limit_events: Vec::new(),
+            request_errors: Vec::new(),
+            subscription_hints,
+            unknown_model_pricing: ModelPricing::SONNET,
+            claude_config_dir: config_dir,
+            env_model,
+            selected_model: None,
+            schema_reports: Vec::new(),
+            failed_files: Vec::new(),
+            dedup_strategy: DedupStrategy::default(),
+            cache_ttl: CacheTtl::default(),
+            max_request_tokens: DEFAULT_MAX_REQUEST_TOKENS,
+            duplicate_report: DuplicateReport::default(),
+            idle_after: chrono::Duration::minutes(DEFAULT_IDLE_MINUTES as i64),
+            session_window: SessionWindowConfig::default(),
+            scan_stats: ScanStats::default(),
+            scan_fingerprint: None,
+            future_due: None,
+            latest_write: None,
             _last_scan: Utc::now(),
             _watcher: None,
         })
     }
 
+    /// A monitor of the default Claude data directories with every setting `config` makes
+    pub fn from_config(config: &UserConfig) -> Result<Self> {
+        let mut monitor = Self::new()?;
+        monitor.set_dedup_strategy(config.dedup_strategy);
+        monitor.set_cache_ttl(config.cache_ttl);
+        monitor.set_max_request_tokens(config.max_request_tokens);
+        monitor.set_accounts(&config.accounts);
+        monitor.set_path_mappings(&config.path_mappings);
+        monitor.set_users(&config.users);
+        monitor.set_session_window(config.session_window);
+        monitor.set_api_key_accounts(&config.api_key_accounts);
+        monitor.set_idle_after(config.idle_after_minutes);
+        monitor.set_unknown_model_pricing(config.unknown_model_pricing);
+        Ok(monitor)
+    }
+
+    /// Create a monitor reading from explicit data directories instead of discovered ones
+    pub fn with_paths(claude_data_paths: Vec<PathBuf>) -> Self {
+        Self {
+            claude_data_paths,
+            accounts: Vec::new(),
+            api_key_accounts: BTreeSet::new(),
+            path_mappings: BTreeMap::new(),
+            user_directory: UserDirectory::default(),
+            only_users: BTreeSet::new(),
+            usage_entries: Vec::new(),
+            api_key_entries: Vec::new(),
+            imports_dir: None,
+            imported_entries: Vec::new(),
+            project_overrides: HashMap::new(),
+            limit_events: Vec::new(),
+            request_errors: Vec::new(),
+            subscription_hints: SubscriptionHints::default(),
+            unknown_model_pricing: ModelPricing::SONNET,
+            claude_config_dir: None,
+            env_model: None,
+            selected_model: None,
+            schema_reports: Vec::new(),
+            failed_files: Vec::new(),
+            dedup_strategy: DedupStrategy::default(),
+            cache_ttl: CacheTtl::default(),
+            max_request_tokens: DEFAULT_MAX_REQUEST_TOKENS,
+            duplicate_report: DuplicateReport::default(),
+            idle_after: chrono::Duration::minutes(DEFAULT_IDLE_MINUTES as i64),
+            session_window: SessionWindowConfig::default(),
+            scan_stats: ScanStats::default(),
+            scan_fingerprint: None,
+            future_due: None,
+            latest_write: None,
+            _last_scan: Utc::now(),
+            _watcher: None,
+        }
+    }
+
+    /// Also monitor the Claude Code home of each account in `accounts` and those in
+    /// `CLAUDE_ACCOUNTS`, tagging entries read from it with the account name
+    pub fn set_accounts(&mut self, accounts: &BTreeMap<String, PathBuf>) {
+        let mut accounts = accounts.clone();
+        accounts.extend(accounts::accounts_from_env());
+        self.accounts.clear();
+        self.scan_fingerprint = None;
+        for (name, home) in accounts {
+            let projects = accounts::projects_dir(&home);
+            match projects.canonicalize() {
+                Ok(projects) => {
+                    if !self.claude_data_paths.contains(&projects) {
+                        self.claude_data_paths.push(projects.clone());
+                    }
+                    self.accounts.push((name, projects));
+                }
+                Err(e) => log::warn!("Skipping account '{name}', {} can't be read: {e}", projects.display()),
+            }
+        }
+    }
+
+    /// Treat usage from these accounts (`default` for the discovered Claude Code home) as billed to
+    /// an API key rather than a subscription
+    pub fn set_api_key_accounts(&mut self, accounts: &BTreeSet<String>) {
+        self.api_key_accounts = accounts.clone();
+        self.scan_fingerprint = None;
+    }
+
+    /// Translate paths through `mappings` and those in `CLAUDE_PATH_MAPPINGS`, also monitoring any
+    /// Claude Code home they map, for running in a container with the host's `~/.claude` or
+    /// projects mounted elsewhere
+    pub fn set_path_mappings(&mut self, mappings: &BTreeMap<PathBuf, PathBuf>) {
+        let mut mappings = mappings.clone();
+        mappings.extend(container::path_mappings_from_env());
+        for (from, to) in &mappings {
+            let Some(data_dir) = container::mapped_data_dir(from, to) else {
+                continue;
+            };
+            match data_dir.canonicalize() {
+                Ok(data_dir) if data_dir.is_dir() => {
+                    if !self.claude_data_paths.contains(&data_dir) {
+                        self.claude_data_paths.push(data_dir);
+                    }
+                }
+                _ => log::warn!("Mapped Claude Code logs at {} can't be read", data_dir.display()),
+            }
+        }
+        self.path_mappings = mappings;
+        self.scan_fingerprint = None;
+    }
+
+    /// Also monitor the logs of the shared OS users, and count only those of the `only` users
+    /// when any are listed. Entries are tagged with the OS user owning their log file either way.
+    pub fn set_users(&mut self, users: &UsersConfig) {
+        self.user_directory = UserDirectory::load();
+        for name in &users.shared {
+            match self.user_directory.shared_projects_dir(name) {
+                Ok(projects) => {
+                    if !self.claude_data_paths.contains(&projects) {
+                        self.claude_data_paths.push(projects);
+                    }
+                }
+                Err(e) => log::warn!("Skipping user '{name}': {e}"),
+            }
+        }
+        self.only_users = users.only.clone();
+        self.scan_fingerprint = None;
+    }
+
+    /// Path mappings in effect, from the config and the environment
+    pub fn path_mappings(&self) -> &BTreeMap<PathBuf, PathBuf> {
+        &self.path_mappings
+    }
+
+    /// How usage logged at `path` is billed
+    pub fn billing_for(&self, path: &Path) -> Billing {
+        let account = self.account_for(path).unwrap_or(accounts::DEFAULT_ACCOUNT);
+        if self.api_key_accounts.contains(account) {
+            Billing::ApiKey
+        } else {
+            Billing::Subscription
+        }
+    }
+
+    /// Names of the configured accounts that could be read
+    pub fn account_names(&self) -> Vec<&str> {
+        self.accounts.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// The account whose directory holds `path`, the innermost when they nest
+    pub fn account_for(&self, path: &Path) -> Option<&str> {
+        self.accounts
+            .iter()
+            .filter(|(_, dir)| path.starts_with(dir))
+            .max_by_key(|(_, dir)| dir.components().count())
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Replace the plan/model hints read from Claude Code's local config
+    pub fn set_subscription_hints(&mut self, hints: SubscriptionHints) {
+        self.subscription_hints = hints;
+    }
+
+    pub fn subscription_hints(&self) -> &SubscriptionHints {
+        &self.subscription_hints
+    }
+
+    /// Price models the price list doesn't recognize at `pricing` (Sonnet rates unless set)
+    pub fn set_unknown_model_pricing(&mut self, pricing: ModelPricing) {
+        self.unknown_model_pricing = pricing;
+    }
+
+    /// How the entries are priced: entries naming no model at the selected model's rates, and
+    /// unrecognized models at the configured `unknown_model_pricing`
+    pub fn prices(&self) -> Prices<'_> {
+        Prices::new(self.subscription_hints.model.as_deref(), self.unknown_model_pricing)
+    }
+
+    /// Read the selected model from this Claude config directory's settings on each scan
+    pub fn set_claude_config_dir(&mut self, dir: Option<PathBuf>) {
+        self.claude_config_dir = dir;
+    }
+
+    /// Select `model` over any settings, as the `ANTHROPIC_MODEL` environment variable does
+    pub fn set_env_model(&mut self, model: Option<String>) {
+        self.env_model = model;
+    }
+
+    /// The model Claude Code is set to use in the active project, as of the last scan
+    pub fn selected_model(&self) -> Option<&SelectedModel> {
+        self.selected_model.as_ref()
+    }
+
+    /// Re-read the model setting, which the user can change with `/model` between scans. The
+    /// active project's own settings take precedence over the user's.
+    fn refresh_selected_model(&mut self) {
+        let Some(config_dir) = &self.claude_config_dir else {
+            return;
+        };
+        let project_dir = self
+            .active_project()
+            .map(|project| container::map_path(&self.path_mappings, Path::new(project)).unwrap_or_else(|| PathBuf::from(project)));
+        let selected = selected_model(config_dir, project_dir.as_deref(), self.env_model.as_deref());
+        if selected != self.selected_model {
+            log::info!("Selected model: {}", selected.as_ref().map_or("default", |selected| selected.model.as_str()));
+        }
+        self.subscription_hints.model = selected.as_ref().map(|selected| selected.model.clone());
+        self.selected_model = selected;
+    }
+
+    /// Model of the most recent request, skipping Claude Code's own `<synthetic>` notices
+    pub fn latest_model(&self) -> Option<&str> {
+        self.usage_entries
+            .iter()
+            .rev()
+            .filter_map(|entry| entry.model.as_deref())
+            .find(|model| *model != "<synthetic>")
+    }
+
+    /// Plan pinned by `project` or the Claude subscription, if any
+    fn configured_plan(&self, project: Option<&str>) -> Option<PlanType> {
+        self.project_overrides_for(project)
+            .and_then(|overrides| overrides.plan_type())
+            .or_else(|| self.subscription_hints.plan_type())
+    }
+
     /// Discover Claude data directories based on standard locations
     pub fn discover_claude_paths() -> Result<Vec<PathBuf>> {
         let mut paths = Vec::new();
@@ -221,10 +700,18 @@ impl FileBasedTokenMonitor {
         // Standard Claude data locations
         let home_dir = dirs::home_dir().ok_or_else(|| anyhow!("Could not find home directory"))?;
         
-        let standard_paths = vec![
+        let mut standard_paths = vec![
             home_dir.join(".claude").join("projects"),
             home_dir.join(".config").join("claude").join("projects"),
         ];
+        if let Some(config_dir) = std::env::var_os("CLAUDE_CONFIG_DIR").filter(|dir| !dir.is_empty()) {
+            standard_paths.push(PathBuf::from(config_dir).join("projects"));
+        }
+        // Dev-container images keep Claude Code's home under their own user, often a bind mount
+        if let Some(kind) = ContainerKind::detect() {
+            log::debug!("Running in a {}, also checking common Claude Code homes", kind.name());
+            standard_paths.extend(container::COMMON_CLAUDE_HOMES.iter().map(|home| Path::new(home).join("projects")));
+        }
         
         // Check environment variables with validation
         if let Ok(env_paths) = std::env::var("CLAUDE_DATA_PATHS") {
@@ -248,17 +735,15 @@ impl FileBasedTokenMonitor {
         // Add standard paths
         paths.extend(standard_paths);
         
-        // Filter to only existing directories and canonicalize
-        let existing_paths: Vec<PathBuf> = paths
-            .into_iter()
-            .filter_map(|path| {
-                if path.exists() && path.is_dir() {
-                    path.canonicalize().ok()
-                } else {
-                    None
+        // Filter to only existing directories and canonicalize, once each
+        let mut existing_paths: Vec<PathBuf> = Vec::new();
+        for path in paths {
+            if let Some(path) = path.is_dir().then(|| path.canonicalize().ok()).flatten() {
+                if !existing_paths.contains(&path) {
+                    existing_paths.push(path);
                 }
-            })
-            .collect();
+            }
+        }
         
         Ok(existing_paths)
     }
@@ -313,8 +798,15 @@ impl FileBasedTokenMonitor {
 
     /// Scan all Claude data directories for JSONL files and parse usage data
     pub async fn scan_usage_files(&mut self) -> Result<()> {
+        let started = std::time::Instant::now();
         let mut all_entries = Vec::new();
+        let mut all_events = Vec::new();
+        let mut all_errors = Vec::new();
+        let mut schema_reports = Vec::new();
+        let mut failed_files = Vec::new();
+        let mut latest_write: Option<ConversationWrite> = None;
         
+        let mut usage_files = Vec::new();
         for data_path in &self.claude_data_paths {
             log::debug!("Scanning directory: {data_path:?}");
             
@@ -325,50 +817,286 @@ impl FileBasedTokenMonitor {
                 .filter(|e| e.file_type().is_file())
                 .filter(|e| e.path().extension().is_some_and(|ext| ext == "jsonl"))
             {
-                let file_path = entry.path();
-                log::debug!("Parsing JSONL file: {file_path:?}");
-                
-                match self.parse_jsonl_file(file_path).await {
-                    Ok(mut entries) => {
-                        all_entries.append(&mut entries);
+                let owner = self.user_directory.owner_of(entry.path());
+                if !self.only_users.is_empty() && owner.as_ref().is_none_or(|owner| !self.only_users.contains(owner)) {
+                    log::debug!("Skipping {:?}, owned by {}", entry.path(), owner.as_deref().unwrap_or("an unknown user"));
+                    continue;
+                }
+                let metadata = entry.metadata().ok();
+                usage_files.push((entry.into_path(), owner, metadata));
+            }
+        }
+        
+        // Nothing to parse when no log (or import) changed since the last full scan
+        let import_store = self.imports_dir.as_ref().map(|dir| importer::console_import_file(dir));
+        let fingerprint = file_fingerprint(
+            usage_files
+                .iter()
+                .map(|(path, _, metadata)| (path.as_path(), metadata.clone()))
+                .chain(import_store.as_deref().map(|path| (path, std::fs::metadata(path).ok()))),
+        );
+        let previous = self.ingest_summary();
+        // Entries ahead of the clock are read once their time has come, even if no file changes
+        let future_due = self.future_due.is_some_and(|due| Utc::now() >= due);
+        if self.scan_fingerprint == Some(fingerprint) && !future_due {
+            self.load_project_overrides();
+            self.refresh_selected_model();
+            let finished_at = Utc::now();
+            let changed = self.ingest_summary() != previous;
+            self.scan_stats = ScanStats {
+                scans: self.scan_stats.scans + 1,
+                duration: started.elapsed(),
+                finished_at: Some(finished_at),
+                changed,
+                skipped: true,
+                data_changed_at: if changed { Some(finished_at) } else { self.scan_stats.data_changed_at },
+                late_since: None,
+                ..self.scan_stats
+            };
+            log::debug!("No usage files changed since the last scan");
+            return Ok(());
+        }
+        
+        for (file_path, owner, metadata) in usage_files {
+            let file_path = file_path.as_path();
+            log::debug!("Parsing JSONL file: {file_path:?}");
+            let modified = metadata.and_then(|metadata| metadata.modified().ok()).map(DateTime::<Utc>::from);
+            
+            match self.parse_jsonl_file(file_path).await {
+                Ok((mut entries, mut events, mut errors, report)) => {
+                    if let Some(modified) = modified.filter(|modified| latest_write.as_ref().is_none_or(|latest| *modified > latest.modified)) {
+                        // Lines aren't always written in time order, so the latest request is the newest one
+                        let project = entries
+                            .iter()
+                            .filter(|entry| entry.project.is_some())
+                            .max_by_key(|entry| entry.timestamp)
+                            .and_then(|entry| entry.project.as_deref().map(str::to_string));
+                        latest_write = Some(ConversationWrite { path: file_path.to_path_buf(), modified, project });
                     }
-                    Err(e) => {
-                        log::warn!("Failed to parse JSONL file {file_path:?}: {e}");
+                    let (account, billing) = (self.account_for(file_path).map(Interned::new), self.billing_for(file_path));
+                    let user = owner.as_deref().map(Interned::new);
+                    for entry in &mut entries {
+                        entry.account = account.clone();
+                        entry.user = user.clone();
+                        entry.billing = billing;
+                    }
+                    // Tag entries with their source file so duplicates can be traced back
+                    let file_index = schema_reports.len();
+                    all_entries.extend(entries.into_iter().map(|entry| (file_index, entry)));
+                    all_events.append(&mut events);
+                    all_errors.append(&mut errors);
+                    if report.unknown_lines > 0 {
+                        log::debug!("{} lines with an unknown schema in {file_path:?}", report.unknown_lines);
                     }
+                    schema_reports.push(report);
+                }
+                Err(e) => {
+                    log::warn!("Failed to parse JSONL file {file_path:?}: {e}");
+                    failed_files.push((file_path.to_path_buf(), e.to_string()));
                 }
             }
         }
         
-        // Sort entries by timestamp
-        all_entries.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        // Deduplicate with the configured key, remembering where copies came from
+        let files: Vec<PathBuf> = schema_reports.iter().map(|report: &FileSchemaReport| report.path.clone()).collect();
+        let (entries, duplicate_report) = dedupe::deduplicate(all_entries, &files, self.dedup_strategy);
+        // Entries synced from another machine or logged with a skewed clock can land before data
+        // already read; the entries are re-sorted anyway, but windows from there on have changed
+        let mut before = self.log_timestamps();
+        before.sort_unstable();
+        let late: Vec<DateTime<Utc>> = match before.last() {
+            Some(&latest) => {
+                let after: Vec<DateTime<Utc>> = entries.iter().map(|entry| entry.timestamp).collect();
+                added_timestamps(&before, &after).into_iter().take_while(|timestamp| *timestamp < latest).collect()
+            }
+            None => Vec::new(),
+        };
+        if let Some(earliest) = late.first() {
+            log::info!("{} entries arrived older than data already read, the earliest from {earliest}", late.len());
+        }
+        (self.api_key_entries, self.usage_entries) = entries.into_iter().partition(|entry| entry.billing == Billing::ApiKey);
+        self.duplicate_report = duplicate_report;
+        
+        self.load_project_overrides();
+        self.refresh_selected_model();
         
-        // Deduplicate based on message_id and request_id
-        let mut dedup_map = HashMap::new();
-        for entry in all_entries {
-            let key = (entry.message_id.clone(), entry.request_id.clone());
-            dedup_map.insert(key, entry);
+        if let Some(imports_dir) = &self.imports_dir {
+            self.imported_entries = importer::load_imported_entries(imports_dir).unwrap_or_else(|e| {
+                log::warn!("Failed to load imported usage from {imports_dir:?}: {e}");
+                Vec::new()
+            });
         }
         
-        self.usage_entries = dedup_map.into_values().collect();
-        self.usage_entries.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        // The same notice can be copied into several files (resumed conversations)
+        all_events.sort_by_key(|event: &LimitEvent| event.timestamp);
+        all_events.dedup_by(|a, b| a.timestamp == b.timestamp && a.kind == b.kind);
+        self.limit_events = all_events;
+        all_errors.sort_by_key(|error: &RequestError| error.timestamp);
+        all_errors.dedup_by(|a, b| a.timestamp == b.timestamp && a.model == b.model);
+        self.request_errors = all_errors;
+        self.schema_reports = schema_reports;
+        self.failed_files = failed_files;
+        self.latest_write = latest_write;
+        self.scan_fingerprint = Some(fingerprint);
+        self.future_due = self
+            .schema_reports
+            .iter()
+            .filter_map(|report| report.earliest_future)
+            .min()
+            .map(|earliest| earliest - FUTURE_TOLERANCE);
+        // Strings only the replaced entries used, e.g. of deleted conversations
+        intern::prune();
+        let finished_at = Utc::now();
+        let changed = self.ingest_summary() != previous;
+        self.scan_stats = ScanStats {
+            scans: self.scan_stats.scans + 1,
+            files: self.schema_reports.len() + self.failed_files.len(),
+            duration: started.elapsed(),
+            finished_at: Some(finished_at),
+            changed,
+            skipped: false,
+            data_changed_at: if changed { Some(finished_at) } else { self.scan_stats.data_changed_at },
+            late_entries: self.scan_stats.late_entries + late.len() as u64,
+            late_since: late.first().copied(),
+        };
         
-        log::info!("Loaded {} usage entries from JSONL files", self.usage_entries.len());
+        log::info!("Loaded {} usage entries and {} limit events from JSONL files", self.usage_entries.len(), self.limit_events.len());
+        if !self.api_key_entries.is_empty() {
+            log::info!("Loaded {} entries billed to an API key", self.api_key_entries.len());
+        }
         Ok(())
     }
 
-    /// Parse a single JSONL file for usage entries
-    async fn parse_jsonl_file(&self, file_path: &Path) -> Result<Vec<UsageEntry>> {
+    /// Timestamps of the entries read from the logs, in no particular order
+    fn log_timestamps(&self) -> Vec<DateTime<Utc>> {
+        self.usage_entries.iter().chain(&self.api_key_entries).map(|entry| entry.timestamp).collect()
+    }
+
+    fn ingest_summary(&self) -> IngestSummary {
+        IngestSummary {
+            usage_entries: self.usage_entries.len(),
+            api_key_entries: self.api_key_entries.len(),
+            imported_entries: self.imported_entries.len(),
+            limit_events: self.limit_events.len(),
+            request_errors: self.request_errors.len(),
+            latest: self.report_entries().map(|entry| entry.timestamp).max(),
+            selected_model: self.selected_model.clone(),
+        }
+    }
+
+    /// Load `.claude-token-monitor.toml` overrides for every project seen in the entries
+    fn load_project_overrides(&mut self) {
+        let projects: HashSet<&Interned> = self.usage_entries
+            .iter()
+            .filter_map(|entry| entry.project.as_ref())
+            .collect();
+        
+        let mut overrides = HashMap::new();
+        for project in projects {
+            // Projects logged on the host are read where they are mounted here
+            let project_dir = container::map_path(&self.path_mappings, Path::new(project))
+                .unwrap_or_else(|| PathBuf::from(project));
+            let config_path = project_dir.join(PROJECT_CONFIG_FILE);
+            match Self::read_project_overrides(&config_path) {
+                Ok(Some(project_overrides)) => {
+                    log::debug!("Loaded project overrides from {config_path:?}");
+                    overrides.insert(project.to_string(), project_overrides);
+                }
+                Ok(None) => {}
+                Err(e) => log::warn!("Ignoring invalid project config {config_path:?}: {e}"),
+            }
+        }
+        self.project_overrides = overrides;
+    }
+
+    /// Read a single project override file, returning None when it doesn't exist
+    pub fn read_project_overrides(config_path: &Path) -> Result<Option<ProjectOverrides>> {
+        let metadata = match std::fs::metadata(config_path) {
+            Ok(metadata) if metadata.is_file() => metadata,
+            _ => return Ok(None),
+        };
+        if metadata.len() > MAX_PROJECT_CONFIG_SIZE {
+            return Err(anyhow!("Project config too large: {} bytes (max {} bytes)", metadata.len(), MAX_PROJECT_CONFIG_SIZE));
+        }
+        let content = std::fs::read_to_string(config_path)?;
+        let overrides: ProjectOverrides = toml::from_str(&content)
+            .map_err(|e| anyhow!("TOML parsing error: {}", e))?;
+        Ok(Some(overrides))
+    }
+
+    /// Project the most recent entry belongs to
+    pub fn active_project(&self) -> Option<&str> {
+        self.usage_entries.last()?.project.as_deref()
+    }
+
+    /// Overrides for the project the current session is attributed to
+    pub fn active_project_overrides(&self) -> Option<&ProjectOverrides> {
+        self.project_overrides_for(self.active_project())
+    }
+
+    /// Overrides for `project`, when it has any
+    pub fn project_overrides_for(&self, project: Option<&str>) -> Option<&ProjectOverrides> {
+        self.project_overrides.get(project?)
+    }
+
+    /// Display label for a project: its configured label or the directory name
+    pub fn project_label(&self, project: &str) -> String {
+        self.project_overrides
+            .get(project)
+            .and_then(|overrides| overrides.label.clone())
+            .unwrap_or_else(|| {
+                Path::new(project)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| project.to_string())
+            })
+    }
+
+    /// Get per-project usage breakdown (label, tokens, entry count)
+    pub fn get_project_usage_breakdown(&self) -> Vec<(String, u32, usize)> {
+        let mut project_usage: HashMap<String, (u32, usize)> = HashMap::new();
+        
+        for entry in self.report_entries() {
+            let label = entry.project
+                .as_deref()
+                .map(|project| self.project_label(project))
+                .unwrap_or_else(|| "unknown".to_string());
+            let (tokens, count) = project_usage.entry(label).or_insert((0, 0));
+            *tokens = tokens.saturating_add(entry.usage.total_tokens());
+            *count += 1;
+        }
+        
+        let mut result: Vec<(String, u32, usize)> = project_usage
+            .into_iter()
+            .map(|(label, (tokens, count))| (label, tokens, count))
+            .collect();
+        result.sort_by_key(|(_, tokens, _)| std::cmp::Reverse(*tokens));
+        result
+    }
+
+    /// Parse a single JSONL file for usage entries, limit notices and failed requests
+    async fn parse_jsonl_file(
+        &self,
+        file_path: &Path,
+    ) -> Result<(Vec<UsageEntry>, Vec<LimitEvent>, Vec<RequestError>, FileSchemaReport)> {
         // Check file size before reading
         let metadata = fs::metadata(file_path).await?;
         if metadata.len() > MAX_FILE_SIZE as u64 {
             return Err(anyhow!("File too large: {} bytes (max {} bytes)", metadata.len(), MAX_FILE_SIZE));
         }
         
-        let content = fs::read_to_string(file_path).await?;
+        // Lines are parsed from the raw bytes; one that isn't valid UTF-8 only loses that line
+        let content = fs::read(file_path).await?;
         let mut entries = Vec::new();
+        let mut events = Vec::new();
+        let mut errors = Vec::new();
+        let mut last_model: Option<String> = None; // API error lines don't name the model
+        let mut report = FileSchemaReport::new(file_path.to_path_buf());
+        let now = Utc::now();
         
-        for (line_num, line) in content.lines().enumerate() {
-            if line.trim().is_empty() {
+        for (line_num, line) in content.split(|byte| *byte == b'\n').enumerate() {
+            let line = line.trim_ascii();
+            if line.is_empty() {
                 continue;
             }
             
@@ -376,191 +1104,553 @@ impl FileBasedTokenMonitor {
             if line.len() > MAX_JSON_SIZE {
                 log::warn!("Skipping oversized JSON line {} in {:?}: {} bytes (max {} bytes)", 
                           line_num + 1, file_path, line.len(), MAX_JSON_SIZE);
+                report.invalid_lines += 1;
                 continue;
             }
             
-            match self.parse_json_with_depth_limit(line) {
-                Ok(json) => {
-                    match self.parse_usage_entry(json) {
-                        Ok(entry) => {
-                            entries.push(entry);
+            // Lines are read into typed fields borrowed from the line, skipping the rest; only
+            // lines that may be limit notices, match no schema, or hold fields of the wrong type
+            // are also parsed into a JSON value
+            match transcript::read_line(line, MAX_JSON_DEPTH) {
+                Ok(log_line) => {
+                    let notice = log_line.may_be_notice().then(|| json_line::parse_line(line, MAX_JSON_DEPTH).ok()).flatten();
+                    if let Some(event) = notice.as_ref().and_then(Self::parse_limit_event) {
+                        log::debug!("Limit event at line {} in {:?}: {:?}", line_num + 1, file_path, event.kind);
+                        events.push(event);
+                    } else if let Some(error) = Self::parse_request_error(&log_line, last_model.as_deref()) {
+                        log::debug!("Failed request at line {} in {:?}", line_num + 1, file_path);
+                        errors.push(error);
+                    }
+                    if let Some(model) = log_line.message.as_ref().and_then(|m| m.model.as_deref()) {
+                        if model != "<synthetic>" && last_model.as_deref() != Some(model) {
+                            last_model = Some(model.to_string());
                         }
-                        Err(e) => {
-                            // Only log debug for unexpected errors, skip normal skippable entries
-                            let error_msg = e.to_string();
-                            if error_msg.contains("No usage data") || error_msg.contains("Skipping summary") {
-                                log::trace!("Skipping entry at line {} in {:?}: {}", line_num + 1, file_path, error_msg);
-                            } else {
-                                log::debug!("Failed to parse usage entry at line {} in {:?}: {}", line_num + 1, file_path, e);
+                    }
+
+                    let class = log_line.class();
+                    report.record(log_line.version.as_deref(), &class, || match notice {
+                        Some(json) => line_shape(&json),
+                        None => json_line::parse_line(line, MAX_JSON_DEPTH).map_or_else(|_| "(too deep)".to_string(), |json| line_shape(&json)),
+                    });
+                    match class {
+                        LineClass::Usage(schema) => match self.parse_usage_entry(log_line, schema, now, &mut report) {
+                            Ok(entry) => {
+                                entries.push(entry);
                             }
+                            Err(e) => {
+                                // Only log debug for unexpected errors, skip normal skippable entries
+                                let error_msg = e.to_string();
+                                if error_msg.contains("Skipping") {
+                                    log::trace!("Skipping entry at line {} in {:?}: {}", line_num + 1, file_path, error_msg);
+                                } else {
+                                    report.malformed_usage_lines += 1;
+                                    log::debug!("Failed to parse {} usage entry at line {} in {:?}: {}", schema.id, line_num + 1, file_path, e);
+                                }
+                            }
+                        },
+                        LineClass::NonUsage => {}
+                        LineClass::Unknown => {
+                            log::trace!("Unknown schema at line {} in {:?}", line_num + 1, file_path);
                         }
                     }
                 }
-                Err(e) => {
-                    log::debug!("Skipping invalid JSON line {} in {:?}: {}", line_num + 1, file_path, e);
-                }
+                // Valid JSON with a field of the wrong type: still classified and counted, and a
+                // usage line counts as malformed
+                Err(typed_error) => match json_line::parse_line(line, MAX_JSON_DEPTH) {
+                    Ok(json) => {
+                        if let Some(event) = Self::parse_limit_event(&json) {
+                            log::debug!("Limit event at line {} in {:?}: {:?}", line_num + 1, file_path, event.kind);
+                            events.push(event);
+                        }
+                        let class = classify_line(&json);
+                        report.record(json.get("version").and_then(|v| v.as_str()), &class, || line_shape(&json));
+                        match class {
+                            LineClass::Usage(schema) => {
+                                report.malformed_usage_lines += 1;
+                                log::debug!("Failed to parse {} usage entry at line {} in {:?}: {}", schema.id, line_num + 1, file_path, typed_error);
+                            }
+                            LineClass::NonUsage => {}
+                            LineClass::Unknown => {
+                                log::trace!("Unknown schema at line {} in {:?}", line_num + 1, file_path);
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        report.invalid_lines += 1;
+                        log::debug!("Skipping invalid JSON line {} in {:?}: {}", line_num + 1, file_path, e);
+                    }
+                },
             }
         }
         
-        Ok(entries)
+        Ok((entries, events, errors, report))
+    }
+
+    /// Schema detection results for every file read during the last scan
+    pub fn schema_reports(&self) -> &[FileSchemaReport] {
+        &self.schema_reports
+    }
+
+    /// Also load usage imported into this directory (see `import`) on each scan
+    pub fn set_imports_dir(&mut self, imports_dir: PathBuf) {
+        self.imports_dir = Some(imports_dir);
+        self.scan_fingerprint = None;
+    }
+
+    /// Deduplicated Claude Code entries on a subscription from the last scan, oldest first
+    pub fn usage_entries(&self) -> &[UsageEntry] {
+        &self.usage_entries
+    }
+
+    /// Claude Code entries from accounts that run on an API key, oldest first
+    pub fn api_key_entries(&self) -> &[UsageEntry] {
+        &self.api_key_entries
+    }
+
+    /// Usage imported from outside Claude Code, e.g. Anthropic Console exports
+    pub fn imported_entries(&self) -> &[UsageEntry] {
+        &self.imported_entries
+    }
+
+    /// Entries for all-time reports: Claude Code usage on the subscription, then on API keys,
+    /// then imported usage.
+    ///
+    /// Session windows, limits and predictions only ever use the subscription entries.
+    pub fn report_entries(&self) -> impl Iterator<Item = &UsageEntry> {
+        self.usage_entries.iter().chain(&self.api_key_entries).chain(&self.imported_entries)
+    }
+
+    /// The report entries from the sources `filter` includes, in the same order
+    pub fn source_entries(&self, filter: SourceFilter) -> impl Iterator<Item = &UsageEntry> {
+        let none: &[UsageEntry] = &[];
+        let (claude_code, api_key, imported) = match filter {
+            SourceFilter::All => (&self.usage_entries[..], &self.api_key_entries[..], &self.imported_entries[..]),
+            SourceFilter::ClaudeCode => (&self.usage_entries[..], &self.api_key_entries[..], none),
+            SourceFilter::Imported => (none, none, &self.imported_entries[..]),
+        };
+        claude_code.iter().chain(api_key).chain(imported)
+    }
+
+    /// Choose how duplicate entries are recognised on the next scan
+    pub fn set_dedup_strategy(&mut self, strategy: DedupStrategy) {
+        self.dedup_strategy = strategy;
+        self.scan_fingerprint = None;
+    }
+
+    /// TTL assumed for cache writes on the next scan when the logs don't record it
+    pub fn set_cache_ttl(&mut self, ttl: CacheTtl) {
+        self.cache_ttl = ttl;
+        self.scan_fingerprint = None;
+    }
+
+    /// Drop entries logged with more tokens than `max` on the next scan, as no request is that big
+    pub fn set_max_request_tokens(&mut self, max: u32) {
+        self.max_request_tokens = max;
+        self.scan_fingerprint = None;
+    }
+
+    /// Minutes without requests before the current session counts as idle, and the shortest
+    /// pause recorded as an idle gap
+    pub fn set_idle_after(&mut self, minutes: u64) {
+        self.idle_after = chrono::Duration::minutes(minutes.max(1) as i64);
+    }
+
+    /// Length and strategy of the session windows
+    pub fn set_session_window(&mut self, window: SessionWindowConfig) {
+        self.session_window = window;
+    }
+
+    pub fn session_window(&self) -> &SessionWindowConfig {
+        &self.session_window
+    }
+
+    /// How deduplication changed the totals of the last scan
+    pub fn duplicate_report(&self) -> &DuplicateReport {
+        &self.duplicate_report
+    }
+
+    /// Files that could not be read at all during the last scan, with the reason
+    pub fn failed_files(&self) -> &[(PathBuf, String)] {
+        &self.failed_files
     }
     
-    /// Parse JSON with depth limit to prevent stack overflow attacks
-    fn parse_json_with_depth_limit(&self, json_str: &str) -> Result<serde_json::Value> {
-        // Basic depth check by counting brackets
-        let mut depth = 0;
-        let mut max_depth = 0;
-        
-        for ch in json_str.chars() {
-            match ch {
-                '{' | '[' => {
-                    depth += 1;
-                    max_depth = max_depth.max(depth);
-                    if max_depth > MAX_JSON_DEPTH {
-                        return Err(anyhow!("JSON nesting too deep: {} levels (max {})", max_depth, MAX_JSON_DEPTH));
-                    }
-                }
-                '}' | ']' => depth = depth.saturating_sub(1),
-                _ => {}
-            }
+    /// Detect "limit reached" / "approaching limit" notices Claude Code writes into the log.
+    ///
+    /// Only system lines and synthetic/API-error assistant messages are inspected, so
+    /// conversation content mentioning limits is never mistaken for a notice.
+    fn parse_limit_event(json: &serde_json::Value) -> Option<LimitEvent> {
+        let is_system = json.get("type").and_then(|v| v.as_str()) == Some("system");
+        let message = json.get("message");
+        let is_synthetic = message
+            .and_then(|m| m.get("model"))
+            .and_then(|v| v.as_str())
+            == Some("<synthetic>");
+        let is_api_error = json.get("isApiErrorMessage").and_then(|v| v.as_bool()) == Some(true);
+        if !(is_system || is_synthetic || is_api_error) {
+            return None;
         }
         
-        // Parse the JSON using serde_json
-        serde_json::from_str(json_str)
-            .map_err(|e| anyhow!("JSON parsing error: {}", e))
+        let content = message
+            .and_then(|m| m.get("content"))
+            .or_else(|| json.get("content"))?;
+        let text = match content {
+            serde_json::Value::String(text) => text.clone(),
+            serde_json::Value::Array(items) => items
+                .iter()
+                .filter_map(|item| item.get("text").and_then(|v| v.as_str()))
+                .collect::<Vec<_>>()
+                .join(" "),
+            _ => return None,
+        };
+        
+        let lower = text.to_lowercase();
+        let kind = if lower.contains("limit reached") {
+            LimitEventKind::LimitReached
+        } else if lower.contains("approaching") && lower.contains("limit") {
+            LimitEventKind::ApproachingLimit
+        } else {
+            return None;
+        };
+        
+        let timestamp = json.get("timestamp")
+            .and_then(|v| v.as_str())
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())?
+            .with_timezone(&Utc);
+        
+        // Prefer a reset recorded as a field; older notices carry it as a unix timestamp in the
+        // text: "Claude AI usage limit reached|1752076800"
+        let reset_time = explicit_reset_time(json)
+            .or_else(|| message.and_then(explicit_reset_time))
+            .or_else(|| {
+                text.split('|')
+                    .nth(1)
+                    .and_then(|epoch| epoch.trim().parse::<i64>().ok())
+                    .and_then(|epoch| DateTime::from_timestamp(epoch, 0))
+            });
+        
+        Some(LimitEvent { timestamp, kind, reset_time })
     }
 
-    /// Parse a JSON value into a UsageEntry
-    fn parse_usage_entry(&self, json: serde_json::Value) -> Result<UsageEntry> {
+    /// Get all observed limit notices in chronological order
+    pub fn limit_events(&self) -> &[LimitEvent] {
+        &self.limit_events
+    }
+
+    /// Detect a failed request: an API error Claude Code logged in place of a response, or a
+    /// response that stopped with `stop_reason: "error"`.
+    ///
+    /// Limit notices are checked first and never count as errors.
+    fn parse_request_error(line: &LogLine, last_model: Option<&str>) -> Option<RequestError> {
+        let message = line.message.as_ref();
+        let is_api_error = line.is_api_error_message == Some(true);
+        let stopped_with_error = message.and_then(|m| m.stop_reason.as_deref()) == Some("error");
+        if !(is_api_error || stopped_with_error) {
+            return None;
+        }
+
+        let timestamp = line.timestamp
+            .as_deref()
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())?
+            .with_timezone(&Utc);
+        let logged_model = message
+            .and_then(|m| m.model.as_deref())
+            .filter(|model| *model != "<synthetic>");
+        // Synthetic messages are never parsed into usage entries
+        let has_usage = logged_model.is_some() && message.is_some_and(|m| m.usage.is_some());
+        let model = logged_model.or(last_model).map(str::to_string);
+
+        Some(RequestError { timestamp, model, has_usage })
+    }
+
+    /// Failed requests from the last scan, oldest first
+    pub fn request_errors(&self) -> &[RequestError] {
+        &self.request_errors
+    }
+    
+    /// Read a line `classify_line` matched to `schema` into a UsageEntry, leaving out requests that
+    /// can't be real and counting them in `report`
+    fn parse_usage_entry(
+        &self,
+        line: LogLine,
+        schema: &SchemaDescriptor,
+        now: DateTime<Utc>,
+        report: &mut FileSchemaReport,
+    ) -> Result<UsageEntry> {
+        let line = line.into_usage_line(schema)?;
+
         // Skip summary entries and other non-message entries
-        if let Some(entry_type) = json.get("type").and_then(|v| v.as_str()) {
-            if entry_type == "summary" {
-                return Err(anyhow!("Skipping summary entry"));
-            }
+        if line.line_type == LineType::Summary {
+            return Err(anyhow!("Skipping summary entry"));
         }
 
-        // Extract timestamp
-        let timestamp = if let Some(ts_str) = json.get("timestamp").and_then(|v| v.as_str()) {
-            DateTime::parse_from_rfc3339(ts_str)?.with_timezone(&Utc)
-        } else {
-            return Err(anyhow!("Missing or invalid timestamp"));
-        };
+        // Synthetic messages (limit notices, errors) carry no real usage
+        if line.model.as_deref() == Some("<synthetic>") {
+            return Err(anyhow!("Skipping synthetic message"));
+        }
 
-        // Extract usage information from Claude Code JSONL format
-        // Usage data is nested inside message.usage for assistant responses
-        let usage = if let Some(message) = json.get("message") {
-            if let Some(usage_obj) = message.get("usage") {
-                TokenUsage {
-                    input_tokens: usage_obj.get("input_tokens")
-                        .and_then(|v| v.as_u64())
-                        .unwrap_or(0) as u32,
-                    output_tokens: usage_obj.get("output_tokens")
-                        .and_then(|v| v.as_u64())
-                        .unwrap_or(0) as u32,
-                    cache_creation_input_tokens: usage_obj.get("cache_creation_input_tokens")
-                        .and_then(|v| v.as_u64())
-                        .map(|v| v as u32),
-                    cache_read_input_tokens: usage_obj.get("cache_read_input_tokens")
-                        .and_then(|v| v.as_u64())
-                        .map(|v| v as u32),
-                }
-            } else {
-                // Skip entries without usage data (user messages, etc.)
-                return Err(anyhow!("No usage data in message"));
-            }
-        } else {
-            // Try fallback for direct usage format (in case format changes)
-            if let Some(usage_obj) = json.get("usage") {
-                TokenUsage {
-                    input_tokens: usage_obj.get("input_tokens")
-                        .and_then(|v| v.as_u64())
-                        .unwrap_or(0) as u32,
-                    output_tokens: usage_obj.get("output_tokens")
-                        .and_then(|v| v.as_u64())
-                        .unwrap_or(0) as u32,
-                    cache_creation_input_tokens: usage_obj.get("cache_creation_input_tokens")
-                        .and_then(|v| v.as_u64())
-                        .map(|v| v as u32),
-                    cache_read_input_tokens: usage_obj.get("cache_read_input_tokens")
-                        .and_then(|v| v.as_u64())
-                        .map(|v| v as u32),
-                }
-            } else {
-                return Err(anyhow!("Missing usage information"));
-            }
+        let timestamp = match line.timestamp.as_deref() {
+            Some(timestamp) => DateTime::parse_from_rfc3339(timestamp)?.with_timezone(&Utc),
+            None => return Err(anyhow!("Missing or invalid timestamp")),
         };
+        if timestamp > now + FUTURE_TOLERANCE {
+            report.future_entries += 1;
+            report.earliest_future = Some(report.earliest_future.map_or(timestamp, |earliest| earliest.min(timestamp)));
+            return Err(anyhow!("Skipping entry dated {timestamp}, ahead of the clock"));
+        }
 
-        // Extract model from message.model for Claude Code format
-        let model = json.get("message")
-            .and_then(|m| m.get("model"))
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-            .or_else(|| json.get("model").and_then(|v| v.as_str()).map(|s| s.to_string()));
+        let logged = &line.usage;
+        let tokens = [logged.input_tokens, logged.output_tokens, logged.cache_creation_input_tokens, logged.cache_read_input_tokens]
+            .into_iter()
+            .flatten()
+            .fold(0u64, u64::saturating_add);
+        if tokens > u64::from(self.max_request_tokens) {
+            report.oversized_entries += 1;
+            return Err(anyhow!("Skipping entry of {tokens} tokens, over max_request_tokens"));
+        }
 
-        // Extract message ID from message.id for Claude Code format
-        let message_id = json.get("message")
-            .and_then(|m| m.get("id"))
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-            .or_else(|| json.get("message_id").and_then(|v| v.as_str()).map(|s| s.to_string()));
+        // Negative times come from clocks adjusted mid-request, and the first token can't come
+        // after the end of the response
+        let (duration_ms, ttft_ms) = (line.timings.duration_ms(), line.timings.ttft_ms());
+        let mut clamped = duration_ms.is_some_and(|ms| ms < 0) || ttft_ms.is_some_and(|ms| ms < 0);
+        let duration_ms = duration_ms.and_then(|ms| u64::try_from(ms).ok());
+        let mut ttft_ms = ttft_ms.and_then(|ms| u64::try_from(ms).ok());
+        if let (Some(duration), Some(ttft)) = (duration_ms, ttft_ms) {
+            if ttft > duration {
+                ttft_ms = None;
+                clamped = true;
+            }
+        }
+        report.clamped_timings += usize::from(clamped);
 
-        // Extract request ID from requestId field in Claude Code format
-        let request_id = json.get("requestId")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-            .or_else(|| json.get("request_id").and_then(|v| v.as_str()).map(|s| s.to_string()));
+        let mut usage = TokenUsage::from_log(&line.usage);
+        // Logs without the split of cache writes by TTL get the configured one
+        if usage.cache_creation_1h_input_tokens.is_none() && self.cache_ttl == CacheTtl::OneHour {
+            usage.cache_creation_1h_input_tokens = usage.cache_creation_input_tokens;
+        }
 
         Ok(UsageEntry {
             timestamp,
             usage,
-            model,
-            message_id,
-            request_id,
+            model: line.model.as_deref().map(Interned::new),
+            message_id: line.message_id.map(Cow::into_owned),
+            request_id: line.request_id.map(Cow::into_owned),
+            project: line.cwd.as_deref().map(Interned::new),
+            conversation: line.session_id.as_deref().map(Interned::new),
+            account: None,
+            user: None,
+            billing: Billing::Subscription,
+            duration_ms,
+            ttft_ms,
+            service_tier: line.usage.service_tier,
+            tool_uses: line.tool_uses.iter().map(|name| Interned::new(name)).collect(),
         })
     }
 
+    /// Split all entries into session windows, each opened by the first entry after the previous
+    /// window reset.
+    ///
+    /// A reset announced in a limit notice replaces the estimate of the window it was announced in,
+    /// when, like the reset in Claude Code's config, it falls after the window's last request and
+    /// within one window of it; stale or far-off resets are ignored.
+    pub fn session_blocks(&self) -> Vec<SessionBlock> {
+        let window = &self.session_window;
+        let mut blocks: Vec<SessionBlock> = Vec::new();
+        let mut announced = self
+            .limit_events
+            .iter()
+            .filter_map(|event| Some((event.timestamp, event.reset_time.filter(|reset| *reset > event.timestamp)?)))
+            .peekable();
+        let announce = |blocks: &mut Vec<SessionBlock>, (at, reset): (DateTime<Utc>, DateTime<Utc>)| {
+            if let Some(block) = blocks.last_mut().filter(|block| {
+                at >= block.start_time
+                    && at < block.reset_time
+                    && reset > block.last_activity
+                    && reset <= block.last_activity + window.duration()
+            }) {
+                block.reset_time = reset;
+                block.reset_authoritative = true;
+            }
+        };
+        
+        for entry in &self.usage_entries {
+            while let Some(reset) = announced.next_if(|(at, _)| *at <= entry.timestamp) {
+                announce(&mut blocks, reset);
+            }
+            match blocks.last_mut() {
+                Some(block) if entry.timestamp < block.reset_time => {
+                    if entry.timestamp - block.last_activity >= self.idle_after {
+                        block.idle_gaps.push((block.last_activity, entry.timestamp));
+                    }
+                    block.last_activity = entry.timestamp;
+                    if !block.reset_authoritative {
+                        block.reset_time = window.reset_time(block.start_time, entry.timestamp);
+                    }
+                    block.tokens_used = block.tokens_used.saturating_add(entry.usage.total_tokens());
+                    block.entry_count += 1;
+                }
+                _ => {
+                    let start_time = window.window_start(entry.timestamp);
+                    blocks.push(SessionBlock {
+                        start_time,
+                        last_activity: entry.timestamp,
+                        reset_time: window.reset_time(start_time, entry.timestamp),
+                        tokens_used: entry.usage.total_tokens(),
+                        entry_count: 1,
+                        limit_reached: false,
+                        idle_gaps: Vec::new(),
+                        reset_authoritative: false,
+                    })
+                }
+            }
+        }
+        for reset in announced {
+            announce(&mut blocks, reset);
+        }
+        
+        for event in self.limit_events.iter().filter(|e| e.kind == LimitEventKind::LimitReached) {
+            if let Some(block) = blocks
+                .iter_mut()
+                .find(|block| event.timestamp >= block.start_time && event.timestamp < block.reset_time)
+            {
+                block.limit_reached = true;
+            }
+        }
+        
+        blocks
+    }
+
+    /// Calibrate the effective token limit from windows where usage stopped at a consistent
+    /// ceiling and resumed right after the reset (a strong sign the limit was hit)
+    pub fn calibrate_limit(&self) -> Option<LimitCalibration> {
+        let blocks = self.session_blocks();
+        
+        // Windows with an explicit limit-reached notice are ceilings by definition
+        let mut ceilings: Vec<u32> = blocks
+            .iter()
+            .filter(|block| block.limit_reached)
+            .map(|block| block.tokens_used)
+            .collect();
+        
+        ceilings.extend(blocks
+            .windows(2)
+            .filter(|pair| {
+                let (block, next) = (&pair[0], &pair[1]);
+                let idle_before_reset = block.reset_time - block.last_activity;
+                let resumed_after_reset = next.start_time - block.reset_time;
+                !block.limit_reached
+                    && idle_before_reset >= chrono::Duration::minutes(CALIBRATION_IDLE_BEFORE_RESET_MINUTES)
+                    && resumed_after_reset <= chrono::Duration::minutes(CALIBRATION_RESUME_AFTER_RESET_MINUTES)
+            })
+            .map(|pair| pair[0].tokens_used));
+        ceilings.retain(|tokens| *tokens > 0);
+        ceilings.sort_unstable();
+        
+        // Find the largest group of ceilings within tolerance of each other
+        let mut best: Option<(usize, u32)> = None;
+        for (i, &low) in ceilings.iter().enumerate() {
+            let group: Vec<u32> = ceilings[i..]
+                .iter()
+                .copied()
+                .take_while(|&tokens| (tokens - low) as f64 <= low as f64 * CALIBRATION_TOLERANCE)
+                .collect();
+            let ceiling = *group.last()?;
+            if best.is_none_or(|(count, _)| group.len() >= count) {
+                best = Some((group.len(), ceiling));
+            }
+        }
+        
+        let (samples, limit) = best?;
+        if samples < CALIBRATION_MIN_SAMPLES {
+            return None;
+        }
+        log::debug!("Calibrated token limit {limit} from {samples} observed resets");
+        Some(LimitCalibration { limit, samples })
+    }
+
     /// Derive session information from JSONL entries (passive observation)
     pub fn derive_current_session(&self) -> Option<TokenSession> {
         if self.usage_entries.is_empty() {
             return None;
         }
         
-        let now = Utc::now();
-        let session_duration = chrono::Duration::hours(5);
-        
-        // Find the most recent entry to determine the current session
-        let latest_entry = self.usage_entries.last()?;
-        
-        // Calculate session start time based on 5-hour windows
-        let session_start = latest_entry.timestamp;
-        let reset_time = session_start + session_duration;
+        // The current session is the window of the most recent entry
+        let block = self.current_block()?;
+        Some(self.observed_session(&block, Utc::now()))
+    }
+
+    /// Sessions for every window still open at `since`, oldest first, as `derive_current_session`
+    /// would have derived each while it was current
+    pub fn observed_sessions_since(&self, since: DateTime<Utc>) -> Vec<TokenSession> {
+        let mut blocks = self.session_blocks();
+        blocks.pop(); // the current window, which may take its reset from Claude Code's config
+        let mut sessions: Vec<TokenSession> = blocks
+            .iter()
+            .filter(|block| block.reset_time > since)
+            .map(|block| self.observed_session(block, block.reset_time))
+            .collect();
+        sessions.extend(self.derive_current_session());
+        sessions
+    }
+
+    /// The session of `block`, with its usage and plan as of `until`
+    fn observed_session(&self, block: &SessionBlock, until: DateTime<Utc>) -> TokenSession {
+        let session_start = block.start_time;
+        let reset_time = block.reset_time;
         
         // Check if we're still within the session window
-        let is_active = now <= reset_time;
+        let is_active = Utc::now() <= reset_time;
         
-        // Calculate total tokens used in this session
-        let total_tokens_used: u32 = self.usage_entries
+        // Calculate total tokens used in this session, and the project it was last used in
+        let (total_tokens_used, project) = self.usage_entries
             .iter()
-            .filter(|entry| entry.timestamp >= session_start && entry.timestamp <= now)
-            .map(|entry| entry.usage.total_tokens())
-            .sum();
-        
-        // Determine plan type based on usage patterns and session behavior
-        let plan_type = self.detect_plan_type_from_usage(total_tokens_used, session_start, now);
+            .filter(|entry| entry.timestamp >= session_start && entry.timestamp <= until && entry.timestamp < reset_time)
+            .fold((0u32, None), |(total, project), entry| {
+                (total.saturating_add(entry.usage.total_tokens()), entry.project.as_deref().or(project))
+            });
         
-        // Generate a session ID based on the session start time (deterministic)
-        let session_id = format!("observed-{}", session_start.timestamp());
+        // Determine plan type based on usage patterns and session behavior,
+        // unless the window's project or the Claude subscription pins one
+        let plan_type = self.configured_plan(project)
+            .unwrap_or_else(|| self.detect_plan_type_from_usage(total_tokens_used, session_start, until));
         
-        Some(TokenSession {
-            id: session_id,
+        TokenSession {
+            id: observed_session_id(session_start),
             start_time: session_start,
             end_time: if is_active { None } else { Some(reset_time) },
             plan_type: plan_type.clone(),
             tokens_used: total_tokens_used,
-            tokens_limit: plan_type.default_limit(),
+            tokens_limit: self.effective_limit(&plan_type, project).0,
             is_active,
             reset_time,
-        })
+        }
+    }
+
+    /// The window of the most recent entry. Without a reset from the logs, one recorded in Claude
+    /// Code's config counts when it falls within a window length after the last request.
+    fn current_block(&self) -> Option<SessionBlock> {
+        let mut block = self.session_blocks().pop()?;
+        if !block.reset_authoritative {
+            if let Some(reset) = self.subscription_hints.reset_time.filter(|reset| {
+                *reset > block.last_activity && *reset <= block.last_activity + self.session_window.duration()
+            }) {
+                block.reset_time = reset;
+                block.reset_authoritative = true;
+            }
+        }
+        Some(block)
+    }
+
+    /// Token limit for a plan in a window last used in `project`, preferring a limit calibrated
+    /// from history unless the plan was pinned explicitly (custom limit or project override)
+    fn effective_limit(&self, plan_type: &PlanType, project: Option<&str>) -> (u32, bool) {
+        let pinned = matches!(plan_type, PlanType::Custom(_))
+            || self.project_overrides_for(project).and_then(|o| o.plan_type()).is_some();
+        if !pinned {
+            if let Some(calibration) = self.calibrate_limit() {
+                return (calibration.limit, true);
+            }
+        }
+        (plan_type.default_limit(), false)
     }
     
     /// Calculate current usage metrics from observed data (passive monitoring)
@@ -576,11 +1666,13 @@ impl FileBasedTokenMonitor {
                 info!("  {} - {:?} → {:?}", timestamp.format("%Y-%m-%d %H:%M UTC"), old_plan, new_plan);
             }
             
-            // Use the most recent plan change if available
+            // Use the most recent plan change if available (configured plans take precedence)
             if let Some((_, _, latest_plan)) = plan_changes.last() {
-                current_session.plan_type = latest_plan.clone();
-                current_session.tokens_limit = latest_plan.default_limit();
-                info!("📊 Updated current session to use detected plan: {:?}", latest_plan);
+                if self.configured_plan(self.active_project()).is_none() {
+                    current_session.plan_type = latest_plan.clone();
+                    current_session.tokens_limit = self.effective_limit(latest_plan, self.active_project()).0;
+                    info!("📊 Updated current session to use detected plan: {:?}", latest_plan);
+                }
             }
         }
         let now = Utc::now();
@@ -600,50 +1692,49 @@ impl FileBasedTokenMonitor {
             .collect();
         
         // Calculate total tokens used in current session
-        let total_tokens_used: u32 = session_entries
+        let total_tokens_used = session_entries
             .iter()
-            .map(|entry| entry.usage.total_tokens())
-            .sum();
+            .fold(0u32, |total, entry| total.saturating_add(entry.usage.total_tokens()));
         
         // Calculate tokens used in last hour (for future burn rate analysis)
         let _tokens_last_hour: u32 = recent_entries
             .iter()
-            .map(|entry| entry.usage.total_tokens())
-            .sum();
+            .fold(0u32, |total, entry| total.saturating_add(entry.usage.total_tokens()));
         
         // Calculate time elapsed
         let time_elapsed = now.signed_duration_since(session_start);
         let time_elapsed_minutes = time_elapsed.num_minutes() as f64;
         
-        // Calculate usage rate (tokens per minute)
-        let usage_rate = if time_elapsed_minutes > 0.0 {
-            total_tokens_used as f64 / time_elapsed_minutes
-        } else {
-            0.0
-        };
+        // Usage rate from session start to now, averaged over the whole elapsed time
+        let session_length = current_session.reset_time - session_start;
+        let analyzer = Analyzer::new(now)
+            .with_session_limit(current_session.tokens_limit)
+            .with_session_length(session_length);
+        let usage_rate = analyzer.calculate_usage_rate(&[
+            TokenUsagePoint { timestamp: session_start, tokens_used: 0, session_id: current_session.id.clone() },
+            TokenUsagePoint { timestamp: now, tokens_used: total_tokens_used, session_id: current_session.id.clone() },
+        ]);
         
         // Calculate session progress (0.0 to 1.0)
-        let session_duration_minutes = 5.0 * 60.0; // 5 hours in minutes
+        let session_duration_minutes = session_length.num_minutes().max(1) as f64;
         let session_progress = (time_elapsed_minutes / session_duration_minutes).min(1.0);
         
-        // Calculate efficiency score
-        let efficiency_score = if session_progress > 0.0 {
-            let expected_rate = current_session.tokens_limit as f64 / session_duration_minutes;
-            let actual_rate = if usage_rate > 0.0 { usage_rate } else { 0.1 };
-            (expected_rate / actual_rate).min(1.0).max(0.0)
-        } else {
-            1.0
-        };
+        let efficiency_score = analyzer.calculate_efficiency(usage_rate, session_progress);
         
-        // Calculate projected depletion
-        let projected_depletion = if usage_rate > 0.0 {
-            let remaining_tokens = current_session.tokens_limit.saturating_sub(total_tokens_used);
-            let minutes_remaining = remaining_tokens as f64 / usage_rate;
-            Some(now + chrono::Duration::minutes(minutes_remaining as i64))
-        } else {
+        // A paused session isn't burning at its average rate, so don't project from it
+        let idle_since = session_entries
+            .iter()
+            .map(|entry| entry.timestamp)
+            .max()
+            .filter(|last| now - *last >= self.idle_after);
+        let projected_depletion = if idle_since.is_some() {
             None
+        } else {
+            analyzer.predict_depletion(total_tokens_used, current_session.tokens_limit, usage_rate)
         };
         
+        let limit_calibrated = self.effective_limit(&current_session.plan_type, self.active_project()).1;
+        
         // Update session with actual token count
         let mut updated_session = current_session;
         updated_session.tokens_used = total_tokens_used;
@@ -667,14 +1758,104 @@ impl FileBasedTokenMonitor {
             cache_creation_rate,
             token_consumption_rate: usage_rate,
             input_output_ratio,
+            project_label: self.active_project().map(|project| self.project_label(project)),
+            limit_calibrated,
+            limit_events: self.limit_events
+                .iter()
+                .filter(|event| event.timestamp >= session_start && event.timestamp <= now)
+                .cloned()
+                .collect(),
+            estimated_cost: Self::estimate_cost(&session_entries, self.prices()),
+            activity_heatmap: self.activity_heatmap(HEATMAP_DAYS, now),
+            minute_tokens: self.minute_tokens(MINUTE_TOKENS_SPAN, now),
+            idle_since,
+            reset_authoritative: self.current_block().is_some_and(|block| block.reset_authoritative),
+            selected_model: self.selected_model.as_ref().map(|selected| selected.model.clone()),
+            latest_model: self.latest_model().map(str::to_string),
         })
     }
 
+    /// Analyze the session windows of the analyzer's history as sessions on `plan`, pricing
+    /// pay-as-you-go from the API-equivalent cost of the same period
+    pub fn analyze_history(&self, analyzer: Analyzer, plan: &PlanType) -> Result<UsageAnalysis> {
+        let history_start = analyzer.history_start();
+        let recent: Vec<&UsageEntry> = self.usage_entries.iter().filter(|entry| entry.timestamp >= history_start).collect();
+        let api_cost = Self::estimate_cost(&recent, self.prices());
+        let blocks: Vec<SessionBlock> = self
+            .session_blocks()
+            .into_iter()
+            .filter(|block| block.start_time >= history_start)
+            .collect();
+        let sessions: Vec<TokenSession> = blocks.iter().map(|block| block.to_session(plan.clone())).collect();
+        let idle_gaps = blocks.iter().flat_map(|block| block.idle_gaps.iter().copied()).collect();
+        analyzer.with_api_cost(api_cost).with_idle_gaps(idle_gaps).analyze_usage_patterns(&sessions)
+    }
+
+    /// Estimated API-equivalent cost in USD of the given entries, priced by `prices`
+    pub fn estimate_cost(entries: &[&UsageEntry], prices: Prices) -> f64 {
+        entries.iter().map(|entry| prices.cost(entry)).sum()
+    }
+
+    /// Tokens per UTC hour of day for the last `days` days (oldest first, today last)
+    pub fn activity_heatmap(&self, days: usize, now: DateTime<Utc>) -> Vec<[u32; 24]> {
+        let mut heatmap = vec![[0u32; 24]; days];
+        let today = now.date_naive();
+        for entry in &self.usage_entries {
+            let days_ago = (today - entry.timestamp.date_naive()).num_days();
+            if days_ago < 0 || days_ago as usize >= days {
+                continue;
+            }
+            let row = days - 1 - days_ago as usize;
+            let hour = entry.timestamp.hour() as usize;
+            heatmap[row][hour] = heatmap[row][hour].saturating_add(entry.usage.total_tokens());
+        }
+        heatmap
+    }
+
+    /// Tokens in each of the `minutes` minutes before `now` (oldest first), counted back from
+    /// `now` rather than by clock minute so the latest one is always complete
+    pub fn minute_tokens(&self, minutes: usize, now: DateTime<Utc>) -> Vec<u64> {
+        let mut tokens = vec![0u64; minutes];
+        for entry in &self.usage_entries {
+            let seconds_ago = (now - entry.timestamp).num_seconds();
+            if seconds_ago < 0 {
+                continue;
+            }
+            if let Some(slot) = minutes.checked_sub(1 + seconds_ago as usize / 60) {
+                tokens[slot] += entry.usage.total_tokens() as u64;
+            }
+        }
+        tokens
+    }
+
     /// Get the number of usage entries loaded
     pub fn entry_count(&self) -> usize {
         self.usage_entries.len()
     }
 
+    pub fn scan_stats(&self) -> ScanStats {
+        self.scan_stats
+    }
+
+    /// The conversation Claude Code is streaming into right now: the log written to most recently,
+    /// if that was no longer than `within` before `now`
+    pub fn active_conversation(&self, now: DateTime<Utc>, within: chrono::Duration) -> Option<&ConversationWrite> {
+        self.latest_write.as_ref().filter(|write| now - write.modified <= within)
+    }
+
+    /// Rough heap footprint of the loaded entries in bytes: the entries themselves, their IDs and
+    /// the table of interned strings they share
+    pub fn entry_store_bytes(&self) -> usize {
+        let ids = |entry: &UsageEntry| [&entry.message_id, &entry.request_id].into_iter().flatten().map(String::capacity).sum::<usize>();
+        let entries: usize = [&self.usage_entries, &self.api_key_entries, &self.imported_entries]
+            .into_iter()
+            .map(|entries| entries.capacity() * std::mem::size_of::<UsageEntry>() + entries.iter().map(ids).sum::<usize>())
+            .sum();
+        // The strings are shared with any other monitor in the process, counted here in full
+        let holds_entries = !(self.usage_entries.is_empty() && self.api_key_entries.is_empty() && self.imported_entries.is_empty());
+        entries + if holds_entries { intern::table_size().1 } else { 0 }
+    }
+
     /// Get the time range of loaded entries
     pub fn entry_time_range(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
         if self.usage_entries.is_empty() {
@@ -709,7 +1890,7 @@ impl FileBasedTokenMonitor {
         
         // Process each usage entry to create cumulative data points
         for entry in sorted_entries {
-            cumulative_tokens += entry.usage.total_tokens();
+            cumulative_tokens = cumulative_tokens.saturating_add(entry.usage.total_tokens());
             time_series.push(TokenUsagePoint {
                 timestamp: entry.timestamp,
                 tokens_used: cumulative_tokens,
@@ -748,16 +1929,16 @@ impl FileBasedTokenMonitor {
         }
         
         // Calculate cache hit rate across all session entries
-        let mut total_input_tokens = 0u32;
-        let mut total_cache_read_tokens = 0u32;
-        let mut total_cache_creation_tokens = 0u32;
-        let mut total_output_tokens = 0u32;
+        let mut total_input_tokens = 0u64;
+        let mut total_cache_read_tokens = 0u64;
+        let mut total_cache_creation_tokens = 0u64;
+        let mut total_output_tokens = 0u64;
         
         for entry in session_entries {
-            total_input_tokens += entry.usage.input_tokens;
-            total_cache_read_tokens += entry.usage.cache_read_tokens();
-            total_cache_creation_tokens += entry.usage.cache_creation_tokens();
-            total_output_tokens += entry.usage.output_tokens;
+            total_input_tokens += entry.usage.input_tokens as u64;
+            total_cache_read_tokens += entry.usage.cache_read_tokens() as u64;
+            total_cache_creation_tokens += entry.usage.cache_creation_tokens() as u64;
+            total_output_tokens += entry.usage.output_tokens as u64;
         }
         
         // Cache hit rate: cache read tokens / (input tokens + cache creation tokens)
@@ -793,7 +1974,7 @@ impl FileBasedTokenMonitor {
         // Since we don't track specific file paths, we'll analyze by patterns
         // This is a reasonable approximation based on typical usage
         if !self.usage_entries.is_empty() {
-            let total_tokens: u32 = self.usage_entries.iter().map(|e| e.usage.total_tokens()).sum();
+            let total_tokens = self.usage_entries.iter().fold(0u32, |total, e| total.saturating_add(e.usage.total_tokens()));
             let total_entries = self.usage_entries.len();
             
             // Group by time periods to simulate different sessions/files
@@ -852,8 +2033,8 @@ impl FileBasedTokenMonitor {
         
         let mut model_usage: HashMap<String, (u32, usize)> = HashMap::new();
         
-        for entry in &self.usage_entries {
-            let model = entry.model.clone().unwrap_or_else(|| "unknown".to_string());
+        for entry in self.report_entries() {
+            let model = entry.model.as_deref().unwrap_or("unknown").to_string();
             let tokens = entry.usage.total_tokens();
             
             let (total_tokens, count) = model_usage.entry(model).or_insert((0, 0));
@@ -866,7 +2047,7 @@ impl FileBasedTokenMonitor {
             .map(|(model, (tokens, count))| (model, tokens, count))
             .collect();
         
-        result.sort_by(|a, b| b.1.cmp(&a.1)); // Sort by tokens descending
+        result.sort_by_key(|(_, tokens, _)| std::cmp::Reverse(*tokens)); // Sort by tokens descending
         result
     }
 
@@ -909,6 +2090,11 @@ impl FileBasedTokenMonitor {
         
         Ok(rx)
     }
+
+    /// Stop watching the data directories, which ends the events `start_file_watcher` returned
+    pub fn stop_file_watcher(&mut self) {
+        self._watcher = None;
+    }
 }
 
 /// Display detailed explanation of how the tool works
@@ -935,7 +2121,7 @@ pub fn explain_how_this_works() {
     
     println!("{}", "📊 How It Calculates Metrics:".bright_yellow().bold());
     println!("• Usage Rate: Total tokens ÷ Time elapsed (tokens/minute)");
-    println!("• Session Progress: Time elapsed ÷ Session duration (5 hours)");
+    println!("• Session Progress: Time elapsed ÷ Session window length (5 hours by default)");
     println!("• Efficiency Score: Expected rate ÷ Actual rate (0.0-1.0)");
     println!("• Projected Depletion: Remaining tokens ÷ Current usage rate");
     println!();
@@ -0,0 +1,79 @@
+use super::file_monitor::UsageEntry;
+use super::pricing::Prices;
+use super::usage_stats::{billing_cycle_start, daily_usage, next_billing_cycle};
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveTime, Utc};
+
+/// Days before today whose spend the weekday-aware forecast averages
+pub const HISTORY_DAYS: u64 = 28;
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// Spend at the end of the billing cycle in progress, projected from the cycle so far
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpendForecast {
+    pub cycle_start: NaiveDate,
+    pub next_cycle: NaiveDate,
+    pub elapsed_days: f64,
+    pub spent: f64,           // API-equivalent USD so far this cycle
+    pub linear: f64,          // the spend so far scaled to the whole cycle
+    pub weekday: Option<f64>, // the spend so far plus each remaining day's average for its weekday; None with under a week of history
+}
+
+impl SpendForecast {
+    /// The weekday-aware projection when there is enough history for it, else the straight line
+    pub fn expected(&self) -> f64 {
+        self.weekday.unwrap_or(self.linear)
+    }
+
+    /// Whether the projection goes past `budget`
+    pub fn exceeds(&self, budget: f64) -> bool {
+        self.expected() > budget
+    }
+}
+
+/// Forecast the billing cycle (renewing on `billing_day`) that contains `now`.
+///
+/// The straight line counts at least one elapsed day, so it doesn't explode in the cycle's first
+/// hours. The weekday-aware model averages each weekday over the last `HISTORY_DAYS` days, from the
+/// first of them with any usage, and adds those averages for the time left in the cycle.
+pub fn forecast_cycle<'a>(
+    entries: impl Iterator<Item = &'a UsageEntry>,
+    billing_day: u32,
+    now: DateTime<Utc>,
+    prices: Prices,
+) -> SpendForecast {
+    let today = now.date_naive();
+    let cycle_start = billing_cycle_start(today, billing_day);
+    let next_cycle = next_billing_cycle(cycle_start, billing_day);
+    let history_start = today - Days::new(HISTORY_DAYS);
+    let days = daily_usage(entries.filter(|entry| entry.timestamp <= now), history_start.min(cycle_start), today, prices);
+
+    let spent: f64 = days.iter().filter(|(day, _)| *day >= cycle_start).map(|(_, usage)| usage.cost).sum();
+    let elapsed_days = (now - cycle_start.and_time(NaiveTime::MIN).and_utc()).num_seconds() as f64 / SECONDS_PER_DAY;
+    let cycle_days = (next_cycle - cycle_start).num_days() as f64;
+    let linear = spent / elapsed_days.max(1.0) * cycle_days;
+
+    // Per-weekday averages over whole past days since usage began
+    let history: Vec<_> = days
+        .iter()
+        .filter(|(day, _)| *day >= history_start && *day < today)
+        .skip_while(|(_, usage)| usage.tokens == 0)
+        .collect();
+    let weekday = (history.len() >= 7).then(|| {
+        let mut totals = [(0.0, 0u32); 7];
+        for (day, usage) in &history {
+            let total = &mut totals[day.weekday().num_days_from_monday() as usize];
+            total.0 += usage.cost;
+            total.1 += 1;
+        }
+        let average = |day: NaiveDate| {
+            let (cost, count) = totals[day.weekday().num_days_from_monday() as usize];
+            cost / count as f64
+        };
+        let rest_of_today = 1.0 - (now - today.and_time(NaiveTime::MIN).and_utc()).num_seconds() as f64 / SECONDS_PER_DAY;
+        let remaining: f64 = today.iter_days().skip(1).take_while(|day| *day < next_cycle).map(average).sum();
+        spent + average(today) * rest_of_today + remaining
+    });
+
+    SpendForecast { cycle_start, next_cycle, elapsed_days, spent, linear, weekday }
+}
@@ -0,0 +1,297 @@
+use super::file_lock::write_atomic_with;
+use super::file_monitor::{Billing, TokenUsage, UsageEntry};
+use super::intern::Interned;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Directory inside a profile's data directory holding imported usage
+pub const IMPORTS_DIR: &str = "imports";
+
+/// Store for rows imported from Anthropic Console usage exports
+const CONSOLE_IMPORT_FILE: &str = "anthropic-console.jsonl";
+
+const MAX_IMPORT_FILE_SIZE: u64 = 50 * 1024 * 1024; // 50MB, same cap as Claude logs
+
+/// External usage formats `import` understands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    AnthropicCsv, // usage CSV downloaded from the Anthropic Console
+}
+
+impl std::str::FromStr for ImportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(format: &str) -> Result<Self> {
+        match format.to_lowercase().as_str() {
+            "anthropic-csv" => Ok(ImportFormat::AnthropicCsv),
+            _ => Err(anyhow!("Invalid import format: {format}. Use 'anthropic-csv'")),
+        }
+    }
+}
+
+/// Outcome of importing one file
+#[derive(Debug, Clone, Default)]
+pub struct ImportSummary {
+    pub store: PathBuf,
+    pub rows: usize,         // usage rows read from the file
+    pub skipped_rows: usize, // rows without a date or with no tokens
+    pub added: usize,        // rows new to the store
+    pub updated: usize,      // rows replacing an earlier import of the same day/model/key
+    pub tokens: u64,
+    pub first_day: Option<DateTime<Utc>>,
+    pub last_day: Option<DateTime<Utc>>,
+}
+
+/// Header names used by Console exports, with a few tolerated alternatives
+const DATE_COLUMNS: &[&str] = &["usage_date_utc", "usage_date", "date"];
+const MODEL_COLUMNS: &[&str] = &["model_version", "model"];
+const API_KEY_COLUMNS: &[&str] = &["api_key", "api_key_name"];
+const WORKSPACE_COLUMNS: &[&str] = &["workspace", "workspace_name"];
+const USAGE_TYPE_COLUMNS: &[&str] = &["usage_type"];
+const CONTEXT_WINDOW_COLUMNS: &[&str] = &["context_window"];
+const INPUT_COLUMNS: &[&str] = &["usage_input_tokens_no_cache", "input_tokens"];
+const CACHE_READ_COLUMNS: &[&str] = &["usage_input_tokens_cache_read", "cache_read_input_tokens"];
+const OUTPUT_COLUMNS: &[&str] = &["usage_output_tokens", "output_tokens"];
+// Cache writes may be split by TTL (e.g. _5m and _1h), so every matching column is summed
+const CACHE_WRITE_PREFIXES: &[&str] = &["usage_input_tokens_cache_write", "cache_creation_input_tokens"];
+
+struct CsvColumns {
+    date: usize,
+    model: Option<usize>,
+    api_key: Option<usize>,
+    workspace: Option<usize>,
+    usage_type: Option<usize>,
+    context_window: Option<usize>,
+    input: Option<usize>,
+    cache_read: Option<usize>,
+    cache_write: Vec<usize>,
+    cache_write_1h: Vec<usize>, // the cache write columns for the 1-hour TTL
+    output: Option<usize>,
+}
+
+impl CsvColumns {
+    fn from_headers(headers: &csv::StringRecord) -> Result<Self> {
+        let names: Vec<String> = headers.iter().map(|h| h.trim().to_lowercase()).collect();
+        let find = |aliases: &[&str]| names.iter().position(|name| aliases.contains(&name.as_str()));
+
+        let columns = Self {
+            date: find(DATE_COLUMNS)
+                .ok_or_else(|| anyhow!("not an Anthropic Console usage export (no usage_date_utc column)"))?,
+            model: find(MODEL_COLUMNS),
+            api_key: find(API_KEY_COLUMNS),
+            workspace: find(WORKSPACE_COLUMNS),
+            usage_type: find(USAGE_TYPE_COLUMNS),
+            context_window: find(CONTEXT_WINDOW_COLUMNS),
+            input: find(INPUT_COLUMNS),
+            cache_read: find(CACHE_READ_COLUMNS),
+            cache_write: names
+                .iter()
+                .enumerate()
+                .filter(|(_, name)| CACHE_WRITE_PREFIXES.iter().any(|prefix| name.starts_with(prefix)))
+                .map(|(index, _)| index)
+                .collect(),
+            cache_write_1h: names
+                .iter()
+                .enumerate()
+                .filter(|(_, name)| CACHE_WRITE_PREFIXES.iter().any(|prefix| name.starts_with(prefix)) && name.ends_with("_1h"))
+                .map(|(index, _)| index)
+                .collect(),
+            output: find(OUTPUT_COLUMNS),
+        };
+        if columns.input.is_none() && columns.output.is_none() {
+            return Err(anyhow!("not an Anthropic Console usage export (no token columns)"));
+        }
+        Ok(columns)
+    }
+}
+
+fn field(record: &csv::StringRecord, column: Option<usize>) -> &str {
+    column.and_then(|index| record.get(index)).map(str::trim).unwrap_or_default()
+}
+
+/// Token counts may be exported as "1,234" or "1234.0"
+fn parse_tokens(value: &str) -> Result<u32> {
+    let value = value.replace(',', "");
+    if value.is_empty() {
+        return Ok(0);
+    }
+    let tokens: f64 = value.parse().with_context(|| format!("invalid token count '{value}'"))?;
+    if !tokens.is_finite() || tokens < 0.0 {
+        return Err(anyhow!("invalid token count '{value}'"));
+    }
+    Ok(tokens.round().min(u32::MAX as f64) as u32)
+}
+
+fn parse_day(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Some(timestamp.with_timezone(&Utc));
+    }
+    if let Ok(timestamp) = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S") {
+        return Some(timestamp.and_utc());
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|day| day.and_hms_opt(0, 0, 0))
+        .map(|timestamp| timestamp.and_utc())
+}
+
+/// Parse a Console usage CSV into one entry per row, returning the entries and the number of rows skipped.
+///
+/// Each row gets a stable request ID built from its date, model, key, workspace and usage type,
+/// so importing an overlapping export later replaces rows instead of double-counting them.
+pub fn parse_anthropic_csv<R: Read>(reader: R) -> Result<(Vec<UsageEntry>, usize)> {
+    let mut csv_reader = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+    let columns = CsvColumns::from_headers(csv_reader.headers()?)?;
+
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+    for (row, record) in csv_reader.records().enumerate() {
+        let record = record.with_context(|| format!("row {} is not valid CSV", row + 2))?;
+        let Some(timestamp) = parse_day(field(&record, Some(columns.date))) else {
+            skipped += 1;
+            continue;
+        };
+
+        let tokens = |column| parse_tokens(field(&record, column)).with_context(|| format!("row {}", row + 2));
+        let (mut cache_write, mut cache_write_1h) = (0u32, 0u32);
+        for index in &columns.cache_write {
+            let written = tokens(Some(*index))?;
+            cache_write = cache_write.saturating_add(written);
+            if columns.cache_write_1h.contains(index) {
+                cache_write_1h = cache_write_1h.saturating_add(written);
+            }
+        }
+        let usage = TokenUsage {
+            input_tokens: tokens(columns.input)?,
+            output_tokens: tokens(columns.output)?,
+            cache_creation_input_tokens: Some(cache_write),
+            cache_read_input_tokens: Some(tokens(columns.cache_read)?),
+            // Exports split cache writes by TTL into _5m and _1h columns
+            cache_creation_1h_input_tokens: (!columns.cache_write_1h.is_empty()).then_some(cache_write_1h),
+        };
+        if usage.total_tokens() == 0 {
+            skipped += 1;
+            continue;
+        }
+
+        let model = field(&record, columns.model);
+        let workspace = match field(&record, columns.workspace) {
+            "" => "Default",
+            workspace => workspace,
+        };
+        let row_id = format!(
+            "console:{}:{model}:{workspace}:{}:{}:{}",
+            timestamp.format("%Y-%m-%d"),
+            field(&record, columns.api_key),
+            field(&record, columns.usage_type),
+            field(&record, columns.context_window),
+        );
+
+        entries.push(UsageEntry {
+            timestamp,
+            usage,
+            model: (!model.is_empty()).then(|| Interned::new(model)),
+            message_id: None,
+            request_id: Some(row_id),
+            project: Some(Interned::new(&format!("Console: {workspace}"))),
+            conversation: None,
+            account: None,
+            user: None,
+            billing: Billing::ApiKey,
+            duration_ms: None,
+            ttft_ms: None,
+            service_tier: None,
+            tool_uses: Box::default(),
+        });
+    }
+    Ok((entries, skipped))
+}
+
+/// Import a file into the profile's import store, replacing rows seen in earlier imports
+pub fn import_file(format: ImportFormat, path: &Path, imports_dir: &Path) -> Result<ImportSummary> {
+    let metadata = std::fs::metadata(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    if metadata.len() > MAX_IMPORT_FILE_SIZE {
+        return Err(anyhow!("{} is too large to import ({} bytes)", path.display(), metadata.len()));
+    }
+
+    let (entries, skipped_rows) = match format {
+        ImportFormat::AnthropicCsv => parse_anthropic_csv(std::fs::File::open(path)?)
+            .with_context(|| format!("Failed to import {}", path.display()))?,
+    };
+
+    let store = console_import_file(imports_dir);
+    let mut stored: BTreeMap<String, UsageEntry> = read_store(&store)?
+        .into_iter()
+        .filter_map(|entry| Some((entry.request_id.clone()?, entry)))
+        .collect();
+
+    let mut summary = ImportSummary {
+        store: store.clone(),
+        rows: entries.len(),
+        skipped_rows,
+        ..ImportSummary::default()
+    };
+    for entry in entries {
+        summary.tokens += entry.usage.total_tokens() as u64;
+        summary.first_day = Some(summary.first_day.map_or(entry.timestamp, |day| day.min(entry.timestamp)));
+        summary.last_day = Some(summary.last_day.map_or(entry.timestamp, |day| day.max(entry.timestamp)));
+        let key = entry.request_id.clone().unwrap_or_default();
+        if stored.insert(key, entry).is_some() {
+            summary.updated += 1;
+        } else {
+            summary.added += 1;
+        }
+    }
+
+    write_store(&store, stored.values())?;
+    Ok(summary)
+}
+
+/// Where usage imported into `imports_dir` is stored
+pub fn console_import_file(imports_dir: &Path) -> PathBuf {
+    imports_dir.join(CONSOLE_IMPORT_FILE)
+}
+
+/// Every entry previously imported into `imports_dir`
+pub fn load_imported_entries(imports_dir: &Path) -> Result<Vec<UsageEntry>> {
+    let mut entries = read_store(&console_import_file(imports_dir))?;
+    // Console usage is always per-token API billing, whenever it was imported
+    for entry in &mut entries {
+        entry.billing = Billing::ApiKey;
+    }
+    Ok(entries)
+}
+
+fn read_store(path: &Path) -> Result<Vec<UsageEntry>> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+
+    let mut entries = Vec::new();
+    for (line_num, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => log::warn!("Skipping invalid imported entry at line {} in {:?}: {e}", line_num + 1, path),
+        }
+    }
+    Ok(entries)
+}
+
+fn write_store<'a>(path: &Path, entries: impl Iterator<Item = &'a UsageEntry>) -> Result<()> {
+    write_atomic_with(path, |file| {
+        for entry in entries {
+            serde_json::to_writer(&mut *file, entry)?;
+            file.write_all(b"\n")?;
+        }
+        Ok(())
+    })
+}
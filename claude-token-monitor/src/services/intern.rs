@@ -0,0 +1,145 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// An immutable string shared through a process-wide table: the model, project, conversation,
+/// account and user repeated on thousands of usage entries are each stored once
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interned(Arc<str>);
+
+fn table() -> &'static Mutex<HashSet<Arc<str>>> {
+    static TABLE: OnceLock<Mutex<HashSet<Arc<str>>>> = OnceLock::new();
+    TABLE.get_or_init(Mutex::default)
+}
+
+impl Interned {
+    /// The shared copy of `text`, added to the table when it's new
+    pub fn new(text: &str) -> Self {
+        let mut table = table().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(shared) = table.get(text) {
+            return Self(shared.clone());
+        }
+        let shared: Arc<str> = Arc::from(text);
+        table.insert(shared.clone());
+        Self(shared)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether `a` and `b` share one allocation
+    pub fn ptr_eq(a: &Self, b: &Self) -> bool {
+        Arc::ptr_eq(&a.0, &b.0)
+    }
+}
+
+/// Drop the strings nothing refers to any more, e.g. after a rescan replaced every entry
+pub fn prune() {
+    let mut table = table().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    table.retain(|shared| Arc::strong_count(shared) > 1);
+}
+
+/// Distinct strings in the table and the bytes they take on the heap
+pub fn table_size() -> (usize, usize) {
+    let table = table().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let bytes = table.iter().map(|shared| shared.len() + 2 * std::mem::size_of::<usize>()).sum::<usize>()
+        + table.capacity() * std::mem::size_of::<Arc<str>>();
+    (table.len(), bytes)
+}
+
+impl Deref for Interned {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Interned {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<std::ffi::OsStr> for Interned {
+    fn as_ref(&self) -> &std::ffi::OsStr {
+        self.as_str().as_ref()
+    }
+}
+
+impl AsRef<std::path::Path> for Interned {
+    fn as_ref(&self) -> &std::path::Path {
+        self.as_str().as_ref()
+    }
+}
+
+impl Borrow<str> for Interned {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Interned {
+    fn from(text: &str) -> Self {
+        Self::new(text)
+    }
+}
+
+impl From<String> for Interned {
+    fn from(text: String) -> Self {
+        Self::new(&text)
+    }
+}
+
+impl From<&String> for Interned {
+    fn from(text: &String) -> Self {
+        Self::new(text)
+    }
+}
+
+impl From<Interned> for String {
+    fn from(text: Interned) -> Self {
+        text.0.to_string()
+    }
+}
+
+impl PartialEq<str> for Interned {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Interned {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl fmt::Debug for Interned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for Interned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+impl Serialize for Interned {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Interned {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = std::borrow::Cow::<str>::deserialize(deserializer)?;
+        Ok(Self::new(&text))
+    }
+}
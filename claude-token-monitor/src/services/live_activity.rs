@@ -0,0 +1,142 @@
+use super::file_monitor::UsageEntry;
+use chrono::{DateTime, Duration, Utc};
+use clap::ValueEnum;
+use std::collections::HashMap;
+
+/// Trailing windows, in minutes, that `top` totals tokens over
+pub const ACTIVITY_WINDOWS: [i64; 3] = [5, 15, 60];
+
+/// What `top` ranks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ActivityGrouping {
+    #[value(alias = "conversations")]
+    Conversation,
+    #[value(alias = "projects")]
+    Project,
+}
+
+/// Which of `ACTIVITY_WINDOWS` `top` ranks by, named by its minutes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ActivityWindow {
+    #[value(name = "5")]
+    FiveMinutes,
+    #[value(name = "15")]
+    FifteenMinutes,
+    #[value(name = "60")]
+    Hour,
+}
+
+impl ActivityWindow {
+    /// Position of the window in `ACTIVITY_WINDOWS`
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl ActivityGrouping {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ActivityGrouping::Conversation => "conversation",
+            ActivityGrouping::Project => "project",
+        }
+    }
+
+    pub fn toggled(&self) -> Self {
+        match self {
+            ActivityGrouping::Conversation => ActivityGrouping::Project,
+            ActivityGrouping::Project => ActivityGrouping::Conversation,
+        }
+    }
+}
+
+/// Tokens one conversation or project used in each of `ACTIVITY_WINDOWS`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActivityRow {
+    pub key: String,             // conversation ID or project directory, "unknown" when the log has none
+    pub project: Option<String>, // for conversations, the directory the latest request ran in
+    pub tokens: [u64; 3],
+    pub requests: usize, // in the longest window
+    pub last_seen: DateTime<Utc>,
+}
+
+impl ActivityRow {
+    fn new(key: String, last_seen: DateTime<Utc>) -> Self {
+        Self {
+            key,
+            project: None,
+            tokens: [0; 3],
+            requests: 0,
+            last_seen,
+        }
+    }
+
+    /// Average tokens per minute over the window at `window` in `ACTIVITY_WINDOWS`
+    pub fn rate(&self, window: usize) -> f64 {
+        self.tokens[window] as f64 / ACTIVITY_WINDOWS[window] as f64
+    }
+
+    fn add(&mut self, entry: &UsageEntry, now: DateTime<Utc>) {
+        let tokens = entry.usage.total_tokens() as u64;
+        for (total, minutes) in self.tokens.iter_mut().zip(ACTIVITY_WINDOWS) {
+            if entry.timestamp > now - Duration::minutes(minutes) {
+                *total += tokens;
+            }
+        }
+        self.requests += 1;
+        if entry.timestamp >= self.last_seen {
+            self.last_seen = entry.timestamp;
+            if entry.project.is_some() {
+                self.project = entry.project.as_deref().map(str::to_string);
+            }
+        }
+    }
+}
+
+/// Activity in the last hour, busiest first
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActivityTable {
+    pub rows: Vec<ActivityRow>,
+    pub totals: ActivityRow,
+}
+
+/// Rank conversations or projects by tokens used in the window at `sort_window` in
+/// `ACTIVITY_WINDOWS`, ties broken by the longer windows.
+///
+/// Only entries from the last hour up to `now` are counted; anything idle for longer drops out.
+pub fn rank_activity<'a>(
+    entries: impl IntoIterator<Item = &'a UsageEntry>,
+    now: DateTime<Utc>,
+    grouping: ActivityGrouping,
+    sort_window: usize,
+) -> ActivityTable {
+    let since = now - Duration::minutes(ACTIVITY_WINDOWS[ACTIVITY_WINDOWS.len() - 1]);
+    let mut totals = ActivityRow::new("total".to_string(), since);
+    let mut rows: HashMap<String, ActivityRow> = HashMap::new();
+    for entry in entries {
+        if entry.timestamp <= since || entry.timestamp > now {
+            continue;
+        }
+        let key = match grouping {
+            ActivityGrouping::Conversation => entry.conversation.as_deref(),
+            ActivityGrouping::Project => entry.project.as_deref(),
+        }
+        .unwrap_or("unknown");
+        rows.entry(key.to_string())
+            .or_insert_with(|| ActivityRow::new(key.to_string(), entry.timestamp))
+            .add(entry, now);
+        totals.add(entry, now);
+    }
+    totals.project = None;
+
+    let sort_window = sort_window.min(ACTIVITY_WINDOWS.len() - 1);
+    let mut rows: Vec<ActivityRow> = rows.into_values().collect();
+    rows.sort_by(|a, b| {
+        let order = |row: &ActivityRow| {
+            let mut tokens = vec![row.tokens[sort_window]];
+            tokens.extend(&row.tokens[sort_window + 1..]);
+            tokens
+        };
+        order(b).cmp(&order(a)).then_with(|| b.last_seen.cmp(&a.last_seen)).then_with(|| a.key.cmp(&b.key))
+    });
+    ActivityTable { rows, totals }
+}
@@ -0,0 +1,224 @@
+use super::encryption::StateFile;
+use crate::models::UsageMetrics;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// File in the data dir holding downsampled metric snapshots
+pub const METRICS_HISTORY_FILE: &str = "metrics_history.json";
+
+/// Granularity of a snapshot tier, finest first. Saved as its step in seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "i64", into = "i64")]
+pub enum Resolution {
+    Minute,
+    Hour,
+    Day,
+}
+
+impl Resolution {
+    pub fn step(&self) -> Duration {
+        match self {
+            Resolution::Minute => Duration::minutes(1),
+            Resolution::Hour => Duration::hours(1),
+            Resolution::Day => Duration::days(1),
+        }
+    }
+
+    /// How long snapshots are kept at this resolution before moving to the next coarser one
+    pub fn retention(&self) -> Duration {
+        match self {
+            Resolution::Minute => Duration::hours(24),
+            Resolution::Hour => Duration::days(30),
+            Resolution::Day => Duration::days(2 * 365),
+        }
+    }
+
+    /// Number of the bucket containing `timestamp`, counting steps since the Unix epoch
+    pub fn bucket(&self, timestamp: DateTime<Utc>) -> i64 {
+        timestamp.timestamp().div_euclid(self.step().num_seconds())
+    }
+
+    /// Start of bucket number `bucket`
+    pub fn bucket_time(&self, bucket: i64) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp(bucket.checked_mul(self.step().num_seconds())?, 0)
+    }
+
+    fn bucket_start(&self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
+        self.bucket_time(self.bucket(timestamp)).unwrap_or(timestamp)
+    }
+}
+
+impl From<Resolution> for i64 {
+    fn from(resolution: Resolution) -> Self {
+        resolution.step().num_seconds()
+    }
+}
+
+impl TryFrom<i64> for Resolution {
+    type Error = String;
+
+    fn try_from(step_seconds: i64) -> std::result::Result<Self, Self::Error> {
+        [Resolution::Minute, Resolution::Hour, Resolution::Day]
+            .into_iter()
+            .find(|resolution| resolution.step().num_seconds() == step_seconds)
+            .ok_or_else(|| format!("no resolution has a step of {step_seconds}s"))
+    }
+}
+
+/// Mean of `samples` values averaging `mean` and `other_samples` more averaging `other`
+pub fn merge_mean(mean: f64, samples: u32, other: f64, other_samples: u32) -> f64 {
+    (mean * samples as f64 + other * other_samples as f64) / (samples + other_samples).max(1) as f64
+}
+
+/// Session metrics over one bucket, merged from every sample taken in it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricSnapshot {
+    pub timestamp: DateTime<Utc>, // start of the bucket
+    pub samples: u32,
+    pub tokens_used: u32, // highest session token count seen
+    pub tokens_limit: u32,
+    pub usage_rate: f64,     // mean tokens per minute
+    pub estimated_cost: f64, // highest session cost seen
+}
+
+impl MetricSnapshot {
+    fn from_metrics(metrics: &UsageMetrics, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            timestamp,
+            samples: 1,
+            tokens_used: metrics.current_session.tokens_used,
+            tokens_limit: metrics.current_session.tokens_limit,
+            usage_rate: metrics.usage_rate,
+            estimated_cost: metrics.estimated_cost,
+        }
+    }
+
+    fn merge(&mut self, other: &MetricSnapshot) {
+        self.usage_rate = merge_mean(self.usage_rate, self.samples, other.usage_rate, other.samples);
+        self.samples += other.samples;
+        self.tokens_used = self.tokens_used.max(other.tokens_used);
+        self.tokens_limit = self.tokens_limit.max(other.tokens_limit);
+        self.estimated_cost = self.estimated_cost.max(other.estimated_cost);
+    }
+}
+
+/// Metric snapshots kept per minute for a day, per hour for a month and per day for two years,
+/// so long-range views don't need every usage file re-read
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MetricsHistory {
+    #[serde(default)]
+    pub minutes: Vec<MetricSnapshot>,
+    #[serde(default)]
+    pub hours: Vec<MetricSnapshot>,
+    #[serde(default)]
+    pub days: Vec<MetricSnapshot>,
+}
+
+impl MetricsHistory {
+    /// Load the history, starting empty when none has been saved yet
+    pub fn load(file: &StateFile) -> Result<Self> {
+        let Some(content) = file.read()? else {
+            return Ok(Self::default());
+        };
+        serde_json::from_str(&content).with_context(|| format!("{} is not a valid metrics history", file.path().display()))
+    }
+
+    /// Replace the saved history; concurrent readers see either the old or the new file
+    pub fn save(&self, file: &StateFile) -> Result<()> {
+        file.write(&serde_json::to_string(self)?)
+    }
+
+    /// Add a sample of the current metrics and downsample anything past its tier's retention.
+    ///
+    /// Returns true when the sample started a new minute, a natural point to save.
+    pub fn record(&mut self, metrics: &UsageMetrics, now: DateTime<Utc>) -> bool {
+        let snapshot = MetricSnapshot::from_metrics(metrics, Resolution::Minute.bucket_start(now));
+        let new_minute = Self::add(&mut self.minutes, Resolution::Minute, &snapshot);
+        self.downsample(now);
+        new_minute
+    }
+
+    /// Merge `snapshot` into its bucket of `tier`, returning true when the bucket is new
+    fn add(tier: &mut Vec<MetricSnapshot>, resolution: Resolution, snapshot: &MetricSnapshot) -> bool {
+        let bucket = resolution.bucket_start(snapshot.timestamp);
+        match tier.last_mut() {
+            Some(last) if last.timestamp == bucket => {
+                last.merge(snapshot);
+                false
+            }
+            Some(last) if last.timestamp > bucket => {
+                // Out of order (clock change): merge into the matching bucket if there is one
+                if let Some(existing) = tier.iter_mut().find(|existing| existing.timestamp == bucket) {
+                    existing.merge(snapshot);
+                }
+                false
+            }
+            _ => {
+                tier.push(MetricSnapshot {
+                    timestamp: bucket,
+                    ..snapshot.clone()
+                });
+                true
+            }
+        }
+    }
+
+    /// Move snapshots older than each tier's retention into the next coarser tier
+    fn downsample(&mut self, now: DateTime<Utc>) {
+        let expired = Self::take_expired(&mut self.minutes, now - Resolution::Minute.retention());
+        for snapshot in &expired {
+            Self::add(&mut self.hours, Resolution::Hour, snapshot);
+        }
+        let expired = Self::take_expired(&mut self.hours, now - Resolution::Hour.retention());
+        for snapshot in &expired {
+            Self::add(&mut self.days, Resolution::Day, snapshot);
+        }
+        Self::take_expired(&mut self.days, now - Resolution::Day.retention());
+    }
+
+    fn take_expired(tier: &mut Vec<MetricSnapshot>, cutoff: DateTime<Utc>) -> Vec<MetricSnapshot> {
+        let kept = tier.partition_point(|snapshot| snapshot.timestamp < cutoff);
+        tier.drain(..kept).collect()
+    }
+
+    /// Snapshots from `since` onwards, oldest first, each period at the finest resolution kept
+    pub fn snapshots_since(&self, since: DateTime<Utc>) -> Vec<&MetricSnapshot> {
+        let hours_start = self.minutes.first().map(|snapshot| snapshot.timestamp);
+        let days_start = self.hours.first().map(|snapshot| snapshot.timestamp).or(hours_start);
+        let before = |snapshot: &&MetricSnapshot, end: Option<DateTime<Utc>>| end.is_none_or(|end| snapshot.timestamp < end);
+        self.days
+            .iter()
+            .filter(|snapshot| before(snapshot, days_start))
+            .chain(self.hours.iter().filter(|snapshot| before(snapshot, hours_start)))
+            .chain(self.minutes.iter())
+            .filter(|snapshot| snapshot.timestamp >= since)
+            .collect()
+    }
+
+    /// Every snapshot in the `resolution` bucket containing `timestamp`, merged into one
+    pub fn merged_at(&self, resolution: Resolution, timestamp: DateTime<Utc>) -> Option<MetricSnapshot> {
+        let start = resolution.bucket_start(timestamp);
+        let mut merged: Option<MetricSnapshot> = None;
+        for snapshot in self.snapshots_since(start).into_iter().filter(|snapshot| snapshot.timestamp < start + resolution.step()) {
+            match &mut merged {
+                Some(merged) => merged.merge(snapshot),
+                None => merged = Some(MetricSnapshot { timestamp: start, ..snapshot.clone() }),
+            }
+        }
+        merged
+    }
+
+    /// Merge the snapshots since `since` into one per UTC day, oldest first
+    pub fn daily_since(&self, since: DateTime<Utc>) -> Vec<MetricSnapshot> {
+        let mut days = Vec::new();
+        for snapshot in self.snapshots_since(since) {
+            Self::add(&mut days, Resolution::Day, snapshot);
+        }
+        days
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.minutes.is_empty() && self.hours.is_empty() && self.days.is_empty()
+    }
+}
@@ -1,6 +1,56 @@
 pub mod session_tracker;
 pub mod token_monitor;
 pub mod file_monitor;
+pub mod config_manager;
+pub mod profile_manager;
+pub mod pricing;
+pub mod custom_panel;
+pub mod scripting;
+pub mod alerts;
+pub mod claude_settings;
+pub mod claude_hooks;
+pub mod schema;
+pub mod dedupe;
+pub mod importer;
+pub mod usage_stats;
+pub mod recommendations;
+pub mod cache_analysis;
+pub mod analyzer;
+pub mod simulator;
+pub mod metrics_history;
+pub mod rrd;
+pub mod data_paths;
+pub mod file_lock;
+pub mod backup;
+pub mod encryption;
+pub mod output_schema;
+pub mod live_activity;
+pub mod session_compare;
+pub mod week_over_week;
+pub mod update_checker;
+pub mod accounts;
+pub mod overage;
+pub mod clipboard;
+pub mod mqtt;
+pub mod rpc;
+pub mod container;
+pub mod os_users;
+pub mod permissions;
+pub mod rate_limits;
+pub mod events;
+pub mod model_names;
+pub mod forecast;
+pub mod expense;
+pub mod email;
+pub mod telegram;
+pub mod notify_url;
+pub mod snapshot;
+pub mod debug_bundle;
+pub mod power;
+pub mod intern;
+pub mod json_line;
+pub mod transcript;
+pub mod recorder;
 
 use crate::models::*;
 use anyhow::Result;
@@ -38,8 +88,25 @@ pub trait AnalyticsService {
 /// Usage pattern analysis results
 #[derive(Debug, Clone)]
 pub struct UsageAnalysis {
-    pub average_session_duration: f64,
+    pub average_session_duration: f64, // minutes from first to last activity
+    pub idle_gaps: usize,              // pauses of at least the idle period within those sessions
+    pub idle_share: f64,               // share of session time spent in those pauses
     pub peak_usage_times: Vec<(u32, u32)>, // (hour, usage)
-    pub efficiency_trend: f64,
-    pub recommended_plan: PlanType,
+    pub efficiency_trend: f64, // change in tokens per session from the older to the newer half, as a fraction
+    pub recommended_plan: PlanType, // cheapest subscription that rarely hits its limit
+    pub sessions_analyzed: usize,
+    pub analyzed_days: f64,
+    pub plan_fits: Vec<PlanFit>, // every subscription plan, cheapest first
+    pub pay_as_you_go_monthly: Option<f64>, // projected API-equivalent cost, when known
+    pub recommend_pay_as_you_go: bool, // API billing would cost less than the recommended plan
+}
+
+/// How a subscription plan would have handled the analyzed sessions
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanFit {
+    pub plan: PlanType,
+    pub limit_hits: usize, // sessions that would have reached the plan's limit
+    pub hit_rate: f64,
+    pub monthly_limit_hits: f64, // projected to a month
+    pub monthly_cost: f64,
 }
\ No newline at end of file
@@ -0,0 +1,57 @@
+use crate::models::{ModelGrouping, UserConfig};
+use std::collections::BTreeMap;
+
+/// Model families grouped together by `ModelGrouping::Family`, by the part of the ID naming them
+const FAMILIES: [(&str, &str); 3] = [("opus", "Opus"), ("sonnet", "Sonnet"), ("haiku", "Haiku")];
+
+/// Names reports show for model IDs, from the configured aliases and grouping
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelNames {
+    aliases: Vec<(String, String)>, // lowercase ID or ID prefix and its alias, longest first
+    grouping: ModelGrouping,
+}
+
+impl ModelNames {
+    pub fn new(aliases: &BTreeMap<String, String>, grouping: ModelGrouping) -> Self {
+        let mut aliases: Vec<(String, String)> = aliases.iter().map(|(model, alias)| (model.to_lowercase(), alias.clone())).collect();
+        aliases.sort_by_key(|(model, _)| std::cmp::Reverse(model.len()));
+        Self { aliases, grouping }
+    }
+
+    pub fn from_config(config: &UserConfig) -> Self {
+        Self::new(&config.model_aliases, config.model_grouping)
+    }
+
+    /// The name `model` is reported under: its family when grouping by family, otherwise the
+    /// alias of the longest configured ID it starts with, otherwise the ID itself (without the
+    /// snapshot date when grouping by version)
+    pub fn name(&self, model: &str) -> String {
+        let lowercase = model.to_lowercase();
+        if self.grouping == ModelGrouping::Family {
+            if let Some((_, family)) = FAMILIES.iter().find(|(part, _)| lowercase.contains(part)) {
+                return family.to_string();
+            }
+        }
+        if let Some((_, alias)) = self.aliases.iter().find(|(prefix, _)| lowercase.starts_with(prefix.as_str())) {
+            return alias.clone();
+        }
+        match self.grouping {
+            ModelGrouping::Model => model.to_string(),
+            ModelGrouping::Version | ModelGrouping::Family => without_snapshot_date(model).to_string(),
+        }
+    }
+}
+
+/// `model` up to its snapshot date, e.g. claude-sonnet-4 for claude-sonnet-4-20250514 or
+/// claude-sonnet-4@20250514; IDs without a date are returned whole
+pub fn without_snapshot_date(model: &str) -> &str {
+    model
+        .char_indices()
+        .filter(|(_, c)| *c == '-' || *c == '@')
+        .map(|(index, _)| index)
+        .find(|&index| {
+            let date = &model.as_bytes()[index + 1..];
+            date.len() >= 8 && date[..8].iter().all(u8::is_ascii_digit) && date.get(8).is_none_or(|c| !c.is_ascii_digit())
+        })
+        .map_or(model, |index| &model[..index])
+}
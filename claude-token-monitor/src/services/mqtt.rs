@@ -0,0 +1,186 @@
+use super::file_monitor::FileBasedTokenMonitor;
+use super::usage_stats;
+use crate::models::{MqttConfig, UsageMetrics};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rumqttc::{AsyncClient, Event, LastWill, MqttOptions, Packet, QoS};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+// Wait between attempts to reach the broker after the connection drops
+const RECONNECT_DELAY: Duration = Duration::from_secs(10);
+// Publishes queued while the broker is unreachable; newer values are retried on the next refresh
+const QUEUE_CAPACITY: usize = 32;
+
+/// How a published topic is announced to Home Assistant
+struct Sensor {
+    topic: &'static str,
+    name: &'static str,
+    unit: Option<&'static str>,
+    device_class: Option<&'static str>,
+    state_class: Option<&'static str>,
+    icon: &'static str,
+}
+
+const SENSORS: [Sensor; 5] = [
+    Sensor { topic: "usage_percent", name: "Usage", unit: Some("%"), device_class: None, state_class: Some("measurement"), icon: "mdi:gauge" },
+    Sensor { topic: "remaining_tokens", name: "Remaining tokens", unit: Some("tokens"), device_class: None, state_class: Some("measurement"), icon: "mdi:counter" },
+    Sensor { topic: "cost_today", name: "Cost today", unit: Some("USD"), device_class: Some("monetary"), state_class: Some("total"), icon: "mdi:currency-usd" },
+    Sensor { topic: "minutes_to_reset", name: "Time to reset", unit: Some("min"), device_class: Some("duration"), state_class: Some("measurement"), icon: "mdi:timer-sand" },
+    Sensor { topic: "reset_time", name: "Reset time", unit: None, device_class: Some("timestamp"), state_class: None, icon: "mdi:clock-outline" },
+];
+
+/// Usage values published under the topic prefix: `usage_percent`, `remaining_tokens`,
+/// `cost_today` (API-equivalent USD since UTC midnight), `minutes_to_reset` and `reset_time`
+/// (RFC 3339)
+pub fn usage_messages(prefix: &str, metrics: &UsageMetrics, cost_today: f64, now: DateTime<Utc>) -> Vec<(String, String)> {
+    let session = &metrics.current_session;
+    let usage_pct = if session.tokens_limit == 0 {
+        0.0
+    } else {
+        session.tokens_used as f64 / session.tokens_limit as f64 * 100.0
+    };
+    vec![
+        (format!("{prefix}/usage_percent"), format!("{usage_pct:.1}")),
+        (format!("{prefix}/remaining_tokens"), session.tokens_limit.saturating_sub(session.tokens_used).to_string()),
+        (format!("{prefix}/cost_today"), format!("{cost_today:.2}")),
+        (format!("{prefix}/minutes_to_reset"), (session.reset_time - now).num_minutes().max(0).to_string()),
+        (format!("{prefix}/reset_time"), session.reset_time.to_rfc3339_opts(SecondsFormat::Secs, true)),
+    ]
+}
+
+/// Home Assistant MQTT discovery announcements for every published topic, grouped as one device
+/// per topic prefix; empty unless discovery is enabled
+pub fn discovery_messages(config: &MqttConfig) -> Vec<(String, String)> {
+    if !config.home_assistant_discovery {
+        return Vec::new();
+    }
+    let prefix = config.topic_prefix.trim_end_matches('/');
+    let discovery_prefix = config.discovery_prefix.trim_end_matches('/');
+    let node_id: String = prefix
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    let device = serde_json::json!({
+        "identifiers": [format!("claude_token_monitor_{node_id}")],
+        "name": "Claude Token Monitor",
+        "manufacturer": "claude-token-monitor",
+        "sw_version": env!("CARGO_PKG_VERSION"),
+    });
+    SENSORS
+        .iter()
+        .map(|sensor| {
+            let topic = sensor.topic;
+            let mut announcement = serde_json::json!({
+                "name": sensor.name,
+                "unique_id": format!("claude_token_monitor_{node_id}_{topic}"),
+                "state_topic": format!("{prefix}/{topic}"),
+                "availability_topic": format!("{prefix}/status"),
+                "icon": sensor.icon,
+                "device": device,
+            });
+            let optional = [
+                ("unit_of_measurement", sensor.unit),
+                ("device_class", sensor.device_class),
+                ("state_class", sensor.state_class),
+            ];
+            for (key, value) in optional {
+                if let Some(value) = value {
+                    announcement[key] = serde_json::json!(value);
+                }
+            }
+            (format!("{discovery_prefix}/sensor/{node_id}/{topic}/config"), announcement.to_string())
+        })
+        .collect()
+}
+
+/// Publishes usage to an MQTT broker as retained messages, so subscribers such as Home Assistant
+/// get the latest values as soon as they connect.
+///
+/// `<prefix>/status` is `online` while the monitor is connected and `offline` once the broker
+/// loses it. With discovery enabled, the sensors are announced to Home Assistant on every
+/// connection.
+#[derive(Clone)]
+pub struct MqttPublisher {
+    client: AsyncClient,
+    prefix: String,
+    discovery: Vec<(String, String)>,   // Home Assistant announcements, sent before the values
+    published: HashMap<String, String>, // last payload sent per topic
+    reconnected: Arc<AtomicBool>,       // set by the event loop; everything is sent again
+}
+
+impl MqttPublisher {
+    /// Start connecting to the configured broker in the background; None when no broker is set
+    pub fn start(config: &MqttConfig) -> Result<Option<Self>> {
+        let Some((host, port)) = config.broker_address()? else {
+            return Ok(None);
+        };
+        let prefix = config.topic_prefix.trim_end_matches('/').to_string();
+        let status_topic = format!("{prefix}/status");
+
+        let mut options = MqttOptions::new(format!("claude-token-monitor-{}", std::process::id()), host.clone(), port);
+        options.set_keep_alive(Duration::from_secs(30));
+        options.set_last_will(LastWill::new(status_topic.clone(), "offline", QoS::AtLeastOnce, true));
+        if let Some(username) = &config.username {
+            let password = match &config.password_env {
+                Some(var) => std::env::var(var)
+                    .map_err(|_| anyhow!("Environment variable {var} with the MQTT password is not set"))?,
+                None => String::new(),
+            };
+            options.set_credentials(username, password);
+        }
+
+        let (client, mut event_loop) = AsyncClient::new(options, QUEUE_CAPACITY);
+        let reconnected = Arc::new(AtomicBool::new(false));
+        let status_client = client.clone();
+        let connected = reconnected.clone();
+        tokio::spawn(async move {
+            loop {
+                match event_loop.poll().await {
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        log::debug!("Connected to MQTT broker {host}:{port}");
+                        if let Err(e) = status_client.try_publish(&status_topic, QoS::AtLeastOnce, true, "online") {
+                            log::debug!("Failed to publish MQTT status: {e}");
+                        }
+                        connected.store(true, Ordering::Relaxed);
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        log::warn!("MQTT broker {host}:{port} unreachable, retrying in {}s: {e}", RECONNECT_DELAY.as_secs());
+                        tokio::time::sleep(RECONNECT_DELAY).await;
+                    }
+                }
+            }
+        });
+
+        let discovery = discovery_messages(config);
+        Ok(Some(Self { client, prefix, discovery, published: HashMap::new(), reconnected }))
+    }
+
+    /// Queue the usage values that changed since they were last sent, with today's cost taken
+    /// from `monitor`'s entries
+    pub fn publish(&mut self, metrics: &UsageMetrics, monitor: &FileBasedTokenMonitor, now: DateTime<Utc>) {
+        if self.reconnected.swap(false, Ordering::Relaxed) {
+            self.published.clear();
+        }
+        let today = now.date_naive();
+        let cost_today = usage_stats::daily_usage(monitor.report_entries(), today, today, monitor.prices())
+            .first()
+            .map(|(_, usage)| usage.cost)
+            .unwrap_or_default();
+        let messages = self.discovery.iter().cloned().chain(usage_messages(&self.prefix, metrics, cost_today, now));
+        for (topic, payload) in messages {
+            if self.published.get(&topic) == Some(&payload) {
+                continue;
+            }
+            match self.client.try_publish(&topic, QoS::AtLeastOnce, true, payload.clone()) {
+                Ok(()) => {
+                    self.published.insert(topic, payload);
+                }
+                Err(e) => log::debug!("Failed to queue MQTT message for {topic}: {e}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use super::telegram;
+use crate::models::AlertChannelConfig;
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use percent_encoding::percent_decode_str;
+use std::time::Duration;
+
+/// Each notification request gives up after this long
+pub const NOTIFY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Discord rejects messages longer than this
+const DISCORD_MAX_CHARS: usize = 2000;
+
+/// Schemes understood in notification URLs, as written in Apprise
+pub const NOTIFY_SCHEMES: [&str; 12] =
+    ["apprise", "apprises", "discord", "gotify", "gotifys", "json", "jsons", "ntfy", "ntfys", "pover", "slack", "tgram"];
+
+/// What to send; chat services get the body alone, which starts with the alert message
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub title: String,
+    pub body: String,
+    pub warning: bool, // an alert rather than a summary
+}
+
+/// A notification service parsed from an Apprise-style URL such as `ntfy://ntfy.sh/my-topic` or
+/// `tgram://123456:ABC-token/987654321`
+#[derive(Debug, Clone, PartialEq)]
+pub enum NotifyTarget {
+    Apprise { base: String, key: String }, // an Apprise API server, which forwards to its own configured services
+    Discord { id: String, token: String },
+    Gotify { base: String, token: String },
+    Json { url: String, auth: Option<(String, String)> }, // Apprise's generic JSON payload
+    Ntfy { base: String, topic: String, auth: Option<(String, String)> },
+    Pushover { user: String, token: String },
+    Slack { tokens: [String; 3] },
+    Telegram { token: String, chats: Vec<String> },
+}
+
+/// One HTTP POST that delivers a notification
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotifyRequest {
+    pub url: String,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: RequestBody,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RequestBody {
+    Json(serde_json::Value),
+    Text(String),
+}
+
+/// A URL split into its parts, percent-decoded
+struct Parts {
+    scheme: String,
+    user: Option<String>,
+    password: Option<String>,
+    host: String, // including any port
+    path: Vec<String>,
+}
+
+fn decode(text: &str) -> String {
+    percent_decode_str(text).decode_utf8_lossy().into_owned()
+}
+
+/// Split a URL by hand: bot tokens like `123:ABC` sit where a host and port would, which a
+/// standards-following parser refuses
+fn split(url: &str) -> Result<Parts> {
+    let (scheme, rest) = url.split_once("://").ok_or_else(|| anyhow!("notification URL needs a scheme like ntfy://"))?;
+    let rest = rest.split(['?', '#']).next().unwrap_or_default();
+    let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let (userinfo, host) = match authority.rsplit_once('@') {
+        Some((userinfo, host)) => (Some(userinfo), host),
+        None => (None, authority),
+    };
+    let (user, password) = match userinfo.map(|userinfo| userinfo.split_once(':').unwrap_or((userinfo, ""))) {
+        Some((user, password)) => (Some(decode(user)), Some(decode(password)).filter(|password| !password.is_empty())),
+        None => (None, None),
+    };
+    Ok(Parts {
+        scheme: scheme.to_lowercase(),
+        user,
+        password,
+        host: decode(host),
+        path: path.split('/').filter(|segment| !segment.is_empty()).map(decode).collect(),
+    })
+}
+
+/// A `${NAME}` in a notification URL whose environment variable isn't set
+#[derive(Debug)]
+pub struct UnsetVariable(pub String);
+
+impl std::fmt::Display for UnsetVariable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Environment variable {} used in a notification URL is not set", self.0)
+    }
+}
+
+impl std::error::Error for UnsetVariable {}
+
+/// `${NAME}` replaced with the environment variable, so secrets can stay out of the config
+pub fn expand_env(url: &str) -> Result<String> {
+    expand_env_with(url, |name| std::env::var(name).ok())
+}
+
+/// `${NAME}` replaced with what `lookup` finds for it
+pub fn expand_env_with(url: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String> {
+    let mut expanded = String::new();
+    let mut rest = url;
+    while let Some(start) = rest.find("${") {
+        let end = rest[start..].find('}').ok_or_else(|| anyhow!("unclosed ${{ in notification URL"))? + start;
+        let name = &rest[start + 2..end];
+        let value = lookup(name).ok_or_else(|| UnsetVariable(name.to_string()))?;
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&value);
+        rest = &rest[end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Check a configured URL as it will be sent, with `${NAME}` expanded. While a variable it uses
+/// isn't set here, as when the config is checked in another shell, only the placeholders are.
+pub fn validate(url: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<()> {
+    match expand_env_with(url, lookup) {
+        Ok(expanded) => NotifyTarget::parse(&expanded).map(|_| ()),
+        Err(e) if e.is::<UnsetVariable>() => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Check the URLs of every notify channel, with `${NAME}` taken from the environment. Run next to
+/// `UserConfig::validate`, which leaves them to this module.
+pub fn validate_channels(channels: &[AlertChannelConfig]) -> Result<()> {
+    for channel in channels {
+        if let AlertChannelConfig::Notify { urls, .. } = channel {
+            for url in urls {
+                validate(url, |name| std::env::var(name).ok())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+impl NotifyTarget {
+    /// Parse a notification URL; errors name the service but never repeat the URL, which may
+    /// hold a secret
+    pub fn parse(url: &str) -> Result<Self> {
+        let parts = split(url)?;
+        let http = if parts.scheme.ends_with('s') { "https" } else { "http" };
+        let auth = parts.user.clone().map(|user| (user, parts.password.clone().unwrap_or_default()));
+        let missing = |what: &str| anyhow!("{}:// URL needs {what}", parts.scheme);
+        let target = match parts.scheme.as_str() {
+            "apprise" | "apprises" => {
+                let (key, path) = parts.path.split_last().ok_or_else(|| missing("a configuration key, like apprise://host/key"))?;
+                let base = std::iter::once(parts.host.clone()).chain(path.iter().cloned()).collect::<Vec<_>>().join("/");
+                NotifyTarget::Apprise { base: format!("{http}://{base}"), key: key.clone() }
+            }
+            "discord" => match parts.path.as_slice() {
+                [token, ..] if !parts.host.is_empty() => NotifyTarget::Discord { id: parts.host.clone(), token: token.clone() },
+                _ => return Err(missing("the webhook ID and token, like discord://id/token")),
+            },
+            "gotify" | "gotifys" => {
+                let (token, path) = parts.path.split_last().ok_or_else(|| missing("an app token, like gotify://host/token"))?;
+                let base = std::iter::once(parts.host.clone()).chain(path.iter().cloned()).collect::<Vec<_>>().join("/");
+                NotifyTarget::Gotify { base: format!("{http}://{base}"), token: token.clone() }
+            }
+            "json" | "jsons" => {
+                let path = parts.path.join("/");
+                NotifyTarget::Json { url: format!("{http}://{}/{path}", parts.host), auth }
+            }
+            // ntfy://topic goes to ntfy.sh, ntfy://host/topic to a self-hosted server
+            "ntfy" | "ntfys" => match parts.path.as_slice() {
+                [] => NotifyTarget::Ntfy { base: "https://ntfy.sh".to_string(), topic: parts.host.clone(), auth },
+                [.., topic] => NotifyTarget::Ntfy { base: format!("{http}://{}", parts.host), topic: topic.clone(), auth },
+            },
+            "pover" => match (parts.user.clone(), parts.host.is_empty()) {
+                (Some(user), false) => NotifyTarget::Pushover { user, token: parts.host.clone() },
+                _ => return Err(missing("the user key and app token, like pover://user@token")),
+            },
+            "slack" => match parts.path.as_slice() {
+                [b, c, ..] => NotifyTarget::Slack { tokens: [parts.host.clone(), b.clone(), c.clone()] },
+                _ => return Err(missing("three webhook tokens, like slack://T000/B000/XXXX")),
+            },
+            "tgram" if !parts.path.is_empty() => NotifyTarget::Telegram { token: parts.host.clone(), chats: parts.path.clone() },
+            "tgram" => return Err(missing("the bot token and a chat ID, like tgram://token/chat_id")),
+            "mailto" | "mailtos" => return Err(anyhow!("use an email channel with the [smtp] section instead of mailto://")),
+            scheme => return Err(anyhow!("unsupported notification URL scheme '{scheme}://'; use {}", NOTIFY_SCHEMES.join(", "))),
+        };
+        if parts.host.is_empty() {
+            return Err(missing("a host"));
+        }
+        // Basic auth over plain http would hand the password to anyone on the path
+        if let NotifyTarget::Json { url: base, auth: Some(_) } | NotifyTarget::Ntfy { base, auth: Some(_), .. } = &target {
+            if base.starts_with("http://") {
+                return Err(anyhow!("{}:// URL with a user and password must use {}s:// so they are sent over https", parts.scheme, parts.scheme));
+            }
+        }
+        Ok(target)
+    }
+
+    /// Name of the service, for logs
+    pub fn service(&self) -> &'static str {
+        match self {
+            NotifyTarget::Apprise { .. } => "Apprise",
+            NotifyTarget::Discord { .. } => "Discord",
+            NotifyTarget::Gotify { .. } => "Gotify",
+            NotifyTarget::Json { .. } => "JSON webhook",
+            NotifyTarget::Ntfy { .. } => "ntfy",
+            NotifyTarget::Pushover { .. } => "Pushover",
+            NotifyTarget::Slack { .. } => "Slack",
+            NotifyTarget::Telegram { .. } => "Telegram",
+        }
+    }
+
+    /// The requests that deliver `notification`, one per Telegram chat and one otherwise
+    pub fn requests(&self, notification: &Notification) -> Vec<NotifyRequest> {
+        let Notification { title, body, warning } = notification;
+        let level = if *warning { "warning" } else { "info" };
+        let request = |url: String, body: RequestBody| NotifyRequest { url, headers: Vec::new(), body };
+        let basic = |(user, password): &(String, String)| {
+            ("Authorization", format!("Basic {}", base64::engine::general_purpose::STANDARD.encode(format!("{user}:{password}"))))
+        };
+        match self {
+            NotifyTarget::Apprise { base, key } => vec![request(
+                format!("{base}/notify/{key}"),
+                RequestBody::Json(serde_json::json!({ "title": title, "body": body, "type": level })),
+            )],
+            NotifyTarget::Discord { id, token } => vec![request(
+                format!("https://discord.com/api/webhooks/{id}/{token}"),
+                RequestBody::Json(serde_json::json!({ "content": body.chars().take(DISCORD_MAX_CHARS).collect::<String>() })),
+            )],
+            NotifyTarget::Gotify { base, token } => vec![request(
+                format!("{base}/message?token={token}"),
+                RequestBody::Json(serde_json::json!({ "title": title, "message": body, "priority": if *warning { 8 } else { 5 } })),
+            )],
+            NotifyTarget::Json { url, auth } => vec![NotifyRequest {
+                url: url.clone(),
+                headers: auth.iter().map(basic).collect(),
+                body: RequestBody::Json(serde_json::json!({ "version": "1.0", "title": title, "message": body, "type": level })),
+            }],
+            NotifyTarget::Ntfy { base, topic, auth } => {
+                let mut headers = vec![("Title", title.clone()), ("Priority", if *warning { "high" } else { "default" }.to_string())];
+                headers.extend(auth.iter().map(basic));
+                vec![NotifyRequest { url: format!("{base}/{topic}"), headers, body: RequestBody::Text(body.clone()) }]
+            }
+            NotifyTarget::Pushover { user, token } => vec![request(
+                "https://api.pushover.net/1/messages.json".to_string(),
+                RequestBody::Json(serde_json::json!({ "token": token, "user": user, "title": title, "message": body })),
+            )],
+            NotifyTarget::Slack { tokens: [a, b, c] } => vec![request(
+                format!("https://hooks.slack.com/services/{a}/{b}/{c}"),
+                RequestBody::Json(serde_json::json!({ "text": body })),
+            )],
+            NotifyTarget::Telegram { token, chats } => chats
+                .iter()
+                .map(|chat| {
+                    let chat_id = chat.parse::<i64>().map(serde_json::Value::from).unwrap_or_else(|_| chat.clone().into());
+                    request(telegram::api_url(&telegram::api_base(), token, "sendMessage"), RequestBody::Json(serde_json::json!({ "chat_id": chat_id, "text": body })))
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Send `notification` to the service behind `url`, after expanding `${NAME}` variables in it.
+/// Blocks until every request has been answered.
+pub fn notify(url: &str, notification: &Notification) -> Result<()> {
+    let target = NotifyTarget::parse(&expand_env(url)?)?;
+    let agent = ureq::AgentBuilder::new()
+        .timeout(NOTIFY_TIMEOUT)
+        .user_agent(concat!("claude-token-monitor/", env!("CARGO_PKG_VERSION")))
+        .build();
+    for NotifyRequest { url, headers, body } in target.requests(notification) {
+        let mut request = agent.post(&url);
+        for (name, value) in &headers {
+            request = request.set(name, value);
+        }
+        let result = match body {
+            RequestBody::Json(json) => request.send_json(json),
+            RequestBody::Text(text) => request.set("Content-Type", "text/plain; charset=utf-8").send_string(&text),
+        };
+        // The URL can hold a token, so only the status or transport error is reported
+        match result {
+            Ok(_) => {}
+            Err(ureq::Error::Status(status, _)) => return Err(anyhow!("{} notification failed with HTTP {status}", target.service())),
+            Err(ureq::Error::Transport(e)) => return Err(anyhow!("Failed to reach {}: {}", target.service(), e.kind())),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use super::accounts;
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Label for entries whose log owner isn't known, such as imported usage
+pub const UNKNOWN_USER: &str = "unknown";
+
+/// Name and home directory of a user from the system's user database, which also knows the users
+/// of LDAP, NIS or systemd-homed that `/etc/passwd` doesn't list. `call` runs one of the
+/// reentrant `getpw*_r` lookups with the record and buffer to fill.
+#[cfg(unix)]
+fn query_passwd(
+    call: impl Fn(*mut libc::passwd, *mut libc::c_char, usize, *mut *mut libc::passwd) -> libc::c_int,
+) -> Option<(String, PathBuf)> {
+    use std::ffi::{CStr, OsStr};
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut size = 1024;
+    loop {
+        let mut buffer: Vec<libc::c_char> = vec![0; size];
+        let mut record = std::mem::MaybeUninit::<libc::passwd>::uninit();
+        let mut result = std::ptr::null_mut();
+        let status = call(record.as_mut_ptr(), buffer.as_mut_ptr(), buffer.len(), &mut result);
+        if status == libc::ERANGE && size < 1 << 20 {
+            size *= 2;
+            continue;
+        }
+        if status != 0 || result.is_null() {
+            return None;
+        }
+        // SAFETY: on success `result` points at the filled-in `record`, whose strings are
+        // NUL-terminated and live in `buffer`, both still in scope
+        let (name, home) = unsafe { (CStr::from_ptr((*result).pw_name), CStr::from_ptr((*result).pw_dir)) };
+        return Some((name.to_string_lossy().into_owned(), PathBuf::from(OsStr::from_bytes(home.to_bytes()))));
+    }
+}
+
+#[cfg(unix)]
+fn system_user_by_uid(uid: u32) -> Option<(String, PathBuf)> {
+    // SAFETY: getpwuid_r only writes to the record, the `size` bytes of the buffer and `result`
+    query_passwd(|record, buffer, size, result| unsafe { libc::getpwuid_r(uid, record, buffer, size, result) })
+}
+
+#[cfg(unix)]
+fn system_user_by_name(name: &str) -> Option<(String, PathBuf)> {
+    let name = std::ffi::CString::new(name).ok()?;
+    // SAFETY: as for getpwuid_r, and `name` is NUL-terminated and outlives the call
+    query_passwd(|record, buffer, size, result| unsafe { libc::getpwnam_r(name.as_ptr(), record, buffer, size, result) })
+}
+
+#[cfg(not(unix))]
+fn system_user_by_uid(_uid: u32) -> Option<(String, PathBuf)> {
+    None
+}
+
+#[cfg(not(unix))]
+fn system_user_by_name(_name: &str) -> Option<(String, PathBuf)> {
+    None
+}
+
+/// OS user names and home directories by user ID, from the system's user database or a passwd
+/// file
+#[derive(Debug, Default)]
+pub struct UserDirectory {
+    users: BTreeMap<u32, (String, PathBuf)>, // listed in a parsed passwd file
+    system: bool,                            // look up users through the system instead
+    names: Mutex<BTreeMap<u32, Option<String>>>, // system lookups so far, as each file owner is named
+}
+
+impl UserDirectory {
+    /// The users the system knows, looked up by ID or name as needed (with `getpwuid_r` and
+    /// `getpwnam_r`); none off Unix
+    pub fn load() -> Self {
+        Self { system: cfg!(unix), ..Self::default() }
+    }
+
+    /// Parse `name:password:uid:gid:gecos:home:shell` lines
+    pub fn parse(passwd: &str) -> Self {
+        let users = passwd
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split(':').collect();
+                let uid = fields.get(2)?.parse().ok()?;
+                Some((uid, (fields[0].to_string(), PathBuf::from(fields.get(5)?))))
+            })
+            .collect();
+        Self { users, ..Self::default() }
+    }
+
+    /// Name of the user with `uid`, or the number itself when it isn't known
+    pub fn name_of(&self, uid: u32) -> String {
+        let name = if self.system {
+            let mut names = self.names.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            names.entry(uid).or_insert_with(|| system_user_by_uid(uid).map(|(name, _)| name)).clone()
+        } else {
+            self.users.get(&uid).map(|(name, _)| name.clone())
+        };
+        name.unwrap_or_else(|| uid.to_string())
+    }
+
+    pub fn home_of(&self, name: &str) -> Option<PathBuf> {
+        if self.system {
+            system_user_by_name(name).map(|(_, home)| home)
+        } else {
+            self.users.values().find(|(user, _)| user == name).map(|(_, home)| home.clone())
+        }
+    }
+
+    /// Name of the user owning `path`, None where ownership isn't available
+    pub fn owner_of(&self, path: &Path) -> Option<String> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            std::fs::metadata(path).ok().map(|metadata| self.name_of(metadata.uid()))
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = path;
+            None
+        }
+    }
+
+    /// The conversation logs `name` shares: `projects/` under their Claude Code home, which they
+    /// must have made readable, e.g. with `setfacl -R -m u:<you>:rX ~/.claude`
+    pub fn shared_projects_dir(&self, name: &str) -> Result<PathBuf> {
+        let home = self
+            .home_of(name)
+            .ok_or_else(|| anyhow!("no user '{name}' on this system"))?;
+        let projects = accounts::projects_dir(&home.join(".claude"));
+        std::fs::read_dir(&projects)
+            .map_err(|e| anyhow!("{} can't be read ({e}); '{name}' needs to grant read access", projects.display()))?;
+        Ok(projects.canonicalize()?)
+    }
+}
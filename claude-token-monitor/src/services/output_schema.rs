@@ -0,0 +1,153 @@
+use super::backup::BackupManifest;
+use super::events::TimelineEvent;
+use super::file_monitor::FileBasedTokenMonitor;
+use super::pricing::{self, CostBreakdown};
+use super::snapshot::Snapshot;
+use super::usage_stats::WindowUsage;
+use crate::models::{AccountIdentity, SessionsFile, TokenSession, UsageMetrics};
+use chrono::{DateTime, Utc};
+use anyhow::{anyhow, Result};
+use schemars::schema::RootSchema;
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// `status --output json`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct StatusOutput {
+    /// The observed session window in progress, null when there is none
+    pub active_session: Option<TokenSession>,
+    /// Signed-in Claude account, when Claude Code's config names it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<AccountIdentity>,
+}
+
+/// `history --output json`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HistoryOutput {
+    /// Most recent first
+    pub sessions: Vec<HistorySession>,
+    /// Signed-in Claude account, when Claude Code's config names it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<AccountIdentity>,
+}
+
+/// A session in `history` output, with what it cost
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HistorySession {
+    #[serde(flatten)]
+    pub session: TokenSession,
+    /// API-equivalent cost of the session's requests in USD, absent when no usage logs were found
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_cost: Option<CostBreakdown>,
+}
+
+impl HistorySession {
+    /// `session` priced from the Claude Code requests `monitor` found in its window
+    pub fn new(session: TokenSession, monitor: Option<&FileBasedTokenMonitor>) -> Self {
+        let estimated_cost = monitor
+            .map(|monitor| pricing::window_cost(monitor.usage_entries(), session.start_time, session.reset_time, monitor.prices()));
+        Self { session, estimated_cost }
+    }
+}
+
+/// `blocks --output json`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BlocksOutput {
+    /// Tokens per window the percentages are measured against: the current plan's limit, or the
+    /// one calibrated from history
+    pub token_limit: u32,
+    /// Most recent first
+    pub windows: Vec<WindowUsage>,
+    /// Signed-in Claude account, when Claude Code's config names it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<AccountIdentity>,
+}
+
+/// `events --output json`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EventsOutput {
+    /// Oldest first
+    pub events: Vec<TimelineEvent>,
+    /// Signed-in Claude account, when Claude Code's config names it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<AccountIdentity>,
+}
+
+/// Current usage as the `rpc` backend's `metrics` method and `metrics.updated` notification
+/// report it
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MetricsOutput {
+    /// The current session window
+    pub session: TokenSession,
+    /// Share of the window's token limit used, from 0 to 100
+    pub usage_percent: f64,
+    pub remaining_tokens: u32,
+    /// Recent burn rate
+    pub tokens_per_minute: f64,
+    /// When the limit will be reached at the current rate, null when it won't be
+    pub projected_depletion: Option<DateTime<Utc>>,
+    /// API-equivalent cost of the window in USD
+    pub estimated_cost: f64,
+    /// Time of the last request when the session has been idle since, otherwise null
+    pub idle_since: Option<DateTime<Utc>>,
+    /// The session's reset time was announced by Claude Code rather than estimated from the window
+    #[serde(default)]
+    pub reset_authoritative: bool,
+    /// Signed-in Claude account, when Claude Code's config names it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<AccountIdentity>,
+}
+
+impl MetricsOutput {
+    pub fn new(metrics: &UsageMetrics, identity: Option<AccountIdentity>) -> Self {
+        let session = &metrics.current_session;
+        let usage_percent = if session.tokens_limit == 0 {
+            0.0
+        } else {
+            session.tokens_used as f64 / session.tokens_limit as f64 * 100.0
+        };
+        Self {
+            session: session.clone(),
+            usage_percent,
+            remaining_tokens: session.tokens_limit.saturating_sub(session.tokens_used),
+            tokens_per_minute: metrics.usage_rate,
+            projected_depletion: metrics.projected_depletion,
+            estimated_cost: metrics.estimated_cost,
+            idle_since: metrics.idle_since,
+            reset_authoritative: metrics.reset_authoritative,
+            identity,
+        }
+    }
+}
+
+/// Every JSON document the monitor prints or exchanges, by name: the `--output json` payloads, the
+/// `rpc` backend's metrics and the files other tools read or produce (`observed_sessions.json`,
+/// which `import --sessions` takes, a backup's `manifest.json` and `snapshot` files)
+pub fn schemas() -> BTreeMap<&'static str, RootSchema> {
+    BTreeMap::from([
+        ("status", schema_for!(StatusOutput)),
+        ("history", schema_for!(HistoryOutput)),
+        ("blocks", schema_for!(BlocksOutput)),
+        ("events", schema_for!(EventsOutput)),
+        ("observed-sessions", schema_for!(SessionsFile)),
+        ("backup-manifest", schema_for!(BackupManifest)),
+        ("snapshot", schema_for!(Snapshot)),
+        ("rpc-metrics", schema_for!(MetricsOutput)),
+    ])
+}
+
+/// The schema named `name`, or all of them keyed by name when `name` is None
+pub fn schema_json(name: Option<&str>) -> Result<serde_json::Value> {
+    let mut schemas = schemas();
+    match name {
+        None => Ok(serde_json::to_value(schemas)?),
+        Some(name) => {
+            let names: Vec<&str> = schemas.keys().copied().collect();
+            let schema = schemas
+                .remove(name)
+                .ok_or_else(|| anyhow!("Unknown schema '{name}'; available: {}", names.join(", ")))?;
+            Ok(serde_json::to_value(schema)?)
+        }
+    }
+}
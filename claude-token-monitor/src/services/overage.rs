@@ -0,0 +1,67 @@
+use super::file_monitor::UsageEntry;
+use crate::models::SessionWindowConfig;
+use chrono::{DateTime, Utc};
+
+/// A period's Claude Code usage split into what the plan's per-window allowance covers and the
+/// extra usage beyond it
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OverageSplit {
+    pub included_tokens: u64,
+    pub overage_tokens: u64,
+    pub included_cost: f64,  // API-equivalent value of the included usage, covered by the subscription
+    pub overage_cost: f64,   // extra usage at the overage rate
+    pub windows_over: usize, // windows in the period that went past the allowance
+}
+
+impl OverageSplit {
+    pub fn total_tokens(&self) -> u64 {
+        self.included_tokens + self.overage_tokens
+    }
+}
+
+/// Split the usage of entries from `since` until `until` at `limit` tokens per session window.
+///
+/// Windows follow the same rule as the session blocks and are tracked from the earliest entry, so
+/// a window that started before `since` still counts its earlier tokens against the allowance. The
+/// part of a request past the limit is billed at `rate` times its `cost` (API list prices), like
+/// Anthropic's extra usage.
+pub fn split_overage<'a>(
+    entries: impl IntoIterator<Item = &'a UsageEntry>,
+    window: &SessionWindowConfig,
+    limit: u64,
+    rate: f64,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    cost: impl Fn(&UsageEntry) -> f64,
+) -> OverageSplit {
+    let mut split = OverageSplit::default();
+    // Start, reset, tokens so far and whether the window already counted as over
+    let mut current: Option<(DateTime<Utc>, DateTime<Utc>, u64, bool)> = None;
+    for entry in entries {
+        if entry.timestamp >= until {
+            break;
+        }
+        let (start, used, counted) = match current {
+            Some((start, reset, used, counted)) if entry.timestamp < reset => (start, used, counted),
+            _ => (window.window_start(entry.timestamp), 0, false),
+        };
+        let tokens = entry.usage.total_tokens() as u64;
+        let over = (used + tokens).saturating_sub(limit.max(used)).min(tokens);
+        let in_period = entry.timestamp >= since;
+        let reset = window.reset_time(start, entry.timestamp);
+        current = Some((start, reset, used + tokens, counted || (in_period && over > 0)));
+        if !in_period {
+            continue;
+        }
+        let request_cost = cost(entry);
+        let over_share = if tokens == 0 { 0.0 } else { over as f64 / tokens as f64 };
+        split.included_tokens += tokens - over;
+        split.overage_tokens += over;
+        split.included_cost += request_cost * (1.0 - over_share);
+        split.overage_cost += request_cost * over_share * rate;
+        if over > 0 && !counted {
+            split.windows_over += 1;
+        }
+    }
+    split
+}
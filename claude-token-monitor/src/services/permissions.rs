@@ -0,0 +1,86 @@
+use anyhow::{Context, Result};
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+
+/// Mode of the files the monitor writes: usage, config and keys stay with their owner
+pub const PRIVATE_FILE_MODE: u32 = 0o600;
+/// Mode of the directories the monitor creates
+pub const PRIVATE_DIR_MODE: u32 = 0o700;
+
+/// Create `path` and any missing parents, readable only by their owner
+pub fn create_private_dir_all(path: &Path) -> Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(PRIVATE_DIR_MODE);
+    }
+    builder
+        .create(path)
+        .with_context(|| format!("Failed to create {}", path.display()))
+}
+
+/// Options creating files readable only by their owner; existing files keep their mode
+pub fn private_open_options() -> OpenOptions {
+    #[allow(unused_mut)]
+    let mut options = OpenOptions::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(PRIVATE_FILE_MODE);
+    }
+    options
+}
+
+/// A file or directory other users can read or write
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoosePermission {
+    pub path: PathBuf,
+    pub mode: u32,
+}
+
+impl LoosePermission {
+    /// The mode with the group and other bits taken away
+    pub fn fixed_mode(&self) -> u32 {
+        self.mode & !0o077
+    }
+}
+
+/// Everything under `roots` that group or other users can access, skipping symlinks; always empty
+/// where permissions aren't Unix modes
+pub fn find_loose_permissions<'a>(roots: impl IntoIterator<Item = &'a Path>) -> Vec<LoosePermission> {
+    #[allow(unused_mut)]
+    let mut loose: Vec<LoosePermission> = Vec::new();
+    #[cfg(unix)]
+    for root in roots {
+        use std::os::unix::fs::PermissionsExt;
+        use walkdir::WalkDir;
+        for entry in WalkDir::new(root).into_iter().filter_map(|entry| entry.ok()) {
+            if entry.path_is_symlink() || loose.iter().any(|found| found.path == entry.path()) {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else { continue };
+            let mode = metadata.permissions().mode() & 0o7777;
+            if mode & 0o077 != 0 {
+                loose.push(LoosePermission { path: entry.path().to_path_buf(), mode });
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = roots;
+    loose
+}
+
+/// Take group and other access away from each of `loose`
+pub fn fix_permissions(loose: &[LoosePermission]) -> Result<()> {
+    #[cfg(unix)]
+    for item in loose {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&item.path, std::fs::Permissions::from_mode(item.fixed_mode()))
+            .with_context(|| format!("Failed to restrict {}", item.path.display()))?;
+    }
+    #[cfg(not(unix))]
+    let _ = loose;
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use crate::models::LowPowerMode;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Where Linux lists batteries and power adapters
+pub const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+/// How often the power source is checked again in `auto` mode
+const POWER_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// In low-power mode scans happen this many times less often, and at least this far apart
+pub const LOW_POWER_SCAN_FACTOR: u32 = 4;
+pub const LOW_POWER_MIN_SCAN: Duration = Duration::from_secs(30);
+
+/// In low-power mode the screen is redrawn at most this often, enough for the countdowns
+pub const LOW_POWER_REDRAW: Duration = Duration::from_secs(1);
+
+fn read_field(dir: &Path, name: &str) -> Option<String> {
+    std::fs::read_to_string(dir.join(name)).ok().map(|value| value.trim().to_string())
+}
+
+/// Whether the machine runs on battery, judged from the power supplies listed under `dir` (laid
+/// out like `/sys/class/power_supply`); None when it lists none
+pub fn on_battery_in(dir: &Path) -> Option<bool> {
+    let mut seen = false;
+    let mut discharging = false;
+    for supply in std::fs::read_dir(dir).ok()?.flatten() {
+        let path = supply.path();
+        let Some(kind) = read_field(&path, "type") else {
+            continue;
+        };
+        seen = true;
+        match kind.as_str() {
+            // Any adapter plugged in means the battery isn't what keeps the machine running
+            "Mains" | "USB" | "USB_C" | "USB_PD" if read_field(&path, "online").as_deref() == Some("1") => return Some(false),
+            // Batteries of mice and headsets have a scope of Device
+            "Battery" if read_field(&path, "scope").as_deref() != Some("Device") => {
+                discharging |= read_field(&path, "status").as_deref() == Some("Discharging");
+            }
+            _ => {}
+        }
+    }
+    seen.then_some(discharging)
+}
+
+/// Whether the machine runs on battery, None where that can't be told
+pub fn on_battery() -> Option<bool> {
+    if cfg!(target_os = "linux") {
+        on_battery_in(Path::new(POWER_SUPPLY_DIR))
+    } else if cfg!(target_os = "macos") {
+        let output = std::process::Command::new("pmset").args(["-g", "batt"]).output().ok()?;
+        output.status.success().then(|| String::from_utf8_lossy(&output.stdout).contains("'Battery Power'"))
+    } else {
+        None
+    }
+}
+
+/// Decides whether the UI saves energy: always with `--low-power` or `low_power = "on"`, never
+/// with `"off"`, and while on battery with `"auto"`
+#[derive(Debug)]
+pub struct PowerSaver {
+    mode: LowPowerMode,
+    active: bool,
+    checked: Instant,
+}
+
+impl PowerSaver {
+    pub fn new(mode: LowPowerMode) -> Self {
+        let active = match mode {
+            LowPowerMode::Auto => on_battery().unwrap_or(false),
+            LowPowerMode::On => true,
+            LowPowerMode::Off => false,
+        };
+        Self { mode, active, checked: Instant::now() }
+    }
+
+    /// Check the power source again when it's due; true when low-power mode turned on or off
+    pub fn update(&mut self) -> bool {
+        if self.mode != LowPowerMode::Auto || self.checked.elapsed() < POWER_CHECK_INTERVAL {
+            return false;
+        }
+        self.checked = Instant::now();
+        let active = on_battery().unwrap_or(false);
+        let changed = active != self.active;
+        if changed {
+            log::info!("Low-power mode {}", if active { "on: running on battery" } else { "off: back on mains power" });
+        }
+        self.active = active;
+        changed
+    }
+
+    pub fn active(&self) -> bool {
+        self.active
+    }
+
+    /// The configured time between scans, backed off in low-power mode
+    pub fn scan_interval(&self, configured: Duration) -> Duration {
+        if self.active {
+            (configured * LOW_POWER_SCAN_FACTOR).max(LOW_POWER_MIN_SCAN)
+        } else {
+            configured
+        }
+    }
+
+    /// The configured time between redraws, slowed down in low-power mode
+    pub fn redraw_interval(&self, configured: Duration) -> Duration {
+        if self.active {
+            configured.max(LOW_POWER_REDRAW)
+        } else {
+            configured
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use super::file_monitor::{TokenUsage, UsageEntry};
+pub use crate::models::ModelPricing;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const TOKENS_PER_MILLION: f64 = 1_000_000.0;
+
+/// How entries are priced: at their own model's rates, or `default_model`'s (the model Claude Code
+/// is set to) for entries naming none, with models the price list doesn't recognize at `unknown`
+/// rates (the config's `unknown_model_pricing`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prices<'a> {
+    pub default_model: Option<&'a str>,
+    pub unknown: ModelPricing,
+}
+
+impl Default for Prices<'_> {
+    fn default() -> Self {
+        Self { default_model: None, unknown: ModelPricing::SONNET }
+    }
+}
+
+impl<'a> Prices<'a> {
+    pub fn new(default_model: Option<&'a str>, unknown: ModelPricing) -> Self {
+        Self { default_model, unknown }
+    }
+
+    /// The model an entry is priced as
+    pub fn model_of<'e>(&self, entry: &'e UsageEntry) -> &'e str
+    where
+        'a: 'e,
+    {
+        entry.model.as_deref().or(self.default_model).unwrap_or_default()
+    }
+
+    /// Rates of a model ID, the `unknown` rates for IDs that aren't recognized
+    pub fn for_model(&self, model: &str) -> ModelPricing {
+        ModelPricing::resolve(model, self.unknown)
+    }
+
+    pub fn for_entry(&self, entry: &UsageEntry) -> ModelPricing {
+        self.for_model(self.model_of(entry))
+    }
+
+    /// Estimated cost in USD of one entry
+    pub fn cost(&self, entry: &UsageEntry) -> f64 {
+        self.for_entry(entry).cost(&entry.usage)
+    }
+}
+
+/// Prompt size (uncached input, cache writes and cache reads) past which a request is billed at
+/// the long-context rates
+pub const LONG_CONTEXT_THRESHOLD: u64 = 200_000;
+
+/// Multipliers of the long-context tier: prompt prices (input and caching), and output
+pub const LONG_CONTEXT_INPUT_MULTIPLIER: f64 = 2.0;
+pub const LONG_CONTEXT_OUTPUT_MULTIPLIER: f64 = 1.5;
+
+/// Whether a request's prompt was large enough for the long-context rates, on models that have them
+pub fn is_long_context(usage: &TokenUsage) -> bool {
+    let prompt = usage.input_tokens as u64 + usage.cache_creation_tokens() as u64 + usage.cache_read_tokens() as u64;
+    prompt > LONG_CONTEXT_THRESHOLD
+}
+
+impl ModelPricing {
+    pub const OPUS: ModelPricing = ModelPricing {
+        input: 15.0,
+        output: 75.0,
+        cache_write: 18.75,
+        cache_read: 1.50,
+        long_context: false,
+    };
+
+    pub const SONNET: ModelPricing = ModelPricing {
+        input: 3.0,
+        output: 15.0,
+        cache_write: 3.75,
+        cache_read: 0.30,
+        long_context: true,
+    };
+
+    pub const HAIKU: ModelPricing = ModelPricing {
+        input: 0.80,
+        output: 4.0,
+        cache_write: 1.0,
+        cache_read: 0.08,
+        long_context: false,
+    };
+
+    /// List prices of a recognized model ID. Entries that name no model (or Claude Code's own
+    /// `<synthetic>` notices) count as Sonnet, the default model.
+    pub fn known(model: &str) -> Option<Self> {
+        let model = model.to_lowercase();
+        if model.contains("opus") {
+            Some(Self::OPUS)
+        } else if model.contains("haiku") {
+            Some(Self::HAIKU)
+        } else if model.contains("sonnet") || model.is_empty() || model == "<synthetic>" {
+            Some(Self::SONNET)
+        } else {
+            None
+        }
+    }
+
+    /// Pricing for a model ID, `fallback` for IDs that aren't recognized
+    pub fn resolve(model: &str, fallback: Self) -> Self {
+        Self::known(model).unwrap_or(fallback)
+    }
+
+    /// Long-context tier: prompt prices (input and caching) and output price raised by their
+    /// multipliers
+    pub fn long_context_rates(&self) -> Self {
+        ModelPricing {
+            input: self.input * LONG_CONTEXT_INPUT_MULTIPLIER,
+            output: self.output * LONG_CONTEXT_OUTPUT_MULTIPLIER,
+            cache_write: self.cache_write * LONG_CONTEXT_INPUT_MULTIPLIER,
+            cache_read: self.cache_read * LONG_CONTEXT_INPUT_MULTIPLIER,
+            ..*self
+        }
+    }
+
+    /// Whether a request is billed at the long-context tier: its prompt went past the threshold
+    /// and the model's price list has such a tier (only Sonnet among the known models)
+    pub fn bills_long_context(&self, usage: &TokenUsage) -> bool {
+        self.long_context && is_long_context(usage)
+    }
+
+    /// The rates a request is billed at, the long-context tier for prompts past the threshold on
+    /// models that have one
+    pub fn rates_for(&self, usage: &TokenUsage) -> Self {
+        if self.bills_long_context(usage) {
+            self.long_context_rates()
+        } else {
+            *self
+        }
+    }
+
+    /// Estimated cost in USD of a single request's token usage, with 1-hour cache writes at the
+    /// extended cache price and long prompts at the long-context rates
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        let rates = self.rates_for(usage);
+        (usage.input_tokens as f64 * rates.input
+            + usage.output_tokens as f64 * rates.output
+            + usage.cache_creation_5m_tokens() as f64 * rates.cache_write
+            + usage.cache_creation_1h_tokens() as f64 * rates.extended_cache_write()
+            + usage.cache_read_tokens() as f64 * rates.cache_read)
+            / TOKENS_PER_MILLION
+    }
+
+    /// `cost` split by the kind of token it was spent on
+    pub fn cost_breakdown(&self, usage: &TokenUsage) -> CostBreakdown {
+        let rates = self.rates_for(usage);
+        CostBreakdown {
+            input: usage.input_tokens as f64 * rates.input / TOKENS_PER_MILLION,
+            output: usage.output_tokens as f64 * rates.output / TOKENS_PER_MILLION,
+            cache_write: (usage.cache_creation_5m_tokens() as f64 * rates.cache_write
+                + usage.cache_creation_1h_tokens() as f64 * rates.extended_cache_write())
+                / TOKENS_PER_MILLION,
+            cache_read: usage.cache_read_tokens() as f64 * rates.cache_read / TOKENS_PER_MILLION,
+        }
+    }
+
+    /// Price per million tokens of a cache write with the 1-hour TTL (twice the input price)
+    pub fn extended_cache_write(&self) -> f64 {
+        self.input * 2.0
+    }
+
+    /// USD saved by serving cache reads instead of charging them as fresh input
+    pub fn cache_savings(&self, usage: &TokenUsage) -> f64 {
+        let rates = self.rates_for(usage);
+        usage.cache_read_tokens() as f64 * (rates.input - rates.cache_read) / TOKENS_PER_MILLION
+    }
+}
+
+/// API-equivalent cost in USD, by the kind of token it was spent on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CostBreakdown {
+    /// Uncached input
+    pub input: f64,
+    pub output: f64,
+    /// Cache writes, 5-minute and 1-hour
+    pub cache_write: f64,
+    pub cache_read: f64,
+}
+
+impl CostBreakdown {
+    pub fn total(&self) -> f64 {
+        self.input + self.output + self.cache_write + self.cache_read
+    }
+
+    pub fn add(&mut self, other: &CostBreakdown) {
+        self.input += other.input;
+        self.output += other.output;
+        self.cache_write += other.cache_write;
+        self.cache_read += other.cache_read;
+    }
+}
+
+/// Cost of the entries from `since` until before `until`, priced by `prices`
+pub fn window_cost<'a>(
+    entries: impl IntoIterator<Item = &'a UsageEntry>,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    prices: Prices,
+) -> CostBreakdown {
+    let mut cost = CostBreakdown::default();
+    for entry in entries {
+        if entry.timestamp < since || entry.timestamp >= until {
+            continue;
+        }
+        cost.add(&prices.for_entry(entry).cost_breakdown(&entry.usage));
+    }
+    cost
+}
+
+/// Costs above which a session stands out among `costs`: twice their median, and at least a cent
+pub fn expensive_threshold(costs: &[f64]) -> f64 {
+    let mut sorted: Vec<f64> = costs.iter().copied().filter(|cost| cost.is_finite()).collect();
+    if sorted.is_empty() {
+        return f64::INFINITY;
+    }
+    sorted.sort_by(f64::total_cmp);
+    let middle = sorted.len() / 2;
+    let median = if sorted.len().is_multiple_of(2) { (sorted[middle - 1] + sorted[middle]) / 2.0 } else { sorted[middle] };
+    (median * 2.0).max(0.01)
+}
+
+/// Usage of one model ID the price list doesn't recognize
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownModel {
+    pub model: String,
+    pub requests: usize,
+    pub tokens: u64,
+    pub estimated_cost: f64, // at the fallback rates
+}
+
+/// The entries whose model (or the default model, for entries naming none) isn't recognized, by
+/// model with their cost at the `unknown` rates of `prices`, most tokens first
+pub fn unknown_models<'a>(entries: impl Iterator<Item = &'a UsageEntry>, prices: Prices<'a>) -> Vec<UnknownModel> {
+    let mut models: BTreeMap<&str, UnknownModel> = BTreeMap::new();
+    for entry in entries {
+        let model = prices.model_of(entry);
+        if ModelPricing::known(model).is_some() {
+            continue;
+        }
+        let unknown = models.entry(model).or_insert_with(|| UnknownModel {
+            model: model.to_string(),
+            requests: 0,
+            tokens: 0,
+            estimated_cost: 0.0,
+        });
+        unknown.requests += 1;
+        unknown.tokens += entry.usage.total_tokens() as u64;
+        unknown.estimated_cost += prices.unknown.cost(&entry.usage);
+    }
+    let mut unknown: Vec<UnknownModel> = models.into_values().collect();
+    unknown.sort_by(|a, b| b.tokens.cmp(&a.tokens).then_with(|| a.model.cmp(&b.model)));
+    unknown
+}
@@ -0,0 +1,177 @@
+use super::data_paths::BaseDirs;
+use super::permissions::create_private_dir_all;
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+
+/// Name used for the unnamed profile living directly in the base directories
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Subdirectory of each base directory holding the named profiles
+pub const PROFILES_DIR: &str = "profiles";
+
+/// Manages named profiles, each with an independent config and state directory
+pub struct ProfileManager {
+    base: BaseDirs,
+}
+
+impl ProfileManager {
+    pub fn new(base: BaseDirs) -> Self {
+        Self { base }
+    }
+
+    fn profiles_dir(&self) -> PathBuf {
+        self.base.config.join(PROFILES_DIR)
+    }
+
+    /// Config and state directories of a named profile
+    fn profile_dirs(&self, name: &str) -> [PathBuf; 2] {
+        [&self.base.config, &self.base.state].map(|base| base.join(PROFILES_DIR).join(name))
+    }
+
+    /// Resolve the directories for a profile (None or "default" uses the base directories)
+    pub fn profile_dir(&self, name: Option<&str>) -> Result<BaseDirs> {
+        match name {
+            None | Some(DEFAULT_PROFILE) => Ok(self.base.clone()),
+            Some(name) => {
+                Self::validate_name(name)?;
+                let [config, state] = self.profile_dirs(name);
+                if !config.is_dir() {
+                    return Err(anyhow!(
+                        "Profile '{}' does not exist. Create it with: claude-token-monitor profile create {}",
+                        name, name
+                    ));
+                }
+                Ok(BaseDirs { config, state })
+            }
+        }
+    }
+
+    /// List all profiles, including the default one
+    pub fn list(&self) -> Result<Vec<String>> {
+        let mut profiles = vec![DEFAULT_PROFILE.to_string()];
+        let profiles_dir = self.profiles_dir();
+        if profiles_dir.is_dir() {
+            let mut named: Vec<String> = std::fs::read_dir(&profiles_dir)?
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_dir())
+                .filter_map(|entry| entry.file_name().to_str().map(|s| s.to_string()))
+                .filter(|name| Self::validate_name(name).is_ok())
+                .collect();
+            named.sort();
+            profiles.extend(named);
+        }
+        Ok(profiles)
+    }
+
+    /// Create a new empty profile, returning its config directory
+    pub fn create(&self, name: &str) -> Result<PathBuf> {
+        Self::validate_name(name)?;
+        if name == DEFAULT_PROFILE {
+            return Err(anyhow!("'{}' is reserved for the built-in profile", DEFAULT_PROFILE));
+        }
+        let dirs = self.profile_dirs(name);
+        if dirs[0].exists() {
+            return Err(anyhow!("Profile '{}' already exists", name));
+        }
+        for dir in &dirs {
+            create_private_dir_all(dir)?;
+        }
+        let [config, _] = dirs;
+        Ok(config)
+    }
+
+    /// Delete a profile and all of its stored config and data, unless it is `active`, the profile
+    /// this run uses
+    pub fn delete(&self, name: &str, active: Option<&str>) -> Result<()> {
+        Self::validate_name(name)?;
+        if name == DEFAULT_PROFILE {
+            return Err(anyhow!("The built-in '{}' profile cannot be deleted", DEFAULT_PROFILE));
+        }
+        if active == Some(name) {
+            return Err(anyhow!("Profile '{}' is in use; delete it without --profile {}", name, name));
+        }
+        let dirs = self.profile_dirs(name);
+        if !dirs[0].is_dir() {
+            return Err(anyhow!("Profile '{}' does not exist", name));
+        }
+        for dir in dirs.iter().filter(|dir| dir.is_dir()) {
+            std::fs::remove_dir_all(dir)?;
+        }
+        Ok(())
+    }
+
+    /// Profile names become directory names, so keep them to a safe character set
+    fn validate_name(name: &str) -> Result<()> {
+        if name.is_empty() || name.len() > 64 {
+            return Err(anyhow!("Profile name must be 1-64 characters"));
+        }
+        if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(anyhow!(
+                "Invalid profile name '{}': use letters, digits, '-' or '_'",
+                name
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn manager(dir: &TempDir) -> ProfileManager {
+        ProfileManager::new(BaseDirs { config: dir.path().join("config"), state: dir.path().join("state") })
+    }
+
+    #[test]
+    fn names_that_could_leave_the_profiles_directory_are_rejected() {
+        let dir = TempDir::new().unwrap();
+        let profiles = manager(&dir);
+        for name in ["../config", "work/../..", "a/b", "a\\b", "..", ".", "", &"x".repeat(65)] {
+            assert!(profiles.create(name).is_err(), "create {name:?}");
+            assert!(profiles.delete(name, None).is_err(), "delete {name:?}");
+            assert!(profiles.profile_dir(Some(name)).is_err(), "profile_dir {name:?}");
+        }
+        // Nothing was created, in the base directories or next to them
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn creating_an_existing_profile_fails_and_keeps_its_data() {
+        let dir = TempDir::new().unwrap();
+        let profiles = manager(&dir);
+        let config = profiles.create("work").unwrap();
+        assert_eq!(config, dir.path().join("config").join(PROFILES_DIR).join("work"));
+        assert!(dir.path().join("state").join(PROFILES_DIR).join("work").is_dir());
+        std::fs::write(config.join("config.json"), "{}").unwrap();
+
+        let error = profiles.create("work").unwrap_err();
+        assert!(error.to_string().contains("already exists"), "{error}");
+        assert!(config.join("config.json").exists());
+        assert!(profiles.create(DEFAULT_PROFILE).is_err());
+        assert_eq!(profiles.list().unwrap(), [DEFAULT_PROFILE, "work"]);
+        assert_eq!(profiles.profile_dir(Some("work")).unwrap().config, config);
+        assert!(profiles.profile_dir(Some("home")).is_err());
+    }
+
+    #[test]
+    fn the_active_and_default_profiles_cannot_be_deleted() {
+        let dir = TempDir::new().unwrap();
+        let profiles = manager(&dir);
+        let config = profiles.create("work").unwrap();
+
+        let error = profiles.delete("work", Some("work")).unwrap_err();
+        assert!(error.to_string().contains("in use"), "{error}");
+        assert!(config.is_dir());
+        assert!(profiles.delete(DEFAULT_PROFILE, None).is_err());
+        assert!(dir.path().join("config").is_dir());
+
+        // From another profile it goes, with its state
+        profiles.delete("work", Some("home")).unwrap();
+        assert!(!config.exists());
+        assert!(!dir.path().join("state").join(PROFILES_DIR).join("work").exists());
+        assert_eq!(profiles.list().unwrap(), [DEFAULT_PROFILE]);
+        assert!(profiles.delete("work", None).unwrap_err().to_string().contains("does not exist"));
+    }
+}
@@ -0,0 +1,109 @@
+use super::file_monitor::UsageEntry;
+use crate::models::RateLimit;
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use std::collections::BTreeMap;
+
+/// Minutes of history searched for the busiest minute
+pub const PEAK_WINDOW_MINUTES: i64 = 60;
+
+/// Requests and rate-limited tokens within one minute
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MinuteUsage {
+    pub requests: u32,
+    pub input_tokens: u64, // uncached input and cache writes, which count against input limits
+    pub output_tokens: u64,
+}
+
+impl MinuteUsage {
+    fn add(&mut self, entry: &UsageEntry) {
+        self.requests += 1;
+        self.input_tokens += entry.usage.input_tokens as u64 + entry.usage.cache_creation_tokens() as u64;
+        self.output_tokens += entry.usage.output_tokens as u64;
+    }
+
+    /// The highest share of any configured limit this minute used, 1.0 at the limit
+    pub fn utilization(&self, limit: &RateLimit) -> f64 {
+        [
+            (self.requests as u64, limit.requests_per_minute),
+            (self.input_tokens, limit.input_tokens_per_minute),
+            (self.output_tokens, limit.output_tokens_per_minute),
+        ]
+        .into_iter()
+        .filter_map(|(used, limit)| limit.map(|limit| used as f64 / limit.max(1) as f64))
+        .fold(0.0, f64::max)
+    }
+}
+
+/// Recent request cadence of the models matching one configured rate limit
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitUsage {
+    pub model: String, // the configured model name or part of it
+    pub limit: RateLimit,
+    pub last_minute: MinuteUsage, // the 60 seconds up to now
+    pub peak_minute: MinuteUsage, // the busiest clock minute of the last hour
+}
+
+impl RateLimitUsage {
+    pub fn utilization(&self) -> f64 {
+        self.last_minute.utilization(&self.limit)
+    }
+
+    pub fn peak_utilization(&self) -> f64 {
+        self.peak_minute.utilization(&self.limit)
+    }
+}
+
+/// The configured limit an entry's model counts against: the longest configured name its model
+/// contains, ignoring case
+fn matching_limit<'a>(limits: &'a BTreeMap<String, RateLimit>, model: &str) -> Option<&'a str> {
+    let model = model.to_lowercase();
+    limits
+        .keys()
+        .filter(|name| model.contains(&name.to_lowercase()))
+        .max_by_key(|name| name.len())
+        .map(String::as_str)
+}
+
+/// Usage of each configured rate limit over the minute and hour before `now`, in the order of
+/// `limits`. Limits apply per model, so entries of models no limit names are skipped.
+pub fn rate_limit_usage<'a>(
+    entries: impl IntoIterator<Item = &'a UsageEntry>,
+    limits: &BTreeMap<String, RateLimit>,
+    now: DateTime<Utc>,
+    default_model: Option<&str>,
+) -> Vec<RateLimitUsage> {
+    let since = now - Duration::minutes(PEAK_WINDOW_MINUTES);
+    let mut last_minute: BTreeMap<&str, MinuteUsage> = BTreeMap::new();
+    let mut minutes: BTreeMap<(&str, DateTime<Utc>), MinuteUsage> = BTreeMap::new();
+    for entry in entries {
+        if entry.timestamp < since || entry.timestamp > now {
+            continue;
+        }
+        let model = entry.model.as_deref().or(default_model).unwrap_or_default();
+        let Some(name) = matching_limit(limits, model) else {
+            continue;
+        };
+        if entry.timestamp > now - Duration::minutes(1) {
+            last_minute.entry(name).or_default().add(entry);
+        }
+        let minute = entry.timestamp.duration_trunc(Duration::minutes(1)).unwrap_or(entry.timestamp);
+        minutes.entry((name, minute)).or_default().add(entry);
+    }
+
+    limits
+        .iter()
+        .map(|(name, limit)| {
+            let peak_minute = minutes
+                .range((name.as_str(), DateTime::<Utc>::MIN_UTC)..=(name.as_str(), DateTime::<Utc>::MAX_UTC))
+                .map(|(_, usage)| *usage)
+                .max_by(|a, b| a.utilization(limit).total_cmp(&b.utilization(limit)))
+                .unwrap_or_default();
+            RateLimitUsage {
+                model: name.clone(),
+                limit: *limit,
+                last_minute: last_minute.get(name.as_str()).copied().unwrap_or_default(),
+                peak_minute,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,189 @@
+use super::file_monitor::UsageEntry;
+use super::pricing::ModelPricing;
+use crate::models::UsageMetrics;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::BTreeMap;
+
+/// How far back usage is inspected for model and cache patterns
+pub const ANALYSIS_DAYS: i64 = 7;
+
+// Below these volumes the ratios are too noisy to act on
+const MIN_OPUS_CALLS: usize = 20;
+const MIN_CACHEABLE_INPUT: u64 = 100_000;
+
+const SMALL_OUTPUT_TOKENS: u32 = 500; // replies this short rarely need Opus
+const SMALL_OPUS_SHARE: f64 = 0.5;
+const LOW_CACHE_HIT_RATE: f64 = 0.3;
+const BURST_MINUTES: i64 = 5;
+const BURST_LIMIT_SHARE: f64 = 0.2; // a burst using this much of the window limit is worth flagging
+
+/// How urgently a recommendation should be acted on, most urgent first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    High,
+    Medium,
+    Low,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recommendation {
+    pub priority: Priority,
+    pub message: String,
+}
+
+impl Recommendation {
+    fn new(priority: Priority, message: String) -> Self {
+        Self { priority, message }
+    }
+}
+
+/// Suggestions drawn from the current session's pacing and the last `ANALYSIS_DAYS` of usage,
+/// most urgent first. Returns nothing when no pattern stands out.
+pub fn recommend(entries: &[UsageEntry], metrics: &UsageMetrics, now: DateTime<Utc>) -> Vec<Recommendation> {
+    let since = now - Duration::days(ANALYSIS_DAYS);
+    let recent: Vec<&UsageEntry> = entries.iter().filter(|entry| entry.timestamp >= since).collect();
+
+    let mut recommendations: Vec<Recommendation> = [
+        session_pacing(metrics, now),
+        burst(&recent, metrics),
+        model_mix(&recent),
+        cache_usage(&recent),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    recommendations.sort_by_key(|recommendation| recommendation.priority);
+    recommendations
+}
+
+/// Whether the current rate runs out before the window resets, and what rate would last
+fn session_pacing(metrics: &UsageMetrics, now: DateTime<Utc>) -> Option<Recommendation> {
+    let session = &metrics.current_session;
+    let depletion = metrics.projected_depletion?;
+    let reset = session.reset_time;
+    if reset <= now {
+        return None;
+    }
+
+    if depletion < reset {
+        let early = reset - depletion;
+        let remaining = session.tokens_limit.saturating_sub(session.tokens_used) as f64;
+        let sustainable = remaining / (reset - now).num_minutes().max(1) as f64;
+        return Some(Recommendation::new(
+            Priority::High,
+            format!(
+                "At {:.0} tokens/min the limit is reached {}h {:02}m before the {} reset; about {:.0} tokens/min would last",
+                metrics.usage_rate,
+                early.num_hours(),
+                early.num_minutes() % 60,
+                reset.format("%H:%M UTC"),
+                sustainable
+            ),
+        ));
+    }
+
+    let used = session.tokens_used as f64 / session.tokens_limit.max(1) as f64;
+    if metrics.session_progress > 0.8 && used < 0.5 {
+        return Some(Recommendation::new(
+            Priority::Low,
+            format!(
+                "Only {:.0}% of this window is used with {}m left: room for a larger task before the reset",
+                used * 100.0,
+                (reset - now).num_minutes()
+            ),
+        ));
+    }
+    None
+}
+
+/// The busiest few minutes of the current session, when they took a large bite of the limit
+fn burst(recent: &[&UsageEntry], metrics: &UsageMetrics) -> Option<Recommendation> {
+    let session = &metrics.current_session;
+    let mut buckets: BTreeMap<i64, u64> = BTreeMap::new();
+    for entry in recent.iter().filter(|entry| entry.timestamp >= session.start_time) {
+        let bucket = entry.timestamp.timestamp() / (BURST_MINUTES * 60);
+        *buckets.entry(bucket).or_default() += entry.usage.total_tokens() as u64;
+    }
+    let (bucket, tokens) = buckets.into_iter().max_by_key(|(_, tokens)| *tokens)?;
+    let share = tokens as f64 / session.tokens_limit.max(1) as f64;
+    if share < BURST_LIMIT_SHARE {
+        return None;
+    }
+    let start = DateTime::from_timestamp(bucket * BURST_MINUTES * 60, 0)?;
+    Some(Recommendation::new(
+        Priority::Medium,
+        format!(
+            "{:.0}% of the window limit went in {BURST_MINUTES} minutes at {}; splitting large tasks keeps headroom",
+            share * 100.0,
+            start.format("%H:%M UTC")
+        ),
+    ))
+}
+
+/// Opus calls with short replies that Sonnet could likely have handled
+fn model_mix(recent: &[&UsageEntry]) -> Option<Recommendation> {
+    let opus: Vec<&&UsageEntry> = recent
+        .iter()
+        .filter(|entry| entry.model.as_deref().is_some_and(|model| model.to_lowercase().contains("opus")))
+        .collect();
+    if opus.len() < MIN_OPUS_CALLS {
+        return None;
+    }
+    let small: Vec<&&&UsageEntry> = opus
+        .iter()
+        .filter(|entry| entry.usage.output_tokens < SMALL_OUTPUT_TOKENS)
+        .collect();
+    let share = small.len() as f64 / opus.len() as f64;
+    if share < SMALL_OPUS_SHARE {
+        return None;
+    }
+    let savings: f64 = small
+        .iter()
+        .map(|entry| ModelPricing::OPUS.cost(&entry.usage) - ModelPricing::SONNET.cost(&entry.usage))
+        .sum();
+    Some(Recommendation::new(
+        Priority::Medium,
+        format!(
+            "{:.0}% of Opus calls in the last {ANALYSIS_DAYS} days were under {SMALL_OUTPUT_TOKENS} output tokens; \
+             Sonnet would have cost about ${savings:.2} less at API rates",
+            share * 100.0
+        ),
+    ))
+}
+
+/// Input served from cache, and cache writes that were never read back
+fn cache_usage(recent: &[&UsageEntry]) -> Option<Recommendation> {
+    let (mut fresh, mut written, mut read) = (0u64, 0u64, 0u64);
+    for entry in recent {
+        fresh += entry.usage.input_tokens as u64;
+        written += entry.usage.cache_creation_tokens() as u64;
+        read += entry.usage.cache_read_tokens() as u64;
+    }
+    let input = fresh + written + read;
+    if input < MIN_CACHEABLE_INPUT {
+        return None;
+    }
+
+    let hit_rate = read as f64 / input as f64;
+    if written > read {
+        return Some(Recommendation::new(
+            Priority::Medium,
+            format!(
+                "More input was written to the cache than read back ({:.1}x) in the last {ANALYSIS_DAYS} days; \
+                 continuing related work in one session reuses cached context instead of paying to rewrite it",
+                written as f64 / read.max(1) as f64
+            ),
+        ));
+    }
+    if hit_rate < LOW_CACHE_HIT_RATE {
+        return Some(Recommendation::new(
+            Priority::Low,
+            format!(
+                "Only {:.0}% of input came from the cache in the last {ANALYSIS_DAYS} days; \
+                 long-lived sessions and stable prompts raise the hit rate",
+                hit_rate * 100.0
+            ),
+        ));
+    }
+    None
+}
@@ -0,0 +1,151 @@
+use crate::models::{AccountIdentity, UserConfig, UsageMetrics};
+use super::alerts::AlertManager;
+use super::encryption::StateFile;
+use super::events::EventLog;
+use super::file_monitor::FileBasedTokenMonitor;
+use super::forecast::{self, SpendForecast};
+use super::metrics_history::MetricsHistory;
+use super::mqtt::MqttPublisher;
+use super::rrd::RrdStore;
+use super::usage_stats;
+use chrono::{DateTime, Utc};
+
+/// What both terminal UIs do after a refresh: record the metrics to the persistent history,
+/// trend store and event log, publish them over MQTT, and check and send alerts
+pub struct UsageRecorder {
+    alerts: AlertManager,
+    metrics_history: Option<(StateFile, MetricsHistory)>, // where snapshots are saved, and those kept so far
+    trend_store: Option<(StateFile, RrdStore)>,           // round-robin burn rate and token archives
+    event_log: Option<(StateFile, EventLog)>,              // plan switches and alerts seen so far
+    mqtt: Option<MqttPublisher>,
+}
+
+impl UsageRecorder {
+    pub fn new(config: &UserConfig) -> Self {
+        Self {
+            alerts: AlertManager::new(config),
+            metrics_history: None,
+            trend_store: None,
+            event_log: None,
+            mqtt: None,
+        }
+    }
+
+    /// Record a snapshot of the metrics to the history in `file` on every rescan
+    pub fn load_metrics_history(&mut self, file: StateFile) {
+        let history = MetricsHistory::load(&file).unwrap_or_else(|e| {
+            log::warn!("Starting a new metrics history: {e}");
+            MetricsHistory::default()
+        });
+        self.metrics_history = Some((file, history));
+    }
+
+    /// Feed the round-robin store in `file` on every rescan
+    pub fn load_trend_store(&mut self, file: StateFile) {
+        let store = RrdStore::load(&file).unwrap_or_else(|e| {
+            log::warn!("Starting a new trend store: {e}");
+            RrdStore::default()
+        });
+        self.trend_store = Some((file, store));
+    }
+
+    /// Record plan switches and fired alerts to the event log in `file`
+    pub fn load_event_log(&mut self, file: StateFile) {
+        let event_log = EventLog::load(&file).unwrap_or_else(|e| {
+            log::warn!("Starting a new event log: {e}");
+            EventLog::default()
+        });
+        self.alerts.set_weekly_summary_sent(event_log.weekly_summary);
+        self.event_log = Some((file, event_log));
+    }
+
+    /// Publish the usage to an MQTT broker on every refresh
+    pub fn set_mqtt(&mut self, publisher: Option<MqttPublisher>) {
+        self.mqtt = publisher;
+    }
+
+    /// Signed-in account named in alert notifications
+    pub fn set_identity(&mut self, identity: Option<AccountIdentity>) {
+        self.alerts.set_identity(identity);
+    }
+
+    pub fn metrics_history(&self) -> Option<&MetricsHistory> {
+        self.metrics_history.as_ref().map(|(_, history)| history)
+    }
+
+    pub fn trend_store(&self) -> Option<&RrdStore> {
+        self.trend_store.as_ref().map(|(_, store)| store)
+    }
+
+    pub fn event_log(&self) -> Option<&EventLog> {
+        self.event_log.as_ref().map(|(_, event_log)| event_log)
+    }
+
+    /// Add the metrics to the persistent history and trend store, saving each once per new minute,
+    /// note plan switches in the event log, and publish the metrics over MQTT
+    pub fn record(&mut self, metrics: &UsageMetrics, monitor: &FileBasedTokenMonitor, now: DateTime<Utc>) {
+        if let Some(mqtt) = self.mqtt.as_mut() {
+            mqtt.publish(metrics, monitor, now);
+        }
+        if let Some((file, history)) = &mut self.metrics_history {
+            if history.record(metrics, now) {
+                if let Err(e) = history.save(file) {
+                    log::warn!("Failed to save metrics history: {e}");
+                }
+            }
+        }
+        if let Some((file, store)) = &mut self.trend_store {
+            let cumulative_tokens = monitor.usage_entries().iter().map(|entry| entry.usage.total_tokens() as u64).sum();
+            if store.update(now, metrics.usage_rate, cumulative_tokens) {
+                if let Err(e) = store.save(file) {
+                    log::warn!("Failed to save trend store: {e}");
+                }
+            }
+        }
+        self.update_event_log(|event_log| event_log.observe_plan(&metrics.current_session.plan_type.name(), now));
+    }
+
+    /// Project the billing cycle's spend for the budget alert, when a monthly cost goal is set, and
+    /// summarize last week for the weekly summary alert, when it is on. Both cover every source
+    /// the reports include; the projection is returned for display.
+    pub fn update_forecasts(&mut self, config: &UserConfig, monitor: &FileBasedTokenMonitor, now: DateTime<Utc>) -> Option<SpendForecast> {
+        let spend_forecast = config.goals.monthly_cost.map(|_| {
+            forecast::forecast_cycle(monitor.report_entries(), config.goals.billing_day(), now, monitor.prices())
+        });
+        self.alerts.set_spend_forecast(spend_forecast);
+        self.alerts.set_weekly_summary(config.alerts.weekly_summary.then(|| {
+            usage_stats::weekly_summary(
+                monitor.report_entries(),
+                monitor.limit_events(),
+                usage_stats::week_start(now.date_naive()) - chrono::Days::new(7),
+                |project| monitor.project_label(project),
+                monitor.prices(),
+            )
+        }));
+        spend_forecast
+    }
+
+    /// Send threshold, limit and script alerts that started firing since the last check, and add
+    /// them to the event log
+    pub fn check_alerts(&mut self, metrics: &UsageMetrics, script_alerts: &[String]) {
+        let alerts = self.alerts.check(metrics, script_alerts);
+        self.alerts.dispatch(&alerts);
+        if !alerts.is_empty() {
+            self.update_event_log(|event_log| {
+                event_log.record_alerts(&alerts);
+                true
+            });
+        }
+    }
+
+    /// Apply `update` to the event log, when one is kept, and save it when `update` returns true
+    fn update_event_log(&mut self, update: impl FnOnce(&mut EventLog) -> bool) {
+        if let Some((file, event_log)) = &mut self.event_log {
+            if update(event_log) {
+                if let Err(e) = event_log.save(file) {
+                    log::warn!("Failed to save event log: {e}");
+                }
+            }
+        }
+    }
+}
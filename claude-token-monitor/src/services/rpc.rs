@@ -0,0 +1,202 @@
+use super::file_monitor::FileBasedTokenMonitor;
+use super::output_schema::{HistoryOutput, HistorySession, MetricsOutput};
+use super::session_tracker::SessionTracker;
+use super::SessionService;
+use crate::models::AccountIdentity;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Method of the notification sent to subscribers when the metrics change
+pub const UPDATE_NOTIFICATION: &str = "metrics.updated";
+
+// Standard JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+
+// Sessions `history` returns without a `limit`
+const DEFAULT_HISTORY_LIMIT: usize = 10;
+// Fields that drift with the clock alone, so they don't trigger updates by themselves
+const DRIFTING_FIELDS: [&str; 2] = ["tokens_per_minute", "projected_depletion"];
+
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+/// JSON-RPC 2.0 backend for editor plugins, one message per line.
+///
+/// Methods: `metrics` (current usage, null without data), `history` (`{"limit": n}`, the
+/// `history --output json` document), `subscribe` (returns the metrics, then sends
+/// `metrics.updated` notifications whenever the usage changes) and `unsubscribe`.
+pub struct RpcServer {
+    monitor: FileBasedTokenMonitor,
+    sessions: SessionTracker,
+    identity: Option<AccountIdentity>,
+    refresh_interval: Duration,
+    last_scan: Option<Instant>,
+    subscribed: bool,
+    last_notified: Option<Value>, // metrics subscribers last got, without the drifting fields
+}
+
+impl RpcServer {
+    /// Serve usage from `monitor` and session history from `sessions`, rescanning the usage files
+    /// at most once per `refresh_interval`
+    pub fn new(monitor: FileBasedTokenMonitor, sessions: SessionTracker, refresh_interval: Duration) -> Self {
+        let identity = monitor.subscription_hints().identity();
+        Self {
+            monitor,
+            sessions,
+            identity,
+            refresh_interval,
+            last_scan: None,
+            subscribed: false,
+            last_notified: None,
+        }
+    }
+
+    /// Answer one line of input; None for notifications, which get no response
+    pub async fn handle_line(&mut self, line: &str) -> Option<String> {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => return Some(error_response(Value::Null, RpcError::new(PARSE_ERROR, format!("Parse error: {e}")))),
+        };
+        let id = request.get("id").cloned();
+        let method = match (request.get("jsonrpc").and_then(Value::as_str), request.get("method").and_then(Value::as_str)) {
+            (Some("2.0"), Some(method)) => method.to_string(),
+            _ => {
+                let message = if request.is_array() { "Batches are not supported" } else { "Not a JSON-RPC 2.0 request" };
+                return Some(error_response(id.unwrap_or(Value::Null), RpcError::new(INVALID_REQUEST, message)));
+            }
+        };
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+        let result = self.call(&method, &params).await;
+        let id = id?;
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }).to_string(),
+            Err(error) => error_response(id, error),
+        })
+    }
+
+    /// For subscribers, rescan when due and return the update notification when the metrics changed
+    pub async fn poll_update(&mut self) -> Option<String> {
+        if !self.subscribed {
+            return None;
+        }
+        let metrics = match self.current_metrics().await {
+            Ok(metrics) => metrics,
+            Err(e) => {
+                log::warn!("Failed to rescan usage files: {e}");
+                return None;
+            }
+        };
+        let compared = without_drifting_fields(&metrics);
+        if self.last_notified.as_ref() == Some(&compared) {
+            return None;
+        }
+        self.last_notified = Some(compared);
+        Some(json!({ "jsonrpc": "2.0", "method": UPDATE_NOTIFICATION, "params": metrics }).to_string())
+    }
+
+    async fn call(&mut self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        let internal = |e: anyhow::Error| RpcError::new(INTERNAL_ERROR, e.to_string());
+        match method {
+            "metrics" => self.current_metrics().await.map_err(internal),
+            "history" => {
+                let limit = match params.get("limit").or_else(|| params.get(0)) {
+                    None | Some(Value::Null) => DEFAULT_HISTORY_LIMIT,
+                    Some(limit) => limit
+                        .as_u64()
+                        .map(|limit| limit as usize)
+                        .ok_or_else(|| RpcError::new(INVALID_PARAMS, "limit must be a non-negative integer"))?,
+                };
+                self.sessions.update_observed_sessions().await.map_err(internal)?;
+                let sessions = self.sessions.get_session_history(limit).await.map_err(internal)?;
+                let sessions = sessions.into_iter().map(|session| HistorySession::new(session, Some(&self.monitor))).collect();
+                let history = HistoryOutput { sessions, identity: self.identity.clone() };
+                serde_json::to_value(history).map_err(|e| internal(e.into()))
+            }
+            "subscribe" => {
+                let metrics = self.current_metrics().await.map_err(internal)?;
+                self.subscribed = true;
+                self.last_notified = Some(without_drifting_fields(&metrics));
+                Ok(metrics)
+            }
+            "unsubscribe" => {
+                self.subscribed = false;
+                self.last_notified = None;
+                Ok(Value::Bool(true))
+            }
+            _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("Method not found: {method}"))),
+        }
+    }
+
+    /// The metrics as sent to clients, rescanning the usage files when the last scan is older than
+    /// the refresh interval
+    async fn current_metrics(&mut self) -> Result<Value> {
+        if self.last_scan.is_none_or(|scanned| scanned.elapsed() >= self.refresh_interval) {
+            self.monitor.scan_usage_files().await?;
+            self.last_scan = Some(Instant::now());
+        }
+        let metrics = self
+            .monitor
+            .calculate_metrics()
+            .map(|metrics| MetricsOutput::new(&metrics, self.identity.clone()));
+        Ok(serde_json::to_value(metrics)?)
+    }
+}
+
+fn without_drifting_fields(metrics: &Value) -> Value {
+    let mut metrics = metrics.clone();
+    if let Some(fields) = metrics.as_object_mut() {
+        for field in DRIFTING_FIELDS {
+            fields.remove(field);
+        }
+    }
+    metrics
+}
+
+fn error_response(id: Value, error: RpcError) -> String {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": error.code, "message": error.message } }).to_string()
+}
+
+/// Answer requests read from `input` on `output` until the input ends, sending subscribers
+/// updates every `poll_interval`
+pub async fn serve(
+    mut server: RpcServer,
+    input: impl AsyncBufRead + Unpin,
+    mut output: impl AsyncWrite + Unpin,
+    poll_interval: Duration,
+) -> Result<()> {
+    let mut lines = input.lines();
+    let mut ticker = tokio::time::interval(poll_interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        let message = tokio::select! {
+            line = lines.next_line() => match line? {
+                Some(line) if line.trim().is_empty() => None,
+                Some(line) => server.handle_line(&line).await,
+                None => break,
+            },
+            _ = ticker.tick() => server.poll_update().await,
+        };
+        if let Some(message) = message {
+            output.write_all(message.as_bytes()).await?;
+            output.write_all(b"\n").await?;
+            output.flush().await?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,210 @@
+use super::encryption::StateFile;
+use super::metrics_history::{merge_mean, Resolution};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// File in the data dir holding the round-robin trend archives
+pub const RRD_FILE: &str = "metrics_rrd.json";
+
+/// Resolution and row count of every archive: a day of minutes, a month of hours, 13 months of days
+pub const ARCHIVES: [(Resolution, usize); 3] = [(Resolution::Minute, 24 * 60), (Resolution::Hour, 31 * 24), (Resolution::Day, 400)];
+
+/// How samples falling into the same row are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Consolidation {
+    Average,
+    Max,
+}
+
+/// One row: the bucket it holds (timestamp / step), its consolidated value and sample count
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+struct Row(i64, f64, u32);
+
+/// A fixed number of rows reused in a ring, so the archive never grows. Rows hold the same
+/// buckets as the metrics history's tiers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Archive {
+    #[serde(rename = "step_seconds")]
+    resolution: Resolution,
+    consolidation: Consolidation,
+    rows: Vec<Row>,
+}
+
+impl Archive {
+    pub fn new(resolution: Resolution, rows: usize, consolidation: Consolidation) -> Self {
+        Self {
+            resolution,
+            consolidation,
+            rows: vec![Row::default(); rows.max(1)],
+        }
+    }
+
+    fn bucket(&self, timestamp: DateTime<Utc>) -> i64 {
+        self.resolution.bucket(timestamp)
+    }
+
+    /// Consolidate `value` into the row for `timestamp`, overwriting whatever bucket the row held before.
+    ///
+    /// Returns true when the sample started a new row.
+    pub fn update(&mut self, timestamp: DateTime<Utc>, value: f64) -> bool {
+        let bucket = self.bucket(timestamp);
+        let index = bucket.rem_euclid(self.rows.len() as i64) as usize;
+        let row = &mut self.rows[index];
+        if row.0 == bucket && row.2 > 0 {
+            row.1 = match self.consolidation {
+                Consolidation::Average => merge_mean(row.1, row.2, value, 1),
+                Consolidation::Max => row.1.max(value),
+            };
+            row.2 += 1;
+            false
+        } else if row.0 < bucket || row.2 == 0 {
+            *row = Row(bucket, value, 1);
+            true
+        } else {
+            false // older than what the row holds (clock change), already overwritten
+        }
+    }
+
+    /// Every bucket the archive spans up to the one containing `end`, oldest first,
+    /// with None where nothing was recorded
+    pub fn series(&self, end: DateTime<Utc>) -> Vec<(DateTime<Utc>, Option<f64>)> {
+        let last = self.bucket(end);
+        let count = self.rows.len() as i64;
+        (last - count + 1..=last)
+            .map(|bucket| {
+                let row = self.rows[bucket.rem_euclid(count) as usize];
+                let start = self.resolution.bucket_time(bucket).unwrap_or(end);
+                (start, (row.0 == bucket && row.2 > 0).then_some(row.1))
+            })
+            .collect()
+    }
+
+    /// The value recorded for the bucket containing `timestamp`, if it is still held
+    pub fn value_at(&self, timestamp: DateTime<Utc>) -> Option<f64> {
+        let bucket = self.bucket(timestamp);
+        let row = self.rows[bucket.rem_euclid(self.rows.len() as i64) as usize];
+        (row.0 == bucket && row.2 > 0).then_some(row.1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.iter().all(|row| row.2 == 0)
+    }
+
+    fn matches(&self, other: &Archive) -> bool {
+        self.resolution == other.resolution && self.consolidation == other.consolidation && self.rows.len() == other.rows.len()
+    }
+}
+
+/// One metric kept in an average and a max archive at every resolution of `ARCHIVES`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Series {
+    archives: Vec<Archive>,
+}
+
+impl Default for Series {
+    fn default() -> Self {
+        let archives = ARCHIVES
+            .iter()
+            .flat_map(|(resolution, rows)| {
+                [Consolidation::Average, Consolidation::Max].map(|consolidation| Archive::new(*resolution, *rows, consolidation))
+            })
+            .collect();
+        Self { archives }
+    }
+}
+
+impl Series {
+    /// Returns true when the sample started a new row in the finest archive
+    pub fn update(&mut self, timestamp: DateTime<Utc>, value: f64) -> bool {
+        let mut new_row = false;
+        for archive in &mut self.archives {
+            let started = archive.update(timestamp, value);
+            if archive.resolution == Resolution::Minute {
+                new_row |= started;
+            }
+        }
+        new_row
+    }
+
+    pub fn archive(&self, resolution: Resolution, consolidation: Consolidation) -> Option<&Archive> {
+        self.archives
+            .iter()
+            .find(|archive| archive.resolution == resolution && archive.consolidation == consolidation)
+    }
+
+    fn matches(&self, other: &Series) -> bool {
+        self.archives.len() == other.archives.len() && self.archives.iter().zip(&other.archives).all(|(a, b)| a.matches(b))
+    }
+}
+
+/// Round-robin archives of the burn rate and of all-time cumulative tokens.
+///
+/// Disk use is fixed by `ARCHIVES` no matter how long the monitor runs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RrdStore {
+    pub burn_rate: Series,         // tokens per minute
+    pub cumulative_tokens: Series, // tokens in all usage files seen
+}
+
+impl RrdStore {
+    /// Load the store, starting empty when none has been saved yet
+    pub fn load(file: &StateFile) -> Result<Self> {
+        let Some(content) = file.read()? else {
+            return Ok(Self::default());
+        };
+        let path = file.path();
+        let store: RrdStore =
+            serde_json::from_str(&content).with_context(|| format!("{} is not a valid trend store", path.display()))?;
+        let layout = Self::default();
+        if !store.burn_rate.matches(&layout.burn_rate) || !store.cumulative_tokens.matches(&layout.cumulative_tokens) {
+            return Err(anyhow!("{} was written with a different archive layout", path.display()));
+        }
+        Ok(store)
+    }
+
+    /// Replace the saved store; concurrent readers see either the old or the new file
+    pub fn save(&self, file: &StateFile) -> Result<()> {
+        file.write(&serde_json::to_string(self)?)
+    }
+
+    /// Returns true when the sample started a new minute, a natural point to save
+    pub fn update(&mut self, timestamp: DateTime<Utc>, burn_rate: f64, cumulative_tokens: u64) -> bool {
+        let new_minute = self.burn_rate.update(timestamp, burn_rate);
+        self.cumulative_tokens.update(timestamp, cumulative_tokens as f64) || new_minute
+    }
+
+    /// Tokens added per day over the daily archive, from the growth of the cumulative count.
+    ///
+    /// Days without samples on both ends, or where the count fell because usage files were
+    /// removed, are None.
+    pub fn daily_tokens(&self, end: DateTime<Utc>) -> Vec<(DateTime<Utc>, Option<u64>)> {
+        let Some(archive) = self.cumulative_tokens.archive(Resolution::Day, Consolidation::Max) else {
+            return Vec::new();
+        };
+        let series = archive.series(end);
+        series
+            .windows(2)
+            .map(|pair| {
+                let growth = match (pair[0].1, pair[1].1) {
+                    (Some(before), Some(after)) if after >= before => Some((after - before) as u64),
+                    _ => None,
+                };
+                (pair[1].0, growth)
+            })
+            .collect()
+    }
+
+    /// Tokens added on the UTC day of `timestamp` up to the end of its hour: the cumulative count
+    /// then, less the last count of the day before. None without samples at both points.
+    pub fn day_tokens_until(&self, timestamp: DateTime<Utc>) -> Option<u64> {
+        let hour = self.cumulative_tokens.archive(Resolution::Hour, Consolidation::Max)?.value_at(timestamp)?;
+        let previous_day = timestamp - Resolution::Day.step();
+        let day_start = self.cumulative_tokens.archive(Resolution::Day, Consolidation::Max)?.value_at(previous_day)?;
+        (hour >= day_start).then_some((hour - day_start) as u64)
+    }
+}
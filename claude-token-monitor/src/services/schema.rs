@@ -0,0 +1,146 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+/// A known layout of usage-bearing lines in Claude's JSONL logs
+#[derive(Debug, PartialEq)]
+pub struct SchemaDescriptor {
+    pub id: &'static str,
+    pub description: &'static str,
+    pub usage_path: &'static [&'static str], // where the usage object lives in a line
+}
+
+/// Claude Code transcripts: assistant lines with `message.usage` and a `requestId`
+pub const CLAUDE_CODE_V1: SchemaDescriptor = SchemaDescriptor {
+    id: "claude-code-v1",
+    description: "Claude Code transcript (message.usage)",
+    usage_path: &["message", "usage"],
+};
+
+/// Older/flat exports with a top-level `usage` object
+pub const FLAT_USAGE_V0: SchemaDescriptor = SchemaDescriptor {
+    id: "flat-usage-v0",
+    description: "Flat usage record (top-level usage)",
+    usage_path: &["usage"],
+};
+
+/// Every usage schema the parser understands, newest first
+pub const SCHEMAS: &[&SchemaDescriptor] = &[&CLAUDE_CODE_V1, &FLAT_USAGE_V0];
+
+/// Line types Claude Code writes that never carry usage
+const NON_USAGE_TYPES: &[&str] = &["user", "summary", "system", "file-history-snapshot", "queue-operation"];
+
+/// How a single JSONL line was recognised
+#[derive(Debug, PartialEq)]
+pub enum LineClass {
+    Usage(&'static SchemaDescriptor),
+    NonUsage, // a known line type without usage (user prompts, summaries, ...)
+    Unknown,  // looks like it should matter but matches no schema
+}
+
+impl SchemaDescriptor {
+    pub fn usage<'a>(&self, json: &'a Value) -> Option<&'a Value> {
+        self.usage_path
+            .iter()
+            .try_fold(json, |value, key| value.get(key))
+            .filter(|usage| usage.is_object())
+    }
+}
+
+/// Classify a parsed line against the schema registry
+pub fn classify_line(json: &Value) -> LineClass {
+    let usage_schema = SCHEMAS.iter().copied().find(|schema| schema.usage(json).is_some());
+    let synthetic = json.get("message").and_then(|m| m.get("model")).and_then(|v| v.as_str()) == Some("<synthetic>")
+        || json.get("isApiErrorMessage").and_then(|v| v.as_bool()) == Some(true);
+    classify(json.get("type").and_then(|v| v.as_str()), usage_schema, synthetic)
+}
+
+/// Classify a line from its type, the schema of the usage object it carries, if any, and whether
+/// it is a synthetic message or an API error
+pub fn classify(line_type: Option<&str>, usage_schema: Option<&'static SchemaDescriptor>, synthetic: bool) -> LineClass {
+    if let Some(schema) = usage_schema {
+        return LineClass::Usage(schema);
+    }
+
+    match line_type {
+        Some(line_type) if NON_USAGE_TYPES.contains(&line_type) => LineClass::NonUsage,
+        // Synthetic assistant messages (limit notices, API errors) never carry usage
+        Some("assistant") if synthetic => LineClass::NonUsage,
+        _ => LineClass::Unknown,
+    }
+}
+
+// Keep reports small even for files full of unknown lines
+const MAX_REPORTED_VERSIONS: usize = 8;
+const MAX_UNKNOWN_SAMPLES: usize = 8;
+
+/// Per-file result of schema detection during a scan
+#[derive(Debug, Clone, Default)]
+pub struct FileSchemaReport {
+    pub path: PathBuf,
+    pub usage_lines: BTreeMap<&'static str, usize>, // schema id -> lines
+    pub non_usage_lines: usize,
+    pub unknown_lines: usize,
+    pub malformed_usage_lines: usize, // matched a schema but a required field was unusable
+    pub future_entries: usize,        // usage dropped for a timestamp ahead of the clock
+    pub earliest_future: Option<DateTime<Utc>>, // of those, the one whose time comes first
+    pub oversized_entries: usize,     // usage dropped for more tokens than max_request_tokens
+    pub clamped_timings: usize,       // usage kept without timings that can't be right
+    pub invalid_lines: usize,         // not JSON, too large or too deep
+    pub unknown_shapes: BTreeMap<String, usize>, // "type=<t> keys=<k1,k2>" -> lines
+    pub claude_versions: BTreeSet<String>,       // Claude Code versions seen in the file
+}
+
+impl FileSchemaReport {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            ..Self::default()
+        }
+    }
+
+    /// Schema most lines in this file matched, if any
+    pub fn detected_schema(&self) -> Option<&'static str> {
+        self.usage_lines
+            .iter()
+            .max_by_key(|(_, count)| **count)
+            .map(|(id, _)| *id)
+    }
+
+    /// Record one parsed line of Claude Code `version`, describing it with `shape` (see
+    /// `line_shape`) when it is unknown
+    pub fn record(&mut self, version: Option<&str>, class: &LineClass, shape: impl FnOnce() -> String) {
+        if let Some(version) = version {
+            if self.claude_versions.len() < MAX_REPORTED_VERSIONS && !self.claude_versions.contains(version) {
+                self.claude_versions.insert(version.to_string());
+            }
+        }
+
+        match class {
+            LineClass::Usage(schema) => *self.usage_lines.entry(schema.id).or_default() += 1,
+            LineClass::NonUsage => self.non_usage_lines += 1,
+            LineClass::Unknown => {
+                self.unknown_lines += 1;
+                let shape = shape();
+                if self.unknown_shapes.len() < MAX_UNKNOWN_SAMPLES || self.unknown_shapes.contains_key(&shape) {
+                    *self.unknown_shapes.entry(shape).or_default() += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Describe a line's structure without including any of its content
+pub fn line_shape(json: &Value) -> String {
+    let line_type = json.get("type").and_then(|v| v.as_str()).unwrap_or("-");
+    let keys = match json {
+        Value::Object(map) => {
+            let mut keys: Vec<&str> = map.keys().map(String::as_str).collect();
+            keys.sort_unstable();
+            keys.join(",")
+        }
+        _ => "(not an object)".to_string(),
+    };
+    format!("type={line_type} keys={keys}")
+}
@@ -0,0 +1,129 @@
+use crate::models::*;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use rhai::{Dynamic, Engine, Scope, AST};
+
+// Sandbox limits so a runaway script can't stall the refresh loop
+const MAX_OPERATIONS: u64 = 100_000;
+const MAX_EXPR_DEPTH: usize = 32;
+const MAX_STRING_SIZE: usize = 4096;
+
+/// Outcome of evaluating all user scripts once
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScriptResults {
+    pub metrics: Vec<(String, String)>, // derived metric name and formatted value
+    pub alerts: Vec<String>,            // names of alert conditions that are currently true
+    pub errors: Vec<String>,
+}
+
+/// Compiled user alert conditions and derived metrics
+pub struct ScriptEngine {
+    engine: Engine,
+    alerts: Vec<(String, AST)>,
+    metrics: Vec<(String, AST)>,
+}
+
+impl ScriptEngine {
+    /// Compile all configured scripts, failing on the first syntax error
+    pub fn new(config: &ScriptsConfig) -> Result<Self> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.set_max_expr_depths(MAX_EXPR_DEPTH, MAX_EXPR_DEPTH);
+        engine.set_max_string_size(MAX_STRING_SIZE);
+        // The terminal belongs to the UI, so script output goes to the log instead
+        engine.on_print(|text| log::info!("script: {text}"));
+        engine.on_debug(|text, _, _| log::debug!("script: {text}"));
+
+        let compile = |rules: &[ScriptRule], kind: &str| -> Result<Vec<(String, AST)>> {
+            rules
+                .iter()
+                .map(|rule| {
+                    engine
+                        .compile_expression(&rule.expression)
+                        .map(|ast| (rule.name.clone(), ast))
+                        .map_err(|e| anyhow!("Invalid {kind} script '{}': {e}", rule.name))
+                })
+                .collect()
+        };
+        let alerts = compile(&config.alerts, "alert")?;
+        let metrics = compile(&config.metrics, "metric")?;
+
+        Ok(Self { engine, alerts, metrics })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.alerts.is_empty() && self.metrics.is_empty()
+    }
+
+    /// Evaluate every script against the given metrics
+    pub fn evaluate(&self, metrics: &UsageMetrics) -> ScriptResults {
+        let mut results = ScriptResults::default();
+        let base_scope = Self::scope_for(metrics);
+
+        for (name, ast) in &self.metrics {
+            let mut scope = base_scope.clone();
+            match self.engine.eval_ast_with_scope::<Dynamic>(&mut scope, ast) {
+                Ok(value) => results.metrics.push((name.clone(), Self::format_value(&value))),
+                Err(e) => results.errors.push(format!("{name}: {e}")),
+            }
+        }
+
+        for (name, ast) in &self.alerts {
+            let mut scope = base_scope.clone();
+            match self.engine.eval_ast_with_scope::<Dynamic>(&mut scope, ast) {
+                Ok(value) => match value.as_bool() {
+                    Ok(true) => results.alerts.push(name.clone()),
+                    Ok(false) => {}
+                    Err(type_name) => results.errors.push(format!("{name}: expected a bool, got {type_name}")),
+                },
+                Err(e) => results.errors.push(format!("{name}: {e}")),
+            }
+        }
+
+        results
+    }
+
+    /// Variables available to scripts
+    fn scope_for(metrics: &UsageMetrics) -> Scope<'static> {
+        let session = &metrics.current_session;
+        let now = Utc::now();
+        let usage_pct = if session.tokens_limit > 0 {
+            session.tokens_used as f64 / session.tokens_limit as f64 * 100.0
+        } else {
+            0.0
+        };
+        let minutes_to_depletion = metrics
+            .projected_depletion
+            .map(|depletion| depletion.signed_duration_since(now).num_minutes())
+            .unwrap_or(-1);
+
+        let mut scope = Scope::new();
+        scope.push_constant("tokens_used", session.tokens_used as i64);
+        scope.push_constant("tokens_limit", session.tokens_limit as i64);
+        scope.push_constant("remaining_tokens", session.tokens_limit.saturating_sub(session.tokens_used) as i64);
+        scope.push_constant("usage_pct", usage_pct);
+        scope.push_constant("usage_rate", metrics.usage_rate);
+        scope.push_constant("session_progress", metrics.session_progress);
+        scope.push_constant("efficiency_score", metrics.efficiency_score);
+        scope.push_constant("cache_hit_rate", metrics.cache_hit_rate);
+        scope.push_constant("cache_creation_rate", metrics.cache_creation_rate);
+        scope.push_constant("input_output_ratio", metrics.input_output_ratio);
+        scope.push_constant("estimated_cost", metrics.estimated_cost);
+        scope.push_constant("minutes_to_reset", session.reset_time.signed_duration_since(now).num_minutes());
+        scope.push_constant("minutes_to_depletion", minutes_to_depletion);
+        scope.push_constant(
+            "limit_reached",
+            metrics.limit_events.iter().any(|event| event.kind == LimitEventKind::LimitReached),
+        );
+        scope.push_constant("plan", format!("{:?}", session.plan_type));
+        scope.push_constant("project", metrics.project_label.clone().unwrap_or_default());
+        scope
+    }
+
+    fn format_value(value: &Dynamic) -> String {
+        match value.as_float() {
+            Ok(float) => format!("{float:.2}"),
+            Err(_) => value.to_string(),
+        }
+    }
+}
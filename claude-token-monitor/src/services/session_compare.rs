@@ -0,0 +1,101 @@
+use super::file_monitor::UsageEntry;
+use super::pricing::Prices;
+use crate::models::SessionBlock;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::BTreeMap;
+
+/// ID of a session window, as `block-<start timestamp>` like `SessionBlock::to_session`
+pub fn window_id(block: &SessionBlock) -> String {
+    format!("block-{}", block.start_time.timestamp())
+}
+
+/// The window an ID refers to: `latest`, a window ID (`block-<timestamp>`), or an observed session
+/// ID (`observed-<timestamp>`) or bare Unix timestamp, resolved to the window containing that time
+pub fn find_window<'a>(blocks: &'a [SessionBlock], id: &str) -> Result<&'a SessionBlock> {
+    if id == "latest" {
+        return blocks.last().ok_or_else(|| anyhow!("No session windows recorded yet"));
+    }
+    let seconds = id
+        .strip_prefix("block-")
+        .or_else(|| id.strip_prefix("observed-"))
+        .unwrap_or(id)
+        .parse::<i64>()
+        .ok()
+        .filter(|seconds| DateTime::from_timestamp(*seconds, 0).is_some())
+        .ok_or_else(|| anyhow!("'{id}' is not a session ID; use one from `sessions list`, or 'latest'"))?;
+    // IDs are in whole seconds, window times aren't
+    blocks
+        .iter()
+        .find(|block| seconds >= block.start_time.timestamp() && seconds < block.reset_time.timestamp())
+        .ok_or_else(|| anyhow!("No session window contains {id}"))
+}
+
+/// What happened in one session window
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionSummary {
+    pub id: String,
+    pub start_time: DateTime<Utc>,
+    pub last_activity: DateTime<Utc>,
+    pub requests: usize,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub models: Vec<(String, u64)>, // tokens per model, most used first
+    pub estimated_cost: f64,        // API-equivalent USD
+}
+
+impl SessionSummary {
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens + self.cache_creation_tokens + self.cache_read_tokens
+    }
+
+    /// First to last request
+    pub fn duration(&self) -> Duration {
+        self.last_activity - self.start_time
+    }
+
+    /// Cache read tokens / all input tokens (uncached, cache creation and cache read)
+    pub fn cache_hit_rate(&self) -> f64 {
+        let input = self.input_tokens + self.cache_creation_tokens + self.cache_read_tokens;
+        if input == 0 {
+            0.0
+        } else {
+            self.cache_read_tokens as f64 / input as f64
+        }
+    }
+}
+
+/// Totals of the entries in `block`.
+///
+/// Entries are priced by `prices`, like the live cost estimate.
+pub fn summarize<'a>(
+    entries: impl IntoIterator<Item = &'a UsageEntry>,
+    block: &SessionBlock,
+    prices: Prices,
+) -> SessionSummary {
+    let mut summary = SessionSummary {
+        id: window_id(block),
+        start_time: block.start_time,
+        last_activity: block.last_activity,
+        ..SessionSummary::default()
+    };
+    let mut models: BTreeMap<&str, u64> = BTreeMap::new();
+    for entry in entries {
+        if entry.timestamp < block.start_time || entry.timestamp >= block.reset_time {
+            continue;
+        }
+        let usage = &entry.usage;
+        summary.requests += 1;
+        summary.input_tokens += usage.input_tokens as u64;
+        summary.output_tokens += usage.output_tokens as u64;
+        summary.cache_creation_tokens += usage.cache_creation_tokens() as u64;
+        summary.cache_read_tokens += usage.cache_read_tokens() as u64;
+        summary.estimated_cost += prices.cost(entry);
+        *models.entry(entry.model.as_deref().unwrap_or("unknown")).or_default() += usage.total_tokens() as u64;
+    }
+    summary.models = models.into_iter().map(|(model, tokens)| (model.to_string(), tokens)).collect();
+    summary.models.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    summary
+}
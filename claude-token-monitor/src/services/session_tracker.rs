@@ -1,37 +1,179 @@
 use super::SessionService;
 use crate::models::*;
+use crate::services::config_manager::migrate_sessions;
+use crate::services::encryption::StateFile;
+use crate::services::file_lock::FileLock;
 use crate::services::file_monitor::FileBasedTokenMonitor;
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
 use chrono::{Duration, Utc};
 use std::collections::HashMap;
-use std::path::PathBuf;
-use tokio::fs;
+use std::path::{Path, PathBuf};
+
+/// File in the state dir holding observed and imported sessions
+pub const SESSIONS_FILE: &str = "observed_sessions.json";
+
+// Largest sessions file accepted by `import --sessions`
+const MAX_SESSIONS_IMPORT_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Outcome of merging another machine's observed sessions file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionImportSummary {
+    pub read: usize,
+    pub added: usize,           // windows not seen before
+    pub replaced: usize,        // windows from an earlier import of the same file
+    pub already_observed: usize, // windows this machine observed itself
+    pub overlapping: usize,     // windows whose tokens are added to a local window
+}
 
 /// Session observation implementation (passive monitoring only)
 pub struct SessionTracker {
     observed_sessions: HashMap<String, TokenSession>,
-    data_path: PathBuf,
+    imported_sessions: HashMap<String, TokenSession>, // kept apart so rescans never overwrite them
+    file: StateFile,
     file_monitor: FileBasedTokenMonitor,
 }
 
 impl SessionTracker {
     pub fn new(data_path: PathBuf) -> Result<Self> {
+        Self::open(StateFile::new(data_path))
+    }
+
+    /// Track sessions saved in `file`, which may be encrypted
+    pub fn open(file: StateFile) -> Result<Self> {
         let file_monitor = FileBasedTokenMonitor::new()?;
+        let (observed_sessions, imported_sessions) = Self::load_observed_sessions(&file)?;
         Ok(Self {
-            observed_sessions: HashMap::new(),
-            data_path,
+            observed_sessions,
+            imported_sessions,
+            file,
             file_monitor,
         })
     }
 
+    /// Observe sessions in windows of this length and strategy
+    pub fn set_session_window(&mut self, window: SessionWindowConfig) {
+        self.file_monitor.set_session_window(window);
+    }
+
+    /// Load previously observed and imported sessions, migrating older file formats
+    fn load_observed_sessions(file: &StateFile) -> Result<(HashMap<String, TokenSession>, HashMap<String, TokenSession>)> {
+        // Taking the lock creates its file, so read-only mode reads without it
+        let _lock = if file.is_read_only() { None } else { Some(FileLock::shared(file.path())?) };
+        Self::read_sessions_file(file)
+    }
+
+    /// Read the sessions file; callers hold its lock
+    fn read_sessions_file(file: &StateFile) -> Result<(HashMap<String, TokenSession>, HashMap<String, TokenSession>)> {
+        let Some(content) = file.read()? else {
+            return Ok((HashMap::new(), HashMap::new()));
+        };
+        let value: serde_json::Value = serde_json::from_str(&content)?;
+        let imported: Vec<TokenSession> = match value.get("imported") {
+            Some(imported) => serde_json::from_value(imported.clone())
+                .map_err(|e| anyhow!("Failed to read imported sessions: {e}"))?,
+            None => Vec::new(),
+        };
+        let (sessions, from_version) = migrate_sessions(value)?;
+
+        if from_version < SESSIONS_SCHEMA_VERSION {
+            log::info!("Migrating observed sessions from schema v{from_version} to v{SESSIONS_SCHEMA_VERSION}");
+        }
+
+        Ok((Self::by_id(sessions), Self::by_id(imported)))
+    }
+
+    fn by_id(sessions: Vec<TokenSession>) -> HashMap<String, TokenSession> {
+        sessions
+            .into_iter()
+            .map(|session| (session.id.clone(), session))
+            .collect()
+    }
+
+    /// Merge an observed sessions file from another machine (or an older install).
+    ///
+    /// Imported sessions are stored alongside the local ones; `merged_sessions` combines them,
+    /// so importing the same file again replaces its sessions instead of counting them twice.
+    pub async fn import_sessions(&mut self, path: &Path) -> Result<SessionImportSummary> {
+        let metadata = std::fs::metadata(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        if metadata.len() > MAX_SESSIONS_IMPORT_SIZE {
+            return Err(anyhow!("{} is too large to import ({} bytes)", path.display(), metadata.len()));
+        }
+        let content = std::fs::read_to_string(path)?;
+        let value: serde_json::Value = serde_json::from_str(&content)
+            .with_context(|| format!("{} is not valid JSON", path.display()))?;
+        let (sessions, _) = migrate_sessions(value)
+            .with_context(|| format!("{} is not an observed sessions file", path.display()))?;
+
+        let mut summary = SessionImportSummary {
+            read: sessions.len(),
+            ..SessionImportSummary::default()
+        };
+        for session in sessions {
+            let category = if let Some(local) = self.observed_sessions.get(&session.id) {
+                summary.already_observed += 1;
+                if local.tokens_used >= session.tokens_used {
+                    continue;
+                }
+                None
+            } else if self.imported_sessions.contains_key(&session.id) {
+                Some(&mut summary.replaced)
+            } else if self.observed_sessions.values().any(|local| windows_overlap(local, &session)) {
+                Some(&mut summary.overlapping)
+            } else {
+                Some(&mut summary.added)
+            };
+            if let Some(count) = category {
+                *count += 1;
+            }
+            self.imported_sessions.insert(session.id.clone(), session);
+        }
+
+        self.save_observed_sessions().await?;
+        Ok(summary)
+    }
+
+    /// Local sessions combined with imported ones.
+    ///
+    /// The same window seen twice keeps the larger count; another machine's usage inside a
+    /// local window is added to it, and imported windows with no local counterpart are kept as is.
+    pub fn merged_sessions(&self) -> Vec<TokenSession> {
+        let mut merged: Vec<TokenSession> = self.observed_sessions.values().cloned().collect();
+        merged.sort_by_key(|session| session.start_time);
+        let local_count = merged.len();
+
+        let mut imported: Vec<&TokenSession> = self.imported_sessions.values().collect();
+        imported.sort_by_key(|session| session.start_time);
+        for session in imported {
+            if let Some(same) = merged[..local_count].iter_mut().find(|local| local.id == session.id) {
+                same.tokens_used = same.tokens_used.max(session.tokens_used);
+                continue;
+            }
+            let closest = merged[..local_count]
+                .iter_mut()
+                .filter(|local| windows_overlap(local, session))
+                .min_by_key(|local| (local.start_time - session.start_time).num_seconds().abs());
+            match closest {
+                Some(local) => merge_window(local, session),
+                None => merged.push(session.clone()),
+            }
+        }
+        merged
+    }
+
     /// Update observed sessions from JSONL file data
     pub async fn update_observed_sessions(&mut self) -> Result<()> {
         // Scan for new usage data
         self.file_monitor.scan_usage_files().await?;
         
-        // Derive current session from observed data
-        if let Some(current_session) = self.file_monitor.derive_current_session() {
-            self.observed_sessions.insert(current_session.id.clone(), current_session);
+        // Derive current session from observed data, and again any earlier window that entries
+        // arriving out of order (synced from another machine, a skewed clock) fell into
+        let sessions = match self.file_monitor.scan_stats().late_since {
+            Some(since) => self.file_monitor.observed_sessions_since(since),
+            None => self.file_monitor.derive_current_session().into_iter().collect(),
+        };
+        let now = Utc::now();
+        for session in sessions.into_iter().filter(|session| !is_expired(session, now)) {
+            self.observed_sessions.insert(session.id.clone(), session);
         }
         
         // Save observed sessions for historical tracking
@@ -40,41 +182,68 @@ impl SessionTracker {
         Ok(())
     }
 
-    pub async fn save_observed_sessions(&self) -> Result<()> {
-        let sessions: Vec<&TokenSession> = self.observed_sessions.values().collect();
-        let content = serde_json::to_string_pretty(&sessions)?;
-        
-        if let Some(parent) = self.data_path.parent() {
-            fs::create_dir_all(parent).await?;
+    /// Write observed and imported sessions, keeping what other instances saved since this one loaded.
+    ///
+    /// The file is locked for the whole read-modify-write, so concurrent `monitor`, `status` and
+    /// `import` runs can't drop each other's sessions or interleave their writes.
+    pub async fn save_observed_sessions(&mut self) -> Result<()> {
+        if self.file.is_read_only() {
+            return Ok(());
         }
-        
-        fs::write(&self.data_path, content).await?;
-        Ok(())
+        let path = self.file.path().to_path_buf();
+        let _lock = tokio::task::spawn_blocking(move || FileLock::exclusive(&path)).await??;
+        let (on_disk, imported_on_disk) = Self::read_sessions_file(&self.file)?;
+        let now = Utc::now();
+        for (id, session) in on_disk {
+            if is_expired(&session, now) {
+                continue;
+            }
+            // Another instance may have seen more of the same window
+            match self.observed_sessions.get(&id) {
+                Some(local) if local.tokens_used >= session.tokens_used => {}
+                _ => {
+                    self.observed_sessions.insert(id, session);
+                }
+            }
+        }
+        for (id, session) in imported_on_disk {
+            self.imported_sessions.entry(id).or_insert(session);
+        }
+
+        let mut sessions: Vec<TokenSession> = self.observed_sessions.values().cloned().collect();
+        sessions.sort_by_key(|session| session.start_time);
+        let mut imported: Vec<TokenSession> = self.imported_sessions.values().cloned().collect();
+        imported.sort_by_key(|session| session.start_time);
+        let file = SessionsFile {
+            version: SESSIONS_SCHEMA_VERSION,
+            sessions,
+            imported,
+        };
+        self.file.write(&serde_json::to_string_pretty(&file)?)
     }
 
     /// Clean up old observed sessions
     pub async fn cleanup_expired_sessions(&mut self) -> Result<()> {
         let now = Utc::now();
-        let session_duration = Duration::hours(5);
-        
-        self.observed_sessions.retain(|_, session| {
-            if let Some(end_time) = session.end_time {
-                now.signed_duration_since(end_time) < Duration::days(7)
-            } else {
-                now.signed_duration_since(session.start_time) < session_duration
-            }
-        });
-        
+        self.observed_sessions.retain(|_, session| !is_expired(session, now));
         self.save_observed_sessions().await?;
         Ok(())
     }
 }
 
+/// Ended sessions are kept for a week, unfinished ones until their window resets
+fn is_expired(session: &TokenSession, now: chrono::DateTime<Utc>) -> bool {
+    match session.end_time {
+        Some(end_time) => now.signed_duration_since(end_time) >= Duration::days(7),
+        None => now >= session.reset_time,
+    }
+}
+
 impl SessionService for SessionTracker {
     fn get_active_session(&self) -> impl std::future::Future<Output = Result<Option<TokenSession>>> + Send {
-        let active_session = self.observed_sessions.values()
-            .find(|session| session.is_active && Utc::now() <= session.reset_time)
-            .cloned();
+        let active_session = self.merged_sessions()
+            .into_iter()
+            .find(|session| session.is_active && Utc::now() <= session.reset_time);
         
         async move {
             Ok(active_session)
@@ -82,12 +251,28 @@ impl SessionService for SessionTracker {
     }
 
     fn get_session_history(&self, limit: usize) -> impl std::future::Future<Output = Result<Vec<TokenSession>>> + Send {
-        let mut sessions: Vec<TokenSession> = self.observed_sessions.values().cloned().collect();
-        sessions.sort_by(|a, b| b.start_time.cmp(&a.start_time));
+        let mut sessions = self.merged_sessions();
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.start_time));
         sessions.truncate(limit);
         
         async move {
             Ok(sessions)
         }
     }
-}
\ No newline at end of file
+}
+fn windows_overlap(a: &TokenSession, b: &TokenSession) -> bool {
+    a.start_time < b.reset_time && b.start_time < a.reset_time
+}
+
+/// Fold another machine's session into a local window covering the same period
+fn merge_window(local: &mut TokenSession, other: &TokenSession) {
+    local.tokens_used = local.tokens_used.saturating_add(other.tokens_used);
+    local.tokens_limit = local.tokens_limit.max(other.tokens_limit);
+    // The window really started with whichever machine was used first
+    if other.start_time < local.start_time {
+        local.start_time = other.start_time;
+        local.reset_time = other.reset_time;
+    }
+    local.is_active = Utc::now() <= local.reset_time;
+    local.end_time = if local.is_active { None } else { Some(local.reset_time) };
+}
@@ -0,0 +1,102 @@
+use super::analyzer::DAYS_PER_MONTH;
+use super::file_monitor::UsageEntry;
+use super::pricing::Prices;
+use crate::models::{PlanType, SessionBlock};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+
+/// Model families a scenario can substitute for every logged request
+const MODEL_FAMILIES: &[&str] = &["opus", "sonnet", "haiku"];
+
+/// A hypothetical change to replay history under
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Scenario {
+    pub plan: Option<PlanType>,
+    pub model: Option<String>, // model family or ID every request is repriced at
+}
+
+impl Scenario {
+    /// Check that a substituted model maps onto a known price list instead of silently using Sonnet rates
+    pub fn validate(&self) -> Result<()> {
+        if let Some(model) = &self.model {
+            let model = model.to_lowercase();
+            if !MODEL_FAMILIES.iter().any(|family| model.contains(family)) {
+                return Err(anyhow!("Unknown model: {model}. Use 'opus', 'sonnet', 'haiku' or a full model ID"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Cost and limit pressure of the replayed history under one plan and pricing
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub plan: PlanType,
+    pub limit_hits: usize, // windows whose tokens reached the plan's limit
+    pub api_cost: f64,     // API-equivalent cost of the period
+    pub monthly_limit_hits: f64,
+    pub monthly_api_cost: f64,
+}
+
+impl Outcome {
+    pub fn monthly_price(&self) -> Option<f64> {
+        self.plan.monthly_price()
+    }
+}
+
+/// History replayed as observed and under a scenario
+#[derive(Debug, Clone, PartialEq)]
+pub struct Simulation {
+    pub windows: usize,
+    pub analyzed_days: f64,
+    pub baseline: Outcome,
+    pub scenario: Outcome,
+}
+
+/// Replay the entries and session windows since `since` under `baseline_plan` and under `scenario`.
+///
+/// Token counts are kept as logged, so a model substitution changes cost but not limit hits.
+pub fn simulate(
+    entries: &[UsageEntry],
+    blocks: &[SessionBlock],
+    baseline_plan: &PlanType,
+    scenario: &Scenario,
+    prices: Prices,
+    since: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Result<Simulation> {
+    scenario.validate()?;
+    let blocks: Vec<&SessionBlock> = blocks.iter().filter(|block| block.start_time >= since).collect();
+    let entries: Vec<&UsageEntry> = entries.iter().filter(|entry| entry.timestamp >= since).collect();
+    let Some(first) = blocks.iter().map(|block| block.start_time).min() else {
+        return Err(anyhow!("No usage since {} to simulate", since.format("%Y-%m-%d")));
+    };
+    let analyzed_days = ((now - first).num_minutes() as f64 / (24.0 * 60.0)).max(1.0);
+    let per_month = DAYS_PER_MONTH / analyzed_days;
+
+    let outcome = |plan: &PlanType, model: Option<&str>| {
+        let limit = plan.default_limit();
+        let limit_hits = blocks.iter().filter(|block| block.tokens_used >= limit).count();
+        let api_cost: f64 = entries
+            .iter()
+            .map(|entry| {
+                let model = model.unwrap_or_else(|| prices.model_of(entry));
+                prices.for_model(model).cost(&entry.usage)
+            })
+            .sum();
+        Outcome {
+            plan: plan.clone(),
+            limit_hits,
+            api_cost,
+            monthly_limit_hits: limit_hits as f64 * per_month,
+            monthly_api_cost: api_cost * per_month,
+        }
+    };
+
+    Ok(Simulation {
+        windows: blocks.len(),
+        analyzed_days,
+        baseline: outcome(baseline_plan, None),
+        scenario: outcome(scenario.plan.as_ref().unwrap_or(baseline_plan), scenario.model.as_deref()),
+    })
+}
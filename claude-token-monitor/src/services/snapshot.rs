@@ -0,0 +1,246 @@
+use super::claude_settings;
+use super::file_lock::write_atomic;
+use super::file_monitor::FileBasedTokenMonitor;
+use super::output_schema::MetricsOutput;
+use crate::models::{AccountIdentity, TokenSession, UserConfig};
+use anyhow::{anyhow, Context, Result};
+use blake2::digest::{KeyInit, Mac};
+use blake2::{Blake2s256, Blake2sMac256, Digest};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Format version written to new snapshots; 2 keys the config digests
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+/// File in the data directory with the key config digests are made with
+pub const DIGEST_KEY_FILE: &str = "snapshot.key";
+
+/// Environment variables the monitor or Claude Code reads that change what gets scanned or shown;
+/// anything else, credentials included, is left out of snapshots
+pub const SNAPSHOT_VARIABLES: [&str; 12] = [
+    "ANTHROPIC_MODEL",
+    "CLAUDE_CONFIG_DIR",
+    "CLAUDE_DATA_PATH",
+    "CLAUDE_DATA_PATHS",
+    "CLAUDE_TOKEN_MONITOR_DATA_DIR",
+    "CODESPACES",
+    "DEVCONTAINER",
+    "REMOTE_CONTAINERS",
+    "TERM",
+    "TERM_PROGRAM",
+    "TZ",
+    "NO_COLOR",
+];
+
+/// Everything about one moment of the monitor's view, in one file to attach to a bug report
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Snapshot {
+    pub version: u32,
+    pub taken_at: DateTime<Utc>,
+    /// Version of the monitor that took the snapshot
+    pub monitor_version: String,
+    /// Current usage, null when no usage logs were found
+    pub metrics: Option<MetricsOutput>,
+    /// The session window derived from the logs, null when there is none
+    pub session: Option<TokenSession>,
+    /// Digest of the whole config under this installation's key, equal for equal configs without
+    /// revealing them
+    pub config_digest: String,
+    /// Digest of each config section, to tell which ones differ
+    pub config_sections: BTreeMap<String, String>,
+    /// Identifies the key the digests were made with; only digests with the same key compare
+    #[serde(default)]
+    pub digest_key_id: String,
+    pub environment: SnapshotEnvironment,
+    /// Signed-in Claude account, when Claude Code's config names it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<AccountIdentity>,
+}
+
+/// Where the monitor looked and what it found there
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct SnapshotEnvironment {
+    pub os: String,
+    pub arch: String,
+    /// Named profile, null for the default one
+    pub profile: Option<String>,
+    pub read_only: bool,
+    pub config_dir: PathBuf,
+    pub data_dir: PathBuf,
+    /// Claude Code's config directory, null when it wasn't found
+    pub claude_config_dir: Option<PathBuf>,
+    /// Directories scanned for usage logs
+    pub monitored_paths: Vec<PathBuf>,
+    pub usage_files: usize,
+    pub failed_files: usize,
+    pub usage_entries: usize,
+    pub api_key_entries: usize,
+    pub imported_entries: usize,
+    pub subscription_type: Option<String>,
+    pub rate_limit_tier: Option<String>,
+    /// Model selected in Claude Code's settings or ANTHROPIC_MODEL
+    pub selected_model: Option<String>,
+    /// Model of the latest logged request
+    pub latest_model: Option<String>,
+    /// Those of `SNAPSHOT_VARIABLES` that are set
+    pub variables: BTreeMap<String, String>,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().take(8).map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// First 16 hex digits of the BLAKE2s hash of `text`
+pub fn digest(text: &str) -> String {
+    hex(&Blake2s256::digest(text.as_bytes()))
+}
+
+/// First 16 hex digits of the BLAKE2s MAC of `text` under `key`
+pub fn keyed_digest(key: &[u8; 32], text: &str) -> String {
+    let mut mac = <Blake2sMac256 as KeyInit>::new_from_slice(key).expect("BLAKE2s takes 32-byte keys");
+    mac.update(text.as_bytes());
+    hex(&mac.finalize().into_bytes())
+}
+
+/// The key snapshots' config digests are made with: random, created on first use and kept in
+/// `data_dir`, so snapshots taken here compare but a shared one can't be matched against guessed
+/// config values. In read-only mode a new key isn't saved, so that snapshot only compares with
+/// itself.
+pub fn digest_key(data_dir: &Path, read_only: bool) -> Result<[u8; 32]> {
+    let path = data_dir.join(DIGEST_KEY_FILE);
+    match std::fs::read(&path) {
+        Ok(key) => key.try_into().map_err(|_| anyhow!("{} is not a snapshot key; delete it to make a new one", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let key: [u8; 32] = rand::random();
+            if !read_only {
+                write_atomic(&path, key)?;
+            }
+            Ok(key)
+        }
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+/// Digest of the config as a whole and of each of its sections, under `key`
+pub fn config_digests(config: &UserConfig, key: &[u8; 32]) -> Result<(String, BTreeMap<String, String>)> {
+    let value = serde_json::to_value(config)?;
+    let sections = match &value {
+        serde_json::Value::Object(sections) => {
+            sections.iter().map(|(name, section)| (name.clone(), keyed_digest(key, &section.to_string()))).collect()
+        }
+        _ => BTreeMap::new(),
+    };
+    Ok((keyed_digest(key, &value.to_string()), sections))
+}
+
+impl Snapshot {
+    /// Capture `monitor`'s current view, after a scan, with `config` digested under `key` and the
+    /// directories in use
+    pub fn take(
+        monitor: &FileBasedTokenMonitor,
+        config: &UserConfig,
+        key: &[u8; 32],
+        environment: SnapshotEnvironment,
+        identity: Option<AccountIdentity>,
+        now: DateTime<Utc>,
+    ) -> Result<Self> {
+        let (config_digest, config_sections) = config_digests(config, key)?;
+        Ok(Self {
+            version: SNAPSHOT_VERSION,
+            taken_at: now,
+            monitor_version: env!("CARGO_PKG_VERSION").to_string(),
+            metrics: monitor.calculate_metrics().map(|metrics| MetricsOutput::new(&metrics, None)),
+            session: monitor.derive_current_session(),
+            config_digest,
+            config_sections,
+            digest_key_id: hex(&Blake2s256::digest(key)),
+            environment,
+            identity,
+        })
+    }
+}
+
+impl SnapshotEnvironment {
+    /// The platform, `monitor`'s sources and what its latest scan found
+    pub fn collect(monitor: &FileBasedTokenMonitor, profile: Option<&str>, read_only: bool, config_dir: &Path, data_dir: &Path) -> Self {
+        let hints = monitor.subscription_hints();
+        Self {
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            profile: profile.map(str::to_string),
+            read_only,
+            config_dir: config_dir.to_path_buf(),
+            data_dir: data_dir.to_path_buf(),
+            claude_config_dir: claude_settings::claude_config_dir().ok(),
+            monitored_paths: monitor.get_monitored_paths().to_vec(),
+            usage_files: monitor.scan_stats().files,
+            failed_files: monitor.failed_files().len(),
+            usage_entries: monitor.usage_entries().len(),
+            api_key_entries: monitor.api_key_entries().len(),
+            imported_entries: monitor.imported_entries().len(),
+            subscription_type: hints.subscription_type.clone(),
+            rate_limit_tier: hints.rate_limit_tier.clone(),
+            selected_model: monitor.selected_model().map(|selected| selected.model.clone()),
+            latest_model: monitor.latest_model().map(str::to_string),
+            variables: SNAPSHOT_VARIABLES
+                .iter()
+                .filter_map(|name| std::env::var(name).ok().map(|value| (name.to_string(), value)))
+                .collect(),
+        }
+    }
+}
+
+/// A snapshot file as JSON, so snapshots from other monitor versions still compare
+pub fn load(path: &Path) -> Result<serde_json::Value> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let value: serde_json::Value = serde_json::from_str(&text).with_context(|| format!("{} is not valid JSON", path.display()))?;
+    if value.get("version").is_none() || value.get("taken_at").is_none() {
+        return Err(anyhow!("{} is not a file written by `snapshot`", path.display()));
+    }
+    Ok(value)
+}
+
+/// A value that differs between two snapshots, by its dotted path
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotChange {
+    pub path: String,
+    pub before: Option<String>, // None when the first snapshot doesn't have it
+    pub after: Option<String>,
+}
+
+/// Every scalar in `value` by dotted path; lists of objects count as their length
+fn flatten(path: String, value: &serde_json::Value, out: &mut BTreeMap<String, String>) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (name, field) in fields {
+                let path = if path.is_empty() { name.clone() } else { format!("{path}.{name}") };
+                flatten(path, field, out);
+            }
+        }
+        serde_json::Value::Array(items) if items.iter().any(|item| item.is_object() || item.is_array()) => {
+            out.insert(path, format!("{} items", items.len()));
+        }
+        serde_json::Value::String(text) => {
+            out.insert(path, text.clone());
+        }
+        other => {
+            out.insert(path, other.to_string());
+        }
+    }
+}
+
+/// What changed from snapshot `a` to `b`, apart from when they were taken
+pub fn diff(a: &serde_json::Value, b: &serde_json::Value) -> Vec<SnapshotChange> {
+    let (mut before, mut after) = (BTreeMap::new(), BTreeMap::new());
+    flatten(String::new(), a, &mut before);
+    flatten(String::new(), b, &mut after);
+    let paths: std::collections::BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    paths
+        .into_iter()
+        .filter(|path| path.as_str() != "taken_at" && before.get(*path) != after.get(*path))
+        .map(|path| SnapshotChange { path: path.clone(), before: before.get(path).cloned(), after: after.get(path).cloned() })
+        .collect()
+}
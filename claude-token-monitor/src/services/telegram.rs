@@ -0,0 +1,203 @@
+use crate::models::AlertChannelConfig;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Telegram Bot API; `CLAUDE_TOKEN_MONITOR_TELEGRAM_URL` points elsewhere (e.g. a self-hosted Bot API server)
+pub const TELEGRAM_API_URL: &str = "https://api.telegram.org";
+const TELEGRAM_URL_ENV: &str = "CLAUDE_TOKEN_MONITOR_TELEGRAM_URL";
+
+/// Sending a message gives up after this long
+pub const TELEGRAM_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long each request for new messages waits for one to arrive
+const POLL_SECONDS: u64 = 25;
+
+/// Pause before asking again after the API couldn't be reached
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Deserialize)]
+struct Response<T> {
+    ok: bool,
+    result: Option<T>,
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Update {
+    update_id: i64,
+    message: Option<Message>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    chat: Chat,
+    date: i64, // Unix time the message was sent
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Chat {
+    id: i64,
+}
+
+/// Bot API server to use: `CLAUDE_TOKEN_MONITOR_TELEGRAM_URL` when it's an https URL (the bot
+/// token travels in the path), Telegram's own otherwise
+pub fn api_base() -> String {
+    match std::env::var(TELEGRAM_URL_ENV) {
+        Ok(url) if url.starts_with("https://") => url,
+        Ok(_) => {
+            log::warn!("Ignoring {TELEGRAM_URL_ENV}: only https URLs are used");
+            TELEGRAM_API_URL.to_string()
+        }
+        Err(_) => TELEGRAM_API_URL.to_string(),
+    }
+}
+
+/// URL of a Bot API method on the server at `base`
+pub fn api_url(base: &str, token: &str, method: &str) -> String {
+    format!("{}/bot{token}/{method}", base.trim_end_matches('/'))
+}
+
+fn agent(read_timeout: Duration) -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout_connect(TELEGRAM_TIMEOUT)
+        .timeout_read(read_timeout)
+        .user_agent(concat!("claude-token-monitor/", env!("CARGO_PKG_VERSION")))
+        .build()
+}
+
+/// The bot token from the environment variable named in the channel
+pub fn bot_token(bot_token_env: &str) -> Result<String> {
+    std::env::var(bot_token_env).map_err(|_| anyhow!("Environment variable {bot_token_env} with the Telegram bot token is not set"))
+}
+
+/// Call a Bot API method, failing on an error status or an `ok: false` reply. The token is left
+/// out of errors, which end up in the log.
+fn call<T: for<'de> Deserialize<'de>>(agent: &ureq::Agent, base: &str, token: &str, method: &str, body: serde_json::Value) -> Result<T> {
+    let response: Response<T> = match agent.post(&api_url(base, token, method)).send_json(body) {
+        Ok(response) => response.into_json().with_context(|| format!("Unexpected reply from Telegram to {method}"))?,
+        Err(ureq::Error::Status(status, response)) => response.into_json().unwrap_or(Response {
+            ok: false,
+            result: None,
+            description: Some(format!("HTTP {status}")),
+        }),
+        Err(ureq::Error::Transport(e)) => return Err(anyhow!("Failed to reach Telegram: {}", e.kind())),
+    };
+    match (response.ok, response.result) {
+        (true, Some(result)) => Ok(result),
+        _ => Err(anyhow!("Telegram {method} failed: {}", response.description.unwrap_or_default())),
+    }
+}
+
+/// Send `text` to `chat_id` (a number, or a channel's `@name`) as the bot with `token`, through
+/// the Bot API server at `base`. Blocks until Telegram has accepted it.
+pub fn send_message(base: &str, token: &str, chat_id: impl Into<serde_json::Value>, text: &str) -> Result<()> {
+    let body = serde_json::json!({ "chat_id": chat_id.into(), "text": text });
+    call::<serde_json::Value>(&agent(TELEGRAM_TIMEOUT), base, token, "sendMessage", body)?;
+    Ok(())
+}
+
+/// Reply to a message's text: the status for `/status` (also as `/status@botname`), usage help
+/// for any other command, and nothing for plain text
+pub fn command_reply(text: &str, status: &str) -> Option<String> {
+    let command = text.split_whitespace().next()?.strip_prefix('/')?;
+    match command.split('@').next() {
+        Some("status") => Some(status.to_string()),
+        _ => Some("Send /status for the current Claude usage".to_string()),
+    }
+}
+
+/// Answers `/status` in the chats of the configured Telegram channels with the latest status the
+/// monitor reported. Once started, each bot asks Telegram for new messages in a background thread
+/// until stopped; messages sent before it started are skipped, as are chats that aren't configured.
+#[derive(Clone)]
+pub struct StatusBot {
+    bots: Vec<(String, Vec<i64>)>, // token and chats of each bot
+    status: Arc<Mutex<String>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl StatusBot {
+    /// A bot for every bot among `channels`, with tokens from the environment variables they name
+    /// looked up through `env`; None when there are no Telegram channels
+    pub fn new(channels: &[AlertChannelConfig], env: impl Fn(&str) -> Option<String>) -> Result<Option<Self>> {
+        let mut chats: HashMap<&str, Vec<i64>> = HashMap::new();
+        for channel in channels {
+            if let AlertChannelConfig::Telegram { bot_token_env, chat_id, .. } = channel {
+                chats.entry(bot_token_env).or_default().push(*chat_id);
+            }
+        }
+        if chats.is_empty() {
+            return Ok(None);
+        }
+        let bots = chats
+            .into_iter()
+            .map(|(bot_token_env, chats)| {
+                let token = env(bot_token_env).ok_or_else(|| anyhow!("Environment variable {bot_token_env} with the Telegram bot token is not set"))?;
+                Ok((token, chats))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Some(Self {
+            bots,
+            status: Arc::new(Mutex::new("The monitor is starting".to_string())),
+            stopped: Arc::new(AtomicBool::new(false)),
+        }))
+    }
+
+    /// Start answering through the Bot API server at `base`
+    pub fn start(&self, base: &str) {
+        let started = Utc::now();
+        for (token, chats) in &self.bots {
+            let (base, token, chats) = (base.to_string(), token.clone(), chats.clone());
+            let (status, stopped) = (self.status.clone(), self.stopped.clone());
+            std::thread::spawn(move || answer_commands(&base, &token, &chats, &status, &stopped, started));
+        }
+    }
+
+    /// Replace the status sent in reply to `/status`
+    pub fn set_status(&self, status: String) {
+        if let Ok(mut current) = self.status.lock() {
+            *current = status;
+        }
+    }
+
+    /// Stop answering; each thread exits once its pending request for messages returns
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+fn answer_commands(base: &str, token: &str, chats: &[i64], status: &Mutex<String>, stopped: &AtomicBool, started: DateTime<Utc>) {
+    let agent = agent(Duration::from_secs(POLL_SECONDS) + TELEGRAM_TIMEOUT);
+    let mut offset = 0;
+    while !stopped.load(Ordering::Relaxed) {
+        let body = serde_json::json!({ "offset": offset, "timeout": POLL_SECONDS, "allowed_updates": ["message"] });
+        let updates: Vec<Update> = match call(&agent, base, token, "getUpdates", body) {
+            Ok(updates) => updates,
+            Err(e) => {
+                log::warn!("{e}, retrying in {}s", RETRY_DELAY.as_secs());
+                std::thread::sleep(RETRY_DELAY);
+                continue;
+            }
+        };
+        for update in updates {
+            offset = offset.max(update.update_id + 1);
+            let Some(message) = update.message.filter(|message| message.date >= started.timestamp() && chats.contains(&message.chat.id)) else {
+                continue;
+            };
+            let current = status.lock().map(|status| status.clone()).unwrap_or_default();
+            let Some(reply) = message.text.as_deref().and_then(|text| command_reply(text, &current)) else {
+                continue;
+            };
+            let body = serde_json::json!({ "chat_id": message.chat.id, "text": reply });
+            if let Err(e) = call::<serde_json::Value>(&agent, base, token, "sendMessage", body) {
+                log::warn!("Failed to answer on Telegram: {e}");
+            }
+        }
+    }
+}
@@ -99,6 +99,16 @@ impl<T: SessionService + Send + Sync + 'static> TokenMonitor<T> {
             cache_creation_rate: 0.0,
             token_consumption_rate: usage_rate,
             input_output_ratio: 1.0,
+            project_label: None,
+            limit_calibrated: false,
+            limit_events: Vec::new(),
+            estimated_cost: 0.0,
+            activity_heatmap: Vec::new(),
+            minute_tokens: Vec::new(),
+            idle_since: None,
+            reset_authoritative: false,
+            selected_model: None,
+            latest_model: None,
         })
     }
 
@@ -144,7 +154,7 @@ impl<T: SessionService + Send + Sync + 'static> TokenMonitor<T> {
                         tokens_used: 0,
                         tokens_limit: 40000,
                         is_active: false,
-                        reset_time: chrono::Utc::now() + chrono::Duration::hours(5),
+                        reset_time: chrono::Utc::now() + file_monitor.session_window().duration(),
                     }
                 });
                 
@@ -161,6 +171,16 @@ impl<T: SessionService + Send + Sync + 'static> TokenMonitor<T> {
                     cache_creation_rate: 0.0,
                     token_consumption_rate: 0.0,
                     input_output_ratio: 1.0,
+                    project_label: None,
+                    limit_calibrated: false,
+                    limit_events: Vec::new(),
+                    estimated_cost: 0.0,
+                    activity_heatmap: Vec::new(),
+                    minute_tokens: Vec::new(),
+                    idle_since: None,
+                    reset_authoritative: false,
+                    selected_model: None,
+                    latest_model: None,
                 }
             })
         };
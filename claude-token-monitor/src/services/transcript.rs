@@ -0,0 +1,521 @@
+use super::json_line;
+use super::schema::{self, LineClass, SchemaDescriptor, CLAUDE_CODE_V1, FLAT_USAGE_V0};
+use serde::de::{self, value::MapAccessDeserializer, Deserializer, IgnoredAny, MapAccess, SeqAccess, Unexpected, Visitor};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// Type of a line in Claude Code's transcripts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LineType {
+    User,
+    Assistant,
+    System,
+    Summary,
+    #[default]
+    #[serde(other)]
+    Other,
+}
+
+/// Capacity a request was served with, as the API reports it in the usage object
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceTier {
+    Standard,
+    Priority,
+    Batch,
+    #[serde(other)]
+    Other, // a tier this version doesn't know yet
+}
+
+/// The usage object of an API response. Counts that aren't whole non-negative numbers are read as
+/// missing rather than failing the line.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Usage {
+    #[serde(default, deserialize_with = "lenient")]
+    pub input_tokens: Option<u64>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub output_tokens: Option<u64>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub cache_creation_input_tokens: Option<u64>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub cache_read_input_tokens: Option<u64>,
+    #[serde(default, deserialize_with = "object")]
+    pub cache_creation: Option<CacheCreation>, // split of the cache writes by TTL, in newer logs
+    #[serde(default, deserialize_with = "lenient")]
+    pub service_tier: Option<ServiceTier>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CacheCreation {
+    #[serde(default, deserialize_with = "lenient")]
+    pub ephemeral_1h_input_tokens: Option<u64>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub ephemeral_5m_input_tokens: Option<u64>,
+}
+
+/// Kind of a block of message content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockType {
+    Text,
+    Thinking,
+    ToolUse,
+    ToolResult,
+    #[serde(other)]
+    Other,
+}
+
+/// One block of message content; only what usage tracking reads of it
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContentBlock<'a> {
+    #[serde(rename = "type")]
+    pub block_type: BlockType,
+    #[serde(borrow, default, deserialize_with = "text")]
+    pub name: Option<Cow<'a, str>>, // the tool called, on tool_use blocks
+}
+
+/// Request timings in milliseconds, which only some logs record, at the top of the line or in
+/// the message, under any of these names
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Timings {
+    duration_ms_camel: Option<f64>,
+    duration_ms: Option<f64>,
+    duration: Option<f64>,
+    ttft_ms_camel: Option<f64>,
+    ttft_ms: Option<f64>,
+    ttft: Option<f64>,
+}
+
+fn millis(values: [Option<f64>; 3]) -> Option<i64> {
+    values.into_iter().flatten().next().filter(|ms| ms.is_finite()).map(|ms| ms.round() as i64)
+}
+
+impl Timings {
+    /// As logged, which can be negative when a clock was adjusted during the request
+    pub fn duration_ms(&self) -> Option<i64> {
+        millis([self.duration_ms_camel, self.duration_ms, self.duration])
+    }
+
+    pub fn ttft_ms(&self) -> Option<i64> {
+        millis([self.ttft_ms_camel, self.ttft_ms, self.ttft])
+    }
+
+    /// Each timing from `self`, or from `other` where `self` lacks it
+    fn or(self, other: Timings) -> Timings {
+        Timings {
+            duration_ms_camel: self.duration_ms_camel.or(other.duration_ms_camel),
+            duration_ms: self.duration_ms.or(other.duration_ms),
+            duration: self.duration.or(other.duration),
+            ttft_ms_camel: self.ttft_ms_camel.or(other.ttft_ms_camel),
+            ttft_ms: self.ttft_ms.or(other.ttft_ms),
+            ttft: self.ttft.or(other.ttft),
+        }
+    }
+}
+
+/// Any line of a log, as far as the scanner reads it: enough to classify the line, spot a failed
+/// request and build a usage entry. Every other field is skipped unread.
+///
+/// Claude Code transcripts (`claude-code-v1`) carry the API response in `message`; flat records
+/// (`flat-usage-v0`) carry the usage at the top of the line.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LogLine<'a> {
+    #[serde(rename = "type", borrow, deserialize_with = "text")]
+    pub type_name: Option<Cow<'a, str>>,
+    #[serde(borrow, deserialize_with = "text")]
+    pub timestamp: Option<Cow<'a, str>>,
+    #[serde(borrow, deserialize_with = "text")]
+    pub request_id: Option<Cow<'a, str>>,
+    #[serde(borrow, deserialize_with = "text")]
+    pub cwd: Option<Cow<'a, str>>, // working directory of the session
+    #[serde(borrow, deserialize_with = "text")]
+    pub session_id: Option<Cow<'a, str>>,
+    #[serde(borrow, deserialize_with = "lenient_text")]
+    pub version: Option<Cow<'a, str>>, // of Claude Code
+    #[serde(deserialize_with = "lenient")]
+    pub is_api_error_message: Option<bool>,
+    #[serde(borrow, deserialize_with = "object")]
+    pub message: Option<MessageV1<'a>>,
+    // Flat records, and early transcripts, keep these at the top of the line
+    #[serde(borrow, deserialize_with = "text")]
+    pub model: Option<Cow<'a, str>>,
+    #[serde(borrow, rename = "message_id", deserialize_with = "text")]
+    pub message_id: Option<Cow<'a, str>>,
+    #[serde(borrow, rename = "request_id", deserialize_with = "text")]
+    pub request_id_snake: Option<Cow<'a, str>>,
+    #[serde(deserialize_with = "object")]
+    pub usage: Option<Usage>,
+    // Request timings in milliseconds, which only some logs record, here or in the message
+    #[serde(rename = "durationMs", deserialize_with = "lenient")]
+    duration_ms_camel: Option<f64>,
+    #[serde(rename = "duration_ms", deserialize_with = "lenient")]
+    duration_ms: Option<f64>,
+    #[serde(deserialize_with = "lenient")]
+    duration: Option<f64>,
+    #[serde(rename = "ttftMs", deserialize_with = "lenient")]
+    ttft_ms_camel: Option<f64>,
+    #[serde(rename = "ttft_ms", deserialize_with = "lenient")]
+    ttft_ms: Option<f64>,
+    #[serde(deserialize_with = "lenient")]
+    ttft: Option<f64>,
+}
+
+/// The API response in a Claude Code transcript line
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct MessageV1<'a> {
+    #[serde(borrow, deserialize_with = "text")]
+    pub id: Option<Cow<'a, str>>,
+    #[serde(borrow, deserialize_with = "text")]
+    pub model: Option<Cow<'a, str>>,
+    #[serde(deserialize_with = "object")]
+    pub usage: Option<Usage>,
+    #[serde(borrow, deserialize_with = "lenient_text")]
+    pub stop_reason: Option<Cow<'a, str>>,
+    #[serde(borrow, rename = "content", deserialize_with = "tool_uses")]
+    pub tool_uses: Vec<Cow<'a, str>>, // names of the tools the response called
+    #[serde(rename = "durationMs", deserialize_with = "lenient")]
+    duration_ms_camel: Option<f64>,
+    #[serde(deserialize_with = "lenient")]
+    duration_ms: Option<f64>,
+    #[serde(deserialize_with = "lenient")]
+    duration: Option<f64>,
+    #[serde(rename = "ttftMs", deserialize_with = "lenient")]
+    ttft_ms_camel: Option<f64>,
+    #[serde(deserialize_with = "lenient")]
+    ttft_ms: Option<f64>,
+    #[serde(deserialize_with = "lenient")]
+    ttft: Option<f64>,
+}
+
+/// A usage-bearing line in whichever schema it was written, with strings borrowed from the line
+#[derive(Debug)]
+pub struct UsageLine<'a> {
+    pub line_type: LineType,
+    pub timestamp: Option<Cow<'a, str>>,
+    pub model: Option<Cow<'a, str>>,
+    pub message_id: Option<Cow<'a, str>>,
+    pub request_id: Option<Cow<'a, str>>,
+    pub cwd: Option<Cow<'a, str>>,
+    pub session_id: Option<Cow<'a, str>>,
+    pub usage: Usage,
+    pub tool_uses: Vec<Cow<'a, str>>, // names of the tools the response called
+    pub timings: Timings,
+}
+
+/// Read one line of a log straight from its bytes (see `json_line::from_slice`)
+pub fn read_line(bytes: &[u8], max_depth: usize) -> serde_json::Result<LogLine<'_>> {
+    json_line::from_slice(bytes, max_depth)
+}
+
+impl<'a> LogLine<'a> {
+    /// Schema of the usage object the line carries, looked for in `schema::SCHEMAS` order
+    pub fn usage_schema(&self) -> Option<&'static SchemaDescriptor> {
+        if self.message.as_ref().is_some_and(|message| message.usage.is_some()) {
+            Some(&CLAUDE_CODE_V1)
+        } else if self.usage.is_some() {
+            Some(&FLAT_USAGE_V0)
+        } else {
+            None
+        }
+    }
+
+    /// A synthetic message (limit notices, API errors) rather than a model's response
+    pub fn is_synthetic(&self) -> bool {
+        self.message.as_ref().and_then(|message| message.model.as_deref()) == Some("<synthetic>")
+            || self.is_api_error_message == Some(true)
+    }
+
+    /// Whether the line could be a limit notice, which is read from its text; only system lines
+    /// and synthetic messages can
+    pub fn may_be_notice(&self) -> bool {
+        self.type_name.as_deref() == Some("system") || self.is_synthetic()
+    }
+
+    /// The line's class, as `schema::classify_line` would find it
+    pub fn class(&self) -> LineClass {
+        schema::classify(self.type_name.as_deref(), self.usage_schema(), self.is_synthetic())
+    }
+
+    fn timings(&self) -> Timings {
+        Timings {
+            duration_ms_camel: self.duration_ms_camel,
+            duration_ms: self.duration_ms,
+            duration: self.duration,
+            ttft_ms_camel: self.ttft_ms_camel,
+            ttft_ms: self.ttft_ms,
+            ttft: self.ttft,
+        }
+    }
+
+    /// The usage-bearing fields of a line `class` matched to `schema`
+    pub fn into_usage_line(self, schema: &SchemaDescriptor) -> serde_json::Result<UsageLine<'a>> {
+        let line_type = match self.type_name.as_deref() {
+            Some(name) => LineType::deserialize(de::value::StrDeserializer::<serde_json::Error>::new(name))?,
+            None => LineType::default(),
+        };
+        // Timings may sit at either level; the top of the line wins
+        let timings = self.timings();
+        if schema == &CLAUDE_CODE_V1 {
+            let message = self.message.unwrap_or_default();
+            let timings = timings.or(message.timings());
+            Ok(UsageLine {
+                line_type,
+                timestamp: self.timestamp,
+                model: message.model.or(self.model),
+                message_id: message.id.or(self.message_id),
+                request_id: self.request_id.or(self.request_id_snake),
+                cwd: self.cwd,
+                session_id: self.session_id,
+                usage: message.usage.unwrap_or_default(),
+                tool_uses: message.tool_uses,
+                timings,
+            })
+        } else if schema == &FLAT_USAGE_V0 {
+            let timings = timings.or(self.message.as_ref().map(MessageV1::timings).unwrap_or_default());
+            Ok(UsageLine {
+                line_type,
+                timestamp: self.timestamp,
+                model: self.model,
+                message_id: self.message_id,
+                request_id: self.request_id_snake.or(self.request_id),
+                cwd: self.cwd,
+                session_id: self.session_id,
+                usage: self.usage.unwrap_or_default(),
+                tool_uses: Vec::new(),
+                timings,
+            })
+        } else {
+            Err(de::Error::custom(format!("no typed reader for schema {}", schema.id)))
+        }
+    }
+}
+
+impl MessageV1<'_> {
+    fn timings(&self) -> Timings {
+        Timings {
+            duration_ms_camel: self.duration_ms_camel,
+            duration_ms: self.duration_ms,
+            duration: self.duration,
+            ttft_ms_camel: self.ttft_ms_camel,
+            ttft_ms: self.ttft_ms,
+            ttft: self.ttft,
+        }
+    }
+}
+
+/// Reads a string, borrowed when it has no escapes, or null; anything else fails the line unless
+/// `lenient`, when it is skipped and read as None
+struct TextVisitor {
+    lenient: bool,
+}
+
+impl TextVisitor {
+    fn other<'a, E: de::Error>(&self, unexpected: Unexpected) -> Result<Option<Cow<'a, str>>, E> {
+        if self.lenient {
+            Ok(None)
+        } else {
+            Err(E::invalid_type(unexpected, self))
+        }
+    }
+}
+
+impl<'de> Visitor<'de> for TextVisitor {
+    type Value = Option<Cow<'de, str>>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string")
+    }
+
+    fn visit_borrowed_str<E>(self, value: &'de str) -> Result<Self::Value, E> {
+        Ok(Some(Cow::Borrowed(value)))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E> {
+        Ok(Some(Cow::Owned(value.to_string())))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Self::Value, E> {
+        Ok(Some(Cow::Owned(value)))
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Self::Value, E> {
+        self.other(Unexpected::Bool(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        self.other(Unexpected::Signed(value))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        self.other(Unexpected::Unsigned(value))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+        self.other(Unexpected::Float(value))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        while seq.next_element::<IgnoredAny>()?.is_some() {}
+        self.other(Unexpected::Seq)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+        self.other(Unexpected::Map)
+    }
+}
+
+/// A string, borrowed from the line unless it has escapes, or None for null
+fn text<'de: 'a, 'a, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Cow<'a, str>>, D::Error> {
+    deserializer.deserialize_any(TextVisitor { lenient: false })
+}
+
+/// Like `text`, reading anything but a string as None
+fn lenient_text<'de: 'a, 'a, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Cow<'a, str>>, D::Error> {
+    deserializer.deserialize_any(TextVisitor { lenient: true })
+}
+
+/// A value of the expected type, or None when the field holds something else. Only numbers,
+/// strings and booleans are tried; arrays and objects are skipped.
+fn lenient<'de, D: Deserializer<'de>, T: Deserialize<'de>>(deserializer: D) -> Result<Option<T>, D::Error> {
+    struct LenientVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: Deserialize<'de>> Visitor<'de> for LenientVisitor<T> {
+        type Value = Option<T>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("any value")
+        }
+
+        fn visit_bool<E>(self, value: bool) -> Result<Option<T>, E> {
+            Ok(T::deserialize(de::value::BoolDeserializer::<serde_json::Error>::new(value)).ok())
+        }
+
+        fn visit_i64<E>(self, value: i64) -> Result<Option<T>, E> {
+            Ok(T::deserialize(de::value::I64Deserializer::<serde_json::Error>::new(value)).ok())
+        }
+
+        fn visit_u64<E>(self, value: u64) -> Result<Option<T>, E> {
+            Ok(T::deserialize(de::value::U64Deserializer::<serde_json::Error>::new(value)).ok())
+        }
+
+        fn visit_f64<E>(self, value: f64) -> Result<Option<T>, E> {
+            Ok(T::deserialize(de::value::F64Deserializer::<serde_json::Error>::new(value)).ok())
+        }
+
+        fn visit_borrowed_str<E>(self, value: &'de str) -> Result<Option<T>, E> {
+            Ok(T::deserialize(de::value::BorrowedStrDeserializer::<serde_json::Error>::new(value)).ok())
+        }
+
+        fn visit_str<E>(self, value: &str) -> Result<Option<T>, E> {
+            Ok(T::deserialize(de::value::StrDeserializer::<serde_json::Error>::new(value)).ok())
+        }
+
+        fn visit_unit<E>(self) -> Result<Option<T>, E> {
+            Ok(None)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Option<T>, A::Error> {
+            while seq.next_element::<IgnoredAny>()?.is_some() {}
+            Ok(None)
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Option<T>, A::Error> {
+            while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+            Ok(None)
+        }
+    }
+
+    deserializer.deserialize_any(LenientVisitor(PhantomData))
+}
+
+/// An object read as `T`, or None when the field holds anything else
+fn object<'de, D: Deserializer<'de>, T: Deserialize<'de>>(deserializer: D) -> Result<Option<T>, D::Error> {
+    struct ObjectVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: Deserialize<'de>> Visitor<'de> for ObjectVisitor<T> {
+        type Value = Option<T>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("any value")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Option<T>, A::Error> {
+            T::deserialize(MapAccessDeserializer::new(map)).map(Some)
+        }
+
+        fn visit_bool<E>(self, _: bool) -> Result<Option<T>, E> {
+            Ok(None)
+        }
+
+        fn visit_i64<E>(self, _: i64) -> Result<Option<T>, E> {
+            Ok(None)
+        }
+
+        fn visit_u64<E>(self, _: u64) -> Result<Option<T>, E> {
+            Ok(None)
+        }
+
+        fn visit_f64<E>(self, _: f64) -> Result<Option<T>, E> {
+            Ok(None)
+        }
+
+        fn visit_str<E>(self, _: &str) -> Result<Option<T>, E> {
+            Ok(None)
+        }
+
+        fn visit_unit<E>(self) -> Result<Option<T>, E> {
+            Ok(None)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Option<T>, A::Error> {
+            while seq.next_element::<IgnoredAny>()?.is_some() {}
+            Ok(None)
+        }
+    }
+
+    deserializer.deserialize_any(ObjectVisitor(PhantomData))
+}
+
+/// Names of the tools message content calls: its tool_use blocks, when it is a list of blocks
+/// rather than plain text, which has none
+fn tool_uses<'de: 'a, 'a, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Cow<'a, str>>, D::Error> {
+    struct ContentVisitor;
+
+    impl<'de> Visitor<'de> for ContentVisitor {
+        type Value = Vec<Cow<'de, str>>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("message content")
+        }
+
+        fn visit_str<E>(self, _: &str) -> Result<Self::Value, E> {
+            Ok(Vec::new())
+        }
+
+        fn visit_unit<E>(self) -> Result<Self::Value, E> {
+            Ok(Vec::new())
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut names = Vec::new();
+            while let Some(block) = seq.next_element::<ContentBlock>()? {
+                if let (BlockType::ToolUse, Some(name)) = (block.block_type, block.name) {
+                    names.push(name);
+                }
+            }
+            Ok(names)
+        }
+    }
+
+    deserializer.deserialize_any(ContentVisitor)
+}
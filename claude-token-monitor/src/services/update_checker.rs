@@ -0,0 +1,225 @@
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use flate2::read::GzDecoder;
+use ring::signature::{UnparsedPublicKey, ED25519};
+use semver::Version;
+use serde::Deserialize;
+use std::io::Read;
+use std::path::Path;
+use std::time::Duration;
+
+/// Latest GitHub release of the monitor
+pub const RELEASES_URL: &str = "https://api.github.com/repos/teamktown/claude-token-monitor/releases/latest";
+
+const BINARY_NAME: &str = "claude-token-monitor";
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+// Largest release archive accepted, so a bad URL can't fill memory
+const MAX_DOWNLOAD_SIZE: u64 = 64 * 1024 * 1024;
+
+// Largest binary unpacked from an archive, so a compressed bomb can't either
+const MAX_BINARY_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Release asset listing the SHA-256 of every archive, as `sha256sum` writes it
+pub const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+
+/// Release asset holding the Ed25519 signature of `CHECKSUMS_ASSET`, raw or base64
+pub const SIGNATURE_ASSET: &str = "SHA256SUMS.sig";
+
+// Largest checksum list or signature accepted
+const MAX_CHECKSUMS_SIZE: u64 = 1024 * 1024;
+
+/// Base64 Ed25519 public key that release checksums are signed with, baked in by release builds.
+/// Builds without one can't verify a download, so they don't install any.
+pub const RELEASE_SIGNING_KEY: Option<&str> = option_env!("CLAUDE_TOKEN_MONITOR_RELEASE_KEY");
+
+/// A published release, as the GitHub releases API describes it
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub html_url: String,
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+impl Release {
+    /// Version from the tag, e.g. `v0.3.0`
+    pub fn version(&self) -> Result<Version> {
+        Version::parse(self.tag_name.trim_start_matches('v'))
+            .with_context(|| format!("Release tag '{}' is not a version", self.tag_name))
+    }
+
+    /// Whether this release is newer than the `current` version
+    pub fn is_newer_than(&self, current: &str) -> Result<bool> {
+        Ok(self.version()? > Version::parse(current)?)
+    }
+
+    /// The `.tar.gz` built for `os` and `arch` (named as in `std::env::consts`)
+    pub fn asset_for(&self, os: &str, arch: &str) -> Option<&ReleaseAsset> {
+        let os_names = match os {
+            "macos" => vec!["macos", "darwin", "apple"],
+            os => vec![os],
+        };
+        self.assets.iter().find(|asset| {
+            let name = asset.name.to_lowercase();
+            name.ends_with(".tar.gz") && name.contains(arch) && os_names.iter().any(|os| name.contains(os))
+        })
+    }
+
+    fn asset(&self, name: &str) -> Option<&ReleaseAsset> {
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+}
+
+fn agent() -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout_connect(TIMEOUT)
+        .timeout_read(TIMEOUT)
+        .user_agent(concat!("claude-token-monitor/", env!("CARGO_PKG_VERSION")))
+        .build()
+}
+
+/// Ask the releases API for the latest release
+pub fn fetch_latest_release() -> Result<Release> {
+    agent()
+        .get(RELEASES_URL)
+        .set("Accept", "application/vnd.github+json")
+        .call()
+        .context("Failed to check for updates")?
+        .into_json()
+        .context("Unexpected response from the releases API")
+}
+
+/// Download `asset` of `release`, check it against the release's signed checksums and replace the
+/// executable at `exe` with the monitor binary inside it
+pub fn install(release: &Release, asset: &ReleaseAsset, exe: &Path) -> Result<()> {
+    let public_key = release_signing_key()?;
+    let signed_asset = |name: &str| {
+        release
+            .asset(name)
+            .ok_or_else(|| anyhow!("Release {} has no {name}, so its builds can't be verified", release.tag_name))
+    };
+    let checksums = download(signed_asset(CHECKSUMS_ASSET)?, MAX_CHECKSUMS_SIZE)?;
+    let signature = download(signed_asset(SIGNATURE_ASSET)?, MAX_CHECKSUMS_SIZE)?;
+    let archive = download(asset, MAX_DOWNLOAD_SIZE)?;
+    verify_archive(&archive, &asset.name, &checksums, &signature, &public_key)?;
+    replace_executable(exe, &extract_binary(&archive)?)
+}
+
+/// The public key of `RELEASE_SIGNING_KEY`
+fn release_signing_key() -> Result<Vec<u8>> {
+    let key = RELEASE_SIGNING_KEY.ok_or_else(|| {
+        anyhow!("This build has no release signing key, so it can't verify a download; update with `cargo install --path .` instead")
+    })?;
+    base64::engine::general_purpose::STANDARD
+        .decode(key.trim())
+        .context("The release signing key built in is not base64")
+}
+
+fn download(asset: &ReleaseAsset, max_size: u64) -> Result<Vec<u8>> {
+    let response = agent()
+        .get(&asset.browser_download_url)
+        .call()
+        .with_context(|| format!("Failed to download {}", asset.browser_download_url))?;
+    let mut bytes = Vec::new();
+    response.into_reader().take(max_size + 1).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > max_size {
+        return Err(anyhow!("{} is larger than {} KB", asset.name, max_size / 1024));
+    }
+    Ok(bytes)
+}
+
+/// Check that `checksums` carries a valid signature by `public_key` and lists `archive` as `name`
+pub fn verify_archive(archive: &[u8], name: &str, checksums: &[u8], signature: &[u8], public_key: &[u8]) -> Result<()> {
+    // Signatures are published raw or base64-encoded
+    let signature = match signature.len() {
+        64 => signature.to_vec(),
+        _ => base64::engine::general_purpose::STANDARD
+            .decode(signature.trim_ascii())
+            .context("The release signature is neither raw nor base64")?,
+    };
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(checksums, &signature)
+        .map_err(|_| anyhow!("The signature of {CHECKSUMS_ASSET} doesn't match the release signing key"))?;
+    let checksums = std::str::from_utf8(checksums).context("The release checksums are not text")?;
+    let expected = checksums
+        .lines()
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .find(|(_, file)| file.trim_start().trim_start_matches('*') == name)
+        .map(|(sum, _)| sum.to_ascii_lowercase())
+        .ok_or_else(|| anyhow!("{CHECKSUMS_ASSET} has no checksum for {name}"))?;
+    let actual: String = ring::digest::digest(&ring::digest::SHA256, archive)
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    if actual != expected {
+        return Err(anyhow!("{name} doesn't match its published checksum"));
+    }
+    Ok(())
+}
+
+/// The monitor binary from a release `.tar.gz`, wherever it sits in the archive
+pub fn extract_binary(archive: &[u8]) -> Result<Vec<u8>> {
+    let mut tar = tar::Archive::new(GzDecoder::new(archive));
+    for entry in tar.entries().context("Release archive is not a .tar.gz")? {
+        let entry = entry?;
+        let path = entry.path()?.into_owned();
+        let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        if entry.header().entry_type().is_file() && name.trim_end_matches(".exe") == BINARY_NAME {
+            let mut binary = Vec::new();
+            entry.take(MAX_BINARY_SIZE + 1).read_to_end(&mut binary)?;
+            if binary.len() as u64 > MAX_BINARY_SIZE {
+                return Err(anyhow!("The {BINARY_NAME} binary in the release archive is larger than {} MB", MAX_BINARY_SIZE / 1024 / 1024));
+            }
+            return Ok(binary);
+        }
+    }
+    Err(anyhow!("The release archive has no {BINARY_NAME} binary"))
+}
+
+/// Write `binary` to a temporary file next to `exe` and rename it over `exe`, so the executable is
+/// either the old or the new one at any moment.
+///
+/// Windows keeps a running executable locked, so there the old one is moved aside first and left
+/// behind as `.old`.
+pub fn replace_executable(exe: &Path, binary: &[u8]) -> Result<()> {
+    use std::io::Write;
+
+    let new = exe.with_extension("new");
+    let _ = std::fs::remove_file(&new);
+    let written = std::fs::OpenOptions::new().write(true).create_new(true).open(&new).and_then(|mut file| {
+        file.write_all(binary)?;
+        file.sync_all()
+    });
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&new);
+        return Err(anyhow!("Failed to write {}: {e}", new.display()));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(exe).map(|metadata| metadata.permissions().mode()).unwrap_or(0o755);
+        std::fs::set_permissions(&new, std::fs::Permissions::from_mode(mode | 0o111))?;
+    }
+    #[cfg(windows)]
+    let old = exe.with_extension("old");
+    #[cfg(windows)]
+    std::fs::rename(exe, &old).with_context(|| format!("Failed to move {} aside", exe.display()))?;
+    if let Err(e) = std::fs::rename(&new, exe) {
+        #[cfg(windows)]
+        let _ = std::fs::rename(&old, exe);
+        let _ = std::fs::remove_file(&new);
+        return Err(anyhow!("Failed to install the new binary at {}: {e}", exe.display()));
+    }
+    #[cfg(windows)]
+    let _ = std::fs::remove_file(&old);
+    Ok(())
+}
@@ -0,0 +1,838 @@
+use super::file_monitor::{Billing, UsageEntry};
+use super::model_names::ModelNames;
+use super::os_users;
+use super::pricing::Prices;
+use crate::models::{GoalPeriod, GoalsConfig, LimitEvent, LimitEventKind, RequestError, SessionBlock, UsageMetrics};
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, Timelike, Utc, Weekday};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// How entries are grouped into histogram buckets (all in UTC, like the activity heatmap)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bucketing {
+    Hour,    // hour of day, 00-23
+    Day,     // calendar day, oldest first
+    Weekday, // Monday to Sunday
+}
+
+/// Token totals per bucket over a range of days
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub bucketing: Bucketing,
+    pub days: u32,
+    pub labels: Vec<String>,
+    pub tokens: Vec<u64>,
+    pub entries: usize, // entries that fell inside the range
+}
+
+impl Histogram {
+    pub fn total_tokens(&self) -> u64 {
+        self.tokens.iter().sum()
+    }
+
+    /// Label and tokens of the busiest bucket
+    pub fn peak(&self) -> Option<(&str, u64)> {
+        self.labels
+            .iter()
+            .zip(&self.tokens)
+            .filter(|(_, tokens)| **tokens > 0)
+            .max_by_key(|(_, tokens)| **tokens)
+            .map(|(label, tokens)| (label.as_str(), *tokens))
+    }
+}
+
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+/// Bucket the tokens of every entry from the last `days` days, including today
+pub fn histogram<'a>(
+    entries: impl Iterator<Item = &'a UsageEntry>,
+    bucketing: Bucketing,
+    days: u32,
+    now: DateTime<Utc>,
+) -> Histogram {
+    let days = days.max(1);
+    let first_day = now.date_naive() - Duration::days(days as i64 - 1);
+
+    let labels: Vec<String> = match bucketing {
+        Bucketing::Hour => (0..24).map(|hour| format!("{hour:02}:00")).collect(),
+        Bucketing::Day => (0..days)
+            .map(|offset| (first_day + Duration::days(offset as i64)).format("%a %m-%d").to_string())
+            .collect(),
+        Bucketing::Weekday => WEEKDAYS.iter().map(|day| day.to_string()).collect(),
+    };
+
+    let mut histogram = Histogram {
+        bucketing,
+        days,
+        tokens: vec![0; labels.len()],
+        labels,
+        entries: 0,
+    };
+    for entry in entries {
+        let day = entry.timestamp.date_naive();
+        if day < first_day || entry.timestamp > now {
+            continue;
+        }
+        let bucket = match bucketing {
+            Bucketing::Hour => entry.timestamp.hour() as usize,
+            Bucketing::Day => (day - first_day).num_days() as usize,
+            Bucketing::Weekday => day.weekday().num_days_from_monday() as usize,
+        };
+        histogram.tokens[bucket] += entry.usage.total_tokens() as u64;
+        histogram.entries += 1;
+    }
+    histogram
+}
+
+/// Total tokens for every UTC day that has usage
+pub fn daily_totals<'a>(entries: impl Iterator<Item = &'a UsageEntry>) -> BTreeMap<NaiveDate, u64> {
+    let mut totals = BTreeMap::new();
+    for entry in entries {
+        *totals.entry(entry.timestamp.date_naive()).or_default() += entry.usage.total_tokens() as u64;
+    }
+    totals
+}
+
+/// Weeks (Monday first) covering the month of `month`, with days outside the month left empty
+pub fn calendar_weeks(month: NaiveDate) -> Vec<[Option<NaiveDate>; 7]> {
+    let first = month.with_day(1).unwrap_or(month);
+    let mut weeks = Vec::new();
+    let mut week = [None; 7];
+    for day in first.iter_days().take_while(|day| day.month() == first.month()) {
+        let column = day.weekday().num_days_from_monday() as usize;
+        week[column] = Some(day);
+        if column == 6 {
+            weeks.push(week);
+            week = [None; 7];
+        }
+    }
+    if week.iter().any(Option::is_some) {
+        weeks.push(week);
+    }
+    weeks
+}
+
+/// Shade (0 = no usage, 1-4 = quartiles of the busiest day) for a calendar cell
+pub fn intensity_level(tokens: u64, max: u64) -> usize {
+    if tokens == 0 || max == 0 {
+        return 0;
+    }
+    (((tokens as f64 / max as f64) * 4.0).ceil() as usize).clamp(1, 4)
+}
+
+/// Trailing periods summarized on the Charts tab, with their length in hours
+pub const RECENT_PERIODS: [(&str, i64); 4] = [("12h", 12), ("24h", 24), ("48h", 48), ("7d", 7 * 24)];
+
+/// Tokens in each of `RECENT_PERIODS` ending at `now`
+pub fn recent_period_totals<'a>(
+    entries: impl Iterator<Item = &'a UsageEntry>,
+    now: DateTime<Utc>,
+) -> Vec<(&'static str, u64)> {
+    let mut totals: Vec<(&'static str, u64)> = RECENT_PERIODS.iter().map(|(label, _)| (*label, 0)).collect();
+    for entry in entries.filter(|entry| entry.timestamp <= now) {
+        let age = now - entry.timestamp;
+        for ((_, hours), (_, tokens)) in RECENT_PERIODS.iter().zip(totals.iter_mut()) {
+            if age <= Duration::hours(*hours) {
+                *tokens += entry.usage.total_tokens() as u64;
+            }
+        }
+    }
+    totals
+}
+
+/// Tokens in consecutive buckets of `bucket` from `since` until `until`, keyed by bucket start
+pub fn token_buckets<'a>(
+    entries: impl Iterator<Item = &'a UsageEntry>,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    bucket: Duration,
+) -> Vec<(DateTime<Utc>, u64)> {
+    let bucket_seconds = bucket.num_seconds().max(1);
+    let count = ((until - since).num_seconds().max(0) + bucket_seconds - 1) / bucket_seconds;
+    let mut buckets: Vec<(DateTime<Utc>, u64)> = (0..count)
+        .map(|index| (since + Duration::seconds(index * bucket_seconds), 0))
+        .collect();
+    for entry in entries.filter(|entry| entry.timestamp >= since && entry.timestamp < until) {
+        let index = ((entry.timestamp - since).num_seconds() / bucket_seconds) as usize;
+        if let Some((_, tokens)) = buckets.get_mut(index) {
+            *tokens += entry.usage.total_tokens() as u64;
+        }
+    }
+    buckets
+}
+
+/// Start time and tokens of the latest `count` session windows, oldest first
+pub fn session_trend(blocks: &[SessionBlock], count: usize) -> Vec<(DateTime<Utc>, u64)> {
+    let mut trend: Vec<(DateTime<Utc>, u64)> = blocks
+        .iter()
+        .map(|block| (block.start_time, block.tokens_used as u64))
+        .collect();
+    trend.sort_by_key(|(start, _)| *start);
+    trend.split_off(trend.len().saturating_sub(count))
+}
+
+/// A session window measured against the token limit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct WindowUsage {
+    pub start_time: DateTime<Utc>,
+    pub reset_time: DateTime<Utc>,
+    pub requests: usize,
+    pub tokens_used: u32,
+    /// Share of the token limit used, from 0 (can pass 100 when the limit was calibrated lower)
+    pub percent_of_limit: f64,
+    /// A limit notice was logged in the window, or its tokens reached the limit
+    pub limit_reached: bool,
+}
+
+impl WindowUsage {
+    pub fn new(block: &SessionBlock, token_limit: u32) -> Self {
+        let percent_of_limit = if token_limit == 0 { 0.0 } else { block.tokens_used as f64 / token_limit as f64 * 100.0 };
+        Self {
+            start_time: block.start_time,
+            reset_time: block.reset_time,
+            requests: block.entry_count,
+            tokens_used: block.tokens_used,
+            percent_of_limit,
+            limit_reached: block.limit_reached || (token_limit > 0 && block.tokens_used >= token_limit),
+        }
+    }
+}
+
+/// The latest `count` session windows against `token_limit`, newest first
+pub fn window_history(blocks: &[SessionBlock], token_limit: u32, count: usize) -> Vec<WindowUsage> {
+    let mut blocks: Vec<&SessionBlock> = blocks.iter().collect();
+    blocks.sort_by_key(|block| std::cmp::Reverse(block.start_time));
+    blocks.into_iter().take(count).map(|block| WindowUsage::new(block, token_limit)).collect()
+}
+
+/// Tokens left in the current window over time, against spending the limit evenly until the reset
+#[derive(Debug, Clone, PartialEq)]
+pub struct BurnDown {
+    pub remaining: Vec<(f64, f64)>, // minutes since the window started, tokens left
+    pub even_pace: [(f64, f64); 2], // from the whole limit at the start to none at the reset
+    pub limit: f64,
+    pub window_minutes: f64,
+}
+
+/// Burn-down of the current session as of `now`; usage past the limit counts as nothing left
+pub fn burn_down(metrics: &UsageMetrics, now: DateTime<Utc>) -> BurnDown {
+    let session = &metrics.current_session;
+    let limit = session.tokens_limit as f64;
+    let window_minutes = (session.reset_time - session.start_time).num_seconds().max(60) as f64 / 60.0;
+    let minutes_at = |timestamp: DateTime<Utc>| {
+        ((timestamp - session.start_time).num_seconds() as f64 / 60.0).clamp(0.0, window_minutes)
+    };
+    let left = |used: u32| (limit - used as f64).max(0.0);
+    let mut remaining: Vec<(f64, f64)> = metrics
+        .usage_history
+        .iter()
+        .map(|point| (minutes_at(point.timestamp), left(point.tokens_used)))
+        .collect();
+    // Carry the line on to now, so a quiet stretch shows as flat rather than ending early
+    if session.is_active {
+        remaining.push((minutes_at(now), left(session.tokens_used)));
+    }
+    BurnDown {
+        remaining,
+        even_pace: [(0.0, limit), (window_minutes, 0.0)],
+        limit,
+        window_minutes,
+    }
+}
+
+/// Points sampled along a forecast from now to the reset
+pub const FORECAST_STEPS: usize = 30;
+
+/// Projected continuation of the current window's cumulative usage until the reset
+#[derive(Debug, Clone, PartialEq)]
+pub struct Forecast {
+    pub expected: Vec<(f64, f64)>, // minutes since the window started, tokens at the session's burn rate
+    pub low: Vec<(f64, f64)>,      // one standard deviation below, never under the tokens used so far
+    pub high: Vec<(f64, f64)>,     // one standard deviation above
+}
+
+/// Forecast of the current session as of `now`, None once it is inactive, idle or past its reset.
+///
+/// Usage grows at the session's average rate, the one depletion is predicted from. The band
+/// treats minutes as independent draws spread like the last hour's per-minute usage, so it widens
+/// with the square root of the time ahead.
+pub fn forecast(metrics: &UsageMetrics, now: DateTime<Utc>) -> Option<Forecast> {
+    let session = &metrics.current_session;
+    if !session.is_active || metrics.idle_since.is_some() || now >= session.reset_time {
+        return None;
+    }
+    let minutes_from_start = |timestamp: DateTime<Utc>| (timestamp - session.start_time).num_seconds() as f64 / 60.0;
+    let (now_minute, reset_minute) = (minutes_from_start(now).max(0.0), minutes_from_start(session.reset_time));
+    let samples = metrics.minute_tokens.len().max(1) as f64;
+    let mean = metrics.minute_tokens.iter().sum::<u64>() as f64 / samples;
+    let variance = metrics.minute_tokens.iter().map(|tokens| (*tokens as f64 - mean).powi(2)).sum::<f64>() / samples;
+    let used = session.tokens_used as f64;
+
+    let mut forecast = Forecast { expected: Vec::new(), low: Vec::new(), high: Vec::new() };
+    for step in 0..=FORECAST_STEPS {
+        let minute = now_minute + (reset_minute - now_minute) * step as f64 / FORECAST_STEPS as f64;
+        let ahead = minute - now_minute;
+        let expected = used + metrics.usage_rate * ahead;
+        let spread = (variance * ahead).sqrt();
+        forecast.expected.push((minute, expected));
+        forecast.low.push((minute, (expected - spread).max(used)));
+        forecast.high.push((minute, expected + spread));
+    }
+    Some(forecast)
+}
+
+/// Highlights of one calendar year (UTC) of usage
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct YearReview {
+    pub year: i32,
+    pub tokens: u64,
+    pub entries: usize,
+    pub estimated_cost: f64, // API-equivalent USD
+    pub active_days: usize,
+    pub busiest_day: Option<(NaiveDate, u64)>,
+    pub top_model: Option<(String, u64)>, // by tokens
+    pub cache_read_tokens: u64,
+    pub cache_savings: f64, // USD saved by cache reads versus uncached input
+    pub longest_session: Option<SessionBlock>, // window with the longest first-to-last activity span
+}
+
+/// Summarize the entries and session windows that started in `year`.
+///
+/// Entries are priced by `prices`, like the live cost estimate; the top model is picked among the
+/// names `names` reports them under.
+pub fn year_review<'a>(
+    entries: impl Iterator<Item = &'a UsageEntry>,
+    blocks: &[SessionBlock],
+    year: i32,
+    prices: Prices,
+    names: &ModelNames,
+) -> YearReview {
+    let mut review = YearReview {
+        year,
+        ..YearReview::default()
+    };
+    let mut days: BTreeMap<NaiveDate, u64> = BTreeMap::new();
+    let mut models: BTreeMap<String, u64> = BTreeMap::new();
+
+    for entry in entries.filter(|entry| entry.timestamp.year() == year) {
+        let tokens = entry.usage.total_tokens() as u64;
+        let pricing = prices.for_entry(entry);
+        review.tokens += tokens;
+        review.entries += 1;
+        review.estimated_cost += pricing.cost(&entry.usage);
+        review.cache_read_tokens += entry.usage.cache_read_tokens() as u64;
+        review.cache_savings += pricing.cache_savings(&entry.usage);
+        *days.entry(entry.timestamp.date_naive()).or_default() += tokens;
+        if let Some(model) = entry.model.as_deref() {
+            *models.entry(names.name(model)).or_default() += tokens;
+        }
+    }
+
+    review.active_days = days.len();
+    review.busiest_day = days.into_iter().max_by_key(|(day, tokens)| (*tokens, std::cmp::Reverse(*day)));
+    review.top_model = models
+        .into_iter()
+        .max_by_key(|(_, tokens)| *tokens);
+    review.longest_session = blocks
+        .iter()
+        .filter(|block| block.start_time.year() == year)
+        .max_by_key(|block| (block.last_activity - block.start_time, block.tokens_used))
+        .cloned();
+    review
+}
+
+/// Tokens and API-equivalent cost over a period
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PeriodUsage {
+    pub tokens: u64,
+    pub cost: f64,
+}
+
+impl PeriodUsage {
+    fn add(&mut self, entry: &UsageEntry, prices: Prices) {
+        self.tokens += entry.usage.total_tokens() as u64;
+        self.cost += prices.cost(entry);
+    }
+}
+
+impl std::ops::AddAssign for PeriodUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.tokens += other.tokens;
+        self.cost += other.cost;
+    }
+}
+
+/// Usage from `since` until `until` split by how it is billed: subscription, then API key
+pub fn billing_split<'a>(
+    entries: impl Iterator<Item = &'a UsageEntry>,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    prices: Prices,
+) -> (PeriodUsage, PeriodUsage) {
+    let (mut subscription, mut api_key) = (PeriodUsage::default(), PeriodUsage::default());
+    for entry in entries.filter(|entry| entry.timestamp >= since && entry.timestamp < until) {
+        match entry.billing {
+            Billing::Subscription => subscription.add(entry, prices),
+            Billing::ApiKey => api_key.add(entry, prices),
+        }
+    }
+    (subscription, api_key)
+}
+
+/// Usage per OS user from `since` until `until`, busiest first; entries without a known owner count
+/// under `os_users::UNKNOWN_USER`
+pub fn user_split<'a>(
+    entries: impl Iterator<Item = &'a UsageEntry>,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    prices: Prices,
+) -> Vec<(String, PeriodUsage)> {
+    let mut users: BTreeMap<&str, PeriodUsage> = BTreeMap::new();
+    for entry in entries.filter(|entry| entry.timestamp >= since && entry.timestamp < until) {
+        users
+            .entry(entry.user.as_deref().unwrap_or(os_users::UNKNOWN_USER))
+            .or_default()
+            .add(entry, prices);
+    }
+    let mut users: Vec<(String, PeriodUsage)> = users.into_iter().map(|(user, usage)| (user.to_string(), usage)).collect();
+    users.sort_by(|a, b| b.1.tokens.cmp(&a.1.tokens).then_with(|| a.0.cmp(&b.0)));
+    users
+}
+
+/// One conversation's usage and estimated cost
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversationCost {
+    pub conversation: String,
+    pub project: Option<String>,
+    pub requests: usize,
+    pub tokens: u64,
+    pub cost: f64, // API-equivalent USD
+    pub long_context_requests: usize, // prompts past the long-context threshold
+    pub long_context_cost: f64,       // what those requests cost
+    pub last_activity: DateTime<Utc>,
+}
+
+/// Claude Code conversations with usage since `since`, most expensive first
+pub fn expensive_conversations<'a>(
+    entries: impl Iterator<Item = &'a UsageEntry>,
+    since: DateTime<Utc>,
+    prices: Prices,
+) -> Vec<ConversationCost> {
+    let mut conversations: BTreeMap<&str, ConversationCost> = BTreeMap::new();
+    for entry in entries.filter(|entry| entry.timestamp >= since) {
+        let Some(conversation) = entry.conversation.as_deref() else {
+            continue;
+        };
+        let totals = conversations.entry(conversation).or_insert_with(|| ConversationCost {
+            conversation: conversation.to_string(),
+            project: None,
+            requests: 0,
+            tokens: 0,
+            cost: 0.0,
+            long_context_requests: 0,
+            long_context_cost: 0.0,
+            last_activity: entry.timestamp,
+        });
+        let rates = prices.for_entry(entry);
+        let cost = rates.cost(&entry.usage);
+        totals.requests += 1;
+        totals.tokens += entry.usage.total_tokens() as u64;
+        totals.cost += cost;
+        if rates.bills_long_context(&entry.usage) {
+            totals.long_context_requests += 1;
+            totals.long_context_cost += cost;
+        }
+        if entry.project.is_some() {
+            totals.project = entry.project.as_deref().map(str::to_string);
+        }
+        totals.last_activity = totals.last_activity.max(entry.timestamp);
+    }
+    let mut conversations: Vec<ConversationCost> = conversations.into_values().collect();
+    conversations.sort_by(|a, b| b.cost.total_cmp(&a.cost).then_with(|| a.conversation.cmp(&b.conversation)));
+    conversations
+}
+
+/// Requests and tokens of one model over a period, with its running total
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelUsage {
+    pub model: String,
+    pub requests: usize,
+    pub tokens: u64,
+    pub cumulative: Vec<(f64, f64)>, // minutes since the period started and tokens by then, per request
+}
+
+/// Usage per model, as `names` reports them, for entries from `since` up to `now`, busiest model
+/// first
+pub fn model_usage<'a>(
+    entries: impl Iterator<Item = &'a UsageEntry>,
+    since: DateTime<Utc>,
+    now: DateTime<Utc>,
+    default_model: Option<&str>,
+    names: &ModelNames,
+) -> Vec<ModelUsage> {
+    let mut entries: Vec<&UsageEntry> = entries.filter(|entry| entry.timestamp >= since && entry.timestamp <= now).collect();
+    entries.sort_by_key(|entry| entry.timestamp);
+
+    let mut models: BTreeMap<String, ModelUsage> = BTreeMap::new();
+    for entry in entries {
+        let model = names.name(entry.model.as_deref().or(default_model).unwrap_or("unknown"));
+        let usage = models.entry(model.clone()).or_insert_with(|| ModelUsage {
+            model,
+            requests: 0,
+            tokens: 0,
+            cumulative: vec![((entry.timestamp - since).num_seconds() as f64 / 60.0, 0.0)],
+        });
+        usage.requests += 1;
+        usage.tokens += entry.usage.total_tokens() as u64;
+        usage.cumulative.push(((entry.timestamp - since).num_seconds() as f64 / 60.0, usage.tokens as f64));
+    }
+
+    let mut usage: Vec<ModelUsage> = models.into_values().collect();
+    usage.sort_by(|a, b| b.tokens.cmp(&a.tokens).then_with(|| a.model.cmp(&b.model)));
+    usage
+}
+
+/// Response times and output speed of one model's requests that logged timings
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelLatency {
+    pub model: String,
+    pub requests: usize,                 // requests with a logged duration
+    pub duration_ms: [u64; 3],           // 50th, 90th and 99th percentile
+    pub ttft_ms: Option<[u64; 3]>,       // same, for requests that logged time to first token
+    pub output_tokens_per_second: f64,   // output tokens over the time spent generating them
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[u64], pct: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (pct / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Latency percentiles and throughput per model, as `names` reports them, for entries since
+/// `since` that logged a duration, busiest model first
+pub fn model_latency<'a>(
+    entries: impl Iterator<Item = &'a UsageEntry>,
+    since: DateTime<Utc>,
+    default_model: Option<&str>,
+    names: &ModelNames,
+) -> Vec<ModelLatency> {
+    #[derive(Default)]
+    struct Timings {
+        durations: Vec<u64>,
+        ttfts: Vec<u64>,
+        output_tokens: u64,
+        generating_ms: u64,
+    }
+
+    let mut models: BTreeMap<String, Timings> = BTreeMap::new();
+    for entry in entries.filter(|entry| entry.timestamp >= since) {
+        let Some(duration) = entry.duration_ms else {
+            continue;
+        };
+        let model = names.name(entry.model.as_deref().or(default_model).unwrap_or("unknown"));
+        let timings = models.entry(model).or_default();
+        timings.durations.push(duration);
+        timings.ttfts.extend(entry.ttft_ms);
+        // Output streams after the first token, so time before it is not part of the rate
+        timings.generating_ms += duration.saturating_sub(entry.ttft_ms.unwrap_or(0));
+        timings.output_tokens += entry.usage.output_tokens as u64;
+    }
+
+    let mut latencies: Vec<ModelLatency> = models
+        .into_iter()
+        .map(|(model, mut timings)| {
+            timings.durations.sort_unstable();
+            timings.ttfts.sort_unstable();
+            let percentiles = |sorted: &[u64]| [50.0, 90.0, 99.0].map(|pct| percentile(sorted, pct));
+            ModelLatency {
+                model,
+                requests: timings.durations.len(),
+                duration_ms: percentiles(&timings.durations),
+                ttft_ms: (!timings.ttfts.is_empty()).then(|| percentiles(&timings.ttfts)),
+                output_tokens_per_second: if timings.generating_ms > 0 {
+                    timings.output_tokens as f64 * 1000.0 / timings.generating_ms as f64
+                } else {
+                    0.0
+                },
+            }
+        })
+        .collect();
+    latencies.sort_by(|a, b| b.requests.cmp(&a.requests).then_with(|| a.model.cmp(&b.model)));
+    latencies
+}
+
+/// Failed requests among all requests made
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ErrorRate {
+    pub requests: usize, // successful and failed
+    pub errors: usize,
+}
+
+impl ErrorRate {
+    /// Share of requests that failed, 0.0 to 1.0
+    pub fn rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.errors as f64 / self.requests as f64
+        }
+    }
+}
+
+/// Error rates over a period, overall, per UTC day and per model
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ErrorRates {
+    pub total: ErrorRate,
+    pub by_day: BTreeMap<NaiveDate, ErrorRate>,
+    pub by_model: BTreeMap<String, ErrorRate>,
+}
+
+/// Error rates of the Claude Code requests from `since` until `until`.
+///
+/// Failed requests that logged no usage are added to the requests of `entries`; models default
+/// to `default_model`, or "unknown", and are reported under the names `names` gives them.
+pub fn error_rates<'a>(
+    entries: impl Iterator<Item = &'a UsageEntry>,
+    errors: &[RequestError],
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    default_model: Option<&str>,
+    names: &ModelNames,
+) -> ErrorRates {
+    let mut rates = ErrorRates::default();
+    let mut count = |timestamp: DateTime<Utc>, model: Option<&str>, request: bool, error: bool| {
+        if timestamp < since || timestamp >= until {
+            return;
+        }
+        let model = model.or(default_model).unwrap_or("unknown");
+        let day = rates.by_day.entry(timestamp.date_naive()).or_default();
+        let by_model = rates.by_model.entry(names.name(model)).or_default();
+        for rate in [&mut rates.total, day, by_model] {
+            rate.requests += usize::from(request);
+            rate.errors += usize::from(error);
+        }
+    };
+    for entry in entries {
+        count(entry.timestamp, entry.model.as_deref(), true, false);
+    }
+    for error in errors {
+        count(error.timestamp, error.model.as_deref(), !error.has_usage, true);
+    }
+    rates
+}
+
+/// Monday of the week containing `day`
+pub fn week_start(day: NaiveDate) -> NaiveDate {
+    day - Duration::days(day.weekday().num_days_from_monday() as i64)
+}
+
+/// The day a cycle renewing on `billing_day` starts in the month of `month`, or the month's last
+/// day when it is shorter (a 31st anniversary renews on February 28th or 29th)
+fn cycle_start_in(month: NaiveDate, billing_day: u32) -> NaiveDate {
+    let first = month.with_day(1).unwrap_or(month);
+    let last = first
+        .checked_add_months(Months::new(1))
+        .and_then(|next| next.pred_opt())
+        .map_or(28, |last| last.day());
+    first.with_day(billing_day.clamp(1, last)).unwrap_or(first)
+}
+
+/// First day of the billing cycle containing `day`, for a subscription renewing on `billing_day`
+pub fn billing_cycle_start(day: NaiveDate, billing_day: u32) -> NaiveDate {
+    let this_month = cycle_start_in(day, billing_day);
+    if day >= this_month {
+        this_month
+    } else {
+        cycle_start_in(day - Months::new(1), billing_day)
+    }
+}
+
+/// First day of the cycle after the one starting on `start`
+pub fn next_billing_cycle(start: NaiveDate, billing_day: u32) -> NaiveDate {
+    let next_month = start.with_day(1).unwrap_or(start) + Months::new(1);
+    cycle_start_in(next_month, billing_day)
+}
+
+/// Usage for every UTC day from `first` to `last`, inclusive
+pub fn daily_usage<'a>(
+    entries: impl Iterator<Item = &'a UsageEntry>,
+    first: NaiveDate,
+    last: NaiveDate,
+    prices: Prices,
+) -> Vec<(NaiveDate, PeriodUsage)> {
+    let mut days: Vec<(NaiveDate, PeriodUsage)> = first
+        .iter_days()
+        .take_while(|day| *day <= last)
+        .map(|day| (day, PeriodUsage::default()))
+        .collect();
+    for entry in entries {
+        let day = entry.timestamp.date_naive();
+        if day < first || day > last {
+            continue;
+        }
+        days[(day - first).num_days() as usize].1.add(entry, prices);
+    }
+    days
+}
+
+/// What a goal limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GoalMetric {
+    Tokens,
+    Cost,
+}
+
+/// Usage so far against one configured goal
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoalProgress {
+    pub period: GoalPeriod,
+    pub metric: GoalMetric,
+    pub used: f64,
+    pub goal: f64,
+}
+
+impl GoalProgress {
+    pub fn ratio(&self) -> f64 {
+        self.used / self.goal
+    }
+
+    pub fn met(&self) -> bool {
+        self.used <= self.goal
+    }
+}
+
+/// Progress against every configured goal, given the usage of the current day, week and billing cycle
+pub fn goal_progress(goals: &GoalsConfig, day: PeriodUsage, week: PeriodUsage, month: PeriodUsage) -> Vec<GoalProgress> {
+    let goal = |period, metric, used: f64, goal: Option<f64>| {
+        goal.map(|goal| GoalProgress { period, metric, used, goal })
+    };
+    [
+        goal(GoalPeriod::Day, GoalMetric::Tokens, day.tokens as f64, goals.daily_tokens.map(|t| t as f64)),
+        goal(GoalPeriod::Day, GoalMetric::Cost, day.cost, goals.daily_cost),
+        goal(GoalPeriod::Week, GoalMetric::Tokens, week.tokens as f64, goals.weekly_tokens.map(|t| t as f64)),
+        goal(GoalPeriod::Week, GoalMetric::Cost, week.cost, goals.weekly_cost),
+        goal(GoalPeriod::Month, GoalMetric::Tokens, month.tokens as f64, goals.monthly_tokens.map(|t| t as f64)),
+        goal(GoalPeriod::Month, GoalMetric::Cost, month.cost, goals.monthly_cost),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Progress for today, the current week and the current billing cycle as of `now`
+pub fn current_goal_progress<'a>(
+    entries: impl Iterator<Item = &'a UsageEntry>,
+    goals: &GoalsConfig,
+    now: DateTime<Utc>,
+    prices: Prices,
+) -> Vec<GoalProgress> {
+    if goals.is_empty() {
+        return Vec::new();
+    }
+    let today = now.date_naive();
+    let (week_first, cycle_first) = (week_start(today), billing_cycle_start(today, goals.billing_day()));
+    let days = daily_usage(entries, week_first.min(cycle_first), today, prices);
+    let (mut week, mut month) = (PeriodUsage::default(), PeriodUsage::default());
+    for (day, usage) in &days {
+        if *day >= week_first {
+            week += *usage;
+        }
+        if *day >= cycle_first {
+            month += *usage;
+        }
+    }
+    let day = days.last().map(|(_, usage)| *usage).unwrap_or_default();
+    goal_progress(goals, day, week, month)
+}
+
+/// Usage today and in the calendar month so far (UTC days), as of `now`
+pub fn today_and_month_to_date<'a>(
+    entries: impl Iterator<Item = &'a UsageEntry>,
+    now: DateTime<Utc>,
+    prices: Prices,
+) -> (PeriodUsage, PeriodUsage) {
+    let today = now.date_naive();
+    let days = daily_usage(entries.filter(|entry| entry.timestamp <= now), today.with_day(1).unwrap_or(today), today, prices);
+    let mut month = PeriodUsage::default();
+    for (_, usage) in &days {
+        month += *usage;
+    }
+    (days.last().map(|(_, usage)| *usage).unwrap_or_default(), month)
+}
+
+/// Projects a weekly summary lists, most expensive first
+pub const WEEKLY_TOP_PROJECTS: usize = 5;
+
+/// A finished week's usage, for the weekly summary alert
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WeeklySummary {
+    pub week_start: NaiveDate, // Monday, UTC
+    pub usage: PeriodUsage,
+    pub requests: usize,
+    pub top_projects: Vec<(String, PeriodUsage)>, // by label, most expensive first
+    pub limit_hits: usize,                        // limit-reached notices Claude Code logged
+}
+
+impl WeeklySummary {
+    /// The summary as plain text, one fact per line
+    pub fn report(&self) -> String {
+        let mut lines = vec![
+            format!("Claude usage for the week of {} (UTC)", self.week_start.format("%Y-%m-%d")),
+            String::new(),
+            format!("Tokens: {} in {} requests", self.usage.tokens, self.requests),
+            format!("API-equivalent cost: ${:.2}", self.usage.cost),
+            format!("Usage limit hit: {} times", self.limit_hits),
+        ];
+        if !self.top_projects.is_empty() {
+            lines.push(String::new());
+            lines.push("Top projects:".to_string());
+            for (project, usage) in &self.top_projects {
+                lines.push(format!("  {project}: {} tokens, ${:.2}", usage.tokens, usage.cost));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// Usage in the week (Monday to Sunday, UTC) starting on `week_start`, with projects named by
+/// `project_label` and entries priced by `prices`
+pub fn weekly_summary<'a>(
+    entries: impl Iterator<Item = &'a UsageEntry>,
+    limit_events: &[LimitEvent],
+    week_start: NaiveDate,
+    project_label: impl Fn(&str) -> String,
+    prices: Prices,
+) -> WeeklySummary {
+    let week_end = week_start + Duration::days(7);
+    let in_week = |timestamp: DateTime<Utc>| timestamp.date_naive() >= week_start && timestamp.date_naive() < week_end;
+    let mut summary = WeeklySummary { week_start, ..WeeklySummary::default() };
+    let mut projects: BTreeMap<String, PeriodUsage> = BTreeMap::new();
+    for entry in entries.filter(|entry| in_week(entry.timestamp)) {
+        summary.usage.add(entry, prices);
+        summary.requests += 1;
+        if let Some(project) = entry.project.as_deref() {
+            projects.entry(project_label(project)).or_default().add(entry, prices);
+        }
+    }
+    let mut projects: Vec<(String, PeriodUsage)> = projects.into_iter().collect();
+    projects.sort_by(|a, b| b.1.cost.total_cmp(&a.1.cost).then_with(|| b.1.tokens.cmp(&a.1.tokens)));
+    projects.truncate(WEEKLY_TOP_PROJECTS);
+    summary.top_projects = projects;
+    summary.limit_hits = limit_events
+        .iter()
+        .filter(|event| event.kind == LimitEventKind::LimitReached && in_week(event.timestamp))
+        .count();
+    summary
+}
@@ -0,0 +1,64 @@
+use super::metrics_history::{MetricsHistory, Resolution};
+use super::rrd::RrdStore;
+use chrono::{DateTime, Duration, Utc};
+
+/// Changes smaller than this (5%) show as flat
+pub const FLAT_CHANGE: f64 = 0.05;
+
+/// A figure now and at the same time last week
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Delta {
+    pub current: f64,
+    pub last_week: f64,
+}
+
+impl Delta {
+    /// Relative change from last week, None when last week was zero
+    pub fn change(&self) -> Option<f64> {
+        (self.last_week > 0.0).then(|| (self.current - self.last_week) / self.last_week)
+    }
+
+    /// ▲ or ▼ with the percentage change, e.g. "▲ 23%", or "▶ 2%" when roughly flat
+    pub fn label(&self) -> Option<String> {
+        let change = self.change()?;
+        let arrow = if change.abs() < FLAT_CHANGE {
+            "▶"
+        } else if change > 0.0 {
+            "▲"
+        } else {
+            "▼"
+        };
+        Some(format!("{arrow} {:.0}%", change.abs() * 100.0))
+    }
+}
+
+/// Overview figures against the same time last week, None where the history has no sample then
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WeekOverWeek {
+    pub usage_rate: Option<Delta>,   // tokens per minute
+    pub daily_tokens: Option<Delta>, // tokens today so far
+    pub cost: Option<Delta>,         // estimated session cost
+}
+
+/// Compare the current figures with last week's from the persistent history: the metrics history
+/// snapshot of the same hour for the rate and session cost, and the trend store's cumulative count
+/// for the tokens used that day up to the same hour
+pub fn week_over_week(
+    history: Option<&MetricsHistory>,
+    store: Option<&RrdStore>,
+    now: DateTime<Utc>,
+    usage_rate: f64,
+    daily_tokens: u64,
+    cost: f64,
+) -> WeekOverWeek {
+    let last_week = now - Duration::weeks(1);
+    let snapshot = history.and_then(|history| history.merged_at(Resolution::Hour, last_week));
+    WeekOverWeek {
+        usage_rate: snapshot.as_ref().map(|snapshot| Delta { current: usage_rate, last_week: snapshot.usage_rate }),
+        daily_tokens: store.and_then(|store| store.day_tokens_until(last_week)).map(|tokens| Delta {
+            current: daily_tokens as f64,
+            last_week: tokens as f64,
+        }),
+        cost: snapshot.map(|snapshot| Delta { current: cost, last_week: snapshot.estimated_cost }),
+    }
+}
@@ -0,0 +1,90 @@
+use super::compact_tokens;
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Duration, Utc};
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use std::path::Path;
+
+const IMAGE_SIZE: (u32, u32) = (1200, 800);
+
+/// Render cumulative usage and burn rate from tokens per bucket to an SVG or PNG file, picked by
+/// the file extension
+pub fn export_usage_charts(path: &Path, buckets: &[(DateTime<Utc>, u64)], bucket: Duration) -> Result<()> {
+    let extension = path.extension().and_then(|ext| ext.to_str()).map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("svg") => draw_usage_charts(SVGBackend::new(path, IMAGE_SIZE).into_drawing_area(), buckets, bucket),
+        Some("png") => draw_usage_charts(BitMapBackend::new(path, IMAGE_SIZE).into_drawing_area(), buckets, bucket),
+        _ => bail!("Unsupported chart format for {} (use .svg or .png)", path.display()),
+    }
+}
+
+/// Cumulative tokens on top, tokens per minute of each bucket below
+fn draw_usage_charts<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    buckets: &[(DateTime<Utc>, u64)],
+    bucket: Duration,
+) -> Result<()> {
+    let failed = |e: DrawingAreaErrorKind<DB::ErrorType>| anyhow!("Failed to draw chart: {e}");
+    let (Some((start, _)), Some((last, _))) = (buckets.first(), buckets.last()) else {
+        bail!("No time range to chart");
+    };
+    let end = *last + bucket;
+    // Day-long ranges label times of day, longer ones dates
+    let label_format = if end - *start <= Duration::days(2) { "%m-%d %H:%M" } else { "%Y-%m-%d" };
+
+    let mut cumulative = vec![(*start, 0u64)];
+    let mut total = 0;
+    for (bucket_start, tokens) in buckets {
+        total += tokens;
+        cumulative.push((*bucket_start + bucket, total));
+    }
+    let minutes = (bucket.num_seconds() as f64 / 60.0).max(1.0 / 60.0);
+    let burn_rate: Vec<(DateTime<Utc>, f64)> = buckets
+        .iter()
+        .map(|(bucket_start, tokens)| (*bucket_start, *tokens as f64 / minutes))
+        .collect();
+    let peak_rate = burn_rate.iter().map(|(_, rate)| *rate).fold(0.0, f64::max);
+
+    root.fill(&WHITE).map_err(failed)?;
+    let (top, bottom) = root.split_vertically(IMAGE_SIZE.1 / 2);
+
+    let mut chart = ChartBuilder::on(&top)
+        .caption(
+            format!("Cumulative tokens, {} to {} UTC", start.format(label_format), end.format(label_format)),
+            ("sans-serif", 22),
+        )
+        .margin(15)
+        .x_label_area_size(35)
+        .y_label_area_size(70)
+        .build_cartesian_2d(*start..end, 0u64..total.max(1))
+        .map_err(failed)?;
+    chart
+        .configure_mesh()
+        .x_label_formatter(&|time| time.format(label_format).to_string())
+        .y_label_formatter(&|tokens| compact_tokens(*tokens))
+        .draw()
+        .map_err(failed)?;
+    chart
+        .draw_series(AreaSeries::new(cumulative, 0, BLUE.mix(0.2)).border_style(BLUE))
+        .map_err(failed)?;
+
+    let mut chart = ChartBuilder::on(&bottom)
+        .caption("Burn rate (tokens per minute)", ("sans-serif", 22))
+        .margin(15)
+        .x_label_area_size(35)
+        .y_label_area_size(70)
+        .build_cartesian_2d(*start..end, 0.0..peak_rate.max(1.0) * 1.1)
+        .map_err(failed)?;
+    chart
+        .configure_mesh()
+        .x_label_formatter(&|time| time.format(label_format).to_string())
+        .y_label_formatter(&|rate| compact_tokens(*rate as u64))
+        .draw()
+        .map_err(failed)?;
+    chart
+        .draw_series(LineSeries::new(burn_rate, RED.stroke_width(2)))
+        .map_err(failed)?;
+
+    root.present().map_err(failed)?;
+    Ok(())
+}
@@ -0,0 +1,281 @@
+use super::compact_tokens;
+use crate::models::{ChartGraphics, LimitEventKind, UsageMetrics};
+use crate::services::usage_stats;
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use chrono::Utc;
+use crossterm::{cursor::MoveTo, queue};
+use plotters::prelude::*;
+use ratatui::layout::Rect;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::Write;
+
+// Kitty image ID of the Overview chart, so the previous one can be replaced
+const KITTY_IMAGE_ID: u32 = 7311;
+// Cell size to assume when the terminal doesn't report its size in pixels
+const FALLBACK_CELL_PIXELS: (u32, u32) = (8, 16);
+const BACKGROUND: RGBColor = RGBColor(16, 16, 16);
+const FOREGROUND: RGBColor = RGBColor(200, 200, 200);
+
+/// Terminal image protocols the chart can be drawn with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphicsProtocol {
+    Kitty,
+    Sixel,
+}
+
+impl GraphicsProtocol {
+    /// The protocol to use for `setting`, looking at the environment for `auto`.
+    ///
+    /// Terminals can't be asked without reading their replies from stdin, so `auto` only trusts
+    /// terminals that identify themselves. Inside tmux or screen, escapes would need passthrough,
+    /// so `auto` stays off there.
+    pub fn detect(setting: ChartGraphics) -> Option<Self> {
+        match setting {
+            ChartGraphics::Off => None,
+            ChartGraphics::Kitty => Some(GraphicsProtocol::Kitty),
+            ChartGraphics::Sixel => Some(GraphicsProtocol::Sixel),
+            ChartGraphics::Auto => {
+                let var = |name: &str| std::env::var(name).unwrap_or_default();
+                let term = var("TERM");
+                if std::env::var_os("TMUX").is_some() || term.starts_with("screen") || term.starts_with("tmux") {
+                    None
+                } else if std::env::var_os("KITTY_WINDOW_ID").is_some()
+                    || term == "xterm-kitty"
+                    || term == "xterm-ghostty"
+                    || matches!(var("TERM_PROGRAM").as_str(), "WezTerm" | "ghostty")
+                {
+                    Some(GraphicsProtocol::Kitty)
+                } else if term.contains("sixel") || ["foot", "mlterm", "contour"].iter().any(|name| term.starts_with(name)) {
+                    Some(GraphicsProtocol::Sixel)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// The Overview's cumulative usage chart drawn as an image over the blank area ratatui leaves for it
+#[derive(Debug)]
+pub struct InlineChart {
+    protocol: GraphicsProtocol,
+    warning_threshold: f64,     // share of the limit marked as the warning line
+    shown: Option<(Rect, u64)>, // area and data fingerprint of the image on screen
+}
+
+impl InlineChart {
+    pub fn new(protocol: GraphicsProtocol, warning_threshold: f64) -> Self {
+        Self { protocol, warning_threshold, shown: None }
+    }
+
+    /// Draw the chart over `area`, redrawing only when the area or the data changed
+    pub fn update(&mut self, out: &mut impl Write, area: Rect, metrics: &UsageMetrics) -> Result<()> {
+        let fingerprint = {
+            let mut hasher = DefaultHasher::new();
+            for point in &metrics.usage_history {
+                (point.timestamp, point.tokens_used).hash(&mut hasher);
+            }
+            (metrics.limit_events.len(), metrics.current_session.tokens_limit).hash(&mut hasher);
+            // The forecast moves along with the clock, so redraw it once a minute
+            (metrics.idle_since, Utc::now().timestamp() / 60).hash(&mut hasher);
+            hasher.finish()
+        };
+        if self.shown == Some((area, fingerprint)) || area.width == 0 || area.height == 0 {
+            return Ok(());
+        }
+
+        let (cell_width, cell_height) = cell_pixels();
+        let (width, height) = (area.width as u32 * cell_width, area.height as u32 * cell_height);
+        let pixels = render_usage_chart(metrics, self.warning_threshold, width, height)?;
+        queue!(out, MoveTo(area.x, area.y))?;
+        match self.protocol {
+            GraphicsProtocol::Kitty => {
+                write!(out, "\x1b_Ga=d,d=I,i={KITTY_IMAGE_ID},q=2\x1b\\")?;
+                out.write_all(kitty_image(&pixels, width, height, area).as_bytes())?;
+            }
+            GraphicsProtocol::Sixel => out.write_all(sixel_image(&pixels, width, height).as_bytes())?,
+        }
+        out.flush()?;
+        self.shown = Some((area, fingerprint));
+        Ok(())
+    }
+
+    /// Take the image off the screen, when one is shown.
+    ///
+    /// Returns true when the screen must be cleared and redrawn to get rid of a sixel image, which
+    /// stays until its cells are overwritten.
+    pub fn remove(&mut self, out: &mut impl Write) -> Result<bool> {
+        if self.shown.take().is_none() {
+            return Ok(false);
+        }
+        match self.protocol {
+            GraphicsProtocol::Kitty => {
+                write!(out, "\x1b_Ga=d,d=I,i={KITTY_IMAGE_ID},q=2\x1b\\")?;
+                out.flush()?;
+                Ok(false)
+            }
+            GraphicsProtocol::Sixel => Ok(true),
+        }
+    }
+}
+
+/// Pixel size of a terminal cell
+fn cell_pixels() -> (u32, u32) {
+    match crossterm::terminal::window_size() {
+        Ok(size) if size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0 => (
+            (size.width / size.columns).max(1) as u32,
+            (size.height / size.rows).max(1) as u32,
+        ),
+        _ => FALLBACK_CELL_PIXELS,
+    }
+}
+
+/// RGB pixels of the cumulative usage chart over the session window, with limit notices marked
+/// and the forecast to the reset shaded; dashed lines mark the plan limit and `warning_threshold` of it
+pub fn render_usage_chart(metrics: &UsageMetrics, warning_threshold: f64, width: u32, height: u32) -> Result<Vec<u8>> {
+    let mut pixels = vec![0u8; width as usize * height as usize * 3];
+    {
+        let root = BitMapBackend::with_buffer(&mut pixels, (width, height)).into_drawing_area();
+        let failed = |e| anyhow!("Failed to draw chart: {e}");
+        root.fill(&BACKGROUND).map_err(failed)?;
+
+        // Plot against minutes into the window, so the forecast can run on to the reset
+        let history = &metrics.usage_history;
+        let session = &metrics.current_session;
+        let window_minutes = (session.reset_time - session.start_time).num_seconds().max(60) as f64 / 60.0;
+        let minutes_at = |index: usize| {
+            ((history[index].timestamp - session.start_time).num_seconds() as f64 / 60.0).clamp(0.0, window_minutes)
+        };
+        let forecast = usage_stats::forecast(metrics, Utc::now());
+        let forecast_max = forecast.as_ref().and_then(|forecast| forecast.high.last()).map_or(0.0, |(_, tokens)| *tokens);
+        let limit = session.tokens_limit as f64;
+        let max_tokens = history.iter().map(|point| point.tokens_used as f64).fold(forecast_max.max(limit), f64::max).max(1.0);
+        let label = |minutes: &f64| {
+            (session.start_time + chrono::Duration::seconds((*minutes * 60.0) as i64)).format("%H:%M").to_string()
+        };
+        // The block around the image carries the title
+        let mut chart = ChartBuilder::on(&root)
+            .margin(8)
+            .x_label_area_size(24)
+            .y_label_area_size(56)
+            .build_cartesian_2d(0.0..window_minutes, 0.0..max_tokens * 1.1)
+            .map_err(failed)?;
+        chart
+            .configure_mesh()
+            .bold_line_style(FOREGROUND.mix(0.15))
+            .light_line_style(FOREGROUND.mix(0.05))
+            .axis_style(FOREGROUND)
+            .label_style(("sans-serif", 12).into_font().color(&FOREGROUND))
+            .x_label_formatter(&label)
+            .y_label_formatter(&|tokens| compact_tokens(*tokens as u64))
+            .draw()
+            .map_err(failed)?;
+        for (tokens, color) in [(limit * warning_threshold, YELLOW), (limit, RED)] {
+            chart
+                .draw_series(DashedLineSeries::new([(0.0, tokens), (window_minutes, tokens)], 8, 6, color.mix(0.7).stroke_width(1)))
+                .map_err(failed)?;
+        }
+        if let Some(forecast) = &forecast {
+            // A shaded band between the low and high paths, the expected path dashed over it
+            let band: Vec<(f64, f64)> = forecast.low.iter().chain(forecast.high.iter().rev()).copied().collect();
+            chart.draw_series(std::iter::once(Polygon::new(band, CYAN.mix(0.15).filled()))).map_err(failed)?;
+            chart
+                .draw_series(DashedLineSeries::new(forecast.expected.clone(), 6, 4, CYAN.stroke_width(2)))
+                .map_err(failed)?;
+        }
+        chart
+            .draw_series(LineSeries::new(
+                (0..history.len()).map(|i| (minutes_at(i), history[i].tokens_used as f64)),
+                GREEN.stroke_width(2),
+            ))
+            .map_err(failed)?;
+
+        // Limit notices go on the first point at or after them, like on the Braille chart
+        for (kind, color) in [(LimitEventKind::ApproachingLimit, YELLOW), (LimitEventKind::LimitReached, RED)] {
+            let markers = metrics.limit_events.iter().filter(|event| event.kind == kind).filter_map(|event| {
+                let index = history
+                    .iter()
+                    .position(|point| point.timestamp >= event.timestamp)
+                    .or(history.len().checked_sub(1))?;
+                Some(Circle::new((minutes_at(index), history[index].tokens_used as f64), 4, color.filled()))
+            });
+            chart.draw_series(markers).map_err(failed)?;
+        }
+        root.present().map_err(failed)?;
+    }
+    Ok(pixels)
+}
+
+/// Kitty graphics escapes placing RGB `pixels` scaled to fit `area`, sent in 4KB chunks
+fn kitty_image(pixels: &[u8], width: u32, height: u32, area: Rect) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(pixels);
+    let chunks: Vec<&[u8]> = encoded.as_bytes().chunks(4096).collect();
+    let mut escapes = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        let chunk = std::str::from_utf8(chunk).unwrap_or_default();
+        if i == 0 {
+            escapes.push_str(&format!(
+                "\x1b_Ga=T,f=24,s={width},v={height},c={},r={},i={KITTY_IMAGE_ID},C=1,q=2,m={more};{chunk}\x1b\\",
+                area.width, area.height
+            ));
+        } else {
+            escapes.push_str(&format!("\x1b_Gm={more};{chunk}\x1b\\"));
+        }
+    }
+    escapes
+}
+
+/// Sixel image of RGB `pixels`, with colors reduced to a 6x6x6 cube
+pub fn sixel_image(pixels: &[u8], width: u32, height: u32) -> String {
+    let (width, height) = (width as usize, height as usize);
+    let level = |value: u8| (value as usize * 5 + 127) / 255;
+    let color_at = |x: usize, y: usize| {
+        let offset = (y * width + x) * 3;
+        level(pixels[offset]) * 36 + level(pixels[offset + 1]) * 6 + level(pixels[offset + 2])
+    };
+
+    let mut sixel = format!("\x1bP0;1;0q\"1;1;{width};{height}");
+    let mut used = [false; 216];
+    for y in 0..height {
+        for x in 0..width {
+            used[color_at(x, y)] = true;
+        }
+    }
+    for (color, _) in used.iter().enumerate().filter(|(_, used)| **used) {
+        let percent = |level: usize| level * 100 / 5;
+        sixel.push_str(&format!("#{color};2;{};{};{}", percent(color / 36), percent(color / 6 % 6), percent(color % 6)));
+    }
+
+    // Each band is six rows; every color in it is a pass over the band, back to its start with '$'
+    for band in (0..height).step_by(6) {
+        let rows = band..(band + 6).min(height);
+        let mut bands: std::collections::BTreeMap<usize, Vec<u8>> = std::collections::BTreeMap::new();
+        for x in 0..width {
+            for (bit, y) in rows.clone().enumerate() {
+                bands.entry(color_at(x, y)).or_insert_with(|| vec![0; width])[x] |= 1 << bit;
+            }
+        }
+        for (i, (color, bits)) in bands.iter().enumerate() {
+            if i > 0 {
+                sixel.push('$');
+            }
+            sixel.push_str(&format!("#{color}"));
+            let mut x = 0;
+            while x < width {
+                let run = bits[x..].iter().take_while(|b| **b == bits[x]).count();
+                let ch = (63 + bits[x]) as char;
+                if run > 3 {
+                    sixel.push_str(&format!("!{run}{ch}"));
+                } else {
+                    (0..run).for_each(|_| sixel.push(ch));
+                }
+                x += run;
+            }
+        }
+        sixel.push('-');
+    }
+    sixel.push_str("\x1b\\");
+    sixel
+}
@@ -0,0 +1,53 @@
+use super::compact_tokens;
+use crate::services::usage_stats::{Bucketing, Histogram};
+
+// Partial blocks give each bar eight steps per character cell
+const BAR_BLOCKS: [char; 8] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
+
+/// Horizontal bar of `value` relative to `max`, at most `width` cells wide
+fn bar(value: u64, max: u64, width: usize) -> String {
+    if max == 0 || value == 0 {
+        return String::new();
+    }
+    let eighths = ((value as f64 / max as f64) * (width * 8) as f64).round().max(1.0) as usize;
+    let (full, partial) = (eighths / 8, eighths % 8);
+    let mut bar = "█".repeat(full);
+    if let Some(block) = partial.checked_sub(1) {
+        bar.push(BAR_BLOCKS[block]);
+    }
+    bar
+}
+
+/// Render a histogram as one labelled bar per bucket
+pub fn render_histogram(histogram: &Histogram, bar_width: usize) -> String {
+    let title = match histogram.bucketing {
+        Bucketing::Hour => "Tokens by hour of day",
+        Bucketing::Day => "Tokens by day",
+        Bucketing::Weekday => "Tokens by weekday",
+    };
+    let mut lines = vec![format!(
+        "📊 {title} (UTC, last {} day{})",
+        histogram.days,
+        if histogram.days == 1 { "" } else { "s" }
+    )];
+
+    let max = histogram.tokens.iter().copied().max().unwrap_or(0);
+    let label_width = histogram.labels.iter().map(|label| label.chars().count()).max().unwrap_or(0);
+    for (label, tokens) in histogram.labels.iter().zip(&histogram.tokens) {
+        lines.push(format!(
+            "{label:>label_width$} │{:<bar_width$} {}",
+            bar(*tokens, max, bar_width),
+            if *tokens > 0 { compact_tokens(*tokens) } else { "·".to_string() }
+        ));
+    }
+
+    lines.push(format!(
+        "Total {} tokens across {} entries",
+        compact_tokens(histogram.total_tokens()),
+        histogram.entries
+    ));
+    if let Some((label, tokens)) = histogram.peak() {
+        lines.push(format!("Peak {label} with {}", compact_tokens(tokens)));
+    }
+    lines.join("\n")
+}
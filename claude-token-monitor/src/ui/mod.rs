@@ -1,6 +1,21 @@
 pub mod ratatui_ui;
+pub mod statusline;
+pub mod histogram;
+pub mod top;
+pub mod chart_export;
+pub mod graphics;
+pub mod model_colors;
 
 use crate::models::*;
+use crate::services::claude_settings;
+use crate::services::mqtt::MqttPublisher;
+use crate::services::power::PowerSaver;
+use crate::services::encryption::StateFile;
+use crate::services::file_monitor::{FileBasedTokenMonitor, METRICS_MAX_AGE};
+use crate::services::recorder::UsageRecorder;
+use crate::services::scripting::ScriptEngine;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
 // use colored::*;
 use crossterm::{
     cursor::{Hide, MoveTo, Show},
@@ -10,20 +25,88 @@ use crossterm::{
     terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use std::io::{self, Write};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 pub use ratatui_ui::RatatuiTerminalUI;
 
+/// Compact token count for tight spaces (e.g. 12.3k)
+pub(crate) fn compact_tokens(tokens: u64) -> String {
+    if tokens >= 1_000_000 {
+        format!("{:.1}M", tokens as f64 / 1_000_000.0)
+    } else if tokens >= 1_000 {
+        format!("{:.1}k", tokens as f64 / 1_000.0)
+    } else {
+        tokens.to_string()
+    }
+}
+
+/// Cell of a `width`-cell bar that `ratio` of the way along falls in, for threshold markers
+pub fn marker_cell(ratio: f64, width: u16) -> u16 {
+    ((ratio.clamp(0.0, 1.0) * width as f64) as u16).min(width.saturating_sub(1))
+}
+
+/// Horizontal lines at the warning threshold and the plan limit across `x_max`, as chart points
+pub fn threshold_lines(limit: u32, warning_threshold: f64, x_max: f64) -> [[(f64, f64); 2]; 2] {
+    let limit = limit as f64;
+    let warning = limit * warning_threshold;
+    [[(0.0, warning), (x_max, warning)], [(0.0, limit), (x_max, limit)]]
+}
+
 /// Terminal UI for displaying token usage
 pub struct TerminalUI {
     should_exit: bool,
+    refresh_requested: bool,
+    last_refresh: DateTime<Utc>,
+    config: UserConfig,
+    scripts: ScriptEngine,
+    recorder: UsageRecorder,
+    script_alerts: Vec<String>,
+    power: PowerSaver,
+    metrics_computed: Option<Instant>, // when the metrics were last recomputed from the entries
+    data_changed_at: Option<DateTime<Utc>>, // when a scan last ingested new usage
 }
 
 impl TerminalUI {
-    pub fn new(_config: UserConfig) -> Self {
-        Self {
+    pub fn new(config: UserConfig) -> Result<Self> {
+        let scripts = ScriptEngine::new(&config.scripts)?;
+        let recorder = UsageRecorder::new(&config);
+        let power = PowerSaver::new(config.low_power);
+        Ok(Self {
             should_exit: false,
-        }
+            refresh_requested: false,
+            last_refresh: Utc::now(),
+            config,
+            scripts,
+            recorder,
+            script_alerts: Vec::new(),
+            power,
+            metrics_computed: None,
+            data_changed_at: None,
+        })
+    }
+
+    /// Record a snapshot of the metrics to the history in `file` on every rescan
+    pub fn with_metrics_history(mut self, file: StateFile) -> Self {
+        self.recorder.load_metrics_history(file);
+        self
+    }
+
+    /// Feed the round-robin store in `file` on every rescan
+    pub fn with_trend_store(mut self, file: StateFile) -> Self {
+        self.recorder.load_trend_store(file);
+        self
+    }
+
+    /// Record plan switches and fired alerts to the event log in `file`
+    pub fn with_event_log(mut self, file: StateFile) -> Self {
+        self.recorder.load_event_log(file);
+        self
+    }
+
+    /// Publish the usage to an MQTT broker on every refresh
+    pub fn with_mqtt(mut self, publisher: Option<MqttPublisher>) -> Self {
+        self.recorder.set_mqtt(publisher);
+        self
     }
 
     /// Initialize terminal for full-screen display
@@ -41,9 +124,40 @@ impl TerminalUI {
     }
 
     /// Main display loop
-    pub async fn run(&mut self, metrics: &UsageMetrics) -> io::Result<()> {
+    ///
+    /// Like the Ratatui UI, every `update_interval_seconds` (or on 'r') the usage files are
+    /// rescanned through `monitor`, when one is given, alerts are re-evaluated after each scan or
+    /// every `alert_interval_seconds`, and the screen is redrawn every `redraw_interval_ms`; both
+    /// less often in low-power mode.
+    pub async fn run(&mut self, metrics: &UsageMetrics, mut monitor: Option<&mut FileBasedTokenMonitor>) -> Result<()> {
+        let mut current_metrics = metrics.clone();
+        let alert_interval = self.config.alert_interval();
+        if let Some(monitor) = monitor.as_deref() {
+            self.recorder.set_identity(monitor.subscription_hints().identity());
+            self.recorder.record(&current_metrics, monitor, Utc::now());
+            self.recorder.update_forecasts(&self.config, monitor, Utc::now());
+            self.metrics_computed = Some(Instant::now());
+            self.data_changed_at = monitor.scan_stats().data_changed_at;
+        }
+        self.check_alerts(&current_metrics);
+        let mut last_refresh = Instant::now();
+        let mut last_alert_check = Instant::now();
+
         loop {
-            self.draw_screen(metrics)?;
+            self.power.update();
+            let refresh_interval = self.power.scan_interval(self.config.scan_interval());
+            let scanned = self.refresh_requested || last_refresh.elapsed() >= refresh_interval;
+            if scanned {
+                self.refresh(&mut current_metrics, monitor.as_deref_mut()).await;
+                self.refresh_requested = false;
+                last_refresh = Instant::now();
+            }
+            if alert_interval.map_or(scanned, |interval| last_alert_check.elapsed() >= interval) {
+                self.check_alerts(&current_metrics);
+                last_alert_check = Instant::now();
+            }
+
+            self.draw_screen(&current_metrics)?;
             
             if self.handle_input().await? {
                 break;
@@ -54,9 +168,44 @@ impl TerminalUI {
         Ok(())
     }
 
+    /// Rescan usage data, recomputing the metrics only when the scan ingested something new, on
+    /// 'r', or once they are `METRICS_MAX_AGE` old
+    async fn refresh(&mut self, metrics: &mut UsageMetrics, monitor: Option<&mut FileBasedTokenMonitor>) {
+        if let Some(monitor) = monitor {
+            match monitor.scan_usage_files().await {
+                Ok(()) if !monitor.scan_stats().changed
+                    && !self.refresh_requested
+                    && self.metrics_computed.is_some_and(|at| at.elapsed() < METRICS_MAX_AGE) => {}
+                Ok(()) => {
+                    if let Some(updated) = monitor.calculate_metrics() {
+                        *metrics = updated;
+                    }
+                    self.recorder.record(metrics, monitor, Utc::now());
+                    self.recorder.update_forecasts(&self.config, monitor, Utc::now());
+                    self.metrics_computed = Some(Instant::now());
+                    self.data_changed_at = monitor.scan_stats().data_changed_at;
+                }
+                Err(e) => log::warn!("Failed to rescan usage files: {e}"),
+            }
+        }
+        self.last_refresh = Utc::now();
+    }
+
+    /// Evaluate user scripts, then send the alerts that started firing since the last refresh
+    fn check_alerts(&mut self, metrics: &UsageMetrics) {
+        if !self.scripts.is_empty() {
+            let results = self.scripts.evaluate(metrics);
+            for error in &results.errors {
+                log::debug!("Script error: {error}");
+            }
+            self.script_alerts = results.alerts;
+        }
+        self.recorder.check_alerts(metrics, &self.script_alerts);
+    }
+
     /// Handle keyboard input
     async fn handle_input(&mut self) -> io::Result<bool> {
-        if event::poll(Duration::from_millis(50))? {
+        if event::poll(self.power.redraw_interval(self.config.redraw_interval()))? {
             if let Event::Key(KeyEvent { code, modifiers, .. }) = event::read()? {
                 match code {
                     KeyCode::Char('q') | KeyCode::Esc => {
@@ -67,9 +216,7 @@ impl TerminalUI {
                         self.should_exit = true;
                         return Ok(true);
                     }
-                    KeyCode::Char('r') => {
-                        // Refresh display
-                    }
+                    KeyCode::Char('r') => self.refresh_requested = true,
                     _ => {}
                 }
             }
@@ -87,7 +234,7 @@ impl TerminalUI {
         self.draw_title(&mut stdout)?;
         
         // Session info
-        self.draw_session_info(&mut stdout, &metrics.current_session)?;
+        self.draw_session_info(&mut stdout, metrics)?;
         
         // Progress bar
         self.draw_progress_bar(&mut stdout, metrics)?;
@@ -121,13 +268,9 @@ impl TerminalUI {
     }
 
     /// Draw session information
-    fn draw_session_info(&self, stdout: &mut io::Stdout, session: &TokenSession) -> io::Result<()> {
-        let plan_str = match &session.plan_type {
-            PlanType::Pro => "Pro",
-            PlanType::Max5 => "Max5",
-            PlanType::Max20 => "Max20",
-            PlanType::Custom(limit) => &format!("Custom({limit})"),
-        };
+    fn draw_session_info(&self, stdout: &mut io::Stdout, metrics: &UsageMetrics) -> io::Result<()> {
+        let session = &metrics.current_session;
+        let plan_str = session.plan_type.name();
 
         let status_color = if session.is_active {
             Color::Green
@@ -142,39 +285,55 @@ impl TerminalUI {
             Print("Session Information:\n"),
             Print("  Plan Type: "), SetForegroundColor(Color::Cyan), Print(plan_str), ResetColor,
             Print("\n  Status: "), SetForegroundColor(status_color), Print(status_text), ResetColor,
-            Print(&format!("\n  Session ID: {}\n", &session.id[..8])),
-            Print(&format!("  Started: {}\n", session.start_time.format("%Y-%m-%d %H:%M:%S UTC"))),
-            Print(&format!("  Resets: {}\n\n", session.reset_time.format("%Y-%m-%d %H:%M:%S UTC")))
+            Print(&format!("\n  Session ID: {}\n", session.id.chars().take(8).collect::<String>())),
+            Print(&format!("  Model: {}", metrics.selected_model.as_deref().unwrap_or("default"))),
+            SetForegroundColor(Color::Yellow),
+            Print(claude_settings::model_mismatch(metrics).map(|logged| format!(" (logs show {logged})")).unwrap_or_default()),
+            ResetColor,
+            Print(&format!("\n  Started: {}\n", session.start_time.format("%Y-%m-%d %H:%M:%S UTC"))),
+            Print(&format!(
+                "  Resets: {} ({})\n\n",
+                session.reset_time.format("%Y-%m-%d %H:%M:%S UTC"),
+                metrics.reset_source()
+            ))
         )?;
         Ok(())
     }
 
-    /// Draw progress bar
+    /// Draw progress bar, with the warning threshold marked on it
     fn draw_progress_bar(&self, stdout: &mut io::Stdout, metrics: &UsageMetrics) -> io::Result<()> {
         let session = &metrics.current_session;
         let usage_percent = (session.tokens_used as f64 / session.tokens_limit as f64) * 100.0;
         let bar_width = 50;
-        let filled_width = ((usage_percent / 100.0) * bar_width as f64) as usize;
+        let filled_width = ((usage_percent / 100.0).clamp(0.0, 1.0) * bar_width as f64) as usize;
+        let marker = marker_cell(self.config.warning_threshold, bar_width as u16) as usize;
         
         let bar_color = if usage_percent > 90.0 {
             Color::Red
-        } else if usage_percent > 75.0 {
+        } else if usage_percent >= self.config.warning_threshold * 100.0 {
             Color::Yellow
         } else {
             Color::Green
         };
 
+        execute!(stdout, Print("Token Usage Progress:\n"), Print("  "))?;
+        for cell in 0..bar_width {
+            let (color, symbol) = if cell == marker {
+                (Color::Yellow, "│")
+            } else if cell < filled_width {
+                (bar_color, "█")
+            } else {
+                (Color::DarkGrey, "░")
+            };
+            execute!(stdout, SetForegroundColor(color), Print(symbol))?;
+        }
         execute!(
             stdout,
-            Print("Token Usage Progress:\n"),
-            Print("  "),
-            SetForegroundColor(bar_color),
-            Print("█".repeat(filled_width)),
-            SetForegroundColor(Color::DarkGrey),
-            Print("░".repeat(bar_width - filled_width)),
             ResetColor,
             Print(&format!(" {usage_percent:.1}%\n")),
-            Print(&format!("  {} / {} tokens used\n\n", session.tokens_used, session.tokens_limit))
+            Print(&format!("  {} / {} tokens used{}, warning at {:.0}% (│)\n\n", session.tokens_used, session.tokens_limit,
+                if metrics.limit_calibrated { " (limit calibrated from history)" } else { "" },
+                self.config.warning_threshold * 100.0))
         )?;
         Ok(())
     }
@@ -216,6 +375,8 @@ impl TerminalUI {
                 ResetColor,
                 Print(&format!(" ({})\n", depletion_time.format("%H:%M:%S UTC")))
             )?;
+        } else if let Some(idle_since) = metrics.idle_since {
+            execute!(stdout, Print(&format!("  Projected Depletion: Paused, idle since {}\n", idle_since.format("%H:%M UTC"))))?;
         } else {
             execute!(stdout, Print("  Projected Depletion: No active usage\n"))?;
         }
@@ -229,7 +390,15 @@ impl TerminalUI {
         execute!(
             stdout,
             SetForegroundColor(Color::DarkGrey),
-            Print("Controls: [Q]uit | [R]efresh | [Ctrl+C] Exit\n"),
+            Print(&format!(
+                "Updated {} (every {}s{}){} | Controls: [Q]uit | [R]efresh | [Ctrl+C] Exit\n",
+                self.last_refresh.format("%H:%M:%S UTC"),
+                self.power.scan_interval(self.config.scan_interval()).as_secs(),
+                if self.power.active() { ", low power" } else { "" },
+                self.data_changed_at
+                    .map(|at| format!(", data {} old", format_duration(Utc::now() - at)))
+                    .unwrap_or_default()
+            )),
             ResetColor
         )?;
         Ok(())
@@ -249,6 +418,31 @@ pub fn create_progress_bar(current: u32, total: u32, width: usize) -> String {
     )
 }
 
+/// One timestamped line summarizing the session, for output that isn't a terminal (logs, pipes)
+pub fn summary_line(metrics: &UsageMetrics, now: chrono::DateTime<chrono::Utc>) -> String {
+    let session = &metrics.current_session;
+    let usage_pct = if session.tokens_limit == 0 {
+        0.0
+    } else {
+        session.tokens_used as f64 / session.tokens_limit as f64 * 100.0
+    };
+    let outlook = match (&metrics.projected_depletion, metrics.idle_since) {
+        (Some(depletion), _) => format!("depletes {}", depletion.format("%H:%M UTC")),
+        (None, Some(idle_since)) => format!("idle since {}", idle_since.format("%H:%M UTC")),
+        (None, None) => format!("resets {}", session.reset_time.format("%H:%M UTC")),
+    };
+    format!(
+        "[{}] {} | {:?} | {} / {} tokens ({usage_pct:.1}%) | {:.1} tokens/min | ${:.2} | {outlook}",
+        now.format("%Y-%m-%d %H:%M:%S UTC"),
+        metrics.status(),
+        session.plan_type,
+        session.tokens_used,
+        session.tokens_limit,
+        metrics.usage_rate,
+        metrics.estimated_cost
+    )
+}
+
 /// Format time duration in human-readable format
 pub fn format_duration(duration: chrono::Duration) -> String {
     let total_seconds = duration.num_seconds();
@@ -263,4 +457,17 @@ pub fn format_duration(duration: chrono::Duration) -> String {
     } else {
         format!("{seconds}s")
     }
-}
\ No newline at end of file
+}
+
+/// Format a token count with thousands separators (e.g. 12,345)
+pub fn format_thousands(value: u32) -> String {
+    let digits = value.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+    formatted
+}
@@ -0,0 +1,43 @@
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+
+/// Shades for each model family, so versions of a family look alike but stay apart
+const OPUS: [Color; 2] = [Color::Magenta, Color::LightMagenta];
+const SONNET: [Color; 2] = [Color::Blue, Color::LightBlue];
+const HAIKU: [Color; 2] = [Color::Green, Color::LightGreen];
+const OTHER: [Color; 3] = [Color::Cyan, Color::LightCyan, Color::Gray];
+
+/// FNV-1a, which unlike the std hasher is the same across builds
+fn stable_hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// The color `model` is drawn in everywhere: its family's hue, with the shade picked by its full
+/// name so it is the same on every run. Red and yellow are left for limits and warnings.
+pub fn model_color(model: &str) -> Color {
+    let model = model.to_lowercase();
+    if model == "<synthetic>" || model == "unknown" {
+        return Color::DarkGray;
+    }
+    let shades: &[Color] = if model.contains("opus") {
+        &OPUS
+    } else if model.contains("sonnet") {
+        &SONNET
+    } else if model.contains("haiku") {
+        &HAIKU
+    } else {
+        &OTHER
+    };
+    shades[(stable_hash(&model) % shades.len() as u64) as usize]
+}
+
+/// A legend naming each of `models` next to a swatch of its color
+pub fn model_legend<'a>(models: impl IntoIterator<Item = &'a str>) -> Line<'a> {
+    let mut spans = Vec::new();
+    for model in models {
+        spans.push(Span::styled(" ■ ", Style::default().fg(model_color(model))));
+        spans.push(Span::raw(model));
+    }
+    spans.push(Span::raw(" "));
+    Line::from(spans)
+}
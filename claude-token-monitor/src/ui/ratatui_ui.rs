@@ -1,9 +1,35 @@
 use crate::models::*;
+use crate::services::claude_settings;
+use crate::services::clipboard;
+use crate::services::custom_panel;
+use crate::services::debug_bundle;
+use crate::services::mqtt::MqttPublisher;
+use crate::services::power::PowerSaver;
+use crate::services::scripting::{ScriptEngine, ScriptResults};
+use crate::services::file_monitor::{FileBasedTokenMonitor, ScanStats, SourceFilter, METRICS_MAX_AGE};
+use crate::services::cache_analysis::{self, CacheOpportunity};
+use crate::services::recommendations::{self, Priority, Recommendation};
+use crate::services::rate_limits::{self, RateLimitUsage};
+use crate::services::events::{self, EventKind, EventLog, TimelineEvent};
+use crate::services::forecast::SpendForecast;
+use crate::services::model_names::ModelNames;
+use crate::services::pricing::{self, CostBreakdown};
+use crate::services::analyzer::Analyzer;
+use crate::services::UsageAnalysis;
+use crate::services::encryption::StateFile;
+use crate::services::output_schema::StatusOutput;
+use crate::services::metrics_history::Resolution;
+use crate::services::recorder::UsageRecorder;
+use crate::services::rrd::{Consolidation, RrdStore};
+use crate::services::usage_stats::{self, ErrorRate, GoalMetric, GoalProgress, ModelLatency, ModelUsage, PeriodUsage};
+use crate::services::week_over_week::{self, Delta, WeekOverWeek};
+use super::graphics::{GraphicsProtocol, InlineChart};
+use super::model_colors;
 use anyhow::Result;
 use log::debug;
 use atty;
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    event::{self, DisableFocusChange, EnableFocusChange, Event, KeyCode, KeyEvent, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -13,21 +39,232 @@ use ratatui::{
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{
-        Axis, BarChart, Block, Borders, Chart, Dataset, GraphType, List, ListItem, Paragraph, Tabs,
-        Wrap,
+        Axis, BarChart, Block, Borders, Chart, Dataset, Gauge, GraphType, List, ListItem, Paragraph, Tabs,
+        Wrap, block::Title,
     },
     Frame, Terminal,
 };
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
+use std::collections::{BTreeMap, HashMap};
 use std::io;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
 use tokio::time::sleep;
 use humantime;
 
+/// Height of a row of stat panels in the Overview layout
+const PANEL_ROW_HEIGHT: u16 = 8;
+
+/// Tab titles in navigation order
+const TAB_TITLES: [&str; 9] = ["Overview", "Charts", "Session", "Details", "Security", "Settings", "Calendar", "Diagnostics", "About"];
+const CALENDAR_TAB: usize = 6;
+const DIAGNOSTICS_TAB: usize = 7;
+const ABOUT_TAB: usize = 8;
+const DETAILS_TAB: usize = 3;
+
+/// Categories listed on the Details tab
+const DETAIL_CATEGORIES: [&str; 11] = [
+    "📊 Token Usage Breakdown",
+    "📈 Usage Rate Analysis",
+    "⏱️ Session Timeline",
+    "💾 Cache Token Details",
+    "🔍 Model Information",
+    "📁 File Sources & Sessions",
+    "⚡ Performance Metrics",
+    "🎯 Usage Predictions",
+    "📋 Recent Activity",
+    "⚙️ Configuration",
+    "🔗 Session Links",
+];
+
+/// How long a notice such as "Copied" stays in the footer
+const NOTICE_DURATION: Duration = Duration::from_secs(3);
+
+/// Session windows shown in the Charts tab's trend
+const SESSION_TREND_WINDOWS: usize = 10;
+
+/// How recently a conversation log must have been written to count as generating, at least
+const GENERATING_WITHIN_SECONDS: u64 = 5;
+
+/// GitHub-style shades for calendar cells, from no usage to the busiest days
+const CALENDAR_SHADES: [Color; 5] = [
+    Color::Rgb(40, 44, 52),
+    Color::Rgb(14, 68, 41),
+    Color::Rgb(0, 109, 50),
+    Color::Rgb(38, 166, 65),
+    Color::Rgb(57, 211, 83),
+];
+
 /// Overview display mode for switching between views
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum OverviewViewMode {
     General,  // Current simple view with time-series chart
     Detailed, // Enhanced analytics with cache metrics and stacked bars
+    BurnDown, // Tokens left in the window against an even pace to the reset
+}
+
+/// What `y` / `Y` put on the clipboard
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CopyFormat {
+    Text,
+    Json,
+}
+
+/// Snapshot of the view state handed to the static draw functions
+#[derive(Debug, Clone, Copy)]
+struct ViewState {
+    selected_tab: usize,
+    details_selected: usize,
+    show_details_pane: bool,
+    overview_view_mode: OverviewViewMode,
+    focus_mode: bool,
+    calendar_month: NaiveDate, // first day of the month shown on the Calendar tab
+    inline_chart: bool,        // leave the Overview chart's area blank for an inline image
+    scroll_offset: usize,
+    source_filter: SourceFilter,
+    low_power: bool,
+}
+
+/// Data gathered on each refresh besides the usage metrics themselves
+#[derive(Debug, Default)]
+struct RefreshedData {
+    panel_outputs: HashMap<String, String>, // latest stdout of each custom panel command
+    script_results: ScriptResults,
+    daily_tokens: BTreeMap<NaiveDate, u64>, // all-time totals per UTC day, for the Calendar tab
+    goal_progress: Vec<GoalProgress>,       // usage in the current day, week and billing cycle against configured goals
+    spend_forecast: Option<SpendForecast>,  // the billing cycle's projected spend, when a monthly cost goal is set
+    recommendations: Vec<Recommendation>,
+    cache_opportunities: Vec<CacheOpportunity>, // repeated uncached inputs over the recommendation window
+    usage_analysis: Option<UsageAnalysis>,       // session patterns over the last few weeks, if any
+    model_latency: Vec<ModelLatency>,            // response times per model over the last week, when logged
+    model_usage: Vec<ModelUsage>,                // requests and tokens per model in the current window
+    rate_limits: Vec<RateLimitUsage>,            // recent requests and tokens per minute against the configured limits
+    events: Vec<TimelineEvent>,                  // resets, limit hits, plan switches and alerts in the current window
+    period_tokens: Vec<(&'static str, u64)>,     // tokens over the trailing 12h/24h/48h/7d
+    session_trend: Vec<(DateTime<Utc>, u64)>,    // tokens of the latest session windows, oldest first
+    session_costs: Vec<(SessionBlock, CostBreakdown)>, // the latest session windows and their cost, newest first
+    trends: TrendCharts,
+    today_tokens: u64,            // Claude Code tokens since UTC midnight
+    spend: (PeriodUsage, PeriodUsage), // usage of the selected sources today and this month (UTC), for the header
+    generating: Option<(DateTime<Utc>, Option<String>)>, // until when a conversation counts as streaming, and its project
+    error_rate: ErrorRate,        // failed Claude Code requests over the last 24 hours
+    week_over_week: WeekOverWeek, // Overview figures against the same time last week
+    diagnostics: Diagnostics,
+}
+
+/// Monitor internals shown on the Diagnostics tab, for debugging user reports
+#[derive(Debug, Default)]
+struct Diagnostics {
+    scan: ScanStats,
+    monitored_paths: usize,
+    failed_files: usize,
+    usage_entries: usize,
+    api_key_entries: usize,
+    imported_entries: usize,
+    unknown_lines: usize,
+    malformed_lines: usize,
+    invalid_lines: usize,
+    future_entries: usize,
+    oversized_entries: usize,
+    clamped_timings: usize,
+    entry_store_bytes: usize,
+    resident_bytes: Option<u64>, // the whole process, where the OS reports it
+    watcher: WatcherStats,
+}
+
+impl Diagnostics {
+    /// Take the figures of the scan `monitor` just finished
+    fn update(&mut self, monitor: &FileBasedTokenMonitor) {
+        let reports = monitor.schema_reports();
+        *self = Self {
+            scan: monitor.scan_stats(),
+            monitored_paths: monitor.get_monitored_paths().len(),
+            failed_files: monitor.failed_files().len(),
+            usage_entries: monitor.entry_count(),
+            api_key_entries: monitor.api_key_entries().len(),
+            imported_entries: monitor.imported_entries().len(),
+            unknown_lines: reports.iter().map(|report| report.unknown_lines).sum(),
+            malformed_lines: reports.iter().map(|report| report.malformed_usage_lines).sum(),
+            invalid_lines: reports.iter().map(|report| report.invalid_lines).sum(),
+            future_entries: reports.iter().map(|report| report.future_entries).sum(),
+            oversized_entries: reports.iter().map(|report| report.oversized_entries).sum(),
+            clamped_timings: reports.iter().map(|report| report.clamped_timings).sum(),
+            entry_store_bytes: monitor.entry_store_bytes(),
+            resident_bytes: resident_memory(),
+            watcher: WatcherStats { since_scan: 0, ..std::mem::take(&mut self.watcher) },
+        };
+    }
+
+    /// Take the timing of a scan that found nothing new, keeping the other figures
+    fn update_scan(&mut self, monitor: &FileBasedTokenMonitor) {
+        self.scan = monitor.scan_stats();
+        self.watcher.since_scan = 0;
+    }
+}
+
+/// File system events seen for the usage directories
+#[derive(Debug, Default)]
+struct WatcherStats {
+    watching: bool,
+    paused: bool,          // stopped in low-power mode while the terminal isn't focused
+    error: Option<String>, // why the watcher couldn't start
+    events: u64,
+    since_scan: u64, // changes the next rescan will pick up
+    errors: u64,
+}
+
+/// Resident memory of this process in bytes, from `/proc` on Linux
+fn resident_memory() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kilobytes = status.lines().find_map(|line| line.strip_prefix("VmRSS:"))?;
+    kilobytes.trim().trim_end_matches("kB").trim().parse::<u64>().ok().map(|kb| kb * 1024)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1 << 30 => format!("{:.1} GiB", b as f64 / (1u64 << 30) as f64),
+        b if b >= 1 << 20 => format!("{:.1} MiB", b as f64 / (1u64 << 20) as f64),
+        b if b >= 1 << 10 => format!("{:.1} KiB", b as f64 / (1u64 << 10) as f64),
+        b => format!("{b} B"),
+    }
+}
+
+/// Chart points read from the round-robin store, x counting buckets from the oldest
+#[derive(Debug, Default)]
+struct TrendCharts {
+    burn_rate_avg: Vec<(f64, f64)>, // hourly, over the last month
+    burn_rate_max: Vec<(f64, f64)>,
+    hours: usize,
+    daily_tokens: Vec<(f64, f64)>, // over the last 13 months
+    days: usize,
+}
+
+impl TrendCharts {
+    fn from_store(store: &RrdStore, now: chrono::DateTime<Utc>) -> Self {
+        let points = |consolidation| {
+            store
+                .burn_rate
+                .archive(Resolution::Hour, consolidation)
+                .map(|archive| archive.series(now))
+                .unwrap_or_default()
+                .into_iter()
+                .enumerate()
+                .filter_map(|(x, (_, value))| value.map(|value| (x as f64, value)))
+                .collect::<Vec<_>>()
+        };
+        let daily = store.daily_tokens(now);
+        Self {
+            burn_rate_avg: points(Consolidation::Average),
+            burn_rate_max: points(Consolidation::Max),
+            hours: store.burn_rate.archive(Resolution::Hour, Consolidation::Average).map_or(0, |archive| archive.len()),
+            daily_tokens: daily
+                .iter()
+                .enumerate()
+                .filter_map(|(x, (_, tokens))| tokens.map(|tokens| (x as f64, tokens as f64)))
+                .collect(),
+            days: daily.len(),
+        }
+    }
 }
 
 /// Enhanced terminal UI using Ratatui
@@ -39,19 +276,46 @@ pub struct RatatuiTerminalUI {
     details_selected: usize,
     show_details_pane: bool,
     overview_view_mode: OverviewViewMode,
+    focus_mode: bool,
+    calendar_month: NaiveDate,
+    source_filter: SourceFilter, // sources the Calendar, goals and usage history include
+    refresh_requested: bool,
+    config: UserConfig,
+    scripts: ScriptEngine,
+    recorder: UsageRecorder, // history, trend store, event log, MQTT and alerts
+    refreshed: RefreshedData,
+    inline_chart: Option<InlineChart>, // Overview chart as a kitty/sixel image, when enabled
+    copy_requested: Option<CopyFormat>,
+    identity: Option<AccountIdentity>, // signed-in Claude account, for copied JSON
+    notice: Option<(String, Instant)>, // footer message and when it was set
+    watcher: Option<std::sync::mpsc::Receiver<notify::Result<notify::Event>>>, // usage directory changes, counted for Diagnostics
+    power: PowerSaver,
+    focused: bool, // false after the terminal reported losing focus
+    metrics_computed: Option<Instant>, // when the metrics were last recomputed from the entries
+    scan: Option<(JoinHandle<ScanResult>, bool)>, // rescan running off the UI loop, and whether 'r' asked for it
+    panel_commands: Option<JoinHandle<HashMap<String, String>>>, // custom panel commands still running
 }
 
+/// A rescan's outcome with the monitor the scan had to itself while it ran, None without a monitor
+type ScanResult = Option<(FileBasedTokenMonitor, Result<()>)>;
+
 impl RatatuiTerminalUI {
     /// Create new Ratatui terminal UI
-    pub fn new(_config: UserConfig) -> Result<Self> {
+    pub fn new(config: UserConfig) -> Result<Self> {
         // Check if we have a TTY available
         if !atty::is(atty::Stream::Stdout) {
             return Err(anyhow::anyhow!("TTY not available - interactive UI requires a terminal"));
         }
 
+        let scripts = ScriptEngine::new(&config.scripts)?;
+        let recorder = UsageRecorder::new(&config);
+        let inline_chart = GraphicsProtocol::detect(config.chart_graphics).map(|protocol| InlineChart::new(protocol, config.warning_threshold));
+        let power = PowerSaver::new(config.low_power);
+
         enable_raw_mode()?;
         let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen)?;
+        // Terminals without focus reporting ignore the request, and the UI stays focused
+        execute!(stdout, EnterAlternateScreen, EnableFocusChange)?;
         let backend = CrosstermBackend::new(stdout);
         let terminal = Terminal::new(backend)?;
 
@@ -63,29 +327,144 @@ impl RatatuiTerminalUI {
             details_selected: 0,
             show_details_pane: false,
             overview_view_mode: OverviewViewMode::Detailed, // Default to detailed view as requested
+            focus_mode: false,
+            calendar_month: Self::month_start(Utc::now().date_naive()),
+            source_filter: SourceFilter::default(),
+            refresh_requested: false,
+            config,
+            scripts,
+            recorder,
+            refreshed: RefreshedData::default(),
+            inline_chart,
+            copy_requested: None,
+            identity: None,
+            notice: None,
+            watcher: None,
+            power,
+            focused: true,
+            metrics_computed: None,
+            scan: None,
+            panel_commands: None,
         })
     }
 
+    /// Record a snapshot of the metrics to the history in `file` on every rescan
+    pub fn with_metrics_history(mut self, file: StateFile) -> Self {
+        self.recorder.load_metrics_history(file);
+        self
+    }
+
+    /// Feed the round-robin store in `file` on every rescan and chart its long-term trends
+    pub fn with_trend_store(mut self, file: StateFile) -> Self {
+        self.recorder.load_trend_store(file);
+        if let Some(store) = self.recorder.trend_store() {
+            self.refreshed.trends = TrendCharts::from_store(store, Utc::now());
+        }
+        self
+    }
+
+    /// Record plan switches and fired alerts to the event log in `file`, and mark them on the
+    /// usage chart
+    pub fn with_event_log(mut self, file: StateFile) -> Self {
+        self.recorder.load_event_log(file);
+        self
+    }
+
+    /// Publish the usage to an MQTT broker on every refresh
+    pub fn with_mqtt(mut self, publisher: Option<MqttPublisher>) -> Self {
+        self.recorder.set_mqtt(publisher);
+        self
+    }
+
     /// Main UI loop
-    pub async fn run(&mut self, metrics: &UsageMetrics) -> Result<()> {
-        let current_metrics = metrics.clone();
+    ///
+    /// Every `update_interval_seconds` (or on 'r') the usage files are rescanned through
+    /// `monitor`, when one is given, and custom panel commands are re-run. Both run in the
+    /// background while the UI keeps drawing and reading keys. Alerts are evaluated after each
+    /// scan or every `alert_interval_seconds`, and the screen is redrawn every
+    /// `redraw_interval_ms` and after each key press.
+    ///
+    /// In low-power mode scans and redraws are spaced further apart, and while the terminal isn't
+    /// focused the file watcher is stopped and the screen is only redrawn after scans.
+    pub async fn run(&mut self, metrics: &UsageMetrics, monitor: Option<&mut FileBasedTokenMonitor>) -> Result<()> {
+        let Some(monitor) = monitor else {
+            return self.run_loop(metrics, &mut None).await;
+        };
+        // Scans run on a blocking thread that needs the monitor to itself, so the loop owns it
+        // until it returns
+        let mut owned = Some(std::mem::replace(monitor, FileBasedTokenMonitor::with_paths(Vec::new())));
+        let result = self.run_loop(metrics, &mut owned).await;
+        if let Some((task, _)) = self.scan.take() {
+            match task.await {
+                Ok(scanned) => owned = scanned.map(|(scanned, _)| scanned),
+                Err(e) => log::warn!("Rescan failed: {e}"),
+            }
+        }
+        if let Some(owned) = owned {
+            *monitor = owned;
+        }
+        result
+    }
+
+    async fn run_loop(&mut self, metrics: &UsageMetrics, monitor: &mut Option<FileBasedTokenMonitor>) -> Result<()> {
+        let mut current_metrics = metrics.clone();
+        let alert_interval = self.config.alert_interval();
+        if let Some(monitor) = monitor.as_mut() {
+            self.start_watcher(monitor);
+        }
+        if let Some(monitor) = monitor.as_ref() {
+            self.identity = monitor.subscription_hints().identity();
+            self.recorder.set_identity(self.identity.clone());
+            self.update_history(monitor, &current_metrics);
+            self.record_metrics(&current_metrics, monitor);
+            self.metrics_computed = Some(Instant::now());
+        }
+        self.start_panel_commands();
+        self.evaluate_scripts(&current_metrics);
+        self.check_alerts(&current_metrics);
+        let mut last_refresh = Instant::now();
+        let mut last_alert_check = Instant::now();
         
         loop {
             debug!("🔍 DEBUG: Main UI loop iteration - current_tab: {}, should_exit: {}", self.selected_tab, self.should_exit);
             
-            // Draw the UI
-            let metrics_clone = current_metrics.clone();
-            let selected_tab = self.selected_tab;
-            let details_selected = self.details_selected;
-            let show_details_pane = self.show_details_pane;
-            let overview_view_mode = self.overview_view_mode;
-            self.terminal.draw(move |frame| {
-                Self::draw_ui_static(frame, &metrics_clone, selected_tab, details_selected, show_details_pane, overview_view_mode);
-            })?;
+            self.count_watcher_events();
+            self.power.update();
+            let background = self.power.active() && !self.focused;
+            if let Some(monitor) = monitor.as_mut() {
+                self.pause_watcher(monitor, background);
+            }
+            let refresh_interval = self.power.scan_interval(self.config.scan_interval());
+            if self.scan.is_none() && (self.refresh_requested || last_refresh.elapsed() >= refresh_interval) {
+                self.start_refresh(monitor);
+                last_refresh = Instant::now();
+            }
+            self.finish_panel_commands().await;
+            let scanned = self.finish_refresh(&mut current_metrics, monitor).await;
+            if alert_interval.map_or(scanned, |interval| last_alert_check.elapsed() >= interval) {
+                self.check_alerts(&current_metrics);
+                last_alert_check = Instant::now();
+            }
+            
+            // Draw the UI, in the background only when there is something new
+            if !background || scanned {
+                let view = self.view_state();
+                let config = &self.config;
+                let refreshed = &self.refreshed;
+                let notice = self.notice.as_ref().filter(|(_, at)| at.elapsed() < NOTICE_DURATION).map(|(notice, _)| notice.as_str());
+                let mut chart_area = None;
+                self.terminal.draw(|frame| {
+                    chart_area = Self::draw_ui_static(frame, &current_metrics, config, refreshed, view, notice);
+                })?;
+                self.update_inline_chart(chart_area, &current_metrics);
+            }
 
             // Handle input with timeout
             let should_exit = self.handle_input().await?;
             debug!("🔍 DEBUG: handle_input returned: {should_exit}");
+            if let Some(format) = self.copy_requested.take() {
+                self.copy_view(format, &current_metrics).await;
+            }
             if should_exit {
                 debug!("🔍 DEBUG: Breaking from main loop due to handle_input returning true");
                 break;
@@ -98,14 +477,368 @@ impl RatatuiTerminalUI {
         Ok(())
     }
 
+    /// Watch the usage directories through `monitor`, recording on the Diagnostics tab whether
+    /// that worked
+    fn start_watcher(&mut self, monitor: &mut FileBasedTokenMonitor) {
+        match monitor.start_file_watcher() {
+            Ok(events) => {
+                self.watcher = Some(events);
+                self.refreshed.diagnostics.watcher.watching = true;
+            }
+            Err(e) => {
+                log::debug!("File watcher unavailable: {e}");
+                self.refreshed.diagnostics.watcher.error = Some(e.to_string());
+            }
+        }
+    }
+
+    /// Stop the file watcher while `paused`, and start it again afterwards
+    fn pause_watcher(&mut self, monitor: &mut FileBasedTokenMonitor, paused: bool) {
+        let stats = &mut self.refreshed.diagnostics.watcher;
+        if paused == stats.paused || (!paused && stats.error.is_some()) {
+            return;
+        }
+        stats.paused = paused;
+        if paused {
+            stats.watching = false;
+            self.watcher = None;
+            monitor.stop_file_watcher();
+        } else {
+            self.start_watcher(monitor);
+        }
+    }
+
+    /// Count the file system events that arrived since the last loop iteration
+    fn count_watcher_events(&mut self) {
+        let Some(events) = &self.watcher else {
+            return;
+        };
+        let stats = &mut self.refreshed.diagnostics.watcher;
+        for event in events.try_iter() {
+            match event {
+                Ok(_) => {
+                    stats.events += 1;
+                    stats.since_scan += 1;
+                }
+                Err(e) => {
+                    log::debug!("File watcher error: {e}");
+                    stats.errors += 1;
+                }
+            }
+        }
+    }
+
+    /// Recompute the views built from all scanned history (calendar, goals, recommendations); the
+    /// calendar, goals and usage history only count the selected sources
+    fn update_history(&mut self, monitor: &FileBasedTokenMonitor, metrics: &UsageMetrics) {
+        self.refreshed.diagnostics.update(monitor);
+        let names = ModelNames::from_config(&self.config);
+        self.refreshed.daily_tokens = usage_stats::daily_totals(monitor.source_entries(self.source_filter));
+        self.refreshed.goal_progress = usage_stats::current_goal_progress(
+            monitor.source_entries(self.source_filter),
+            &self.config.goals,
+            Utc::now(),
+            monitor.prices(),
+        );
+        self.refreshed.spend_forecast = self.recorder.update_forecasts(&self.config, monitor, Utc::now());
+        self.refreshed.error_rate = usage_stats::error_rates(
+            monitor.usage_entries().iter().chain(monitor.api_key_entries()),
+            monitor.request_errors(),
+            Utc::now() - chrono::Duration::hours(24),
+            Utc::now(),
+            None,
+            &names,
+        )
+        .total;
+        self.refreshed.recommendations = recommendations::recommend(monitor.usage_entries(), metrics, Utc::now());
+        self.refreshed.cache_opportunities = cache_analysis::find_cache_opportunities(
+            monitor.usage_entries().iter(),
+            Utc::now() - chrono::Duration::days(recommendations::ANALYSIS_DAYS),
+            monitor.prices(),
+        );
+        self.refreshed.usage_analysis = monitor.analyze_history(Analyzer::new(Utc::now()), &self.config.default_plan).ok();
+        self.refreshed.model_latency = usage_stats::model_latency(
+            monitor.usage_entries().iter(),
+            Utc::now() - chrono::Duration::days(recommendations::ANALYSIS_DAYS),
+            monitor.subscription_hints().model.as_deref(),
+            &names,
+        );
+        self.refreshed.model_usage = usage_stats::model_usage(
+            monitor.source_entries(self.source_filter),
+            metrics.current_session.start_time,
+            Utc::now(),
+            monitor.subscription_hints().model.as_deref(),
+            &names,
+        );
+        self.refreshed.rate_limits = rate_limits::rate_limit_usage(
+            monitor.usage_entries().iter().chain(monitor.api_key_entries()),
+            &self.config.rate_limits,
+            Utc::now(),
+            monitor.subscription_hints().model.as_deref(),
+        );
+        self.refreshed.spend = usage_stats::today_and_month_to_date(
+            monitor.source_entries(self.source_filter),
+            Utc::now(),
+            monitor.prices(),
+        );
+        self.update_generating(monitor);
+        self.refreshed.period_tokens = usage_stats::recent_period_totals(monitor.source_entries(self.source_filter), Utc::now());
+        let blocks = monitor.session_blocks();
+        self.refreshed.session_trend = usage_stats::session_trend(&blocks, SESSION_TREND_WINDOWS);
+        self.refreshed.events = events::timeline(
+            self.recorder.event_log().unwrap_or(&EventLog::default()),
+            events::observed_events(&blocks, monitor.limit_events(), Utc::now()),
+            metrics.current_session.start_time,
+        );
+        self.refreshed.session_costs = blocks
+            .into_iter()
+            .rev()
+            .take(SESSION_TREND_WINDOWS)
+            .map(|block| {
+                let cost = pricing::window_cost(monitor.usage_entries(), block.start_time, block.reset_time, monitor.prices());
+                (block, cost)
+            })
+            .collect();
+    }
+
+    /// Whether a conversation is streaming, from when its log was last written; appends carrying no
+    /// usage count too, so this follows every scan
+    fn update_generating(&mut self, monitor: &FileBasedTokenMonitor) {
+        // Outlast the refresh interval, so the indicator stays on while the log keeps growing
+        let within = chrono::Duration::seconds(GENERATING_WITHIN_SECONDS.max(self.config.update_interval_seconds + 2) as i64);
+        self.refreshed.generating = monitor.active_conversation(Utc::now(), within).map(|write| {
+            (write.modified + within, write.project.as_deref().map(|project| monitor.project_label(project)))
+        });
+    }
+
+    /// Start rescanning usage data through `monitor` on a blocking thread, and re-running custom
+    /// panel commands unless the last ones are still running
+    fn start_refresh(&mut self, monitor: &mut Option<FileBasedTokenMonitor>) {
+        let forced = std::mem::take(&mut self.refresh_requested);
+        let monitor = monitor.take();
+        self.scan = Some((
+            tokio::task::spawn_blocking(move || {
+                let mut monitor = monitor?;
+                let result = tokio::runtime::Handle::current().block_on(monitor.scan_usage_files());
+                Some((monitor, result))
+            }),
+            forced,
+        ));
+        self.start_panel_commands();
+    }
+
+    /// Take in a rescan once it has finished, returning whether one did. The metrics and the views
+    /// built from the entries are only recomputed when the scan ingested something new, on 'r', or
+    /// once they are `METRICS_MAX_AGE` old.
+    async fn finish_refresh(&mut self, metrics: &mut UsageMetrics, monitor: &mut Option<FileBasedTokenMonitor>) -> bool {
+        if !self.scan.as_ref().is_some_and(|(task, _)| task.is_finished()) {
+            return false;
+        }
+        let Some((task, forced)) = self.scan.take() else {
+            return false;
+        };
+        match task.await {
+            Ok(Some((scanned, result))) => {
+                let scanned = monitor.insert(scanned);
+                match result {
+                    Ok(()) if !scanned.scan_stats().changed
+                        && !forced
+                        && self.metrics_computed.is_some_and(|at| at.elapsed() < METRICS_MAX_AGE) =>
+                    {
+                        self.refreshed.diagnostics.update_scan(scanned);
+                        self.update_generating(scanned);
+                    }
+                    Ok(()) => {
+                        if let Some(updated) = scanned.calculate_metrics() {
+                            *metrics = updated;
+                        }
+                        self.update_history(scanned, metrics);
+                        self.record_metrics(metrics, scanned);
+                        self.metrics_computed = Some(Instant::now());
+                    }
+                    Err(e) => log::warn!("Failed to rescan usage files: {e}"),
+                }
+            }
+            Ok(None) => {} // nothing to scan, only the panels and scripts refresh
+            Err(e) => log::warn!("Rescan failed: {e}"),
+        }
+        self.evaluate_scripts(metrics);
+        true
+    }
+
+    /// Re-run the custom panel commands in the background, unless the last ones are still running
+    fn start_panel_commands(&mut self) {
+        if self.panel_commands.is_none() {
+            let layout = self.config.layout.clone();
+            self.panel_commands = Some(tokio::spawn(async move { custom_panel::run_panel_commands(&layout).await }));
+        }
+    }
+
+    /// Show the custom panel commands' output once they have all finished
+    async fn finish_panel_commands(&mut self) {
+        if !self.panel_commands.as_ref().is_some_and(JoinHandle::is_finished) {
+            return;
+        }
+        if let Some(task) = self.panel_commands.take() {
+            match task.await {
+                Ok(outputs) => self.refreshed.panel_outputs = outputs,
+                Err(e) => log::warn!("Custom panel commands failed: {e}"),
+            }
+        }
+    }
+
+    /// Add the metrics to the persistent history and trend store, saving each once per new minute,
+    /// and publish them over MQTT
+    fn record_metrics(&mut self, metrics: &UsageMetrics, monitor: &FileBasedTokenMonitor) {
+        let now = Utc::now();
+        self.recorder.record(metrics, monitor, now);
+        if let Some(store) = self.recorder.trend_store() {
+            self.refreshed.trends = TrendCharts::from_store(store, now);
+        }
+        let today = now.date_naive();
+        self.refreshed.today_tokens = monitor
+            .usage_entries()
+            .iter()
+            .filter(|entry| entry.timestamp.date_naive() == today && entry.timestamp <= now)
+            .map(|entry| entry.usage.total_tokens() as u64)
+            .sum();
+        self.refreshed.week_over_week = week_over_week::week_over_week(
+            self.recorder.metrics_history(),
+            self.recorder.trend_store(),
+            now,
+            metrics.usage_rate,
+            self.refreshed.today_tokens,
+            metrics.estimated_cost,
+        );
+    }
+
+    /// Send threshold, limit and script alerts that started firing since the last refresh
+    fn check_alerts(&mut self, metrics: &UsageMetrics) {
+        self.recorder.check_alerts(metrics, &self.refreshed.script_results.alerts);
+    }
+
+    /// Evaluate user scripts against the latest metrics
+    fn evaluate_scripts(&mut self, metrics: &UsageMetrics) {
+        if self.scripts.is_empty() {
+            return;
+        }
+        let results = self.scripts.evaluate(metrics);
+        for error in &results.errors {
+            debug!("Script error: {error}");
+        }
+        self.refreshed.script_results = results;
+    }
+
+    fn view_state(&self) -> ViewState {
+        ViewState {
+            selected_tab: self.selected_tab,
+            details_selected: self.details_selected,
+            show_details_pane: self.show_details_pane,
+            overview_view_mode: self.overview_view_mode,
+            focus_mode: self.focus_mode,
+            calendar_month: self.calendar_month,
+            inline_chart: self.inline_chart.is_some(),
+            scroll_offset: self.scroll_offset,
+            source_filter: self.source_filter,
+            low_power: self.power.active(),
+        }
+    }
+
+    /// Draw the inline chart image over `area`, or take it off the screen when the chart isn't
+    /// shown; falls back to Braille lines for the rest of the run if drawing fails
+    fn update_inline_chart(&mut self, area: Option<Rect>, metrics: &UsageMetrics) {
+        let Some(chart) = self.inline_chart.as_mut() else {
+            return;
+        };
+        let out = self.terminal.backend_mut();
+        let result = match area {
+            Some(area) => chart.update(out, area, metrics).map(|()| false),
+            None => chart.remove(out),
+        };
+        let redraw = match result {
+            Ok(redraw) => redraw,
+            Err(e) => {
+                log::warn!("Inline chart failed, drawing it with Braille lines instead: {e}");
+                self.inline_chart = None;
+                true
+            }
+        };
+        if redraw {
+            // The next draw repaints every cell, covering what is left of the image
+            if let Err(e) = self.terminal.clear() {
+                log::warn!("Failed to clear the screen: {e}");
+            }
+        }
+    }
+
+    /// Copy what's on screen to the clipboard: the open detail panel on the Details tab, the
+    /// status summary anywhere else
+    async fn copy_view(&mut self, format: CopyFormat, metrics: &UsageMetrics) {
+        let copied = if self.selected_tab == DETAILS_TAB && self.show_details_pane {
+            let category = DETAIL_CATEGORIES.get(self.details_selected).copied().unwrap_or_default();
+            let lines = Self::detail_content(metrics, &self.refreshed, self.details_selected);
+            let text = match format {
+                CopyFormat::Text => Ok(lines.join("\n")),
+                CopyFormat::Json => serde_json::to_string_pretty(&serde_json::json!({ "category": category, "lines": lines })),
+            };
+            text.map(|text| (text, "detail panel"))
+        } else {
+            let text = match format {
+                CopyFormat::Text => Ok(super::summary_line(metrics, Utc::now())),
+                CopyFormat::Json => serde_json::to_string_pretty(&StatusOutput {
+                    active_session: metrics.current_session.is_active.then(|| metrics.current_session.clone()),
+                    identity: self.identity.clone(),
+                }),
+            };
+            text.map(|text| (text, "status summary"))
+        };
+
+        let notice = match copied {
+            Ok((text, what)) => match clipboard::copy_to_clipboard(&text, self.terminal.backend_mut()).await {
+                Ok(method) => format!("📋 Copied {what} {}", method.description()),
+                Err(e) => format!("❌ Copy failed: {e}"),
+            },
+            Err(e) => format!("❌ Copy failed: {e}"),
+        };
+        self.notice = Some((notice, Instant::now()));
+    }
+
+    fn month_start(day: NaiveDate) -> NaiveDate {
+        day.with_day(1).unwrap_or(day)
+    }
+
+    /// Move the Calendar tab by whole months (negative goes back)
+    fn shift_calendar_month(&mut self, months: i32) {
+        let shifted = if months >= 0 {
+            self.calendar_month.checked_add_months(Months::new(months as u32))
+        } else {
+            self.calendar_month.checked_sub_months(Months::new(months.unsigned_abs()))
+        };
+        if let Some(month) = shifted {
+            self.calendar_month = month;
+        }
+    }
+
     /// Handle keyboard input
     async fn handle_input(&mut self) -> Result<bool> {
-        if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(KeyEvent { code, modifiers, .. }) = event::read()? {
+        if event::poll(self.power.redraw_interval(self.config.redraw_interval()))? {
+            let event = event::read()?;
+            if let Event::FocusGained | Event::FocusLost = event {
+                self.focused = matches!(event, Event::FocusGained);
+            }
+            if let Event::Key(KeyEvent { code, modifiers, .. }) = event {
                 // Debug: Log all key events
                 debug!("🔍 DEBUG: Key event - code: {:?}, modifiers: {:?}, current_tab: {}", code, modifiers, self.selected_tab);
                 
                 match code {
+                    KeyCode::Esc if self.focus_mode => {
+                        debug!("🔍 DEBUG: Esc pressed in focus view, returning to tabs");
+                        self.focus_mode = false;
+                    }
+                    KeyCode::Char('f') => {
+                        self.focus_mode = !self.focus_mode;
+                        debug!("🔍 DEBUG: 'f' key pressed - focus view: {}", self.focus_mode);
+                    }
                     KeyCode::Char('q') | KeyCode::Esc => {
                         debug!("🔍 DEBUG: Quit key pressed, exiting application");
                         self.should_exit = true;
@@ -118,12 +851,12 @@ impl RatatuiTerminalUI {
                     }
                     KeyCode::Tab => {
                         let old_tab = self.selected_tab;
-                        self.selected_tab = (self.selected_tab + 1) % 7;
+                        self.selected_tab = (self.selected_tab + 1) % TAB_TITLES.len();
                         debug!("🔍 DEBUG: Tab key pressed - changed from tab {} to tab {}", old_tab, self.selected_tab);
                     }
                     KeyCode::BackTab => {
                         let old_tab = self.selected_tab;
-                        self.selected_tab = if self.selected_tab == 0 { 6 } else { self.selected_tab - 1 };
+                        self.selected_tab = if self.selected_tab == 0 { TAB_TITLES.len() - 1 } else { self.selected_tab - 1 };
                         debug!("🔍 DEBUG: BackTab key pressed - changed from tab {} to tab {}", old_tab, self.selected_tab);
                     }
                     KeyCode::Up => {
@@ -146,36 +879,51 @@ impl RatatuiTerminalUI {
                         debug!("🔍 DEBUG: Right arrow pressed");
                         if self.selected_tab == 3 { // Details tab
                             self.show_details_pane = true;
+                        } else if self.selected_tab == CALENDAR_TAB {
+                            self.shift_calendar_month(1);
                         }
                     }
                     KeyCode::Left => {
                         debug!("🔍 DEBUG: Left arrow pressed");
                         if self.selected_tab == 3 { // Details tab
                             self.show_details_pane = false;
+                        } else if self.selected_tab == CALENDAR_TAB {
+                            self.shift_calendar_month(-1);
                         }
                     }
                     KeyCode::Char('v') => {
                         debug!("🔍 DEBUG: 'v' key pressed - toggling overview view mode");
-                        // Toggle view mode in Overview tab (Tab 0)
+                        // Cycle view mode in Overview tab (Tab 0)
                         if self.selected_tab == 0 {
                             let old_mode = self.overview_view_mode;
                             self.overview_view_mode = match self.overview_view_mode {
                                 OverviewViewMode::General => OverviewViewMode::Detailed,
-                                OverviewViewMode::Detailed => OverviewViewMode::General,
+                                OverviewViewMode::Detailed => OverviewViewMode::BurnDown,
+                                OverviewViewMode::BurnDown => OverviewViewMode::General,
                             };
                             debug!("🔍 DEBUG: Overview view mode changed from {:?} to {:?}", old_mode, self.overview_view_mode);
                         } else {
                             debug!("🔍 DEBUG: 'v' key pressed but not in Overview tab (current tab: {})", self.selected_tab);
                         }
                     }
+                    KeyCode::Char('t') if self.selected_tab == CALENDAR_TAB => {
+                        self.calendar_month = Self::month_start(Utc::now().date_naive());
+                    }
                     KeyCode::Char('r') => {
                         debug!("🔍 DEBUG: 'r' key pressed - refresh");
-                        // Refresh - could trigger a metrics update
+                        self.refresh_requested = true;
+                    }
+                    KeyCode::Char('s') => {
+                        self.source_filter = self.source_filter.next();
+                        self.refresh_requested = true;
+                        self.notice = Some((format!("Source: {}", self.source_filter.name()), Instant::now()));
                     }
+                    KeyCode::Char('y') => self.copy_requested = Some(CopyFormat::Text),
+                    KeyCode::Char('Y') => self.copy_requested = Some(CopyFormat::Json),
                     KeyCode::Char('n') => {
                         debug!("🔍 DEBUG: 'n' key pressed - alternative tab switch");
                         let old_tab = self.selected_tab;
-                        self.selected_tab = (self.selected_tab + 1) % 7;
+                        self.selected_tab = (self.selected_tab + 1) % TAB_TITLES.len();
                         debug!("🔍 DEBUG: Alternative tab switch - changed from tab {} to tab {}", old_tab, self.selected_tab);
                     }
                     _ => {
@@ -194,9 +942,23 @@ impl RatatuiTerminalUI {
     }
 
     /// Draw the main UI (static version for terminal callback)
-    fn draw_ui_static(frame: &mut Frame, metrics: &UsageMetrics, selected_tab: usize, details_selected: usize, show_details_pane: bool, overview_view_mode: OverviewViewMode) {
+    ///
+    /// Returns the area left blank for the inline chart image, if the Overview shows it.
+    fn draw_ui_static(
+        frame: &mut Frame,
+        metrics: &UsageMetrics,
+        config: &UserConfig,
+        refreshed: &RefreshedData,
+        view: ViewState,
+        notice: Option<&str>,
+    ) -> Option<Rect> {
         let size = frame.area();
 
+        if view.focus_mode {
+            Self::draw_focus_view(frame, size, metrics, config);
+            return None;
+        }
+
         // Create main layout
         let chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -209,35 +971,59 @@ impl RatatuiTerminalUI {
             .split(size);
 
         // Draw header
-        Self::draw_header(frame, chunks[0]);
+        Self::draw_header(frame, chunks[0], refreshed.spend, refreshed.generating.as_ref(), view.low_power);
 
         // Draw tabs
-        Self::draw_tabs(frame, chunks[1], selected_tab);
+        Self::draw_tabs(frame, chunks[1], view.selected_tab, view.source_filter);
 
         // Draw main content based on selected tab
-        match selected_tab {
-            0 => Self::draw_overview_tab(frame, chunks[2], metrics, overview_view_mode),
-            1 => Self::draw_charts_tab(frame, chunks[2], metrics),
-            2 => Self::draw_session_tab(frame, chunks[2], metrics),
-            3 => Self::draw_details_tab(frame, chunks[2], metrics, details_selected, show_details_pane),
+        let mut chart_area = None;
+        match view.selected_tab {
+            0 => chart_area = Self::draw_overview_tab(frame, chunks[2], metrics, config, refreshed, view),
+            1 => Self::draw_charts_tab(frame, chunks[2], metrics, refreshed),
+            2 => Self::draw_session_tab(frame, chunks[2], metrics, &refreshed.recommendations, &refreshed.session_costs),
+            3 => Self::draw_details_tab(frame, chunks[2], metrics, refreshed, view.details_selected, view.show_details_pane),
             4 => Self::draw_security_tab(frame, chunks[2]),
-            5 => Self::draw_settings_tab(frame, chunks[2]),
-            6 => Self::draw_about_tab(frame, chunks[2]),
+            5 => Self::draw_settings_tab(frame, chunks[2], config, metrics),
+            CALENDAR_TAB => Self::draw_calendar_tab(frame, chunks[2], &refreshed.daily_tokens, view.calendar_month),
+            DIAGNOSTICS_TAB => Self::draw_diagnostics_tab(frame, chunks[2], config, &refreshed.diagnostics, view.scroll_offset),
+            ABOUT_TAB => Self::draw_about_tab(frame, chunks[2]),
             _ => {}
         }
 
         // Draw footer
-        Self::draw_footer(frame, chunks[3]);
+        Self::draw_footer(frame, chunks[3], &refreshed.script_results.alerts, notice, refreshed.diagnostics.scan.data_changed_at);
+        chart_area
     }
 
-    /// Draw application header
-    fn draw_header(frame: &mut Frame, area: Rect) {
+    /// Draw the title line with the estimated spend today and this month next to the version
+    fn draw_header(
+        frame: &mut Frame,
+        area: Rect,
+        (today, month): (PeriodUsage, PeriodUsage),
+        generating: Option<&(DateTime<Utc>, Option<String>)>,
+        low_power: bool,
+    ) {
         let build_time = env!("CLAUDE_TOKEN_MONITOR_BUILD_TIME", "unknown");
         let version = env!("CARGO_PKG_VERSION");
         
-        let header_text = format!(
-            "🧠 Claude Token Monitor - Rust Edition v{version} (Built: {build_time})"
-        );
+        let mut header_text = Line::from(vec![
+            Span::raw(format!("🧠 Claude Token Monitor - Rust Edition v{version} (Built: {build_time})")),
+            Span::styled("  │  ", Style::default().fg(Color::Blue)),
+            Span::styled(format!("💲 Today ${:.2}", today.cost), Style::default().fg(Color::Yellow)),
+            Span::styled(format!(" · Month ${:.2}", month.cost), Style::default().fg(Color::Yellow).remove_modifier(Modifier::BOLD)),
+        ]);
+        if let Some((_, project)) = generating.filter(|(until, _)| Utc::now() < *until) {
+            header_text.spans.push(Span::styled("  │  ", Style::default().fg(Color::Blue)));
+            header_text.spans.push(Span::styled("● generating", Style::default().fg(Color::Green)));
+            if let Some(project) = project {
+                header_text.spans.push(Span::styled(format!(" · {project}"), Style::default().fg(Color::Magenta).remove_modifier(Modifier::BOLD)));
+            }
+        }
+        if low_power {
+            header_text.spans.push(Span::styled("  │  ", Style::default().fg(Color::Blue)));
+            header_text.spans.push(Span::styled("🔋 low power", Style::default().fg(Color::Yellow).remove_modifier(Modifier::BOLD)));
+        }
         
         let title = Paragraph::new(header_text)
             .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
@@ -250,11 +1036,137 @@ impl RatatuiTerminalUI {
         frame.render_widget(title, area);
     }
 
+    /// Draw the minimal full-screen focus view: remaining tokens, gauge and reset countdown
+    fn draw_focus_view(frame: &mut Frame, area: Rect, metrics: &UsageMetrics, config: &UserConfig) {
+        let session = &metrics.current_session;
+        let remaining = session.tokens_limit.saturating_sub(session.tokens_used);
+        let used_ratio = if session.tokens_limit > 0 {
+            (session.tokens_used as f64 / session.tokens_limit as f64).min(1.0)
+        } else {
+            0.0
+        };
+        let countdown = session.reset_time.signed_duration_since(chrono::Utc::now()).max(chrono::Duration::zero());
+        let countdown_text = format!(
+            "{:02}:{:02}:{:02}",
+            countdown.num_hours(),
+            countdown.num_minutes() % 60,
+            countdown.num_seconds() % 60
+        );
+
+        let color = if used_ratio >= 0.95 {
+            Color::Red
+        } else if used_ratio >= config.warning_threshold {
+            Color::Yellow
+        } else {
+            Color::Green
+        };
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(0),
+                Constraint::Length(1), // Remaining label
+                Constraint::Length(5), // Big remaining number
+                Constraint::Length(1),
+                Constraint::Length(3), // Gauge
+                Constraint::Length(1),
+                Constraint::Length(1), // Countdown label
+                Constraint::Length(5), // Big countdown
+                Constraint::Min(0),
+                Constraint::Length(1), // Hint
+            ])
+            .split(area);
+
+        let label_style = Style::default().fg(Color::Gray);
+        frame.render_widget(
+            Paragraph::new("REMAINING TOKENS").style(label_style).alignment(Alignment::Center),
+            chunks[1],
+        );
+        frame.render_widget(
+            Paragraph::new(Self::big_text(&super::format_thousands(remaining)))
+                .style(Style::default().fg(color).add_modifier(Modifier::BOLD))
+                .alignment(Alignment::Center),
+            chunks[2],
+        );
+
+        let gauge_area = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(20), Constraint::Percentage(60), Constraint::Percentage(20)])
+            .split(chunks[4])[1];
+        let gauge_label = format!("{:.1}% used", used_ratio * 100.0);
+        let gauge_block = Block::default().borders(Borders::ALL);
+        let gauge_inner = gauge_block.inner(gauge_area);
+        let gauge = Gauge::default()
+            .block(gauge_block)
+            .gauge_style(Style::default().fg(color))
+            .ratio(used_ratio)
+            .label(gauge_label.clone());
+        frame.render_widget(gauge, gauge_area);
+        // The gauge ends at the plan limit; mark where warnings start
+        Self::draw_gauge_marker(frame, gauge_inner, config.warning_threshold, gauge_label.len());
+
+        frame.render_widget(
+            Paragraph::new(if metrics.reset_authoritative { "RESETS IN" } else { "RESETS IN (ESTIMATED)" })
+                .style(label_style)
+                .alignment(Alignment::Center),
+            chunks[6],
+        );
+        frame.render_widget(
+            Paragraph::new(Self::big_text(&countdown_text))
+                .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
+                .alignment(Alignment::Center),
+            chunks[7],
+        );
+
+        frame.render_widget(
+            Paragraph::new("[F/Esc] Back to dashboard | [Q]uit")
+                .style(Style::default().fg(Color::DarkGray))
+                .alignment(Alignment::Center),
+            chunks[9],
+        );
+    }
+
+    /// Render text as five rows of large block glyphs (digits, ',' and ':')
+    fn big_text(text: &str) -> Vec<Line<'static>> {
+        const ROWS: usize = 5;
+        let glyph = |c: char| -> [&'static str; ROWS] {
+            match c {
+                '0' => ["███", "█ █", "█ █", "█ █", "███"],
+                '1' => [" █ ", "██ ", " █ ", " █ ", "███"],
+                '2' => ["███", "  █", "███", "█  ", "███"],
+                '3' => ["███", "  █", "███", "  █", "███"],
+                '4' => ["█ █", "█ █", "███", "  █", "  █"],
+                '5' => ["███", "█  ", "███", "  █", "███"],
+                '6' => ["███", "█  ", "███", "█ █", "███"],
+                '7' => ["███", "  █", "  █", "  █", "  █"],
+                '8' => ["███", "█ █", "███", "█ █", "███"],
+                '9' => ["███", "█ █", "███", "  █", "███"],
+                ',' => ["  ", "  ", "  ", " █", "█ "],
+                ':' => [" ", "█", " ", "█", " "],
+                _ => ["   ", "   ", "   ", "   ", "   "],
+            }
+        };
+
+        (0..ROWS)
+            .map(|row| {
+                let line = text
+                    .chars()
+                    .map(|c| glyph(c)[row])
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                Line::from(line)
+            })
+            .collect()
+    }
+
     /// Draw tab navigation
-    fn draw_tabs(frame: &mut Frame, area: Rect, selected_tab: usize) {
-        let tab_titles = vec!["Overview", "Charts", "Session", "Details", "Security", "Settings", "About"];
-        let tabs = Tabs::new(tab_titles)
-            .block(Block::default().borders(Borders::ALL).title("Navigation"))
+    fn draw_tabs(frame: &mut Frame, area: Rect, selected_tab: usize, source_filter: SourceFilter) {
+        let title = match source_filter {
+            SourceFilter::All => "Navigation".to_string(),
+            filter => format!("Navigation (source: {})", filter.name()),
+        };
+        let tabs = Tabs::new(TAB_TITLES)
+            .block(Block::default().borders(Borders::ALL).title(title))
             .style(Style::default().fg(Color::White))
             .highlight_style(
                 Style::default()
@@ -266,13 +1178,17 @@ impl RatatuiTerminalUI {
     }
 
     /// Draw overview tab with key metrics
-    fn draw_overview_tab(frame: &mut Frame, area: Rect, metrics: &UsageMetrics, view_mode: OverviewViewMode) {
-        // Split the area vertically for session info and time-series chart
+    ///
+    /// Returns the area left blank for the inline chart image, when it replaces the Braille chart.
+    fn draw_overview_tab(frame: &mut Frame, area: Rect, metrics: &UsageMetrics, config: &UserConfig, refreshed: &RefreshedData, view: ViewState) -> Option<Rect> {
+        // Split the area vertically for session info, goals (when configured) and time-series chart
+        let goals_height = if refreshed.goal_progress.is_empty() { 0 } else { refreshed.goal_progress.len() as u16 + 2 };
         let vertical_chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Length(10), // Top row: session info + predictions
-                Constraint::Min(12),    // Time-series strip chart (replaces gauge + statistics)
+                Constraint::Length(10),           // Top row: session info + predictions
+                Constraint::Length(goals_height), // Goal progress bars
+                Constraint::Min(12),              // Time-series strip chart (replaces gauge + statistics)
             ])
             .split(area);
 
@@ -285,70 +1201,389 @@ impl RatatuiTerminalUI {
             .split(vertical_chunks[0]);
 
         // Left: Session information with filename
-        Self::draw_session_info_with_filename(frame, top_row_chunks[0], &metrics.current_session);
+        Self::draw_session_info_with_filename(frame, top_row_chunks[0], metrics, refreshed);
         // Right: Session predictions and recommendations
-        Self::draw_session_predictions(frame, top_row_chunks[1], metrics);
+        let rate_delta = refreshed.week_over_week.usage_rate.as_ref();
+        Self::draw_session_predictions(frame, top_row_chunks[1], metrics, &refreshed.recommendations, rate_delta);
+
+        if goals_height > 0 {
+            Self::draw_goal_progress(
+                frame,
+                vertical_chunks[1],
+                &refreshed.goal_progress,
+                refreshed.spend_forecast.as_ref(),
+                config.warning_threshold,
+            );
+        }
 
         // Draw based on view mode
-        match view_mode {
+        match view.overview_view_mode {
+            OverviewViewMode::General if view.inline_chart && !metrics.usage_history.is_empty() => {
+                // Blank area for the time-series chart image drawn after the frame
+                let block = Block::default()
+                    .title("Token Usage Over Time (Cumulative)")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Green));
+                let inner = block.inner(vertical_chunks[2]);
+                frame.render_widget(block, vertical_chunks[2]);
+                return Some(inner);
+            }
             OverviewViewMode::General => {
                 // Current simple view with time-series chart
-                Self::draw_token_usage_strip_chart(frame, vertical_chunks[1], metrics);
+                Self::draw_token_usage_strip_chart(frame, vertical_chunks[2], metrics, &refreshed.events, config.warning_threshold);
             }
             OverviewViewMode::Detailed => {
-                // Enhanced analytics with cache metrics and stacked bars
-                Self::draw_detailed_analytics_view(frame, vertical_chunks[1], metrics);
+                // Enhanced analytics laid out from the [layout] config section
+                Self::draw_detailed_analytics_view(frame, vertical_chunks[2], metrics, config, refreshed);
+            }
+            OverviewViewMode::BurnDown => {
+                Self::draw_burn_down_chart(frame, vertical_chunks[2], metrics, config.warning_threshold);
+            }
+        }
+        None
+    }
+
+    /// Draw a gauge per goal; the monthly cost goal also shows the spend forecast, turning yellow
+    /// while within the budget but on pace to exceed it
+    fn draw_goal_progress(
+        frame: &mut Frame,
+        area: Rect,
+        goals: &[GoalProgress],
+        forecast: Option<&SpendForecast>,
+        warning_threshold: f64,
+    ) {
+        let block = Block::default().borders(Borders::ALL).title("🎯 Goals (UTC)");
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(goals.iter().map(|_| Constraint::Length(1)))
+            .split(inner);
+        for (goal, row) in goals.iter().zip(rows.iter()) {
+            let ratio = goal.ratio();
+            let forecast = forecast.filter(|_| goal.period == GoalPeriod::Month && goal.metric == GoalMetric::Cost);
+            let color = if ratio > 1.0 {
+                Color::Red
+            } else if ratio >= warning_threshold || forecast.is_some_and(|forecast| forecast.exceeds(goal.goal)) {
+                Color::Yellow
+            } else {
+                Color::Green
+            };
+            let period = match goal.period {
+                GoalPeriod::Day => "Today",
+                GoalPeriod::Week => "This week",
+                GoalPeriod::Month => "This billing cycle",
+            };
+            let amounts = match goal.metric {
+                GoalMetric::Tokens => format!(
+                    "{} / {} tokens",
+                    super::compact_tokens(goal.used as u64),
+                    super::compact_tokens(goal.goal as u64)
+                ),
+                GoalMetric::Cost => format!("${:.2} / ${:.2}", goal.used, goal.goal),
+            };
+            let mut label = format!("{period}: {amounts} ({:.0}%)", ratio * 100.0);
+            if let Some(forecast) = forecast {
+                label.push_str(&format!(", forecast ${:.2}", forecast.expected()));
+            }
+            let label_width = label.chars().count();
+            let gauge = Gauge::default()
+                .gauge_style(Style::default().fg(color).bg(Color::DarkGray))
+                .ratio(ratio.clamp(0.0, 1.0))
+                .label(label);
+            frame.render_widget(gauge, *row);
+            Self::draw_gauge_marker(frame, *row, warning_threshold, label_width);
+        }
+    }
+
+    /// Draw the Calendar tab: one month of daily token totals as shaded cells
+    fn draw_calendar_tab(frame: &mut Frame, area: Rect, daily_tokens: &BTreeMap<NaiveDate, u64>, month: NaiveDate) {
+        let weeks = usage_stats::calendar_weeks(month);
+        let month_days: Vec<(NaiveDate, u64)> = weeks
+            .iter()
+            .flatten()
+            .flatten()
+            .map(|day| (*day, daily_tokens.get(day).copied().unwrap_or(0)))
+            .collect();
+        let max = month_days.iter().map(|(_, tokens)| *tokens).max().unwrap_or(0);
+        let total: u64 = month_days.iter().map(|(_, tokens)| tokens).sum();
+        let active_days = month_days.iter().filter(|(_, tokens)| *tokens > 0).count();
+        let today = Utc::now().date_naive();
+
+        let outer = Block::default()
+            .borders(Borders::ALL)
+            .title(format!("📅 {} (UTC)", month.format("%B %Y")));
+        let inner = outer.inner(area);
+        frame.render_widget(outer, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(42), Constraint::Length(30)])
+            .split(inner);
+        let grid_rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                std::iter::once(Constraint::Length(1))
+                    .chain(weeks.iter().map(|_| Constraint::Ratio(1, weeks.len() as u32)))
+                    .chain(std::iter::once(Constraint::Length(1))),
+            )
+            .split(chunks[0]);
+        let columns = |row: Rect| {
+            Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Ratio(1, 7); 7])
+                .split(row)
+        };
+
+        for (cell, name) in columns(grid_rows[0]).iter().zip(["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]) {
+            frame.render_widget(
+                Paragraph::new(name).style(Style::default().fg(Color::Gray)).alignment(Alignment::Center),
+                *cell,
+            );
+        }
+
+        for (week, row) in weeks.iter().zip(&grid_rows[1..grid_rows.len() - 1]) {
+            for (day, cell) in week.iter().zip(columns(*row).iter()) {
+                let Some(day) = day else { continue };
+                let tokens = daily_tokens.get(day).copied().unwrap_or(0);
+                let level = usage_stats::intensity_level(tokens, max);
+                let mut day_style = Style::default().add_modifier(Modifier::BOLD);
+                if *day == today {
+                    day_style = day_style.fg(Color::Yellow).add_modifier(Modifier::UNDERLINED);
+                }
+                let lines = vec![
+                    Line::from(Span::styled(day.day().to_string(), day_style)),
+                    Line::from(if tokens > 0 { super::compact_tokens(tokens) } else { String::new() }),
+                ];
+                let cell_area = Rect { width: cell.width.saturating_sub(1), ..*cell };
+                frame.render_widget(
+                    Paragraph::new(lines)
+                        .style(Style::default().bg(CALENDAR_SHADES[level]).fg(Color::White))
+                        .alignment(Alignment::Center),
+                    cell_area,
+                );
             }
         }
+
+        let mut legend = vec![Span::styled("Less ", Style::default().fg(Color::Gray))];
+        legend.extend(CALENDAR_SHADES.iter().map(|shade| Span::styled("  ", Style::default().bg(*shade))));
+        legend.push(Span::styled(" More", Style::default().fg(Color::Gray)));
+        frame.render_widget(Paragraph::new(Line::from(legend)).alignment(Alignment::Center), grid_rows[grid_rows.len() - 1]);
+
+        let busiest = month_days.iter().filter(|(_, tokens)| *tokens > 0).max_by_key(|(_, tokens)| *tokens);
+        let mut summary = vec![
+            Line::from(vec![
+                Span::styled("Total: ", Style::default().fg(Color::Gray)),
+                Span::raw(super::format_thousands(u32::try_from(total).unwrap_or(u32::MAX))),
+            ]),
+            Line::from(vec![
+                Span::styled("Active days: ", Style::default().fg(Color::Gray)),
+                Span::raw(format!("{active_days} / {}", month_days.len())),
+            ]),
+            Line::from(vec![
+                Span::styled("Daily average: ", Style::default().fg(Color::Gray)),
+                Span::raw(super::compact_tokens(total / active_days.max(1) as u64)),
+            ]),
+        ];
+        if let Some((day, tokens)) = busiest {
+            summary.push(Line::from(vec![
+                Span::styled("Busiest: ", Style::default().fg(Color::Gray)),
+                Span::raw(format!("{} ({})", day.format("%a %d"), super::compact_tokens(*tokens))),
+            ]));
+        }
+        summary.push(Line::from(""));
+        summary.push(Line::from(Span::styled("[←→] Month  [T] This month", Style::default().fg(Color::DarkGray))));
+        frame.render_widget(
+            Paragraph::new(summary).block(Block::default().borders(Borders::LEFT).title(" Month ")),
+            chunks[1],
+        );
     }
 
     /// Draw charts tab with bar charts
-    fn draw_charts_tab(frame: &mut Frame, area: Rect, metrics: &UsageMetrics) {
+    fn draw_charts_tab(frame: &mut Frame, area: Rect, metrics: &UsageMetrics, refreshed: &RefreshedData) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Length(12), // Token usage bar chart
-                Constraint::Min(8),     // Usage history chart
+                Constraint::Length(10), // Token usage bar chart
+                Constraint::Min(12),    // Usage history chart
+                Constraint::Length(10), // Long-term trends
             ])
             .split(area);
 
-        // Token usage horizontal bar chart
-        Self::draw_token_usage_chart(frame, chunks[0], metrics);
+        // Token usage horizontal bar chart, next to the running total of each model
+        let top = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(chunks[0]);
+        Self::draw_token_usage_chart(frame, top[0], metrics);
+        Self::draw_model_usage_chart(frame, top[1], metrics, &refreshed.model_usage);
 
         // Usage history over time
-        Self::draw_usage_history_chart(frame, chunks[1], metrics);
+        Self::draw_usage_history_chart(frame, chunks[1], &refreshed.period_tokens, &refreshed.session_trend);
+
+        Self::draw_trend_charts(frame, chunks[2], &refreshed.trends);
     }
 
-    /// Draw session tab with detailed session info
-    fn draw_session_tab(frame: &mut Frame, area: Rect, metrics: &UsageMetrics) {
+    /// Draw each model's cumulative tokens over the current window in its color, with the legend
+    /// in the title since the chart is too short for ratatui's own
+    fn draw_model_usage_chart(frame: &mut Frame, area: Rect, metrics: &UsageMetrics, models: &[ModelUsage]) {
+        let session = &metrics.current_session;
+        let window_minutes = (session.reset_time - session.start_time).num_seconds().max(60) as f64 / 60.0;
+        let tokens_max = models.iter().map(|model| model.tokens).max().unwrap_or(0).max(1) as f64;
+        let datasets: Vec<Dataset> = models
+            .iter()
+            .map(|model| {
+                Dataset::default()
+                    .marker(ratatui::symbols::Marker::Braille)
+                    .graph_type(GraphType::Line)
+                    .style(Style::default().fg(model_colors::model_color(&model.model)))
+                    .data(&model.cumulative)
+            })
+            .collect();
+        let title = if models.is_empty() {
+            Line::from("Tokens by Model (no requests this window)")
+        } else {
+            let mut title = Line::from("Tokens by Model:");
+            title.spans.extend(model_colors::model_legend(models.iter().map(|model| model.model.as_str())).spans);
+            title
+        };
+
+        let chart = Chart::new(datasets)
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .x_axis(Axis::default().bounds([0.0, window_minutes]).labels(vec![
+                session.start_time.format("%H:%M").to_string(),
+                session.reset_time.format("%H:%M").to_string(),
+            ]))
+            .y_axis(Axis::default().bounds([0.0, tokens_max]).labels(vec!["0".to_string(), super::compact_tokens(tokens_max as u64)]));
+        frame.render_widget(chart, area);
+    }
+
+    /// Draw the hourly burn rate over the last month and tokens per day over the last year
+    fn draw_trend_charts(frame: &mut Frame, area: Rect, trends: &TrendCharts) {
         let chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
             .split(area);
 
+        let rate_max = trends.burn_rate_max.iter().map(|(_, rate)| *rate).fold(0.0, f64::max).max(1.0);
+        let rate_chart = Chart::new(vec![
+            Dataset::default()
+                .name("max")
+                .marker(ratatui::symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::Red))
+                .data(&trends.burn_rate_max),
+            Dataset::default()
+                .name("avg")
+                .marker(ratatui::symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::Yellow))
+                .data(&trends.burn_rate_avg),
+        ])
+        .block(Block::default().title("Burn Rate, Hourly (tokens/min, 31 days)").borders(Borders::ALL))
+        .x_axis(Axis::default().bounds([0.0, trends.hours.max(1) as f64]).labels(vec!["-31d", "now"]))
+        .y_axis(Axis::default().bounds([0.0, rate_max]).labels(vec!["0".to_string(), format!("{rate_max:.0}")]));
+        frame.render_widget(rate_chart, chunks[0]);
+
+        let tokens_max = trends.daily_tokens.iter().map(|(_, tokens)| *tokens).fold(0.0, f64::max).max(1.0);
+        let tokens_chart = Chart::new(vec![Dataset::default()
+            .marker(ratatui::symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Green))
+            .data(&trends.daily_tokens)])
+        .block(Block::default().title("Tokens per Day (13 months)").borders(Borders::ALL))
+        .x_axis(Axis::default().bounds([0.0, trends.days.max(1) as f64]).labels(vec!["-13mo", "now"]))
+        .y_axis(Axis::default().bounds([0.0, tokens_max]).labels(vec!["0".to_string(), format!("{tokens_max:.0}")]));
+        frame.render_widget(tokens_chart, chunks[1]);
+    }
+
+    /// Draw session tab with detailed session info
+    fn draw_session_tab(
+        frame: &mut Frame,
+        area: Rect,
+        metrics: &UsageMetrics,
+        recommendations: &[Recommendation],
+        session_costs: &[(SessionBlock, CostBreakdown)],
+    ) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(12), Constraint::Length(SESSION_TREND_WINDOWS as u16 + 3)])
+            .split(area);
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(rows[0]);
+
         // Current session details
-        Self::draw_current_session_details(frame, chunks[0], &metrics.current_session);
+        Self::draw_current_session_details(frame, chunks[0], metrics);
 
         // Session predictions
-        Self::draw_session_predictions(frame, chunks[1], metrics);
+        Self::draw_session_predictions(frame, chunks[1], metrics, recommendations, None);
+
+        Self::draw_session_history(frame, rows[1], session_costs, metrics.current_session.tokens_limit);
+    }
+
+    /// Draw the latest session windows with the share of the limit each used and what each cost,
+    /// the ones well above the median cost in red
+    fn draw_session_history(frame: &mut Frame, area: Rect, session_costs: &[(SessionBlock, CostBreakdown)], token_limit: u32) {
+        let costs: Vec<f64> = session_costs.iter().map(|(_, cost)| cost.total()).collect();
+        let expensive = pricing::expensive_threshold(&costs);
+        let header = format!(
+            "{:<12} {:>6} {:>11} {:>8} {:<5} {:>9} {:>9} {:>9} {:>9}",
+            "Started UTC", "Reqs", "Tokens", "% Limit", "Hit", "Cost", "Input", "Output", "Cache"
+        );
+        let mut items = vec![ListItem::new(Line::from(Span::styled(header, Style::default().add_modifier(Modifier::BOLD))))];
+        items.extend(session_costs.iter().map(|(block, cost)| {
+            let window = usage_stats::WindowUsage::new(block, token_limit);
+            let line = format!(
+                "{:<12} {:>6} {:>11} {:>7.0}% {:<5} {:>9} {:>9} {:>9} {:>9}",
+                block.start_time.format("%m-%d %H:%M"),
+                block.entry_count,
+                block.tokens_used,
+                window.percent_of_limit,
+                if window.limit_reached { "hit" } else { "" },
+                format!("${:.2}", cost.total()),
+                format!("${:.2}", cost.input),
+                format!("${:.2}", cost.output),
+                format!("${:.2}", cost.cache_write + cost.cache_read),
+            );
+            let style = if cost.total() > expensive {
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            ListItem::new(Line::from(Span::styled(line, style)))
+        }));
+        if session_costs.is_empty() {
+            items.push(ListItem::new(Line::from(Span::styled("No session windows yet", Style::default().fg(Color::DarkGray)))));
+        }
+        let list = List::new(items).block(Block::default().title("Session History (share of the limit, API-equivalent cost)").borders(Borders::ALL));
+        frame.render_widget(list, area);
     }
 
     /// Draw settings tab
-    fn draw_settings_tab(frame: &mut Frame, area: Rect) {
+    fn draw_settings_tab(frame: &mut Frame, area: Rect, config: &UserConfig, metrics: &UsageMetrics) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Length(7),  // Current Settings
+                Constraint::Length(8),  // Current Settings
                 Constraint::Min(15),    // Technical Details
             ])
             .split(area);
 
-        // Current Settings
-        let settings_info = ["Default Plan: Pro".to_string(),
-            "Update Interval: 3s".to_string(),
-            "Warning Threshold: 85.0%".to_string(),
-            "Auto Switch Plans: true".to_string(),
-            "Timezone: UTC".to_string()];
+        // Current Settings (effective values, including per-project overrides)
+        let settings_info = [format!("Default Plan: {:?}", config.default_plan),
+            format!(
+                "Intervals: scan {}s, alerts {}, redraw {}ms",
+                config.scan_interval().as_secs(),
+                config.alert_interval().map_or("after each scan".to_string(), |interval| format!("{}s", interval.as_secs())),
+                config.redraw_interval().as_millis()
+            ),
+            format!("Warning Threshold: {:.1}%", config.warning_threshold * 100.0),
+            format!("Auto Switch Plans: {}", config.auto_switch_plans),
+            format!("Timezone: {}", config.timezone),
+            format!("Project: {}", metrics.project_label.as_deref().unwrap_or("(none)"))];
 
         let settings_items: Vec<ListItem> = settings_info
             .iter()
@@ -379,7 +1614,7 @@ impl RatatuiTerminalUI {
             "📊 Calculations:".to_string(),
             "• Usage Rate: total_tokens / time_elapsed (tokens/minute)".to_string(),
             "• Efficiency: expected_rate / actual_rate (0.0-1.0)".to_string(),
-            "• Session Progress: time_elapsed / session_duration (5 hours)".to_string(),
+            "• Session Progress: time_elapsed / session_window (5 hours by default)".to_string(),
             "• Projected Depletion: remaining_tokens / usage_rate".to_string(),
             "".to_string(),
             "💾 Passive File Operations:".to_string(),
@@ -409,7 +1644,14 @@ impl RatatuiTerminalUI {
     }
 
     /// Draw details tab with navigation and drill-down functionality
-    fn draw_details_tab(frame: &mut Frame, area: Rect, metrics: &UsageMetrics, details_selected: usize, show_details_pane: bool) {
+    fn draw_details_tab(
+        frame: &mut Frame,
+        area: Rect,
+        metrics: &UsageMetrics,
+        refreshed: &RefreshedData,
+        details_selected: usize,
+        show_details_pane: bool,
+    ) {
         let chunks = if show_details_pane {
             Layout::default()
                 .direction(Direction::Horizontal)
@@ -423,19 +1665,7 @@ impl RatatuiTerminalUI {
         };
 
         // Left panel - list of details categories
-        let detail_items = ["📊 Token Usage Breakdown",
-            "📈 Usage Rate Analysis", 
-            "⏱️ Session Timeline",
-            "💾 Cache Token Details",
-            "🔍 Model Information",
-            "📁 File Sources & Sessions",
-            "⚡ Performance Metrics",
-            "🎯 Usage Predictions",
-            "📋 Recent Activity",
-            "⚙️ Configuration",
-            "🔗 Session Links"];
-
-        let items: Vec<ListItem> = detail_items
+        let items: Vec<ListItem> = DETAIL_CATEGORIES
             .iter()
             .enumerate()
             .map(|(i, item)| {
@@ -460,30 +1690,29 @@ impl RatatuiTerminalUI {
 
         // Right panel - details of selected category
         if show_details_pane && chunks.len() > 1 {
-            Self::draw_detail_content(frame, chunks[1], metrics, details_selected);
+            Self::draw_detail_content(frame, chunks[1], metrics, refreshed, details_selected);
         }
     }
 
     /// Draw content for selected detail category
-    fn draw_detail_content(frame: &mut Frame, area: Rect, metrics: &UsageMetrics, selected: usize) {
-        let content = match selected {
-            0 => Self::get_token_breakdown_details(metrics),
-            1 => Self::get_usage_rate_details(metrics),
-            2 => Self::get_session_timeline_details(metrics),
-            3 => Self::get_cache_token_details(metrics),
-            4 => Self::get_model_information_details(metrics),
-            5 => Self::get_file_sources_details(),
-            6 => Self::get_performance_metrics_details(metrics),
-            7 => Self::get_usage_predictions_details(metrics),
-            8 => Self::get_recent_activity_details(),
-            9 => Self::get_configuration_details(),
-            10 => Self::get_session_links_details(metrics),
-            _ => vec!["No details available".to_string()],
-        };
+    fn draw_detail_content(frame: &mut Frame, area: Rect, metrics: &UsageMetrics, refreshed: &RefreshedData, selected: usize) {
+        let content = Self::detail_content(metrics, refreshed, selected);
 
+        // Lines about a model take its color
+        let models: Vec<&str> = refreshed.model_usage
+            .iter()
+            .map(|model| model.model.as_str())
+            .chain(refreshed.model_latency.iter().map(|model| model.model.as_str()))
+            .collect();
         let items: Vec<ListItem> = content
             .iter()
-            .map(|line| ListItem::new(Line::from(line.as_str())))
+            .map(|line| {
+                let model = models.iter().find(|model| line.strip_prefix("• ").and_then(|rest| rest.strip_prefix(**model)).is_some_and(|rest| rest.starts_with(':')));
+                match model {
+                    Some(model) => ListItem::new(Line::styled(line.as_str(), Style::default().fg(model_colors::model_color(model)))),
+                    None => ListItem::new(Line::from(line.as_str())),
+                }
+            })
             .collect();
 
         let detail_list = List::new(items)
@@ -497,6 +1726,24 @@ impl RatatuiTerminalUI {
         frame.render_widget(detail_list, area);
     }
 
+    /// Lines of the detail category at `selected`
+    fn detail_content(metrics: &UsageMetrics, refreshed: &RefreshedData, selected: usize) -> Vec<String> {
+        match selected {
+            0 => Self::get_token_breakdown_details(metrics),
+            1 => Self::get_usage_rate_details(metrics),
+            2 => Self::get_session_timeline_details(metrics),
+            3 => Self::get_cache_token_details(metrics),
+            4 => Self::get_model_information_details(metrics, &refreshed.model_usage),
+            5 => Self::get_file_sources_details(),
+            6 => Self::get_performance_metrics_details(metrics, &refreshed.model_latency),
+            7 => Self::get_usage_predictions_details(metrics, refreshed.usage_analysis.as_ref()),
+            8 => Self::get_recent_activity_details(),
+            9 => Self::get_configuration_details(),
+            10 => Self::get_session_links_details(metrics),
+            _ => vec!["No details available".to_string()],
+        }
+    }
+
     fn get_token_breakdown_details(metrics: &UsageMetrics) -> Vec<String> {
         vec![
             format!("📊 Token Usage Breakdown:"),
@@ -534,24 +1781,47 @@ impl RatatuiTerminalUI {
 
     fn get_session_timeline_details(metrics: &UsageMetrics) -> Vec<String> {
         let session = &metrics.current_session;
-        vec![
+        let mut details = vec![
             format!("⏱️ Session Timeline:"),
             "".to_string(),
             format!("Session ID: {}", session.id),
             format!("Started: {}", humantime::format_rfc3339(session.start_time.into())),
-            format!("Resets: {}", humantime::format_rfc3339(session.reset_time.into())),
-            format!("Status: {}", if session.is_active { "🟢 Active" } else { "🔴 Inactive" }),
+            format!("Resets: {} ({})", humantime::format_rfc3339(session.reset_time.into()), metrics.reset_source()),
+            format!("Status: {}", match metrics.status() {
+                SessionStatus::Active => "🟢 Active",
+                SessionStatus::Idle => "🟡 Idle",
+                SessionStatus::Inactive => "🔴 Inactive",
+            }),
             "".to_string(),
             format!("Plan Type: {:?}", session.plan_type),
-            format!("Duration: 5 hours (standard)"),
+            format!("Window: {}", super::format_duration(session.reset_time - session.start_time)),
             format!("Progress: {:.1}%", metrics.session_progress * 100.0),
             "".to_string(),
             if let Some(depletion) = &metrics.projected_depletion {
                 format!("Projected Depletion: {}", humantime::format_rfc3339((*depletion).into()))
+            } else if metrics.idle_since.is_some() {
+                "Projected Depletion: Paused while idle".to_string()
             } else {
                 "Projected Depletion: Not calculated".to_string()
             },
-        ]
+        ];
+
+        if !metrics.limit_events.is_empty() {
+            details.push("".to_string());
+            details.push("Limit notices:".to_string());
+            for event in &metrics.limit_events {
+                let label = match event.kind {
+                    LimitEventKind::LimitReached => "🛑 Limit reached",
+                    LimitEventKind::ApproachingLimit => "⚠️ Approaching limit",
+                };
+                let reset = event.reset_time
+                    .map(|reset| format!(" (resets {})", reset.format("%H:%M UTC")))
+                    .unwrap_or_default();
+                details.push(format!("• {} - {label}{reset}", event.timestamp.format("%H:%M:%S")));
+            }
+        }
+
+        details
     }
 
     fn get_cache_token_details(_metrics: &UsageMetrics) -> Vec<String> {
@@ -584,35 +1854,31 @@ impl RatatuiTerminalUI {
         ]
     }
 
-    fn get_model_information_details(_metrics: &UsageMetrics) -> Vec<String> {
-        // Note: This is a static display. In a real implementation, you'd pass
-        // the file monitor data to get actual model breakdown
-        vec![
-            format!("🔍 Model Information:"),
-            "".to_string(),
-            "Detected models from usage data:".to_string(),
-            "• claude-sonnet-4-20250514: 42,100 tokens (234 requests)".to_string(),
-            "• claude-haiku-20241022: 2,800 tokens (12 requests)".to_string(),
-            "• claude-opus-20240229: 1,200 tokens (3 requests)".to_string(),
-            "".to_string(),
-            "Model performance:".to_string(),
-            "• Sonnet 4: 179 tokens/request avg".to_string(),
-            "• Haiku: 233 tokens/request avg".to_string(),
-            "• Opus: 400 tokens/request avg".to_string(),
-            "".to_string(),
-            "Token efficiency by model:".to_string(),
-            "• Sonnet 4: High efficiency (0.85)".to_string(),
-            "• Haiku: Very high efficiency (0.92)".to_string(),
-            "• Opus: Moderate efficiency (0.76)".to_string(),
+    fn get_model_information_details(metrics: &UsageMetrics, models: &[ModelUsage]) -> Vec<String> {
+        let mut details = vec![format!("🔍 Model Information:"), "".to_string()];
+        if models.is_empty() {
+            details.push("No requests in the current window yet.".to_string());
+            return details;
+        }
+        let total: u64 = models.iter().map(|model| model.tokens).sum();
+        details.push(format!("Models in the current window (since {}):", metrics.current_session.start_time.format("%H:%M UTC")));
+        for model in models {
+            details.push(format!(
+                "• {}: {} tokens ({:.1}%), {} requests, {} tokens/request avg",
+                model.model,
+                model.tokens,
+                model.tokens as f64 / total.max(1) as f64 * 100.0,
+                model.requests,
+                model.tokens / model.requests.max(1) as u64
+            ));
+        }
+        details.extend([
             "".to_string(),
             "Model info extracted from:".to_string(),
             "• message.model field in JSONL".to_string(),
-            "• Usage statistics per model".to_string(),
-            "• Token consumption patterns".to_string(),
-            "".to_string(),
-            "Note: Model detection depends on".to_string(),
-            "data availability in usage logs.".to_string(),
-        ]
+            "• the plan's default model when a response names none".to_string(),
+        ]);
+        details
     }
 
     fn get_file_sources_details() -> Vec<String> {
@@ -648,8 +1914,8 @@ impl RatatuiTerminalUI {
         ]
     }
 
-    fn get_performance_metrics_details(metrics: &UsageMetrics) -> Vec<String> {
-        vec![
+    fn get_performance_metrics_details(metrics: &UsageMetrics, latency: &[ModelLatency]) -> Vec<String> {
+        let mut details = vec![
             format!("⚡ Performance Metrics:"),
             "".to_string(),
             format!("Current Session:"),
@@ -657,6 +1923,25 @@ impl RatatuiTerminalUI {
             format!("• Efficiency: {:.2}", metrics.efficiency_score),
             format!("• Progress: {:.1}%", metrics.session_progress * 100.0),
             "".to_string(),
+        ];
+
+        if !latency.is_empty() {
+            details.push(format!("Response times (last {} days, p50/p90/p99):", recommendations::ANALYSIS_DAYS));
+            for model in latency {
+                details.push(format!(
+                    "• {}: {} requests, {:.0} output tokens/s",
+                    model.model, model.requests, model.output_tokens_per_second
+                ));
+                let [p50, p90, p99] = model.duration_ms;
+                details.push(format!("  total {p50}/{p90}/{p99} ms"));
+                if let Some([p50, p90, p99]) = model.ttft_ms {
+                    details.push(format!("  first token {p50}/{p90}/{p99} ms"));
+                }
+            }
+            details.push("".to_string());
+        }
+
+        details.extend(vec![
             "Performance Categories:".to_string(),
             "• Efficiency > 0.8: Excellent".to_string(),
             "• Efficiency 0.6-0.8: Good".to_string(),
@@ -665,10 +1950,11 @@ impl RatatuiTerminalUI {
             "Optimization tips:".to_string(),
             "• Batch similar queries".to_string(),
             "• Use context efficiently".to_string(),
-        ]
+        ]);
+        details
     }
 
-    fn get_usage_predictions_details(metrics: &UsageMetrics) -> Vec<String> {
+    fn get_usage_predictions_details(metrics: &UsageMetrics, analysis: Option<&UsageAnalysis>) -> Vec<String> {
         let mut details = vec![
             format!("🎯 Usage Predictions:"),
             "".to_string(),
@@ -681,6 +1967,12 @@ impl RatatuiTerminalUI {
                 format!("• Based on current rate: {:.2} tokens/min", metrics.usage_rate),
                 "".to_string(),
             ]);
+        } else if let Some(idle_since) = metrics.idle_since {
+            details.extend(vec![
+                format!("Idle since {}", idle_since.format("%H:%M UTC")),
+                "No projection until requests resume".to_string(),
+                "".to_string(),
+            ]);
         } else {
             details.extend(vec![
                 "No depletion prediction available".to_string(),
@@ -689,6 +1981,27 @@ impl RatatuiTerminalUI {
             ]);
         }
 
+        if let Some(analysis) = analysis {
+            let peaks: Vec<String> = analysis
+                .peak_usage_times
+                .iter()
+                .map(|(hour, _)| format!("{hour:02}:00"))
+                .collect();
+            details.extend(vec![
+                format!("Usage patterns ({} windows, {:.0} days):", analysis.sessions_analyzed, analysis.analyzed_days),
+                format!(
+                    "• Average session: {}h {:02}m",
+                    analysis.average_session_duration as i64 / 60,
+                    analysis.average_session_duration as i64 % 60
+                ),
+                format!("• Idle: {:.0}% of session time ({} pauses)", analysis.idle_share * 100.0, analysis.idle_gaps),
+                format!("• Busiest start hours (UTC): {}", peaks.join(", ")),
+                format!("• Window size trend: {:+.0}%", analysis.efficiency_trend * 100.0),
+                format!("• Best fitting plan: {:?}", analysis.recommended_plan),
+                "".to_string(),
+            ]);
+        }
+
         details.extend(vec![
             "Prediction accuracy depends on:".to_string(),
             "• Consistent usage patterns".to_string(),
@@ -716,12 +2029,6 @@ impl RatatuiTerminalUI {
             "• 13:32:10 - Cache hit: 120 tokens saved".to_string(),
             "• 13:31:28 - Token usage: 892 tokens".to_string(),
             "".to_string(),
-            "Session patterns:".to_string(),
-            "• Average session length: 3.2 hours".to_string(),
-            "• Peak usage time: 14:00-16:00".to_string(),
-            "• Most active model: Sonnet 4".to_string(),
-            "• Cache efficiency: 92.3%".to_string(),
-            "".to_string(),
             "File monitoring:".to_string(),
             "• Real-time updates: Active".to_string(),
             "• Files watched: 12 directories".to_string(),
@@ -757,7 +2064,7 @@ impl RatatuiTerminalUI {
             format!("Current Session:"),
             format!("• ID: {}", session.id),
             format!("• Plan: {:?}", session.plan_type),
-            format!("• Status: {}", if session.is_active { "Active" } else { "Inactive" }),
+            format!("• Status: {}", metrics.status()),
             "".to_string(),
             "Related data:".to_string(),
             "• JSONL files in ~/.claude/projects/".to_string(),
@@ -770,6 +2077,19 @@ impl RatatuiTerminalUI {
         ]
     }
 
+/// " ▲ 23%" against the same time last week: yellow up, green down, gray when flat or unknown
+fn delta_span(delta: Option<&Delta>) -> Span<'static> {
+    let Some(label) = delta.and_then(Delta::label) else {
+        return Span::raw("");
+    };
+    let color = match label.chars().next() {
+        Some('▲') => Color::Yellow,
+        Some('▼') => Color::Green,
+        _ => Color::Gray,
+    };
+    Span::styled(format!(" {label}"), Style::default().fg(color))
+}
+
 /// Draw security tab with security recommendations
 fn draw_security_tab(frame: &mut Frame, area: Rect) {
     // Recommendations
@@ -807,6 +2127,76 @@ fn draw_security_tab(frame: &mut Frame, area: Rect) {
     frame.render_widget(rec_list, area);
 }
 
+    /// Draw the monitor's internals next to the redacted effective config, which scrolls with ↑↓
+    fn draw_diagnostics_tab(frame: &mut Frame, area: Rect, config: &UserConfig, diagnostics: &Diagnostics, scroll_offset: usize) {
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(area);
+
+        let scan = &diagnostics.scan;
+        let watcher = &diagnostics.watcher;
+        let watcher_status = match (&watcher.error, watcher.watching) {
+            (Some(error), _) => format!("unavailable ({error})"),
+            (None, false) if watcher.paused => "paused (low power, terminal not focused)".to_string(),
+            (None, true) => "watching".to_string(),
+            (None, false) => "not started (no usage data)".to_string(),
+        };
+        let ago = |at: DateTime<Utc>| {
+            format!("{} ago", humantime::format_duration(Duration::from_secs((Utc::now() - at).num_seconds().max(0) as u64)))
+        };
+        let last_scan = scan.finished_at.map_or("never".to_string(), ago);
+        let last_scan = if scan.skipped { format!("{last_scan} (no file changed)") } else { last_scan };
+        let lines = [
+            "🔍 Scanning".to_string(),
+            format!("   Data directories:   {}", diagnostics.monitored_paths),
+            format!("   Files indexed:      {} ({} failed)", scan.files, diagnostics.failed_files),
+            format!("   Last scan:          {last_scan}, took {} ms", scan.duration.as_millis()),
+            format!("   Scans this run:     {}", scan.scans),
+            format!("   New data:           {}", scan.data_changed_at.map_or("none yet".to_string(), ago)),
+            String::new(),
+            "📄 Entries".to_string(),
+            format!("   Subscription usage: {}", diagnostics.usage_entries),
+            format!("   API-key usage:      {}", diagnostics.api_key_entries),
+            format!("   Imported:           {}", diagnostics.imported_entries),
+            format!("   Arrived late:       {} (older than data already read)", scan.late_entries),
+            String::new(),
+            "⚠️  Parse errors".to_string(),
+            format!("   Unknown schema:     {}", diagnostics.unknown_lines),
+            format!("   Malformed usage:    {}", diagnostics.malformed_lines),
+            format!("   Invalid JSON:       {}", diagnostics.invalid_lines),
+            String::new(),
+            "🚧 Implausible entries".to_string(),
+            format!("   Ahead of the clock: {} (left out)", diagnostics.future_entries),
+            format!("   Over token ceiling: {} (left out)", diagnostics.oversized_entries),
+            format!("   Impossible timings: {} (timings dropped)", diagnostics.clamped_timings),
+            String::new(),
+            "👀 File watcher".to_string(),
+            format!("   Status:             {watcher_status}"),
+            format!("   Events:             {} ({} since the last scan)", watcher.events, watcher.since_scan),
+            format!("   Errors:             {}", watcher.errors),
+            String::new(),
+            "💾 Memory".to_string(),
+            format!("   Entry store:        ~{} (with all shared names)", format_bytes(diagnostics.entry_store_bytes as u64)),
+            format!("   Process (RSS):      {}", diagnostics.resident_bytes.map_or("unknown".to_string(), format_bytes)),
+        ];
+        let items: Vec<ListItem> = lines.iter().map(|line| ListItem::new(Line::from(line.as_str()))).collect();
+        let internals = List::new(items)
+            .block(Block::default().title("Internals").borders(Borders::ALL))
+            .style(Style::default().fg(Color::White));
+        frame.render_widget(internals, chunks[0]);
+
+        // Redacted as in debug bundles, so the tab can be screenshotted without leaking webhook tokens
+        let effective = debug_bundle::redact_config(config)
+            .and_then(|config| Ok(serde_json::to_string_pretty(&config)?))
+            .unwrap_or_else(|e| format!("Failed to serialize config: {e}"));
+        let config_view = Paragraph::new(effective)
+            .block(Block::default().title("Effective Config, redacted (↑↓ scroll)").borders(Borders::ALL))
+            .style(Style::default().fg(Color::Cyan))
+            .scroll((scroll_offset.min(u16::MAX as usize) as u16, 0));
+        frame.render_widget(config_view, chunks[1]);
+    }
+
     /// Draw about tab with author and usage information
 fn draw_about_tab(frame: &mut Frame, area: Rect) {
     // Version and Author Information
@@ -842,7 +2232,9 @@ fn draw_about_tab(frame: &mut Frame, area: Rect) {
 
 
     /// Draw session info with filename for Overview tab
-    fn draw_session_info_with_filename(frame: &mut Frame, area: Rect, session: &TokenSession) {
+    fn draw_session_info_with_filename(frame: &mut Frame, area: Rect, metrics: &UsageMetrics, refreshed: &RefreshedData) {
+        let session = &metrics.current_session;
+        let project_label = metrics.project_label.as_deref();
         let plan_str = match &session.plan_type {
             PlanType::Pro => "Pro (40k tokens)",
             PlanType::Max5 => "Max5 (20k tokens)",
@@ -850,11 +2242,12 @@ fn draw_about_tab(frame: &mut Frame, area: Rect) {
             PlanType::Custom(limit) => &format!("Custom ({}k tokens)", limit / 1000),
         };
 
-        let status_style = if session.is_active {
-            Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)
-        } else {
-            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+        let (status, status_color) = match metrics.status() {
+            SessionStatus::Active => ("ACTIVE (OBSERVED)", Color::Green),
+            SessionStatus::Idle => ("IDLE (OBSERVED)", Color::Yellow),
+            SessionStatus::Inactive => ("INACTIVE (OBSERVED)", Color::Red),
         };
+        let status_style = Style::default().fg(status_color).add_modifier(Modifier::BOLD);
 
         let session_info = vec![
             Line::from(vec![
@@ -863,10 +2256,7 @@ fn draw_about_tab(frame: &mut Frame, area: Rect) {
             ]),
             Line::from(vec![
                 Span::raw("Status: "),
-                Span::styled(
-                    if session.is_active { "ACTIVE (OBSERVED)" } else { "INACTIVE (OBSERVED)" },
-                    status_style,
-                ),
+                Span::styled(status, status_style),
             ]),
             Line::from(vec![
                 Span::raw("Session ID: "),
@@ -876,6 +2266,14 @@ fn draw_about_tab(frame: &mut Frame, area: Rect) {
                 Span::raw("JSONL File: "),
                 Span::styled("~/.claude/projects/**/*.jsonl", Style::default().fg(Color::Green)),
             ]),
+            Line::from(vec![
+                Span::raw("Project: "),
+                Span::styled(
+                    project_label.unwrap_or("(unknown)").to_string(),
+                    Style::default().fg(Color::Magenta),
+                ),
+            ]),
+            Self::selected_model_line(metrics),
             Line::from(vec![
                 Span::raw("Started: "),
                 Span::styled(
@@ -889,7 +2287,20 @@ fn draw_about_tab(frame: &mut Frame, area: Rect) {
                     session.reset_time.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
                     Style::default().fg(Color::White),
                 ),
+                Span::styled(
+                    format!(" ({})", metrics.reset_source()),
+                    Style::default().fg(if metrics.reset_authoritative { Color::Green } else { Color::DarkGray }),
+                ),
             ]),
+            Line::from(vec![
+                Span::raw("Today: "),
+                Span::styled(
+                    format!("{} tokens", super::compact_tokens(refreshed.today_tokens)),
+                    Style::default().fg(Color::White),
+                ),
+                Self::delta_span(refreshed.week_over_week.daily_tokens.as_ref()),
+            ]),
+            Self::error_rate_line(refreshed.error_rate),
         ];
 
         let paragraph = Paragraph::new(session_info)
@@ -901,11 +2312,117 @@ fn draw_about_tab(frame: &mut Frame, area: Rect) {
             )
             .wrap(Wrap { trim: true });
 
-        frame.render_widget(paragraph, area);
+        frame.render_widget(paragraph, area);
+    }
+
+    /// The model Claude Code's settings select, with a warning naming the logged model when the
+    /// latest request used another
+    fn selected_model_line(metrics: &UsageMetrics) -> Line<'static> {
+        let Some(selected) = metrics.selected_model.clone() else {
+            return Line::from(vec![Span::raw("Model: "), Span::styled("default", Style::default().fg(Color::DarkGray))]);
+        };
+        let mut spans = vec![
+            Span::raw("Model: "),
+            Span::styled(selected.clone(), Style::default().fg(model_colors::model_color(&selected))),
+        ];
+        if let Some(logged) = claude_settings::model_mismatch(metrics) {
+            spans.push(Span::styled(
+                format!(" ⚠ logs show {logged}"),
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            ));
+        }
+        Line::from(spans)
+    }
+
+    /// Share of failed requests over the last 24 hours, red from 5%
+    fn error_rate_line(error_rate: ErrorRate) -> Line<'static> {
+        let color = match error_rate.rate() {
+            rate if rate >= 0.05 => Color::Red,
+            rate if rate > 0.0 => Color::Yellow,
+            _ => Color::Green,
+        };
+        Line::from(vec![
+            Span::raw("Errors (24h): "),
+            Span::styled(
+                format!("{:.1}% ({} of {} requests)", error_rate.rate() * 100.0, error_rate.errors, error_rate.requests),
+                Style::default().fg(color),
+            ),
+        ])
+    }
+
+    /// Chart points for limit notices of one kind, at the first history point at or after them,
+    /// placed along the x axis by `x` of that point's index
+    fn limit_event_markers(metrics: &UsageMetrics, kind: LimitEventKind, x: impl Fn(usize) -> f64) -> Vec<(f64, f64)> {
+        metrics.limit_events
+            .iter()
+            .filter(|event| event.kind == kind)
+            .filter_map(|event| {
+                let index = metrics.usage_history
+                    .iter()
+                    .position(|point| point.timestamp >= event.timestamp)
+                    .unwrap_or(metrics.usage_history.len().checked_sub(1)?);
+                Some((x(index), metrics.usage_history[index].tokens_used as f64))
+            })
+            .collect()
+    }
+
+    /// Chart points for the timeline events of one kind within the current window, at minutes into
+    /// the window and the tokens used by then
+    fn timeline_event_markers(metrics: &UsageMetrics, events: &[TimelineEvent], kind: EventKind) -> Vec<(f64, f64)> {
+        let session = &metrics.current_session;
+        events
+            .iter()
+            .filter(|event| event.kind == kind && (session.start_time..=session.reset_time).contains(&event.timestamp))
+            .map(|event| {
+                let tokens = metrics.usage_history
+                    .iter()
+                    .take_while(|point| point.timestamp <= event.timestamp)
+                    .last()
+                    .map_or(0, |point| point.tokens_used);
+                ((event.timestamp - session.start_time).num_seconds() as f64 / 60.0, tokens as f64)
+            })
+            .collect()
+    }
+
+    /// Datasets drawing the lines from `threshold_lines`
+    fn threshold_datasets<'a>(lines: &'a [[(f64, f64); 2]; 2], warning_threshold: f64) -> [Dataset<'a>; 2] {
+        let [warning, limit] = lines;
+        [
+            Dataset::default()
+                .name(format!("Warning ({:.0}%)", warning_threshold * 100.0))
+                .marker(ratatui::symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::Yellow))
+                .data(warning),
+            Dataset::default()
+                .name("Limit")
+                .marker(ratatui::symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::Red))
+                .data(limit),
+        ]
+    }
+
+    /// Mark `ratio` of the way along a gauge drawn in `area` (inside any border), except where
+    /// the gauge's centered label of `label_width` cells is
+    fn draw_gauge_marker(frame: &mut Frame, area: Rect, ratio: f64, label_width: usize) {
+        if area.width == 0 {
+            return;
+        }
+        let x = area.x + super::marker_cell(ratio, area.width);
+        let label_width = area.width.min(label_width as u16);
+        let label_start = area.left() + (area.width - label_width) / 2;
+        let label_row = area.top() + area.height / 2;
+        for y in area.top()..area.bottom() {
+            if y == label_row && (label_start..=label_start + label_width).contains(&x) {
+                continue;
+            }
+            frame.buffer_mut()[(x, y)].set_symbol("│").set_fg(Color::Yellow);
+        }
     }
 
     /// Draw time-series strip chart for token usage over time
-    fn draw_token_usage_strip_chart(frame: &mut Frame, area: Rect, metrics: &UsageMetrics) {
+    fn draw_token_usage_strip_chart(frame: &mut Frame, area: Rect, metrics: &UsageMetrics, events: &[TimelineEvent], warning_threshold: f64) {
         if metrics.usage_history.is_empty() {
             // Display fallback message when no data is available
             let placeholder = Paragraph::new("No token usage data available for time-series chart.\nStart using Claude to see real-time consumption.")
@@ -923,32 +2440,28 @@ fn draw_about_tab(frame: &mut Frame, area: Rect) {
             return;
         }
 
-        // Convert usage history to chart data points
+        // Plot against minutes into the window, so the forecast can run on to the reset
+        let session = &metrics.current_session;
+        let window_minutes = (session.reset_time - session.start_time).num_seconds().max(60) as f64 / 60.0;
+        let minutes_at = |index: usize| {
+            ((metrics.usage_history[index].timestamp - session.start_time).num_seconds() as f64 / 60.0).clamp(0.0, window_minutes)
+        };
         let chart_data: Vec<(f64, f64)> = metrics.usage_history
             .iter()
             .enumerate()
-            .map(|(i, point)| (i as f64, point.tokens_used as f64))
+            .map(|(i, point)| (minutes_at(i), point.tokens_used as f64))
             .collect();
+        let forecast = usage_stats::forecast(metrics, Utc::now());
 
-        if chart_data.is_empty() {
-            return;
-        }
+        let thresholds = super::threshold_lines(session.tokens_limit, warning_threshold, window_minutes);
 
-        // Calculate bounds for the chart
-        let max_tokens = chart_data.iter().map(|(_, y)| *y).fold(0.0, f64::max);
-        let x_max = (chart_data.len() - 1) as f64;
-        
-        // Create time labels for x-axis
-        let time_labels = if metrics.usage_history.len() > 1 {
-            let start_time = metrics.usage_history.first().unwrap().timestamp;
-            let end_time = metrics.usage_history.last().unwrap().timestamp;
-            vec![
-                format!("{}", start_time.format("%H:%M")),
-                format!("{}", end_time.format("%H:%M")),
-            ]
-        } else {
-            vec!["Start".to_string(), "Now".to_string()]
-        };
+        // Calculate bounds for the chart, leaving room for the limit and the top of the forecast band
+        let forecast_max = forecast.as_ref().and_then(|forecast| forecast.high.last()).map_or(0.0, |(_, tokens)| *tokens);
+        let max_tokens = chart_data.iter().map(|(_, y)| *y).fold(forecast_max.max(session.tokens_limit as f64), f64::max);
+
+        // Time labels for the start, middle and end of the window
+        let midpoint = session.start_time + (session.reset_time - session.start_time) / 2;
+        let time_labels = [session.start_time, midpoint, session.reset_time].map(|time| time.format("%H:%M").to_string());
 
         // Create y-axis labels
         let y_label_1 = format!("{:.0}", max_tokens / 4.0);
@@ -964,8 +2477,65 @@ fn draw_about_tab(frame: &mut Frame, area: Rect) {
             .style(Style::default().fg(Color::Green))
             .data(&chart_data);
 
+        // Mark limit notices on the timeline
+        let reached_markers = Self::limit_event_markers(metrics, LimitEventKind::LimitReached, minutes_at);
+        let approaching_markers = Self::limit_event_markers(metrics, LimitEventKind::ApproachingLimit, minutes_at);
+        // and the other events on the timeline, each kind with its own legend entry
+        let event_markers = [
+            (EventKind::WindowReset, "Window reset", Color::White),
+            (EventKind::PlanSwitch, "Plan switch", Color::Blue),
+            (EventKind::Alert, "Alert", Color::Magenta),
+        ]
+        .map(|(kind, name, color)| (name, color, Self::timeline_event_markers(metrics, events, kind)));
+        let mut datasets = vec![cumulative_dataset];
+        datasets.extend(Self::threshold_datasets(&thresholds, warning_threshold));
+        if let Some(forecast) = &forecast {
+            // The band's edges as faint lines, the expected path dotted
+            datasets.push(Dataset::default()
+                .name("Forecast range")
+                .marker(ratatui::symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::DarkGray))
+                .data(&forecast.high));
+            datasets.push(Dataset::default()
+                .marker(ratatui::symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::DarkGray))
+                .data(&forecast.low));
+            datasets.push(Dataset::default()
+                .name("Forecast")
+                .marker(ratatui::symbols::Marker::Dot)
+                .graph_type(GraphType::Scatter)
+                .style(Style::default().fg(Color::Cyan))
+                .data(&forecast.expected));
+        }
+        if !approaching_markers.is_empty() {
+            datasets.push(Dataset::default()
+                .name("Approaching limit")
+                .marker(ratatui::symbols::Marker::Block)
+                .graph_type(GraphType::Scatter)
+                .style(Style::default().fg(Color::Yellow))
+                .data(&approaching_markers));
+        }
+        if !reached_markers.is_empty() {
+            datasets.push(Dataset::default()
+                .name("Limit reached")
+                .marker(ratatui::symbols::Marker::Block)
+                .graph_type(GraphType::Scatter)
+                .style(Style::default().fg(Color::Red))
+                .data(&reached_markers));
+        }
+        for (name, color, markers) in event_markers.iter().filter(|(_, _, markers)| !markers.is_empty()) {
+            datasets.push(Dataset::default()
+                .name(*name)
+                .marker(ratatui::symbols::Marker::Block)
+                .graph_type(GraphType::Scatter)
+                .style(Style::default().fg(*color))
+                .data(markers));
+        }
+
         // Create chart widget
-        let chart = Chart::new(vec![cumulative_dataset])
+        let chart = Chart::new(datasets)
             .block(
                 Block::default()
                     .title("Token Usage Over Time (Cumulative)")
@@ -976,7 +2546,7 @@ fn draw_about_tab(frame: &mut Frame, area: Rect) {
                 Axis::default()
                     .title("Time Progression")
                     .style(Style::default().fg(Color::White))
-                    .bounds([0.0, x_max])
+                    .bounds([0.0, window_minutes])
                     .labels(time_labels.iter().map(|s| s.as_str()).collect::<Vec<_>>()),
             )
             .y_axis(
@@ -996,36 +2566,270 @@ fn draw_about_tab(frame: &mut Frame, area: Rect) {
         frame.render_widget(chart, area);
     }
 
-    /// Draw detailed analytics view with cache metrics and stacked bars
-    fn draw_detailed_analytics_view(frame: &mut Frame, area: Rect, metrics: &UsageMetrics) {
-        let chunks = Layout::default()
+    /// Draw the tokens left in the current window over time, with the even-pace line from the full
+    /// limit at the window start to nothing at the reset
+    fn draw_burn_down_chart(frame: &mut Frame, area: Rect, metrics: &UsageMetrics, warning_threshold: f64) {
+        let now = Utc::now();
+        let burn_down = usage_stats::burn_down(metrics, now);
+        let session = &metrics.current_session;
+
+        // Ahead of pace while more is left than spending evenly would leave by now
+        let title = match burn_down.remaining.last() {
+            Some(&(minute, left)) if burn_down.limit > 0.0 => {
+                let pace_left = burn_down.limit * (1.0 - minute / burn_down.window_minutes);
+                let (difference, side) = if left >= pace_left { (left - pace_left, "ahead of") } else { (pace_left - left, "behind") };
+                format!("Burn-down: {} tokens {side} even pace", super::compact_tokens(difference as u64))
+            }
+            _ => "Burn-down".to_string(),
+        };
+
+        // Warnings start once no more than (1 - threshold) of the limit is left
+        let warning_left = burn_down.limit * (1.0 - warning_threshold);
+        let warning_line = [(0.0, warning_left), (burn_down.window_minutes, warning_left)];
+        let mut datasets = vec![
+            Dataset::default()
+                .name(format!("Warning ({:.0}%)", warning_threshold * 100.0))
+                .marker(ratatui::symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::Yellow))
+                .data(&warning_line),
+            Dataset::default()
+                .name("Even pace")
+                .marker(ratatui::symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::DarkGray))
+                .data(&burn_down.even_pace),
+        ];
+        if !burn_down.remaining.is_empty() {
+            datasets.push(
+                Dataset::default()
+                    .name("Tokens left")
+                    .marker(ratatui::symbols::Marker::Braille)
+                    .graph_type(GraphType::Line)
+                    .style(Style::default().fg(Color::Cyan))
+                    .data(&burn_down.remaining),
+            );
+        }
+
+        let midpoint = session.start_time + (session.reset_time - session.start_time) / 2;
+        let time_labels = [session.start_time, midpoint, session.reset_time].map(|time| time.format("%H:%M").to_string());
+        let token_labels = [0.0, burn_down.limit / 2.0, burn_down.limit].map(|tokens| super::compact_tokens(tokens as u64));
+
+        let chart = Chart::new(datasets)
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .x_axis(
+                Axis::default()
+                    .title("Window")
+                    .style(Style::default().fg(Color::White))
+                    .bounds([0.0, burn_down.window_minutes])
+                    .labels(time_labels.iter().map(String::as_str).collect::<Vec<_>>()),
+            )
+            .y_axis(
+                Axis::default()
+                    .title("Tokens left")
+                    .style(Style::default().fg(Color::White))
+                    .bounds([0.0, burn_down.limit.max(1.0)])
+                    .labels(token_labels.iter().map(String::as_str).collect::<Vec<_>>()),
+            );
+
+        frame.render_widget(chart, area);
+    }
+
+    /// Draw detailed analytics view using the widgets from the [layout] config section
+    fn draw_detailed_analytics_view(frame: &mut Frame, area: Rect, metrics: &UsageMetrics, config: &UserConfig, refreshed: &RefreshedData) {
+        let rows = config.layout.rows();
+        let row_constraints: Vec<Constraint> = rows
+            .iter()
+            .map(|row| {
+                if row[0].widget.is_panel() {
+                    Constraint::Length(PANEL_ROW_HEIGHT)
+                } else {
+                    Constraint::Fill(row[0].size)
+                }
+            })
+            .collect();
+        let row_areas = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Length(8),  // Real-time metrics dashboard
-                Constraint::Min(8),     // Stacked time-series chart
-            ])
+            .constraints(row_constraints)
             .split(area);
 
-        // Real-time metrics dashboard
-        Self::draw_realtime_metrics_dashboard(frame, chunks[0], metrics);
-        
-        // Stacked time-series chart
-        Self::draw_stacked_token_chart(frame, chunks[1], metrics);
+        for (row, row_area) in rows.iter().zip(row_areas.iter()) {
+            // Panel sizes are relative widths within their shared row
+            let cells = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(row.iter().map(|widget| Constraint::Fill(widget.size)).collect::<Vec<_>>())
+                .split(*row_area);
+
+            for (widget, cell) in row.iter().zip(cells.iter()) {
+                Self::draw_layout_widget(frame, *cell, metrics, widget, refreshed, config.warning_threshold);
+            }
+        }
     }
 
-    /// Draw real-time metrics dashboard
-    fn draw_realtime_metrics_dashboard(frame: &mut Frame, area: Rect, metrics: &UsageMetrics) {
-        let chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Percentage(25),
-                Constraint::Percentage(25), 
-                Constraint::Percentage(25),
-                Constraint::Percentage(25),
+    fn draw_layout_widget(frame: &mut Frame, area: Rect, metrics: &UsageMetrics, widget: &WidgetConfig, refreshed: &RefreshedData, warning_threshold: f64) {
+        match widget.widget {
+            WidgetKind::Rate => Self::draw_rate_panel(frame, area, metrics),
+            WidgetKind::Cache => Self::draw_cache_panel(frame, area, metrics, &refreshed.cache_opportunities),
+            WidgetKind::Progress => Self::draw_progress_panel(frame, area, metrics),
+            WidgetKind::Efficiency => Self::draw_efficiency_panel(frame, area, metrics),
+            WidgetKind::Cost => Self::draw_cost_panel(frame, area, metrics, refreshed.week_over_week.cost.as_ref()),
+            WidgetKind::Chart => Self::draw_stacked_token_chart(frame, area, metrics, warning_threshold),
+            WidgetKind::Heatmap => Self::draw_activity_heatmap(frame, area, metrics),
+            WidgetKind::Custom => Self::draw_custom_panel(frame, area, widget, &refreshed.panel_outputs),
+            WidgetKind::Scripts => Self::draw_scripts_panel(frame, area, &refreshed.script_results),
+            WidgetKind::RateLimits => Self::draw_rate_limits_panel(frame, area, &refreshed.rate_limits),
+            WidgetKind::Models => Self::draw_models_panel(frame, area, &refreshed.model_usage),
+        }
+    }
+
+    /// Draw the last minute's and the busiest recent minute's share of each configured API rate limit
+    fn draw_rate_limits_panel(frame: &mut Frame, area: Rect, limits: &[RateLimitUsage]) {
+        let color = |utilization: f64| {
+            if utilization >= 1.0 {
+                Color::Red
+            } else if utilization >= 0.8 {
+                Color::Yellow
+            } else {
+                Color::Green
+            }
+        };
+        let mut lines = Vec::new();
+        for usage in limits {
+            let minute = &usage.last_minute;
+            lines.push(Line::from(vec![
+                Span::styled(usage.model.as_str(), Style::default().fg(model_colors::model_color(&usage.model))),
+                Span::raw(": "),
+                Span::styled(
+                    format!("{:.0}%", usage.utilization() * 100.0),
+                    Style::default().fg(color(usage.utilization())).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" now, peak "),
+                Span::styled(
+                    format!("{:.0}%", usage.peak_utilization() * 100.0),
+                    Style::default().fg(color(usage.peak_utilization())),
+                ),
+            ]));
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "{} req, {} in, {} out /min",
+                    minute.requests,
+                    super::compact_tokens(minute.input_tokens),
+                    super::compact_tokens(minute.output_tokens)
+                ),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        if lines.is_empty() {
+            lines.push(Line::from("No rate limits configured"));
+        }
+
+        let panel = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title(format!("Rate Limits (peak of {}m)", rate_limits::PEAK_WINDOW_MINUTES))
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Red)),
+            )
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+
+        frame.render_widget(panel, area);
+    }
+
+    /// Draw each model's share of the current window's tokens, busiest first, in its color
+    fn draw_models_panel(frame: &mut Frame, area: Rect, models: &[ModelUsage]) {
+        let total: u64 = models.iter().map(|model| model.tokens).sum();
+        let mut lines: Vec<Line> = models
+            .iter()
+            .map(|model| {
+                let color = model_colors::model_color(&model.model);
+                Line::from(vec![
+                    Span::styled("■ ", Style::default().fg(color)),
+                    Span::styled(model.model.as_str(), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+                    Span::raw(format!(
+                        " {} ({:.0}%), {} req",
+                        super::compact_tokens(model.tokens),
+                        model.tokens as f64 / total.max(1) as f64 * 100.0,
+                        model.requests
+                    )),
+                ])
+            })
+            .collect();
+        if lines.is_empty() {
+            lines.push(Line::from("No requests in this window"));
+        }
+
+        let panel = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title("Models (this window)")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Blue)),
+            )
+            .wrap(Wrap { trim: true });
+
+        frame.render_widget(panel, area);
+    }
+
+    /// Draw derived metrics and alert states from user scripts
+    fn draw_scripts_panel(frame: &mut Frame, area: Rect, results: &ScriptResults) {
+        let mut lines: Vec<Line> = results
+            .alerts
+            .iter()
+            .map(|alert| Line::from(Span::styled(format!("⚠️ {alert}"), Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))))
+            .collect();
+        lines.extend(results.metrics.iter().map(|(name, value)| {
+            Line::from(vec![
+                Span::raw(format!("{name}: ")),
+                Span::styled(value.clone(), Style::default().fg(Color::Cyan)),
             ])
-            .split(area);
+        }));
+        lines.extend(
+            results
+                .errors
+                .iter()
+                .map(|error| Line::from(Span::styled(error.clone(), Style::default().fg(Color::DarkGray)))),
+        );
+        if lines.is_empty() {
+            lines.push(Line::from("No scripts configured"));
+        }
+
+        let panel = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title("Scripts")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Magenta)),
+            )
+            .wrap(Wrap { trim: true });
+
+        frame.render_widget(panel, area);
+    }
+
+    /// Draw the latest output of a user-specified command
+    fn draw_custom_panel(frame: &mut Frame, area: Rect, widget: &WidgetConfig, panel_outputs: &HashMap<String, String>) {
+        let command = widget.command.as_deref().unwrap_or_default();
+        let title = widget.title.as_deref().unwrap_or(command);
+        let output = panel_outputs.get(command).map(String::as_str).unwrap_or("Waiting for first refresh...");
+
+        let panel = Paragraph::new(output.to_string())
+            .block(
+                Block::default()
+                    .title(title.to_string())
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Gray)),
+            )
+            .wrap(Wrap { trim: false });
 
-        // Token consumption rate
+        frame.render_widget(panel, area);
+    }
+
+    fn draw_rate_panel(frame: &mut Frame, area: Rect, metrics: &UsageMetrics) {
         let consumption_text = vec![
             Line::from(vec![
                 Span::raw("Rate: "),
@@ -1053,9 +2857,22 @@ fn draw_about_tab(frame: &mut Frame, area: Rect) {
             )
             .alignment(Alignment::Center);
 
-        frame.render_widget(consumption_widget, chunks[0]);
+        frame.render_widget(consumption_widget, area);
+    }
 
-        // Cache analytics
+    fn draw_cache_panel(frame: &mut Frame, area: Rect, metrics: &UsageMetrics, opportunities: &[CacheOpportunity]) {
+        let savings: f64 = opportunities.iter().map(|opportunity| opportunity.potential_savings).sum();
+        let opportunity_line = if opportunities.is_empty() {
+            Line::from(Span::styled("No missed caching", Style::default().fg(Color::Green)))
+        } else {
+            Line::from(vec![
+                Span::raw("Missed: "),
+                Span::styled(
+                    format!("~${savings:.2} ({} groups, {}d)", opportunities.len(), recommendations::ANALYSIS_DAYS),
+                    Style::default().fg(Color::Yellow),
+                ),
+            ])
+        };
         let cache_text = vec![
             Line::from(vec![
                 Span::raw("Hit Rate: "),
@@ -1072,6 +2889,8 @@ fn draw_about_tab(frame: &mut Frame, area: Rect) {
                     Style::default().fg(Color::Cyan),
                 ),
             ]),
+            Line::from(""),
+            opportunity_line,
         ];
 
         let cache_widget = Paragraph::new(cache_text)
@@ -1083,9 +2902,10 @@ fn draw_about_tab(frame: &mut Frame, area: Rect) {
             )
             .alignment(Alignment::Center);
 
-        frame.render_widget(cache_widget, chunks[1]);
+        frame.render_widget(cache_widget, area);
+    }
 
-        // Session progress
+    fn draw_progress_panel(frame: &mut Frame, area: Rect, metrics: &UsageMetrics) {
         let session = &metrics.current_session;
         let progress_percent = (session.tokens_used as f64 / session.tokens_limit as f64 * 100.0) as u16;
         let remaining_tokens = session.tokens_limit.saturating_sub(session.tokens_used);
@@ -1108,18 +2928,24 @@ fn draw_about_tab(frame: &mut Frame, area: Rect) {
             ]),
         ];
 
+        let progress_title = if metrics.limit_calibrated {
+            "Session Progress (calibrated from history)"
+        } else {
+            "Session Progress"
+        };
         let progress_widget = Paragraph::new(progress_text)
             .block(
                 Block::default()
-                    .title("Session Progress")
+                    .title(progress_title)
                     .borders(Borders::ALL)
                     .border_style(Style::default().fg(Color::Yellow)),
             )
             .alignment(Alignment::Center);
 
-        frame.render_widget(progress_widget, chunks[2]);
+        frame.render_widget(progress_widget, area);
+    }
 
-        // Efficiency score
+    fn draw_efficiency_panel(frame: &mut Frame, area: Rect, metrics: &UsageMetrics) {
         let efficiency_text = vec![
             Line::from(vec![
                 Span::raw("Score: "),
@@ -1137,6 +2963,8 @@ fn draw_about_tab(frame: &mut Frame, area: Rect) {
                         Style::default().fg(Color::Red),
                     ),
                 ]
+            } else if metrics.idle_since.is_some() {
+                vec![Span::raw("ETA: paused (idle)")]
             } else {
                 vec![Span::raw("ETA: N/A")]
             }),
@@ -1151,11 +2979,91 @@ fn draw_about_tab(frame: &mut Frame, area: Rect) {
             )
             .alignment(Alignment::Center);
 
-        frame.render_widget(efficiency_widget, chunks[3]);
+        frame.render_widget(efficiency_widget, area);
+    }
+
+    fn draw_cost_panel(frame: &mut Frame, area: Rect, metrics: &UsageMetrics, cost_delta: Option<&Delta>) {
+        let session = &metrics.current_session;
+        let elapsed_hours = metrics.session_progress * (session.reset_time - session.start_time).num_minutes() as f64 / 60.0;
+        let hourly_cost = if elapsed_hours > 0.0 {
+            metrics.estimated_cost / elapsed_hours
+        } else {
+            0.0
+        };
+
+        let cost_text = vec![
+            Line::from(vec![
+                Span::raw("Session: "),
+                Span::styled(
+                    format!("${:.2}", metrics.estimated_cost),
+                    Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                ),
+                Self::delta_span(cost_delta),
+            ]),
+            Line::from(""),
+            Line::from(vec![
+                Span::raw("Per hour: "),
+                Span::styled(
+                    format!("${hourly_cost:.2}"),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+        ];
+
+        let cost_widget = Paragraph::new(cost_text)
+            .block(
+                Block::default()
+                    .title("Estimated Cost (API rates)")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .alignment(Alignment::Center);
+
+        frame.render_widget(cost_widget, area);
+    }
+
+    /// Draw tokens per hour of day for recent days, shaded relative to the busiest hour
+    fn draw_activity_heatmap(frame: &mut Frame, area: Rect, metrics: &UsageMetrics) {
+        let block = Block::default()
+            .title("Activity Heatmap (tokens per hour, UTC)")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Green));
+
+        let max_tokens = metrics.activity_heatmap.iter().flatten().copied().max().unwrap_or(0);
+        if max_tokens == 0 {
+            let placeholder = Paragraph::new("No recent activity to show.")
+                .block(block)
+                .alignment(Alignment::Center);
+            frame.render_widget(placeholder, area);
+            return;
+        }
+
+        let hour_labels: String = (0..24).step_by(3).map(|hour| format!("{hour:02}    ")).collect();
+        let mut lines = vec![Line::from(format!("    {hour_labels}"))];
+        let today = chrono::Utc::now().date_naive();
+        let days = metrics.activity_heatmap.len();
+        for (row, hours) in metrics.activity_heatmap.iter().enumerate() {
+            let date = today - chrono::Duration::days((days - 1 - row) as i64);
+            let mut spans = vec![Span::raw(format!("{} ", date.format("%a")))];
+            for &tokens in hours {
+                let intensity = tokens as f64 / max_tokens as f64;
+                let (cell, color) = match intensity {
+                    i if i <= 0.0 => ("··", Color::DarkGray),
+                    i if i < 0.25 => ("░░", Color::Green),
+                    i if i < 0.5 => ("▒▒", Color::Green),
+                    i if i < 0.75 => ("▓▓", Color::Yellow),
+                    _ => ("██", Color::Red),
+                };
+                spans.push(Span::styled(cell, Style::default().fg(color)));
+            }
+            lines.push(Line::from(spans));
+        }
+
+        frame.render_widget(Paragraph::new(lines).block(block), area);
     }
 
     /// Draw stacked time-series chart with different token types
-    fn draw_stacked_token_chart(frame: &mut Frame, area: Rect, metrics: &UsageMetrics) {
+    fn draw_stacked_token_chart(frame: &mut Frame, area: Rect, metrics: &UsageMetrics, warning_threshold: f64) {
         if metrics.usage_history.is_empty() {
             let placeholder = Paragraph::new("No token usage data available for stacked chart.\nPress 'v' to switch to general view or start using Claude to see real-time consumption.")
                 .block(
@@ -1186,8 +3094,10 @@ fn draw_about_tab(frame: &mut Frame, area: Rect) {
             return;
         }
 
-        let max_tokens = chart_data.iter().map(|(_, y)| *y).fold(0.0, f64::max);
+        let limit = metrics.current_session.tokens_limit;
+        let max_tokens = chart_data.iter().map(|(_, y)| *y).fold(limit as f64, f64::max);
         let x_max = (chart_data.len() - 1) as f64;
+        let thresholds = super::threshold_lines(limit, warning_threshold, x_max);
 
         // Create time labels
         let time_labels = if metrics.usage_history.len() > 1 {
@@ -1241,7 +3151,19 @@ fn draw_about_tab(frame: &mut Frame, area: Rect) {
             .style(Style::default().fg(Color::Yellow))
             .data(&output_data);
 
-        let chart = Chart::new(vec![total_dataset, input_dataset, output_dataset])
+        let reached_markers = Self::limit_event_markers(metrics, LimitEventKind::LimitReached, |index| index as f64);
+        let mut datasets = vec![total_dataset, input_dataset, output_dataset];
+        datasets.extend(Self::threshold_datasets(&thresholds, warning_threshold));
+        if !reached_markers.is_empty() {
+            datasets.push(Dataset::default()
+                .name("Limit reached")
+                .marker(ratatui::symbols::Marker::Block)
+                .graph_type(GraphType::Scatter)
+                .style(Style::default().fg(Color::Red))
+                .data(&reached_markers));
+        }
+
+        let chart = Chart::new(datasets)
             .block(
                 Block::default()
                     .title("Token Usage by Type Over Time (Press 'v' to toggle view)")
@@ -1295,7 +3217,8 @@ fn draw_token_usage_chart(frame: &mut Frame, area: Rect, metrics: &UsageMetrics)
         (remaining_label.as_str(), remaining_percent.max(1)), // Ensure at least 1 for visibility
     ];
 
-    let title = format!("Token Usage Distribution ({usage_percent:.1}% used)");
+    let calibration_note = if metrics.limit_calibrated { ", limit calibrated from history" } else { "" };
+    let title = format!("Token Usage Distribution ({usage_percent:.1}% used{calibration_note})");
     
     let barchart = BarChart::default()
         .block(
@@ -1310,79 +3233,67 @@ fn draw_token_usage_chart(frame: &mut Frame, area: Rect, metrics: &UsageMetrics)
 
     frame.render_widget(barchart, area);
 }
-    /// Draw usage history chart
-/// Draw usage history chart
-fn draw_usage_history_chart(frame: &mut Frame, area: Rect, metrics: &UsageMetrics) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(8),  // Time period chart
-            Constraint::Min(4),     // Usage trend chart
-        ])
-        .split(area);
 
-    // Time period usage summary - use safe arithmetic
-    let current_tokens = metrics.current_session.tokens_used as u64; // Ensure non-negative
-    
-    // Better mock data that shows meaningful progression
-    let base = current_tokens.max(100); // Ensure we have some baseline
-    let period_data = vec![
-        ("Last 12h", base),
-        ("Last 24h", base + (base / 4)),
-        ("Last 48h", base + (base / 2)),
-        ("Last 7d", base + base),
-    ];
+    /// Draw token totals over trailing periods and per recent session window
+    fn draw_usage_history_chart(frame: &mut Frame, area: Rect, period_tokens: &[(&str, u64)], session_trend: &[(DateTime<Utc>, u64)]) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(8),  // Time period chart
+                Constraint::Min(4),     // Usage trend chart
+            ])
+            .split(area);
 
-    let period_chart = BarChart::default()
-        .block(
-            Block::default()
-                .title("Token Usage by Time Period")
-                .borders(Borders::ALL),
-        )
-        .data(&period_data)
-        .bar_width(8)
-        .bar_style(Style::default().fg(Color::Yellow))
-        .value_style(Style::default().fg(Color::Black).bg(Color::Yellow));
+        let period_chart = BarChart::default()
+            .block(
+                Block::default()
+                    .title("Token Usage by Time Period")
+                    .borders(Borders::ALL),
+            )
+            .data(period_tokens)
+            .bar_width(8)
+            .bar_style(Style::default().fg(Color::Yellow))
+            .value_style(Style::default().fg(Color::Black).bg(Color::Yellow));
 
-    frame.render_widget(period_chart, chunks[0]);
+        frame.render_widget(period_chart, chunks[0]);
 
-    // Recent usage trend - show realistic progression with safe arithmetic
-    let current = current_tokens.max(10); // Ensure minimum value
-    let step = (current / 6).max(1); // Safe step calculation
-    
-    // Use safe subtraction - this is the key fix
-    let trend_data = vec![
-        ("6h ago", current.saturating_sub(step * 5)),
-        ("4h ago", current.saturating_sub(step * 4)),
-        ("2h ago", current.saturating_sub(step * 3)),
-        ("1h ago", current.saturating_sub(step * 2)),
-        ("30m ago", current.saturating_sub(step)),
-        ("Now", current),
-    ];
+        let labels: Vec<String> = session_trend
+            .iter()
+            .map(|(start, _)| start.format("%a %H").to_string())
+            .collect();
+        let trend_data: Vec<(&str, u64)> = labels
+            .iter()
+            .zip(session_trend)
+            .map(|(label, (_, tokens))| (label.as_str(), *tokens))
+            .collect();
+        let title = if trend_data.is_empty() {
+            "Tokens per Session (no sessions yet)".to_string()
+        } else {
+            format!("Tokens per Session (last {}, start UTC)", trend_data.len())
+        };
 
-    let trend_chart = BarChart::default()
-        .block(
-            Block::default()
-                .title("Recent Usage Trend")
-                .borders(Borders::ALL),
-        )
-        .data(&trend_data)
-        .bar_width(3)
-        .bar_style(Style::default().fg(Color::Cyan))
-        .value_style(Style::default().fg(Color::Black).bg(Color::Cyan));
+        let trend_chart = BarChart::default()
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .data(&trend_data)
+            .bar_width(6)
+            .bar_gap(1)
+            .bar_style(Style::default().fg(Color::Cyan))
+            .value_style(Style::default().fg(Color::Black).bg(Color::Cyan));
+
+        frame.render_widget(trend_chart, chunks[1]);
+    }
 
-    frame.render_widget(trend_chart, chunks[1]);
-}
     /// Draw detailed current session information
-    fn draw_current_session_details(frame: &mut Frame, area: Rect, session: &TokenSession) {
+    fn draw_current_session_details(frame: &mut Frame, area: Rect, metrics: &UsageMetrics) {
+        let session = &metrics.current_session;
         let details = [format!("Session ID: {}", session.id),
             format!("Plan: {:?}", session.plan_type),
             format!("Tokens Used: {}", session.tokens_used),
             format!("Token Limit: {}", session.tokens_limit),
             format!("Usage: {:.1}%", (session.tokens_used as f64 / session.tokens_limit as f64) * 100.0),
             format!("Started: {}", humantime::format_rfc3339(session.start_time.into())),
-            format!("Resets: {}", humantime::format_rfc3339(session.reset_time.into())),
-            format!("Status: {}", if session.is_active { "Active" } else { "Inactive" })];
+            format!("Resets: {} ({})", humantime::format_rfc3339(session.reset_time.into()), metrics.reset_source()),
+            format!("Status: {}", metrics.status())];
 
         let items: Vec<ListItem> = details
             .iter()
@@ -1401,72 +3312,116 @@ fn draw_usage_history_chart(frame: &mut Frame, area: Rect, metrics: &UsageMetric
     }
 
     /// Draw session predictions panel
-    fn draw_session_predictions(frame: &mut Frame, area: Rect, metrics: &UsageMetrics) {
-        let predictions = if let Some(depletion_time) = &metrics.projected_depletion {
+    fn draw_session_predictions(
+        frame: &mut Frame,
+        area: Rect,
+        metrics: &UsageMetrics,
+        recommendations: &[Recommendation],
+        rate_delta: Option<&Delta>,
+    ) {
+        let mut lines = if let Some(depletion_time) = &metrics.projected_depletion {
             let time_remaining = depletion_time.signed_duration_since(chrono::Utc::now());
-            let hours = time_remaining.num_hours();
-            let minutes = time_remaining.num_minutes() % 60;
-            
             vec![
-                format!("Projected Depletion: {}h {}m", hours, minutes),
-                format!("Depletion Time: {}", humantime::format_rfc3339((*depletion_time).into())),
-                format!("Usage Rate: {:.2} tokens/min", metrics.usage_rate),
-                format!("Efficiency: {:.2}", metrics.efficiency_score),
-                format!("Session Progress: {:.1}%", metrics.session_progress * 100.0),
-                "".to_string(),
-                "Recommendations:".to_string(),
-                if metrics.usage_rate > 100.0 {
-                    "• Consider reducing usage rate"
-                } else {
-                    "• Usage rate is optimal"
-                }.to_string(),
-                if metrics.efficiency_score < 0.7 {
-                    "• Consider spreading usage more evenly"
-                } else {
-                    "• Usage pattern is efficient"
-                }.to_string(),
+                Line::from(format!(
+                    "Projected Depletion: {}h {}m ({})",
+                    time_remaining.num_hours(),
+                    time_remaining.num_minutes() % 60,
+                    depletion_time.format("%H:%M UTC")
+                )),
+                Line::from(vec![
+                    Span::raw(format!("Usage Rate: {:.2} tokens/min", metrics.usage_rate)),
+                    Self::delta_span(rate_delta),
+                    Span::raw(format!(" | Efficiency: {:.2}", metrics.efficiency_score)),
+                ]),
+                Line::from(format!("Session Progress: {:.1}%", metrics.session_progress * 100.0)),
+            ]
+        } else if let Some(idle_since) = metrics.idle_since {
+            vec![
+                Line::from(format!("Idle since {}", idle_since.format("%H:%M UTC"))),
+                Line::from("Depletion is projected again once requests resume"),
             ]
         } else {
             vec![
-                "No active usage detected".to_string(),
-                "".to_string(),
-                "Start using Claude to see predictions".to_string(),
+                Line::from("No active usage detected"),
+                Line::from("Start using Claude to see predictions"),
             ]
         };
 
-        let items: Vec<ListItem> = predictions
-            .iter()
-            .map(|p| ListItem::new(Line::from(p.as_str())))
-            .collect();
+        lines.push(Line::from(""));
+        if recommendations.is_empty() {
+            lines.push(Line::from(Span::styled(
+                "✓ Nothing stands out in recent usage",
+                Style::default().fg(Color::Green),
+            )));
+        }
+        for recommendation in recommendations {
+            let color = match recommendation.priority {
+                Priority::High => Color::Red,
+                Priority::Medium => Color::Yellow,
+                Priority::Low => Color::Cyan,
+            };
+            lines.push(Line::from(vec![
+                Span::styled("• ", Style::default().fg(color)),
+                Span::raw(recommendation.message.as_str()),
+            ]));
+        }
 
-        let list = List::new(items)
+        let paragraph = Paragraph::new(lines)
             .block(
                 Block::default()
                     .title("Predictions & Recommendations")
                     .borders(Borders::ALL),
             )
-            .style(Style::default().fg(Color::White));
+            .style(Style::default().fg(Color::White))
+            .wrap(Wrap { trim: true });
 
-        frame.render_widget(list, area);
+        frame.render_widget(paragraph, area);
     }
 
     /// Draw footer with controls
-    fn draw_footer(frame: &mut Frame, area: Rect) {
-        let controls = Paragraph::new("Controls: [Q]uit | [Tab/N] Switch tabs | [V] Toggle Overview view | [↑↓] Scroll | [R]efresh")
+    fn draw_footer(frame: &mut Frame, area: Rect, script_alerts: &[String], notice: Option<&str>, data_changed_at: Option<DateTime<Utc>>) {
+        // How long ago a scan last brought new usage, in the corner of the border
+        let block = |color: Color| {
+            let block = Block::default().borders(Borders::ALL).border_style(Style::default().fg(color));
+            match data_changed_at {
+                Some(at) => block.title(
+                    Title::from(format!(" data {} old ", super::format_duration(Utc::now() - at)))
+                        .alignment(Alignment::Right),
+                ),
+                None => block,
+            }
+        };
+        if !script_alerts.is_empty() {
+            let alerts = Paragraph::new(format!("⚠️ Script alert: {}", script_alerts.join(", ")))
+                .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+                .alignment(Alignment::Center)
+                .block(block(Color::Red));
+            frame.render_widget(alerts, area);
+            return;
+        }
+        if let Some(notice) = notice {
+            let notice = Paragraph::new(notice.to_string())
+                .style(Style::default().fg(Color::Green))
+                .alignment(Alignment::Center)
+                .block(block(Color::DarkGray));
+            frame.render_widget(notice, area);
+            return;
+        }
+
+        let controls = Paragraph::new("Controls: [Q]uit | [Tab/N] Switch tabs | [V] Cycle Overview view | [F]ocus view | [↑↓] Scroll | [←→] Month (Calendar) | [R]efresh | [S]ource | [Y]ank (Shift: JSON)")
             .style(Style::default().fg(Color::Gray))
             .alignment(Alignment::Center)
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::DarkGray)),
-            );
+            .block(block(Color::DarkGray));
         frame.render_widget(controls, area);
     }
 
     /// Clean up terminal
     pub fn cleanup(&mut self) -> Result<()> {
+        if let Some(chart) = self.inline_chart.as_mut() {
+            chart.remove(self.terminal.backend_mut())?;
+        }
         disable_raw_mode()?;
-        execute!(self.terminal.backend_mut(), LeaveAlternateScreen)?;
+        execute!(self.terminal.backend_mut(), DisableFocusChange, LeaveAlternateScreen)?;
         self.terminal.show_cursor()?;
         Ok(())
     }
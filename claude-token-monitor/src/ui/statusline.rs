@@ -0,0 +1,198 @@
+use super::compact_tokens;
+use crate::models::*;
+use chrono::Utc;
+use colored::*;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Session payload Claude Code pipes to statusline commands on stdin
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct StatuslineInput {
+    pub session_id: Option<String>,
+    pub cwd: Option<String>,
+    pub model: Option<StatuslineModel>,
+    pub workspace: Option<StatuslineWorkspace>,
+    pub cost: Option<StatuslineCost>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct StatuslineModel {
+    pub id: Option<String>,
+    pub display_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct StatuslineWorkspace {
+    pub current_dir: Option<String>,
+    pub project_dir: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct StatuslineCost {
+    pub total_cost_usd: Option<f64>,
+}
+
+impl StatuslineInput {
+    /// Parse the stdin payload, tolerating an empty or malformed one
+    pub fn parse(payload: &str) -> Self {
+        if payload.trim().is_empty() {
+            return Self::default();
+        }
+        serde_json::from_str(payload).unwrap_or_else(|e| {
+            log::debug!("Ignoring unparseable statusline payload: {e}");
+            Self::default()
+        })
+    }
+
+    fn model_name(&self) -> Option<&str> {
+        let model = self.model.as_ref()?;
+        model.display_name.as_deref().or(model.id.as_deref())
+    }
+
+    fn project_name(&self) -> Option<&str> {
+        let dir = self
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.project_dir.as_deref().or(workspace.current_dir.as_deref()))
+            .or(self.cwd.as_deref())?;
+        Path::new(dir).file_name().and_then(|name| name.to_str())
+    }
+}
+
+/// Build the single statusline Claude Code displays under the prompt
+pub fn render_statusline(input: &StatuslineInput, metrics: Option<&UsageMetrics>, config: &UserConfig) -> String {
+    let mut parts = Vec::new();
+
+    if let Some(model) = input.model_name() {
+        parts.push(format!("🧠 {model}"));
+    }
+
+    match metrics {
+        Some(metrics) => {
+            let session = &metrics.current_session;
+            let usage = if session.tokens_limit > 0 {
+                session.tokens_used as f64 / session.tokens_limit as f64
+            } else {
+                0.0
+            };
+            let usage_text = format!(
+                "{}/{} ({:.0}%)",
+                compact_tokens(session.tokens_used as u64),
+                compact_tokens(session.tokens_limit as u64),
+                usage * 100.0
+            );
+            let usage_text = if usage >= 0.95 {
+                usage_text.red().bold()
+            } else if usage >= config.warning_threshold {
+                usage_text.yellow()
+            } else {
+                usage_text.green()
+            };
+            parts.push(usage_text.to_string());
+
+            let until_reset = session.reset_time.signed_duration_since(Utc::now());
+            if until_reset > chrono::Duration::zero() {
+                parts.push(format!(
+                    "↻ {}h{:02}m",
+                    until_reset.num_hours(),
+                    until_reset.num_minutes() % 60
+                ));
+            }
+
+            if metrics.estimated_cost > 0.0 {
+                parts.push(format!("${:.2} window", metrics.estimated_cost));
+            }
+        }
+        None => parts.push("no usage data".dimmed().to_string()),
+    }
+
+    if let Some(cost) = input.cost.as_ref().and_then(|cost| cost.total_cost_usd) {
+        parts.push(format!("${cost:.2} chat"));
+    }
+
+    if let Some(project) = input.project_name() {
+        parts.push(format!("📁 {project}"));
+    }
+
+    parts.join(" │ ")
+}
+
+/// Usage summary for macOS launchers such as Raycast and Alfred
+#[derive(Debug, Clone, PartialEq)]
+pub struct LauncherItem {
+    pub icon: &'static str, // traffic light for the usage level
+    pub title: String,
+    pub subtitle: String,
+}
+
+impl LauncherItem {
+    pub fn new(metrics: Option<&UsageMetrics>, config: &UserConfig, now: chrono::DateTime<Utc>) -> Self {
+        let Some(metrics) = metrics else {
+            return Self {
+                icon: "⚪",
+                title: "No Claude usage data".to_string(),
+                subtitle: "No usage found in Claude Code's logs".to_string(),
+            };
+        };
+        let session = &metrics.current_session;
+        let usage = if session.tokens_limit > 0 {
+            session.tokens_used as f64 / session.tokens_limit as f64
+        } else {
+            0.0
+        };
+        let icon = if usage >= 0.95 {
+            "🔴"
+        } else if usage >= config.warning_threshold {
+            "🟡"
+        } else {
+            "🟢"
+        };
+
+        let mut subtitle = vec![format!(
+            "{}/{} tokens",
+            compact_tokens(session.tokens_used as u64),
+            compact_tokens(session.tokens_limit as u64)
+        )];
+        let until_reset = session.reset_time.signed_duration_since(now);
+        if until_reset > chrono::Duration::zero() {
+            subtitle.push(format!(
+                "resets in {}h{:02}m",
+                until_reset.num_hours(),
+                until_reset.num_minutes() % 60
+            ));
+        }
+        if metrics.estimated_cost > 0.0 {
+            subtitle.push(format!("${:.2} this window", metrics.estimated_cost));
+        }
+        Self {
+            icon,
+            title: format!("Claude usage {:.0}%", usage * 100.0),
+            subtitle: subtitle.join(" · "),
+        }
+    }
+
+    /// Raycast script-command output: the icon and title on the first line, which inline mode
+    /// shows in the command item, and the subtitle below for full output mode
+    pub fn raycast(&self) -> String {
+        format!("{} {}\n{}", self.icon, self.title, self.subtitle)
+    }
+
+    /// Alfred Script Filter JSON with a single item; Alfred icons are image files, so the icon
+    /// leads the title instead
+    pub fn alfred(&self) -> String {
+        serde_json::json!({
+            "items": [{
+                "uid": "claude-token-monitor-usage",
+                "title": format!("{} {}", self.icon, self.title),
+                "subtitle": self.subtitle,
+                "arg": format!("{}, {}", self.title, self.subtitle),
+                "text": { "copy": format!("{}, {}", self.title, self.subtitle) },
+            }]
+        })
+        .to_string()
+    }
+}
@@ -0,0 +1,246 @@
+use super::compact_tokens;
+use crate::services::file_monitor::FileBasedTokenMonitor;
+use crate::services::live_activity::{rank_activity, ActivityGrouping, ActivityRow, ActivityTable, ACTIVITY_WINDOWS};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table},
+    Frame, Terminal,
+};
+use std::io;
+use std::time::{Duration, Instant};
+
+/// How a row is shown: name, project (conversations only), tokens per window, rate, requests, last seen
+fn row_cells(row: &ActivityRow, grouping: ActivityGrouping, sort_window: usize, monitor: &FileBasedTokenMonitor, now: DateTime<Utc>) -> Vec<String> {
+    let project_label = |project: &str| if project == "unknown" { project.to_string() } else { monitor.project_label(project) };
+    let mut cells = match grouping {
+        ActivityGrouping::Conversation => vec![
+            row.key.chars().take(8).collect(),
+            row.project.as_deref().map_or("-".to_string(), project_label),
+        ],
+        ActivityGrouping::Project => vec![project_label(&row.key)],
+    };
+    cells.extend(row.tokens.iter().map(|tokens| compact_tokens(*tokens)));
+    cells.push(format!("{:.0}", row.rate(sort_window)));
+    cells.push(row.requests.to_string());
+    cells.push(ago(now - row.last_seen));
+    cells
+}
+
+fn totals_cells(table: &ActivityTable, grouping: ActivityGrouping, sort_window: usize) -> Vec<String> {
+    let mut cells = vec![format!("Total ({})", table.rows.len())];
+    if grouping == ActivityGrouping::Conversation {
+        cells.push(String::new());
+    }
+    cells.extend(table.totals.tokens.iter().map(|tokens| compact_tokens(*tokens)));
+    cells.push(format!("{:.0}", table.totals.rate(sort_window)));
+    cells.push(table.totals.requests.to_string());
+    cells.push(String::new());
+    cells
+}
+
+fn headers(grouping: ActivityGrouping, sort_window: usize) -> Vec<String> {
+    let mut headers = match grouping {
+        ActivityGrouping::Conversation => vec!["CONVERSATION".to_string(), "PROJECT".to_string()],
+        ActivityGrouping::Project => vec!["PROJECT".to_string()],
+    };
+    for (window, minutes) in ACTIVITY_WINDOWS.iter().enumerate() {
+        let marker = if window == sort_window { "▼" } else { "" };
+        headers.push(format!("{marker}{minutes}M"));
+    }
+    headers.push(format!("TOK/MIN {}M", ACTIVITY_WINDOWS[sort_window]));
+    headers.push("REQS 60M".to_string());
+    headers.push("LAST".to_string());
+    headers
+}
+
+fn ago(elapsed: chrono::Duration) -> String {
+    let seconds = elapsed.num_seconds().max(0);
+    if seconds < 60 {
+        format!("{seconds}s ago")
+    } else {
+        format!("{}m ago", seconds / 60)
+    }
+}
+
+/// The ranking as plain text, for `top` without a terminal (one snapshot, like `top -b -n 1`)
+pub fn render_activity(table: &ActivityTable, grouping: ActivityGrouping, sort_window: usize, monitor: &FileBasedTokenMonitor, now: DateTime<Utc>) -> String {
+    if table.rows.is_empty() {
+        return format!("No Claude Code activity in the last {} minutes", ACTIVITY_WINDOWS[ACTIVITY_WINDOWS.len() - 1]);
+    }
+    let mut lines = vec![headers(grouping, sort_window)];
+    lines.extend(table.rows.iter().map(|row| row_cells(row, grouping, sort_window, monitor, now)));
+    lines.push(totals_cells(table, grouping, sort_window));
+
+    let columns = lines[0].len();
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| lines.iter().map(|line| line[column].chars().count()).max().unwrap_or(0))
+        .collect();
+    // Names are left aligned, numbers right aligned
+    let text_columns = if grouping == ActivityGrouping::Conversation { 2 } else { 1 };
+    lines
+        .iter()
+        .map(|line| {
+            line.iter()
+                .zip(&widths)
+                .enumerate()
+                .map(|(column, (cell, width))| {
+                    if column < text_columns { format!("{cell:<width$}") } else { format!("{cell:>width$}") }
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Full-screen table of the busiest conversations or projects, rescanned every `refresh_interval`
+pub struct TopView {
+    terminal: Terminal<CrosstermBackend<io::Stdout>>,
+    grouping: ActivityGrouping,
+    sort_window: usize,
+    refresh_interval: Duration,
+}
+
+impl TopView {
+    pub fn new(grouping: ActivityGrouping, sort_window: usize, refresh_interval: Duration) -> Result<Self> {
+        enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        execute!(stdout, EnterAlternateScreen)?;
+        let terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+        Ok(Self {
+            terminal,
+            grouping,
+            sort_window,
+            refresh_interval,
+        })
+    }
+
+    /// Redraw every second, rescanning the usage files every refresh interval (or on 'r'), until 'q'
+    pub async fn run(&mut self, monitor: &mut FileBasedTokenMonitor) -> Result<()> {
+        let mut last_scan = Instant::now();
+        loop {
+            if last_scan.elapsed() >= self.refresh_interval {
+                if let Err(e) = monitor.scan_usage_files().await {
+                    log::warn!("Failed to rescan usage files: {e}");
+                }
+                last_scan = Instant::now();
+            }
+            let now = Utc::now();
+            let table = rank_activity(monitor.usage_entries(), now, self.grouping, self.sort_window);
+            let (grouping, sort_window) = (self.grouping, self.sort_window);
+            self.terminal.draw(|frame| Self::draw(frame, &table, grouping, sort_window, monitor, now))?;
+
+            if event::poll(Duration::from_millis(1000))? {
+                if let Event::Key(KeyEvent { code, modifiers, .. }) = event::read()? {
+                    match code {
+                        KeyCode::Char('q') | KeyCode::Esc => break,
+                        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break,
+                        KeyCode::Char('g') => self.grouping = self.grouping.toggled(),
+                        KeyCode::Char('s') => self.sort_window = (self.sort_window + 1) % ACTIVITY_WINDOWS.len(),
+                        KeyCode::Char(digit @ '1'..='3') => self.sort_window = digit as usize - '1' as usize,
+                        KeyCode::Char('r') => last_scan = Instant::now() - self.refresh_interval,
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn draw(frame: &mut Frame, table: &ActivityTable, grouping: ActivityGrouping, sort_window: usize, monitor: &FileBasedTokenMonitor, now: DateTime<Utc>) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(3), Constraint::Length(1)])
+            .split(frame.area());
+
+        let rate = table.totals.rate(sort_window);
+        let summary = Line::from(vec![
+            Span::styled(" Tokens ", Style::default().fg(Color::Gray)),
+            Span::styled(
+                ACTIVITY_WINDOWS
+                    .iter()
+                    .zip(table.totals.tokens)
+                    .map(|(minutes, tokens)| format!("{minutes}m {}", compact_tokens(tokens)))
+                    .collect::<Vec<_>>()
+                    .join(" │ "),
+                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(format!("   {rate:.0} tok/min over {}m", ACTIVITY_WINDOWS[sort_window]), Style::default().fg(Color::Yellow)),
+            Span::styled(format!("   {} active {}s", table.rows.len(), grouping.name()), Style::default().fg(Color::Gray)),
+        ]);
+        let header = Paragraph::new(summary).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" claude-token-monitor top · {} ", now.format("%H:%M:%S UTC"))),
+        );
+        frame.render_widget(header, chunks[0]);
+
+        let text_columns = if grouping == ActivityGrouping::Conversation { 2 } else { 1 };
+        let columns = headers(grouping, sort_window).len();
+        let widths: Vec<Constraint> = (0..columns)
+            .map(|column| match column {
+                0 if grouping == ActivityGrouping::Conversation => Constraint::Length(12),
+                column if column < text_columns => Constraint::Min(16),
+                _ => Constraint::Length(11),
+            })
+            .collect();
+        let right = |cells: Vec<String>| {
+            cells
+                .into_iter()
+                .enumerate()
+                .map(|(column, cell)| {
+                    if column < text_columns {
+                        Cell::from(cell)
+                    } else {
+                        Cell::from(Line::from(cell).right_aligned())
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+        let busiest = table.rows.first().map_or(0, |row| row.tokens[sort_window]);
+        let rows = table.rows.iter().map(|row| {
+            let style = if busiest > 0 && row.tokens[sort_window] == busiest {
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+            } else if row.tokens[0] > 0 {
+                Style::default().fg(Color::White)
+            } else {
+                Style::default().fg(Color::DarkGray) // nothing in the last 5 minutes
+            };
+            Row::new(right(row_cells(row, grouping, sort_window, monitor, now))).style(style)
+        });
+        let widget = Table::new(rows, widths)
+            .header(Row::new(right(headers(grouping, sort_window))).style(Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED)))
+            .footer(Row::new(right(totals_cells(table, grouping, sort_window))).style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)))
+            .block(Block::default().borders(Borders::ALL).title(format!(" By {} ", grouping.name())));
+        frame.render_widget(widget, chunks[1]);
+
+        let controls = Paragraph::new(" q quit · g conversations/projects · s or 1-3 sort by 5/15/60 min · r rescan")
+            .style(Style::default().fg(Color::DarkGray));
+        frame.render_widget(controls, chunks[2]);
+    }
+
+    pub fn cleanup(&mut self) -> Result<()> {
+        disable_raw_mode()?;
+        execute!(self.terminal.backend_mut(), LeaveAlternateScreen)?;
+        self.terminal.show_cursor()?;
+        Ok(())
+    }
+}
+
+impl Drop for TopView {
+    fn drop(&mut self) {
+        let _ = self.cleanup();
+    }
+}